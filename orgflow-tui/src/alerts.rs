@@ -0,0 +1,177 @@
+use std::env;
+use std::time::{Duration, Instant};
+
+/// How long the header stays reversed after a flashing alert
+pub const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// Severity of an alert, deciding where it shows up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// Ways of getting an alert in front of the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Text in the header line until the next key press
+    StatusBar,
+    /// The header line is shown reversed for a moment
+    Flash,
+    /// A desktop notification via `notify-send`
+    Desktop,
+}
+
+/// Where alerts may go, read from the environment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlertConfig {
+    /// Only the status bar is used, set via `ORGFLOW_QUIET=1`
+    pub quiet: bool,
+    /// Errors are sent as desktop notifications, set via
+    /// `ORGFLOW_DESKTOP_NOTIFICATIONS=1` in builds with the
+    /// `desktop-notifications` feature
+    pub desktop: bool,
+}
+
+impl AlertConfig {
+    pub fn from_env() -> Self {
+        let enabled = |name: &str| {
+            env::var(name).is_ok_and(|value| matches!(value.as_str(), "1" | "true" | "yes"))
+        };
+        Self {
+            quiet: enabled("ORGFLOW_QUIET"),
+            desktop: cfg!(feature = "desktop-notifications")
+                && enabled("ORGFLOW_DESKTOP_NOTIFICATIONS"),
+        }
+    }
+
+    /// Channels an alert of `level` is routed to
+    pub fn channels(&self, level: AlertLevel) -> Vec<Channel> {
+        match level {
+            _ if self.quiet => vec![Channel::StatusBar],
+            AlertLevel::Info => vec![Channel::StatusBar],
+            AlertLevel::Warn => vec![Channel::StatusBar, Channel::Flash],
+            AlertLevel::Error if self.desktop => {
+                vec![Channel::StatusBar, Channel::Flash, Channel::Desktop]
+            }
+            AlertLevel::Error => vec![Channel::StatusBar, Channel::Flash],
+        }
+    }
+}
+
+/// The alert currently shown in the status bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub level: AlertLevel,
+    pub message: String,
+}
+
+/// Feedback for the user, routed per level to the configured channels
+#[derive(Debug, Default)]
+pub struct Alerts {
+    config: AlertConfig,
+    current: Option<Alert>,
+    flash_until: Option<Instant>,
+}
+
+impl Alerts {
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(AlertLevel::Info, message.into());
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(AlertLevel::Warn, message.into());
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(AlertLevel::Error, message.into());
+    }
+
+    fn push(&mut self, level: AlertLevel, message: String) {
+        for channel in self.config.channels(level) {
+            match channel {
+                Channel::StatusBar => {}
+                Channel::Flash => self.flash_until = Some(Instant::now() + FLASH_DURATION),
+                Channel::Desktop => notify_desktop(&message),
+            }
+        }
+        self.current = Some(Alert { level, message });
+    }
+
+    /// Remove the status bar text, e.g. on the next key press
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+
+    /// Alert shown in the status bar
+    pub fn current(&self) -> Option<&Alert> {
+        self.current.as_ref()
+    }
+
+    /// Whether the header should be shown reversed right now
+    pub fn is_flashing(&self) -> bool {
+        self.flash_until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+#[cfg(feature = "desktop-notifications")]
+fn notify_desktop(message: &str) {
+    // Best effort, the status bar shows the alert anyway
+    let _ = std::process::Command::new("notify-send")
+        .args(["orgflow", message])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn notify_desktop(_message: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routing_table() {
+        use AlertLevel::*;
+        use Channel::*;
+        let default = AlertConfig::default();
+        let desktop = AlertConfig { quiet: false, desktop: true };
+        let quiet = AlertConfig { quiet: true, desktop: true };
+
+        assert_eq!(default.channels(Info), vec![StatusBar]);
+        assert_eq!(default.channels(Warn), vec![StatusBar, Flash]);
+        assert_eq!(default.channels(Error), vec![StatusBar, Flash]);
+        assert_eq!(desktop.channels(Warn), vec![StatusBar, Flash]);
+        assert_eq!(desktop.channels(Error), vec![StatusBar, Flash, Desktop]);
+        for level in [Info, Warn, Error] {
+            assert_eq!(quiet.channels(level), vec![StatusBar]);
+        }
+    }
+
+    #[test]
+    fn test_flash_and_clear() {
+        let mut alerts = Alerts::new(AlertConfig::default());
+        alerts.info("Saved");
+        assert!(!alerts.is_flashing());
+        assert_eq!(alerts.current().unwrap().message, "Saved");
+        alerts.warn("Careful");
+        assert!(alerts.is_flashing());
+        assert_eq!(alerts.current().unwrap().level, AlertLevel::Warn);
+        alerts.clear();
+        assert_eq!(alerts.current(), None);
+
+        let mut quiet = Alerts::new(AlertConfig { quiet: true, desktop: false });
+        quiet.error("Failed");
+        assert!(!quiet.is_flashing());
+        assert_eq!(quiet.current().unwrap().level, AlertLevel::Error);
+    }
+}
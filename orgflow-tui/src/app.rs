@@ -0,0 +1,1263 @@
+use orgflow::{
+    Configuration, Date, DefaultsMap, DraftTask, FilterSet, Note, OnComplete, OrgDocument,
+    ReadOnlyReason, Rollover, TITLE_MAX_LEN, TagSuggestions, Task, TaskFilter, TaskSort,
+    VIEW_EXPORT, apply_project_defaults, export_view, note_from_capture, note_parts, sanitize_line,
+    single_line,
+};
+use std::collections::VecDeque;
+use std::io;
+use std::io::Result as IoResult;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{Event, KeyEvent};
+use ratatui::{DefaultTerminal, Frame};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::alerts::{AlertConfig, Alerts};
+use crate::columns::TaskFormat;
+use crate::drafts::{DraftHistory, NoteDraft};
+use crate::editing::{DraftConflict, EditingNote, Freshness, note_lines};
+use crate::hints::HintContext;
+use crate::persistence::Persistence;
+use crate::selection::LineSelection;
+use crate::session::{SESSION_FILE, SessionManager, SessionState};
+use crate::views::tasks::task_metadata_lines;
+use crate::views::viewer::note_metadata_lines;
+use crate::widgets::autocompletion::AutocompletionWidget;
+use crate::widgets::calendar::Calendar;
+use crate::widgets::projects::ProjectPicker;
+use crate::widgets::duplicates::DuplicateReview;
+use crate::widgets::waiting::WaitingList;
+use crate::scratchpad::ScratchpadMode;
+use crate::{cli, migrate};
+
+/// Section a task is moved to when the document has none between Tasks and
+/// Notes yet
+const WAITING_FOR: &str = "## Waiting For";
+
+/// How long an added task counts as just added for the duplicate check
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(60);
+/// Number of recently added tasks remembered for the duplicate check
+const RECENT_TASKS: usize = 5;
+
+/// Key of the tags marking what to do today, rolled over on a new day
+const TODAY_KEY: &str = "today";
+
+#[derive(Debug)]
+pub struct App {
+    pub document: OrgDocument,
+    pub exit: bool,
+    /// What has the keyboard, see `UiMode`
+    pub mode: UiMode,
+    pub note: TextArea<'static>,
+    pub title: TextArea<'static>,
+    pub note_focus: NoteFocus,
+    pub scratchpad: TextArea<'static>,
+    pub scratchpad_mode: ScratchpadMode,
+    /// Scratchpad input of the note mode, kept apart from the task draft
+    pub note_scratchpad: TextArea<'static>,
+    pub draft_task: DraftTask,
+    /// Note drafts cleared with Ctrl+N, restored with Ctrl+O
+    pub draft_history: DraftHistory,
+    /// Restoring a draft over a non-empty one was requested and awaits confirmation
+    pub confirm_restore: bool,
+    /// The Editor draft is an edit of this note of the document ('e' in the
+    /// Viewer), saving it replaces the note instead of adding one
+    pub editing: Option<EditingNote>,
+    /// The scratchpad task looks like a duplicate, the next Enter adds it anyway
+    pub confirm_duplicate: bool,
+    /// Lines of the tasks added last, to catch accidental re-submissions
+    pub recent_tasks: VecDeque<(Instant, String)>,
+    /// One-off feedback shown in the header until the next key press
+    pub alerts: Alerts,
+    /// Column layout of the Tasks list
+    pub task_format: TaskFormat,
+    /// The document must not be changed, mutating keys only show why
+    pub read_only: Option<ReadOnlyReason>,
+    /// Sections between Tasks and Notes are shown in full below the Tasks
+    /// list, not only by name (toggled with 'w')
+    pub show_between: bool,
+    /// Only tasks of this project are shown in the Tasks list
+    pub project_filter: Option<String>,
+    pub current_tab: AppTab,
+    pub current_note_index: usize,
+    pub current_task_index: usize,
+    pub session_manager: SessionManager,
+    /// All session and document writes go through here
+    pub persistence: Persistence,
+    pub document_path: String,
+    /// What completing a task does, see `Configuration::on_complete`
+    pub on_complete: OnComplete,
+    /// What a new day does to `today:` tags, see `Configuration::rollover`
+    pub rollover: Rollover,
+    /// Width note content is wrapped at when saving, see
+    /// `Configuration::wrap_width`
+    pub wrap_width: usize,
+    /// Document completed tasks are archived to
+    pub archive_path: String,
+    /// Tags added to captured tasks of a project, see `apply_project_defaults`
+    pub project_defaults: DefaultsMap,
+    /// Full document being parsed in the background while only the tasks are loaded
+    pub pending_document: Option<Receiver<IoResult<OrgDocument>>>,
+    pub tag_suggestions: TagSuggestions,
+    pub autocompletion: AutocompletionWidget,          // For scratchpad
+    pub title_autocompletion: AutocompletionWidget,    // For note titles
+}
+
+/// What has the keyboard, the input router matches on it before anything
+/// else
+///
+/// The tab stays shown below every mode, popups stack on top of the mode
+/// they were opened in and give it back when they close.
+/// The mode is held once by the app, so the size of a popup does not matter.
+#[derive(Debug, Default)]
+#[allow(clippy::large_enum_variant)]
+pub enum UiMode {
+    /// Keys go to the current tab
+    #[default]
+    Normal,
+    /// The capture popup floats over the tab (Ctrl+T)
+    Scratchpad,
+    /// Lines of the read-only panes of the Viewer and Tasks tabs are being
+    /// selected ('v')
+    Selecting(LineSelection),
+    /// A popup takes every key until it is closed
+    Modal { kind: Modal, under: Box<UiMode> },
+}
+
+/// Popups that take every key while they are open
+#[derive(Debug)]
+pub enum Modal {
+    /// Problems of the document (quarantined lines, duplicate guids and
+    /// one-offs), Ctrl+L
+    Problems,
+    /// Long text pasted into the task scratchpad, waiting for the decision
+    /// whether to capture it as a note
+    PendingPaste(String),
+    /// Detail lines of the selected task ('d')
+    Details(TextArea<'static>),
+    /// New file name for the document (Alt+R)
+    Rename(TextArea<'static>),
+    /// Lines before the Tasks section of the document (Alt+P), with the
+    /// error of the last Ctrl+S if they were refused
+    Header {
+        editor: TextArea<'static>,
+        error: Option<String>,
+    },
+    /// Text to find and its replacement, one per line (Alt+F); `confirm` once
+    /// the change count was shown and the next Enter applies it
+    Replace {
+        prompt: TextArea<'static>,
+        confirm: bool,
+    },
+    /// The note being edited changed since the draft was started, the user
+    /// picks which version to keep before anything else
+    Conflict(DraftConflict),
+    /// Projects with their progress, to pick the filter from ('p')
+    ProjectPicker(ProjectPicker),
+    /// Date picker for the due date of the selected task ('c')
+    Calendar(Calendar),
+    /// Every key of the layer below ('?' or Alt+H), any key closes it
+    Help(HintContext),
+    /// Open tasks waiting on someone, grouped by person ('a')
+    Waiting(WaitingList),
+    /// Pairs of open tasks that look like duplicates, to keep, merge or
+    /// delete one by one (Shift+D)
+    Duplicates(DuplicateReview),
+    /// Tags of the selected task as one line ('t'), with the error of the
+    /// last Enter if they did not parse
+    Tags {
+        prompt: TextArea<'static>,
+        completion: AutocompletionWidget,
+        error: Option<String>,
+    },
+}
+
+impl UiMode {
+    /// The mode below all popups
+    pub fn base(&self) -> &UiMode {
+        match self {
+            UiMode::Modal { under, .. } => under.base(),
+            mode => mode,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AppTab {
+    Editor,
+    Viewer,
+    Tasks,
+}
+
+impl AppTab {
+    /// Tabs in display order, F1 to F3 jump to them
+    pub const ALL: [AppTab; 3] = [AppTab::Editor, AppTab::Viewer, AppTab::Tasks];
+
+    fn position(&self) -> usize {
+        match self {
+            AppTab::Editor => 0,
+            AppTab::Viewer => 1,
+            AppTab::Tasks => 2,
+        }
+    }
+
+    pub fn next(&self) -> AppTab {
+        Self::ALL[(self.position() + 1) % Self::ALL.len()].clone()
+    }
+
+    pub fn previous(&self) -> AppTab {
+        Self::ALL[(self.position() + Self::ALL.len() - 1) % Self::ALL.len()].clone()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum NoteFocus {
+    Title,
+    Content,
+}
+
+impl App {
+    pub fn new() -> IoResult<Self> {
+        let basefolder = crate::prepare_basefolder()?;
+        let document_path = crate::refile_path(&basefolder)?;
+        Ok(Self::open(&basefolder, document_path))
+    }
+
+    /// App over the document at `document_path`, with the session and the
+    /// archive of `basefolder`
+    pub fn open(basefolder: &str, document_path: String) -> Self {
+        // Load only the tasks up front so capturing is possible right away, the
+        // notes are parsed in the background and merged in by the event loop.
+        // Create an empty document if the file doesn't exist.
+        let (document, pending_document) = match OrgDocument::from_tasks_only(&document_path) {
+            Ok(doc) => {
+                let (sender, receiver) = mpsc::channel();
+                let path = document_path.clone();
+                thread::spawn(move || {
+                    let _ = sender.send(OrgDocument::from_lenient(&path).map(|(doc, _)| doc));
+                });
+                (doc, Some(receiver))
+            }
+            Err(_) => (OrgDocument::default(), None),
+        };
+
+        // Initialize session manager
+        let session_file_path = std::path::Path::new(basefolder).join(SESSION_FILE);
+        let mut session_manager =
+            SessionManager::new(session_file_path.to_str().unwrap().to_string());
+
+        // Load existing session or create default
+        let (session_state, session_error) = match session_manager.load_session() {
+            Ok(state) => (state, None),
+            Err(e) => (SessionState::default(), Some(e)),
+        };
+
+        // The preamble is part of the tasks-only load, the marker is known now
+        let read_only = document.readonly_reason(&document_path);
+        let mut app = Self::from_state(document, document_path, session_manager, session_state);
+        app.pending_document = pending_document;
+        app.read_only = read_only;
+        app.archive_path = Configuration::archive_path_in(basefolder);
+        match Configuration::project_defaults(basefolder) {
+            Ok(defaults) => app.project_defaults = defaults,
+            Err(e) => app.alerts.warn(format!("Project defaults ignored: {e}")),
+        }
+        if let Some(e) = session_error {
+            app.alerts.warn(format!("Failed to load session, starting fresh: {e}"));
+        }
+        if let Err(e) = app.roll_over_day() {
+            app.alerts.warn(format!("Daily rollover failed: {e}"));
+        }
+        app
+    }
+
+    /// Build the application around an already loaded document and session
+    pub fn from_state(
+        document: OrgDocument,
+        document_path: String,
+        session_manager: SessionManager,
+        session_state: SessionState,
+    ) -> Self {
+
+        // Restore UI state from session
+        let current_tab = session_state.current_tab;
+        // Ensure indices are within bounds for current document
+        // (the note index is checked once the notes have been loaded)
+        let current_note_index = if document.is_partial()
+            || session_state.current_note_index < document.notes.len()
+        {
+            session_state.current_note_index
+        } else {
+            0
+        };
+        let current_task_index = if session_state.current_task_index < document.tasks.len() {
+            session_state.current_task_index
+        } else {
+            0
+        };
+        let note_focus = session_state.note_focus;
+        let mode = if session_state.scratchpad_visible {
+            UiMode::Scratchpad
+        } else {
+            UiMode::Normal
+        };
+
+        // Restore draft content from session with cursor positions
+        let title = SessionManager::restore_textarea_with_cursor(
+            &session_state.title_content,
+            session_state.title_cursor_pos,
+        );
+        let note = SessionManager::restore_textarea_with_cursor(
+            &session_state.note_content,
+            session_state.note_cursor_pos,
+        );
+        let scratchpad = SessionManager::restore_textarea_with_cursor(
+            &session_state.scratchpad_content,
+            session_state.scratchpad_cursor_pos,
+        );
+
+        let note_scratchpad = SessionManager::restore_textarea_with_cursor(
+            &session_state.note_scratchpad_content,
+            session_state.note_scratchpad_cursor_pos,
+        );
+
+        let draft_task = DraftTask::with_text(&scratchpad.lines().join(" "));
+
+        // Extract tag suggestions from document
+        let tag_suggestions = document.collect_unique_tags(Configuration::suggest_from());
+        let autocompletion = AutocompletionWidget::new();
+        let title_autocompletion = AutocompletionWidget::new();
+        let (task_format, format_error) = match Configuration::task_format() {
+            Some(format) => match TaskFormat::from_str(&format) {
+                Ok(task_format) => (task_format, None),
+                Err(e) => (TaskFormat::default(), Some(e)),
+            },
+            None => (TaskFormat::default(), None),
+        };
+
+        let mut app = App {
+            document,
+            exit: false,
+            mode,
+            note,
+            title,
+            note_focus,
+            scratchpad,
+            scratchpad_mode: session_state.scratchpad_mode,
+            note_scratchpad,
+            draft_task,
+            draft_history: session_state.draft_history,
+            confirm_restore: false,
+            editing: session_state.editing,
+            confirm_duplicate: false,
+            recent_tasks: VecDeque::new(),
+            alerts: Alerts::new(AlertConfig::from_env()),
+            task_format,
+            read_only: None,
+            show_between: false,
+            project_filter: None,
+            current_tab,
+            current_note_index,
+            current_task_index,
+            session_manager,
+            persistence: Persistence::new(),
+            document_path,
+            on_complete: Configuration::on_complete(),
+            rollover: Configuration::rollover(),
+            wrap_width: Configuration::wrap_width(),
+            archive_path: Configuration::archive_path(),
+            project_defaults: DefaultsMap::default(),
+            pending_document: None,
+            tag_suggestions,
+            autocompletion,
+            title_autocompletion,
+        };
+
+        // Bring back the popup for a restored draft that ends in a tag token
+        if app.scratchpad_visible() {
+            app.refresh_scratchpad_autocompletion();
+        } else if app.note_focus == NoteFocus::Title {
+            app.refresh_title_autocompletion();
+        }
+        if let Some(e) = format_error {
+            app.alerts.warn(format!("{e}, using the default task format"));
+        }
+        // The notes of a partial document are checked once they are loaded
+        if !app.document.is_partial() {
+            app.check_editing_draft();
+        }
+        app
+    }
+    /// Start the application
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        // Infinite loop until variable set
+        while !self.exit {
+            // Pick up the fully loaded document once the background parse is done
+            self.poll_document_load();
+            // A session left running past midnight rolls over as well
+            self.roll_over_day()?;
+
+            // Iterate over frames and draw them one by one
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Wait for key events (with a tick so background loading can be merged)
+            if !ratatui::crossterm::event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            self.handle_event(ratatui::crossterm::event::read()?)?;
+        }
+        self.shutdown()
+    }
+
+    /// React to a key press or a paste and keep the session up to date
+    pub fn handle_event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Key(key_event) => self.handle_key_event(key_event)?,
+            Event::Paste(text) => self.handle_paste(&text),
+            _ => return Ok(()),
+        }
+
+        // Update session state after each keystroke
+        self.update_session_state();
+
+        // Check if we should save session (debounced)
+        if self.session_manager.should_save() {
+            self.session_manager.save_session(&self.persistence)?;
+        }
+        Ok(())
+    }
+
+    /// Save the session one last time and wait for all pending writes
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        // The document goes first, so the session knows whether it was saved
+        self.persistence.flush()?;
+        self.update_session_state();
+        self.session_manager.force_save(&self.persistence)?;
+        self.persistence.flush()?;
+        match self.persistence.dirty_state().error() {
+            Some(error) => Err(io::Error::other(format!("Document was not saved: {error}"))),
+            None => Ok(()),
+        }
+    }
+    /// Merge the background-loaded document if it is ready, without blocking
+    fn poll_document_load(&mut self) {
+        let Some(receiver) = &self.pending_document else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(result) => self.finish_document_load(result),
+            Err(TryRecvError::Empty) => {}
+            // Loader died (e.g. malformed notes), keep the partial document
+            Err(TryRecvError::Disconnected) => self.pending_document = None,
+        }
+    }
+
+    /// Clear or carry the `today:` tags of open tasks on the first start of a
+    /// new day, see `Configuration::rollover`
+    ///
+    /// The day is compared against the one in the session, a session without
+    /// one only gets today recorded.
+    pub fn roll_over_day(&mut self) -> io::Result<()> {
+        let today = Date::now();
+        let last_opened = self.session_manager.last_opened();
+        if last_opened == Some(today) {
+            return Ok(());
+        }
+        self.session_manager.set_last_opened(&today);
+        let Some(day) = last_opened else {
+            return Ok(());
+        };
+        if self.rollover == Rollover::Off || self.read_only.is_some() {
+            return Ok(());
+        }
+        self.ensure_document_loaded();
+        if self.document.is_partial() {
+            return Ok(());
+        }
+        let (count, verb) = match self.rollover {
+            Rollover::Carry => (self.document.carry_tag_key(TODAY_KEY, &day), "carried"),
+            _ => (self.document.clear_tag_key(TODAY_KEY), "cleared"),
+        };
+        if count == 0 {
+            return Ok(());
+        }
+        self.persistence.mark_dirty("rollover");
+        self.save_document()?;
+        log::info!("rollover from={day} to={today} tasks={count}");
+        self.alerts.info(format!("New day, {verb} the {TODAY_KEY}: tags of {count} task(s)"));
+        Ok(())
+    }
+
+    /// Wait for the background load to finish, required before full writes
+    pub fn ensure_document_loaded(&mut self) {
+        if let Some(receiver) = &self.pending_document {
+            match receiver.recv() {
+                Ok(result) => self.finish_document_load(result),
+                Err(_) => self.pending_document = None,
+            }
+        }
+    }
+
+    pub fn finish_document_load(&mut self, result: IoResult<OrgDocument>) {
+        self.pending_document = None;
+        if let Ok(full) = result {
+            self.document.merge_remaining(full);
+            if self.current_note_index >= self.document.notes.len() {
+                self.current_note_index = 0;
+            }
+            self.tag_suggestions = self.document.collect_unique_tags(Configuration::suggest_from());
+            self.check_editing_draft();
+        }
+    }
+
+    /// Make sure a restored edit draft is still based on the note in the
+    /// document, asking the user if the note changed since
+    fn check_editing_draft(&mut self) {
+        let Some(editing) = &self.editing else {
+            return;
+        };
+        match editing.freshness(&self.document) {
+            Freshness::Fresh(_) => {}
+            Freshness::Changed(note_index) => {
+                self.open_modal(Modal::Conflict(DraftConflict {
+                    note_index,
+                    show_both: false,
+                }));
+            }
+            Freshness::Missing => {
+                self.editing = None;
+                self.alerts.warn("The edited note is gone, saving adds the draft as a new note");
+            }
+        }
+    }
+
+    /// Routine about how to draw each frame in application
+    fn draw(&self, frame: &mut Frame) {
+        frame.render_widget(self, frame.area());
+    }
+
+    /// Show `kind` on top of the current mode
+    pub fn open_modal(&mut self, kind: Modal) {
+        let under = Box::new(std::mem::take(&mut self.mode));
+        self.mode = UiMode::Modal { kind, under };
+    }
+
+    /// Close the topmost popup, back to the mode it was opened in
+    pub fn close_modal(&mut self) {
+        self.mode = match std::mem::take(&mut self.mode) {
+            UiMode::Modal { under, .. } => *under,
+            mode => mode,
+        };
+    }
+
+    /// Whether the scratchpad is open, possibly below a popup
+    pub fn scratchpad_visible(&self) -> bool {
+        matches!(self.mode.base(), UiMode::Scratchpad)
+    }
+
+    /// Lines selected in the read-only panes, if any
+    pub fn selection(&self) -> Option<&LineSelection> {
+        match self.mode.base() {
+            UiMode::Selecting(selection) => Some(selection),
+            _ => None,
+        }
+    }
+
+    /// Warning if the scratchpad task was just added or matches an open task
+    pub fn duplicate_warning(&self) -> Option<String> {
+        let Ok(task) = self.draft_task.parsed() else {
+            return None;
+        };
+        if let Some((oneoff, idx)) = self.oneoff_conflict() {
+            return Some(format!(
+                "{oneoff} is already on \"{}\", Ctrl+G goes there, Enter adds anyway",
+                self.document.tasks[idx].description()
+            ));
+        }
+        let line = task.to_string();
+        let just_added = self
+            .recent_tasks
+            .iter()
+            .any(|(at, recent)| at.elapsed() < DUPLICATE_WINDOW && *recent == line);
+        if just_added {
+            return Some(format!(
+                "\"{}\" was just added, press Enter again to add it anyway",
+                task.description()
+            ));
+        }
+        let similar = self.document.find_similar_task(task)?;
+        Some(format!(
+            "Looks like a duplicate of \"{}\", press Enter again to add anyway",
+            self.document.tasks[similar]
+        ))
+    }
+
+    /// One-off tag of the scratchpad task that an open task already has,
+    /// with the index of that task
+    pub fn oneoff_conflict(&self) -> Option<(String, usize)> {
+        let (oneoff, idx) = self.draft_task.oneoff_conflict(&self.tag_suggestions.open_oneoffs)?;
+        // The index is as old as the suggestions, tasks may have moved since
+        let still_there = self.document.tasks.get(idx).is_some_and(|task| {
+            !task.is_completed() && task.tags().iter().any(|t| t.oneoff_tags().contains(&oneoff))
+        });
+        if still_there {
+            return Some((oneoff, idx));
+        }
+        let idx = *self.document.open_oneoffs().get(&oneoff)?;
+        Some((oneoff, idx))
+    }
+
+    /// Show the open task that already has a one-off tag of the scratchpad
+    /// task instead of adding it, the draft stays in the scratchpad
+    pub fn go_to_oneoff_conflict(&mut self) {
+        let Some((oneoff, idx)) = self.oneoff_conflict() else {
+            return;
+        };
+        self.mode = UiMode::Normal;
+        self.autocompletion.hide();
+        if !self.visible_tasks().contains(&idx) {
+            self.project_filter = None;
+        }
+        self.current_task_index = idx;
+        self.switch_to(AppTab::Tasks);
+        self.alerts.info(format!("This task has {oneoff} already, Ctrl+T brings the draft back"));
+    }
+
+    /// Add the task of the scratchpad to the document, an invalid draft
+    /// stays in the scratchpad with its error shown below it
+    pub fn add_draft_task(&mut self) -> io::Result<()> {
+        let mut t = match self.draft_task.take() {
+            Ok(task) => task,
+            // The draft stays for fixing, a blank one is no error
+            Err(error) => {
+                if !self.draft_task.text().trim().is_empty() {
+                    self.alerts.warn(format!("Task not added: {error}"));
+                }
+                return Ok(());
+            }
+        };
+        // Remembered as typed, that is what a re-submission looks like
+        self.remember_recent_task(&t);
+        let added = apply_project_defaults(&mut t, &self.project_defaults);
+        if !added.is_empty() {
+            self.alerts.info(format!("Added {} from the project defaults", added.join(" ")));
+        }
+        log::debug!("add_task line={:?}", t.to_string());
+        // Save to file immediately, only touching the Tasks section
+        // while the notes are still loading
+        if self.document.is_partial() {
+            self.document.push_task(t);
+            self.persistence.mark_dirty("add_task");
+            self.append_task_line(self.document.tasks.last().unwrap());
+        } else {
+            self.document.push_task(t);
+            self.persistence.mark_dirty("add_task");
+            self.save_document()?;
+        }
+
+        self.scratchpad = TextArea::default();
+
+        // Update tag suggestions after adding new task
+        self.tag_suggestions = self.document.collect_unique_tags(Configuration::suggest_from());
+        Ok(())
+    }
+
+    pub fn paste_into_task(&mut self, text: &str) {
+        self.scratchpad.insert_str(sanitize_line(text));
+        self.draft_task.update(&self.scratchpad.lines().join(" "));
+        self.refresh_scratchpad_autocompletion();
+    }
+
+    /// Characters of the note title, lines counted as joined by a space
+    pub fn title_len(&self) -> usize {
+        self.title.lines().join(" ").chars().count()
+    }
+
+    /// Type into the title, which stays a single line of at most
+    /// `TITLE_MAX_LEN` characters
+    ///
+    /// Keys that would break the line or grow a full title are undone.
+    pub fn input_title(&mut self, key_event: KeyEvent) {
+        let (lines, len) = (self.title.lines().len(), self.title_len());
+        if !self.title.input(key_event) {
+            return;
+        }
+        let grown = self.title_len() > len.max(TITLE_MAX_LEN);
+        if self.title.lines().len() > lines || grown {
+            self.title.undo();
+        }
+        if grown {
+            self.alerts.warn(format!("Titles are at most {TITLE_MAX_LEN} characters long"));
+        }
+    }
+
+    /// Paste text into the title as a single line, cut off where the title
+    /// would get longer than `TITLE_MAX_LEN` characters
+    pub fn paste_into_title(&mut self, text: &str) {
+        let room = TITLE_MAX_LEN.saturating_sub(self.title_len());
+        let (line, cut) = single_line(text, room);
+        self.title.insert_str(line);
+        if cut {
+            self.alerts.warn(format!("Pasted title cut to {TITLE_MAX_LEN} characters"));
+        }
+    }
+
+    /// Capture pasted text as a note, the first line being its title
+    pub fn capture_paste_as_note(&mut self, text: &str) -> io::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        let Some((title, content)) = note_parts(text) else {
+            return Ok(());
+        };
+        let note = note_from_capture(&title, &content, Configuration::code_blocks());
+        self.store_note(note)?;
+        self.alerts.info(format!("Captured \"{title}\" as a note"));
+        Ok(())
+    }
+
+    /// Give notes with a duplicate or missing guid a new one, after backing
+    /// up the document as it is on disk
+    pub fn repair_guids(&mut self) -> io::Result<()> {
+        self.ensure_document_loaded();
+        let changes = self.document.repair_guids();
+        if changes.is_empty() {
+            self.alerts.info("No guids to repair");
+            return Ok(());
+        }
+        // Let pending writes land first so that the backup is up to date
+        self.persistence.flush()?;
+        let backup = cli::backup_path(&self.document_path);
+        std::fs::copy(&self.document_path, &backup)?;
+        self.persistence.mark_dirty("repair_guids");
+        self.save_document()?;
+        self.alerts.info(format!(
+            "Repaired {} guid(s), previous version in {backup}",
+            changes.len()
+        ));
+        Ok(())
+    }
+
+    fn remember_recent_task(&mut self, task: &Task) {
+        if self.recent_tasks.len() >= RECENT_TASKS {
+            self.recent_tasks.pop_front();
+        }
+        self.recent_tasks.push_back((Instant::now(), task.to_string()));
+    }
+
+    /// Rename the document file in the base folder and remember the new name
+    /// for the next start, the session follows the document
+    pub fn rename_document(&mut self, name: &str) -> io::Result<()> {
+        // The background load and pending writes still use the old name
+        self.ensure_document_loaded();
+        self.persistence.flush()?;
+        let path = std::path::Path::new(&self.document_path);
+        let (Some(basefolder), Some(from)) = (path.parent(), path.file_name()) else {
+            return Ok(());
+        };
+        match migrate::rename_document(basefolder, &from.to_string_lossy(), name) {
+            Ok(renamed) => {
+                self.document_path = renamed.to_string_lossy().into_owned();
+                self.update_session_state();
+                self.session_manager.force_save(&self.persistence)?;
+                if let Some((var, _)) = Configuration::document_var() {
+                    self.alerts.warn(format!(
+                        "Renamed, but {var} still decides, set it to {}",
+                        name.trim()
+                    ));
+                } else {
+                    self.alerts.info(format!("Renamed document to {}", name.trim()));
+                }
+            }
+            Err(e) => self.alerts.error(format!("Rename failed: {e}")),
+        }
+        Ok(())
+    }
+
+    /// Replace the lines before the Tasks section and save the document,
+    /// `lines` having passed `validate_preamble`
+    ///
+    /// Adding the read-only marker makes the document read-only right away.
+    pub fn save_preamble(&mut self, lines: Vec<String>) -> io::Result<()> {
+        self.ensure_document_loaded();
+        if let Err(e) = self.document.set_preamble(lines) {
+            self.alerts.error(e);
+            return Ok(());
+        }
+        self.persistence.mark_dirty("set_preamble");
+        self.save_document()?;
+        self.read_only = self.document.readonly_reason(&self.document_path);
+        match self.read_only {
+            Some(reason) => self.alerts.warn(format!("Saved the file header, now {reason}")),
+            None => self.alerts.info("Saved the file header"),
+        }
+        Ok(())
+    }
+
+    /// Lines of the read-only panes of the current tab, in selection order
+    ///
+    /// The Viewer's note content is followed by its metadata, the Tasks tab
+    /// offers the details of the selected task.
+    pub fn selectable_lines(&self) -> Vec<String> {
+        match self.current_tab {
+            AppTab::Viewer => match self.document.notes.get(self.current_note_index) {
+                Some(note) => {
+                    let mut lines = note.content().to_vec();
+                    lines.extend(note_metadata_lines(note, &self.document));
+                    lines
+                }
+                None => Vec::new(),
+            },
+            AppTab::Tasks => self
+                .document
+                .tasks
+                .get(self.current_task_index)
+                .map(task_metadata_lines)
+                .unwrap_or_default(),
+            AppTab::Editor => Vec::new(),
+        }
+    }
+
+    /// Complete the selected task, handled according to the configured
+    /// `OnComplete` behaviour
+    pub fn complete_current_task(&mut self) -> io::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        // Moving or removing tasks requires a full write
+        self.ensure_document_loaded();
+
+        let archived = self
+            .document
+            .complete_tasks(&[self.current_task_index], self.on_complete);
+        self.persistence.mark_dirty("complete_task");
+        if !archived.is_empty() {
+            self.persistence.update_document(self.archive_path.clone(), move |archive| {
+                let archive = match archive {
+                    Some(bytes) => String::from_utf8(bytes).map_err(io::Error::other)?,
+                    None => String::from_utf8(OrgDocument::default().to_bytes()?)
+                        .map_err(io::Error::other)?,
+                };
+                Ok(OrgDocument::insert_tasks(&archive, &archived)?.into_bytes())
+            });
+        }
+        self.save_document()?;
+        // Completed tasks may no longer count for the suggestions
+        self.tag_suggestions = self.document.collect_unique_tags(Configuration::suggest_from());
+
+        self.select_visible_task();
+        Ok(())
+    }
+
+    /// Turn the selected task into a plain line of the first section between
+    /// Tasks and Notes, a new `## Waiting For` section if there is none
+    pub fn move_task_to_section(&mut self) -> io::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        // The sections are only known once the whole document is loaded
+        self.ensure_document_loaded();
+        let header = match self.document.between().first() {
+            Some(header) => header.clone(),
+            None => WAITING_FOR.to_string(),
+        };
+        let Some(task) = self.document.move_task_to_section(self.current_task_index, &header)
+        else {
+            return Ok(());
+        };
+        self.persistence.mark_dirty("move_task_to_section");
+        self.save_document()?;
+        self.show_between = true;
+        self.alerts.info(format!(
+            "Moved \"{}\" to {}",
+            task.description(),
+            header.trim_start_matches("## ")
+        ));
+        self.select_visible_task();
+        Ok(())
+    }
+
+    /// Go through the open tasks that look like duplicates of each other,
+    /// see `OrgDocument::find_duplicate_candidates`
+    pub fn review_duplicates(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        self.ensure_document_loaded();
+        let pairs = self.document.find_duplicate_candidates();
+        if pairs.is_empty() {
+            self.alerts.info("No duplicate tasks found");
+            return;
+        }
+        self.open_modal(Modal::Duplicates(DuplicateReview::new(pairs)));
+    }
+
+    /// Filter and order of the Tasks list
+    pub fn task_view(&self) -> FilterSet {
+        FilterSet {
+            filters: self.project_filter.iter().cloned().map(TaskFilter::Project).collect(),
+            sort: TaskSort::Document,
+        }
+    }
+
+    /// Indices of the tasks the Tasks list shows, in document order
+    pub fn visible_tasks(&self) -> Vec<usize> {
+        self.task_view().apply(&self.document.tasks)
+    }
+
+    /// Write the tasks the Tasks list shows as plain text, into `VIEW_EXPORT`
+    /// next to the document
+    pub fn export_task_view(&mut self) {
+        self.ensure_document_loaded();
+        let view = self.task_view();
+        let tasks: Vec<&Task> =
+            self.visible_tasks().into_iter().map(|idx| &self.document.tasks[idx]).collect();
+        let text = export_view(&tasks, &view, &Date::now());
+        let path = Path::new(&self.document_path).with_file_name(VIEW_EXPORT);
+        match std::fs::write(&path, text) {
+            Ok(()) => self
+                .alerts
+                .info(format!("Exported {} task(s) to {}", tasks.len(), path.display())),
+            Err(e) => self.alerts.error(format!("Export failed: {e}")),
+        }
+    }
+
+    /// Move the task selection to a task the Tasks list shows: the selected
+    /// one, the next one shown or else the last one shown
+    ///
+    /// A project filter that no task passes anymore is dropped.
+    pub fn select_visible_task(&mut self) {
+        let mut visible = self.visible_tasks();
+        if visible.is_empty()
+            && let Some(project) = self.project_filter.take()
+        {
+            self.alerts.info(format!("No tasks left in {project}, showing all tasks"));
+            visible = self.visible_tasks();
+        }
+        self.current_task_index = visible
+            .iter()
+            .copied()
+            .find(|&idx| idx >= self.current_task_index)
+            .or(visible.last().copied())
+            .unwrap_or(0);
+    }
+
+    /// Show `tab`, clamping its selection to what the document holds now
+    ///
+    /// Indices can be stale after tasks were completed or archived, the note
+    /// index is left alone while the notes are still loading.
+    pub fn switch_to(&mut self, tab: AppTab) {
+        match tab {
+            AppTab::Viewer if !self.document.is_partial() => {
+                let last = self.document.notes.len().saturating_sub(1);
+                self.current_note_index = self.current_note_index.min(last);
+            }
+            AppTab::Tasks => self.select_visible_task(),
+            _ => {}
+        }
+        self.current_tab = tab;
+        // A selection belongs to the panes of the tab it was started in
+        if let UiMode::Selecting(_) = self.mode {
+            self.mode = UiMode::Normal;
+        }
+        if matches!(self.current_tab, AppTab::Editor) && self.note_focus == NoteFocus::Title {
+            self.refresh_title_autocompletion();
+        } else {
+            self.title_autocompletion.hide();
+        }
+    }
+
+    /// Whether any of the editors holds text that was not turned into a
+    /// note or task yet
+    fn has_drafts(&self) -> bool {
+        let has_text = |area: &TextArea| area.lines().iter().any(|l| !l.trim().is_empty());
+        !self.current_draft().is_empty()
+            || has_text(&self.scratchpad)
+            || has_text(&self.note_scratchpad)
+    }
+
+    /// Current title and content of the Editor
+    fn current_draft(&self) -> NoteDraft {
+        NoteDraft::new(self.title.lines(), self.note.lines())
+    }
+
+    /// Clear the Editor for a new note, stashing a non-empty draft first
+    pub fn new_note(&mut self) {
+        if self.draft_history.push(self.current_draft()) {
+            self.alerts.info("Draft stashed, restore it with Ctrl+O");
+        }
+        self.title = TextArea::default();
+        self.note = TextArea::default();
+        self.note_focus = NoteFocus::Title;
+        self.editing = None;
+        self.refresh_title_autocompletion();
+    }
+
+    /// Load the note shown in the Viewer into the Editor, saving it then
+    /// replaces the note; the draft in the Editor is stashed first
+    pub fn edit_current_note(&mut self) {
+        if self.document.is_partial() || self.refuse_read_only() {
+            return;
+        }
+        let Some(note) = self.document.notes.get(self.current_note_index) else {
+            return;
+        };
+        let editing = EditingNote::start(note);
+        let (title, content) = note_lines(note);
+        self.new_note();
+        self.load_into_editor(title, &content);
+        self.editing = Some(editing);
+        self.switch_to(AppTab::Editor);
+    }
+
+    /// Move the note shown in the Viewer, with the notes nested below it,
+    /// past its previous or next sibling; the Viewer keeps showing it
+    pub fn move_current_note(&mut self, up: bool) -> io::Result<()> {
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        self.ensure_document_loaded();
+        let from = self.current_note_index;
+        let to = match up {
+            true => self.document.previous_sibling_note(from),
+            // Past the whole block of the next sibling, counted without ours
+            false => self
+                .document
+                .next_sibling_note(from)
+                .map(|next| from + self.document.note_block(next).len()),
+        };
+        let Some(to) = to else {
+            return Ok(());
+        };
+        if let Err(e) = self.document.move_note(from, to) {
+            self.alerts.error(e);
+            return Ok(());
+        }
+        self.current_note_index = to;
+        self.persistence.mark_dirty("move_note");
+        self.save_document()
+    }
+
+    /// Replace title and content of the Editor
+    pub fn load_into_editor(&mut self, title: String, content: &[String]) {
+        self.title = TextArea::from([title]);
+        self.title.move_cursor(CursorMove::End);
+        self.note = SessionManager::restore_textarea_with_cursor(content, (0, 0));
+        self.note_focus = NoteFocus::Title;
+        self.refresh_title_autocompletion();
+    }
+
+    /// Bring back the most recently stashed draft
+    ///
+    /// A non-empty draft in the Editor is only replaced after a second Ctrl+O,
+    /// and is stashed in turn so that nothing gets lost.
+    pub fn restore_previous_draft(&mut self) {
+        if self.draft_history.is_empty() {
+            self.alerts.info("No stashed drafts");
+            return;
+        }
+        let current = self.current_draft();
+        if !current.is_empty() && !self.confirm_restore {
+            self.confirm_restore = true;
+            self.alerts.warn("Press Ctrl+O again to swap in the stashed draft");
+            return;
+        }
+        self.confirm_restore = false;
+
+        let Some(previous) = self.draft_history.pop() else {
+            return;
+        };
+        self.editing = None;
+        self.draft_history.push(current);
+        self.title = SessionManager::restore_textarea_with_cursor(&previous.title, (0, 0));
+        self.note = SessionManager::restore_textarea_with_cursor(&previous.content, (0, 0));
+        self.title.move_cursor(tui_textarea::CursorMove::End);
+        self.note_focus = NoteFocus::Title;
+        self.refresh_title_autocompletion();
+        self.alerts.info(format!(
+            "Draft restored ({} more stashed)",
+            self.draft_history.len()
+        ));
+    }
+
+    /// Queue a write of the complete document
+    ///
+    /// A failed write does not end the application, it is kept in the dirty
+    /// state of the persistence coordinator and shown in the header.
+    pub fn save_document(&mut self) -> io::Result<()> {
+        if self.read_only.is_some() {
+            return Ok(());
+        }
+        let contents = self.document.to_bytes_wrapped(self.wrap_width)?;
+        self.persistence.save_document(&self.document_path, contents);
+        let (tasks, notes) = self.document.len();
+        log::info!("save path={} tasks={tasks} notes={notes}", self.document_path);
+        Ok(())
+    }
+
+    /// Insert a task line into the Tasks section of the file on disk without
+    /// rewriting the rest, used while only the tasks are loaded
+    fn append_task_line(&self, task: &Task) {
+        if self.read_only.is_some() {
+            return;
+        }
+        let line = task.to_string();
+        self.persistence.update_document(&self.document_path, move |document| {
+            let document = document.ok_or_else(|| io::Error::other("Document is missing"))?;
+            let document = String::from_utf8(document).map_err(io::Error::other)?;
+            Ok(OrgDocument::insert_tasks(&document, &[line])?.into_bytes())
+        });
+    }
+
+    /// Re-evaluate the scratchpad popup against the current draft text
+    pub fn refresh_scratchpad_autocompletion(&mut self) {
+        let current_text = self.scratchpad_completion_text();
+        self.autocompletion.refresh(&current_text, &self.tag_suggestions);
+    }
+
+    /// Text the scratchpad popup completes: the whole task draft, or the
+    /// line under the cursor in note mode
+    pub fn scratchpad_completion_text(&self) -> String {
+        match self.scratchpad_mode {
+            ScratchpadMode::Task => self.scratchpad.lines().join(" "),
+            ScratchpadMode::Note => {
+                let (row, _) = self.note_scratchpad.cursor();
+                self.note_scratchpad.lines()[row].clone()
+            }
+        }
+    }
+
+    /// Re-evaluate the title popup against the current title text
+    pub fn refresh_title_autocompletion(&mut self) {
+        let current_text = self.title.lines().join(" ");
+        self.title_autocompletion.refresh(&current_text, &self.tag_suggestions);
+    }
+
+    /// Tell the user that the document must not be changed, if so
+    ///
+    /// Returns whether the change has to be dropped. Drafts are kept, so that
+    /// they can still be copied elsewhere.
+    pub fn refuse_read_only(&mut self) -> bool {
+        match self.read_only {
+            Some(reason) => {
+                self.alerts.warn(format!("Read-only document ({reason}), nothing was changed"));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Save the note mode scratchpad as a note, the first line being the
+    /// title, without touching the Editor's draft
+    pub fn capture_note(&mut self) -> io::Result<()> {
+        let lines = self.note_scratchpad.lines();
+        let Some((title, content)) = lines.split_first() else {
+            return Ok(());
+        };
+        if title.trim().is_empty() && content.iter().all(|line| line.trim().is_empty()) {
+            return Ok(());
+        }
+        let note = note_from_capture(title, content, Configuration::code_blocks());
+        if self.refuse_read_only() {
+            return Ok(());
+        }
+        self.store_note(note)?;
+
+        self.note_scratchpad = TextArea::default();
+        self.autocompletion.hide();
+        Ok(())
+    }
+
+    /// Add a note captured from the scratchpad to the document and save it
+    fn store_note(&mut self, note: Note) -> io::Result<()> {
+        // Writing the note needs the complete document
+        self.ensure_document_loaded();
+        log::debug!("add_note title={:?}", note.title());
+        self.document.push_note(note);
+        self.persistence.mark_dirty("add_note");
+        self.save_document()?;
+        self.tag_suggestions = self.document.collect_unique_tags(Configuration::suggest_from());
+        Ok(())
+    }
+
+    pub fn save_note(&mut self) -> io::Result<()> {
+        // A heading is a single line, restored drafts may still span more
+        let title = sanitize_line(&self.title.lines().join(" "));
+        let content: Vec<String> = self.note.lines().iter().map(|s| s.to_string()).collect();
+        if title.chars().count() > TITLE_MAX_LEN {
+            self.alerts.error(format!(
+                "The title is {} characters long, shorten it to {TITLE_MAX_LEN} to save",
+                title.chars().count()
+            ));
+            return Ok(());
+        }
+
+        // Check if we have any meaningful content
+        let has_title = !title.trim().is_empty();
+        let has_content = content.iter().any(|line| !line.trim().is_empty());
+
+        if (has_title || has_content) && !self.refuse_read_only() {
+            // Writing the note needs the complete document
+            self.ensure_document_loaded();
+
+            let note = note_from_capture(&title, &content, Configuration::code_blocks());
+            let edited = match &self.editing {
+                Some(editing) => editing.freshness(&self.document),
+                None => Freshness::Missing,
+            };
+            match edited {
+                Freshness::Fresh(index) => self.document.update_note(index, note),
+                Freshness::Changed(note_index) => {
+                    // Changed while editing, do not overwrite it unasked
+                    self.open_modal(Modal::Conflict(DraftConflict {
+                        note_index,
+                        show_both: false,
+                    }));
+                    return Ok(());
+                }
+                Freshness::Missing => self.document.push_note(note),
+            }
+            self.editing = None;
+            self.persistence.mark_dirty("save_note");
+
+            // Save to file
+            self.save_document()?;
+
+            // Clear the text areas
+            self.title = TextArea::default();
+            self.note = TextArea::default();
+            self.note_focus = NoteFocus::Title;
+
+            // Update tag suggestions after adding new note
+            self.tag_suggestions = self.document.collect_unique_tags(Configuration::suggest_from());
+        }
+        Ok(())
+    }
+
+    /// Update session state with current application state
+    pub fn update_session_state(&mut self) {
+        let has_unsaved = self.persistence.dirty_state().is_dirty() || self.has_drafts();
+
+        self.session_manager.update_state(
+            &self.current_tab,
+            self.current_note_index,
+            self.current_task_index,
+            &self.note_focus,
+            self.scratchpad_visible(),
+            self.scratchpad_mode,
+            &self.title,
+            &self.note,
+            &self.scratchpad,
+            &self.note_scratchpad,
+            &self.draft_history,
+            self.editing.as_ref(),
+            &self.document_path,
+            has_unsaved,
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use orgflow::{
+    ChangeRecord, ChangeTarget, Configuration, Date, FilterSet, LintIssue, OrgDocument,
+    ReplaceOptions, ReplaceScope, Task, TaskFilter, TaskSort, apply_batch, export_view,
+};
+
+use crate::migrate;
+use crate::recovery::{self, RECOVERED_DIR};
+use crate::session::{SESSION_FILE, corrupt_path};
+
+const USAGE: &str = "Usage: orgflow [--quiet] [batch | check [--fix] | contexts | list |
+                migrate --to <dir> | sed | session dump [<file>] |
+                session restore <file>]
+
+  --quiet  Only print what was asked for and errors, not what was done.
+           Exit codes: 0 success, 1 nothing matched or problems found,
+           2 usage error, 3 file or document error.
+
+  batch    Apply operations read from stdin, one per line:
+             add <task>        append a new task
+             done <n>          complete task n
+             tag <n> <tags>    add tags like +project or @context to task n
+             del <n>           delete task n
+           Task numbers count from 1 and refer to the document as it was
+           before the batch. Either all operations are saved or none.
+  check    Report lines that cannot be parsed, tasks whose dates do not fit,
+           notes sharing a guid and open tasks sharing a one-off tag like
+           !ticket-123.
+           With --fix, notes with a duplicate or missing guid get a new one,
+           after copying the document to refile.org.bak.
+  contexts Print the open tasks grouped by context as a checklist.
+             --only @errands,@town   only these contexts
+             --format json           print JSON instead of text
+  list     Print the tasks as a plain text checklist, headed by the view
+           and the date.
+             --project +garden   only tasks of this project
+             --context @errands  only tasks with this context
+             --open              only tasks that are not completed
+             --sort priority     document (default), priority, created, due or project
+             --format json       print JSON instead of text
+             --output view.txt   write to this file instead
+  migrate  Move the whole base folder to <dir>. All files are copied and
+           compared before the originals are deleted; an interrupted move
+           continues when run again. Close orgflow before migrating.
+  sed      Replace <find> with <replace> in task descriptions and note
+           content: sed <find> <replace> [options]
+             --dry-run        only print what would change
+             --ignore-case    match upper and lower case alike
+             --whole-word     do not match inside longer words
+             --tasks/--notes  only look at tasks or notes
+             --tags           also replace in tags like +project
+  session  dump writes the drafts of session.json (or of the copy moved aside
+           when it could not be loaded, or of <file>) as text files into
+           recovered/ in the base folder, even if the session no longer loads.
+           restore <file> loads a text file into the note draft, keeping the
+           previous note draft in recovered/. Close orgflow before restoring.";
+
+/// Why a subcommand failed, telling scripts apart by the exit code
+#[derive(Debug)]
+pub enum CliError {
+    /// Nothing matched or problems were found, exit code 1
+    Failed(String),
+    /// Unknown subcommand or option, or a batch line that makes no sense,
+    /// exit code 2
+    Usage(String),
+    /// A file could not be read or written, or the document not be parsed,
+    /// exit code 3
+    Io(io::Error),
+}
+
+impl CliError {
+    pub fn code(&self) -> u8 {
+        match self {
+            CliError::Failed(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Io(_) => 3,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Failed(msg) | CliError::Usage(msg) => write!(f, "{msg}"),
+            CliError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+/// Set by `--quiet`, see `inform`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Print what a subcommand did, unless `--quiet` was given
+fn inform(message: impl Display) {
+    if !QUIET.load(Ordering::Relaxed) {
+        println!("{message}");
+    }
+}
+
+fn usage() -> CliError {
+    CliError::Usage(USAGE.to_string())
+}
+
+/// Run a subcommand given on the command line instead of the interface
+pub fn run(args: &[String]) -> Result<(), CliError> {
+    let quiet = |arg: &String| arg == "--quiet" || arg == "-q";
+    QUIET.store(args.iter().any(quiet), Ordering::Relaxed);
+    let args: Vec<String> = args.iter().filter(|arg| !quiet(arg)).cloned().collect();
+    let Some(command) = args.first() else {
+        return Err(usage());
+    };
+    match command.as_str() {
+        "batch" if args.len() == 1 => {
+            let mut script = String::new();
+            io::stdin().read_to_string(&mut script)?;
+            batch(&script)
+        }
+        "check" if args.len() == 1 => check(false),
+        "check" if args.len() == 2 && args[1] == "--fix" => check(true),
+        "contexts" => contexts(&args[1..]),
+        "list" => list(&args[1..]),
+        "migrate" if args.len() == 3 && args[1] == "--to" => migrate(Path::new(&args[2])),
+        "sed" => sed(&args[1..]),
+        "session" if args.len() <= 3 && args.get(1).is_some_and(|a| a == "dump") => {
+            session_dump(args.get(2).map(Path::new))
+        }
+        "session" if args.len() == 3 && args[1] == "restore" => {
+            session_restore(Path::new(&args[2]))
+        }
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(usage()),
+    }
+}
+
+/// Apply a batch script to the refile document, written once on success
+fn batch(script: &str) -> Result<(), CliError> {
+    let basefolder = crate::prepare_basefolder()?;
+    let path = crate::refile_path(&basefolder)?;
+    let mut document = if Path::new(&path).exists() {
+        OrgDocument::from(&path)?
+    } else {
+        OrgDocument::default()
+    };
+
+    refuse_read_only(&document, &path)?;
+    let defaults = Configuration::project_defaults(&basefolder)
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+    // Nothing is written on error, the transaction rolls back
+    let mut transaction = document.begin()?;
+    let applied = apply_batch(&mut transaction, script, &defaults).map_err(CliError::Usage)?;
+    if applied > 0 {
+        transaction.commit(&path)?;
+    }
+    inform(format!("Applied {applied} operation(s) to {path}"));
+    Ok(())
+}
+
+/// Report the problems of the refile document, repairing guids with `fix`
+///
+/// Fails if problems remain, so that scripts can tell a clean document.
+fn check(fix: bool) -> Result<(), CliError> {
+    let basefolder = crate::prepare_basefolder()?;
+    let path = crate::refile_path(&basefolder)?;
+    if !Path::new(&path).exists() {
+        inform(format!("{path} does not exist yet"));
+        return Ok(());
+    }
+    let (mut document, _) = OrgDocument::from_lenient(&path)?;
+
+    if fix {
+        refuse_read_only(&document, &path)?;
+        let mut transaction = document.begin()?;
+        let changes = transaction.repair_guids();
+        if !changes.is_empty() {
+            let backup = backup_path(&path);
+            std::fs::copy(&path, &backup)?;
+            transaction.commit(&path)?;
+            print_issues("Fixed", &changes);
+            inform(format!("Previous version saved as {backup}"));
+        }
+    }
+
+    let issues = document.lint();
+    if issues.is_empty() {
+        inform(format!("No problems found in {path}"));
+        return Ok(());
+    }
+    print_issues("Problem", &issues);
+    let hint = if fix { "" } else { ", `orgflow check --fix` repairs guids" };
+    Err(CliError::Failed(format!("{} problem(s) in {path}{hint}", issues.len())))
+}
+
+/// Fail for a document that must not be changed, before anything is written
+fn refuse_read_only(document: &OrgDocument, path: &str) -> io::Result<()> {
+    match document.readonly_reason(path) {
+        Some(reason) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{path} is read-only ({reason}), nothing was changed"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Where the document is copied before it gets repaired
+pub fn backup_path(path: &str) -> String {
+    format!("{path}.bak")
+}
+
+fn print_issues(label: &str, issues: &[LintIssue]) {
+    for issue in issues {
+        println!("{label} at line {}: {}", issue.line, issue.message);
+        for line in issue.content.lines() {
+            println!("    {line}");
+        }
+    }
+}
+
+/// Print the open tasks grouped by context, text or JSON
+fn contexts(args: &[String]) -> Result<(), CliError> {
+    let mut only: Option<Vec<String>> = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next().map(String::as_str)) {
+            ("--only", Some(list)) => {
+                only = Some(list.split(',').map(|c| c.trim().to_string()).collect())
+            }
+            ("--format", Some("json")) => json = true,
+            ("--format", Some("text")) => json = false,
+            _ => return Err(usage()),
+        }
+    }
+
+    let basefolder = crate::prepare_basefolder()?;
+    let path = crate::refile_path(&basefolder)?;
+    let document = if Path::new(&path).exists() {
+        OrgDocument::from_lenient(&path)?.0
+    } else {
+        OrgDocument::default()
+    };
+    let mut groups = document.tasks_by_context();
+    if let Some(only) = &only {
+        groups.retain(|context, _| only.contains(context));
+    }
+    if groups.is_empty() {
+        return Err(CliError::Failed(format!("No open tasks in these contexts in {path}")));
+    }
+
+    if json {
+        let groups: serde_json::Map<String, serde_json::Value> = groups
+            .iter()
+            .map(|(context, tasks)| {
+                let tasks = tasks.iter().map(|task| task_json(task)).collect();
+                (context.clone(), serde_json::Value::Array(tasks))
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&groups).map_err(io::Error::other)?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    for (idx, (context, tasks)) in groups.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        println!("{context}");
+        for task in tasks {
+            println!("[ ] {}", task.description());
+            for detail in task.details() {
+                println!("    {detail}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A task as printed by `--format json`, with the line it was read from
+fn task_json(task: &Task) -> serde_json::Value {
+    serde_json::json!({
+        "completed": task.is_completed(),
+        "description": task.description(),
+        "priority": task.priority_level().as_ref().map(|p| p.to_string()),
+        "tags": task.tags().as_ref().map(|t| t.to_string()),
+        "details": task.details(),
+        "line": task.source_line(),
+    })
+}
+
+/// Print the tasks of a view like the Tasks tab exports it, or as JSON, or
+/// write them to a file
+fn list(args: &[String]) -> Result<(), CliError> {
+    let mut view = FilterSet::default();
+    let mut output = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--open" {
+            view.filters.push(TaskFilter::Open);
+            continue;
+        }
+        match (arg.as_str(), args.next()) {
+            ("--project", Some(project)) => view.filters.push(TaskFilter::Project(project.clone())),
+            ("--context", Some(context)) => view.filters.push(TaskFilter::Context(context.clone())),
+            ("--sort", Some(sort)) => {
+                view.sort = TaskSort::from_str(sort).map_err(CliError::Usage)?
+            }
+            ("--output", Some(path)) => output = Some(path.clone()),
+            ("--format", Some(format)) if format == "json" => json = true,
+            ("--format", Some(format)) if format == "text" => json = false,
+            _ => return Err(usage()),
+        }
+    }
+
+    let basefolder = crate::prepare_basefolder()?;
+    let path = crate::refile_path(&basefolder)?;
+    let document = if Path::new(&path).exists() {
+        OrgDocument::from_lenient(&path)?.0
+    } else {
+        OrgDocument::default()
+    };
+    let tasks: Vec<&Task> =
+        view.apply(&document.tasks).into_iter().map(|idx| &document.tasks[idx]).collect();
+    let text = match json {
+        true => {
+            let tasks = tasks.iter().map(|task| task_json(task)).collect();
+            let json = serde_json::to_string_pretty(&serde_json::Value::Array(tasks))
+                .map_err(io::Error::other)?;
+            format!("{json}\n")
+        }
+        false => export_view(&tasks, &view, &Date::now()),
+    };
+    match output {
+        Some(output) => {
+            std::fs::write(&output, text)?;
+            inform(format!("Wrote {} task(s) to {output}", tasks.len()));
+        }
+        None => print!("{text}"),
+    }
+    match tasks.is_empty() {
+        true => Err(CliError::Failed(format!("No tasks in this view of {path}"))),
+        false => Ok(()),
+    }
+}
+
+/// Move the base folder to `to`, the configuration has to follow by hand
+fn migrate(to: &Path) -> Result<(), CliError> {
+    let basefolder = Configuration::basefolder();
+    let migration = migrate::migrate(Path::new(&basefolder), to)?;
+    inform(format!(
+        "Moved {} file(s) from {basefolder} to {}",
+        migration.copied + migration.resumed,
+        to.display()
+    ));
+    if migration.resumed > 0 {
+        inform(format!("{} of them were already copied by an earlier run", migration.resumed));
+    }
+    inform("Point orgflow to the new folder with:");
+    inform(format!("  export ORGFLOW_BASEFOLDER={}", to.display()));
+    Ok(())
+}
+
+/// Write the drafts of the session file, or of `file`, into `recovered/`
+fn session_dump(file: Option<&Path>) -> Result<(), CliError> {
+    let basefolder = crate::prepare_basefolder()?;
+    let session = match file {
+        Some(file) => file.to_path_buf(),
+        None => {
+            let session = Path::new(&basefolder).join(SESSION_FILE);
+            let corrupt = corrupt_path(&session);
+            if !session.exists() && corrupt.exists() {
+                corrupt
+            } else {
+                session
+            }
+        }
+    };
+    let dir = Path::new(&basefolder).join(RECOVERED_DIR);
+    let written = recovery::dump(&session, &dir, unix_now())?;
+    if written.is_empty() {
+        inform(format!("No drafts in {}", session.display()));
+    }
+    for path in written {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// Load the text file `file` into the note draft of the session
+fn session_restore(file: &Path) -> Result<(), CliError> {
+    let basefolder = crate::prepare_basefolder()?;
+    let session = Path::new(&basefolder).join(SESSION_FILE);
+    let dir = Path::new(&basefolder).join(RECOVERED_DIR);
+    if let Some(kept) = recovery::restore(&session, file, &dir, unix_now())? {
+        inform(format!("Kept the previous note draft in {}", kept.display()));
+    }
+    inform(format!("Restored {} into the note draft", file.display()));
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Replace text in the refile document, printing every change
+fn sed(args: &[String]) -> Result<(), CliError> {
+    let mut options = ReplaceOptions::default();
+    let mut dry_run = false;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--ignore-case" => options.case_sensitive = false,
+            "--whole-word" => options.whole_word = true,
+            "--tasks" => options.scope = ReplaceScope::Tasks,
+            "--notes" => options.scope = ReplaceScope::Notes,
+            "--tags" => options.include_tags = true,
+            _ if arg.starts_with("--") => return Err(usage()),
+            _ => words.push(arg.as_str()),
+        }
+    }
+    let [find, replace] = words[..] else {
+        return Err(usage());
+    };
+    if find.is_empty() {
+        return Err(CliError::Usage("Nothing to find".to_string()));
+    }
+
+    let basefolder = crate::prepare_basefolder()?;
+    let path = crate::refile_path(&basefolder)?;
+    if !Path::new(&path).exists() {
+        return Err(CliError::Failed(format!("{path} does not exist yet")));
+    }
+    let mut document = OrgDocument::from(&path)?;
+    if !dry_run {
+        refuse_read_only(&document, &path)?;
+    }
+    let mut transaction = document.begin()?;
+    let changes = if dry_run {
+        transaction.preview_replace(find, replace, options)
+    } else {
+        transaction.replace_text(find, replace, options)
+    };
+    for change in &changes {
+        print_change(&transaction, change);
+    }
+    if changes.is_empty() {
+        return Err(CliError::Failed(format!("'{find}' was not found in {path}")));
+    }
+    if dry_run {
+        inform(format!("{} change(s) would be made to {path}", changes.len()));
+    } else {
+        transaction.commit(&path)?;
+        inform(format!("Made {} change(s) to {path}", changes.len()));
+    }
+    Ok(())
+}
+
+fn print_change(document: &OrgDocument, change: &ChangeRecord) {
+    let label = match change.target {
+        ChangeTarget::Task(idx) => format!("task {}", idx + 1),
+        ChangeTarget::TaskTags(idx) => format!("tags of task {}", idx + 1),
+        ChangeTarget::NoteLine { note, line } => {
+            format!("note '{}' line {}", document.notes[note].title(), line + 1)
+        }
+        ChangeTarget::NoteTags(note) => format!("tags of note '{}'", document.notes[note].title()),
+    };
+    println!("{label}:");
+    println!("  - {}", change.before);
+    println!("  + {}", change.after);
+}
@@ -0,0 +1,259 @@
+use std::str::FromStr;
+
+use orgflow::Task;
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Span;
+
+/// Layout of a Tasks list line when `ORGFLOW_TASK_FORMAT` is not set
+pub const DEFAULT_TASK_FORMAT: &str = "{status} {pri} {due} {desc} {project:>16}";
+
+/// The flex column keeps at least this many characters on narrow panes,
+/// columns after it are cut off at the right edge instead
+const MIN_FLEX_WIDTH: usize = 10;
+
+/// Value of a task shown in a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Status,
+    Priority,
+    Due,
+    Description,
+    Project,
+    Context,
+}
+
+impl Field {
+    /// Width used when the placeholder does not give one, `None` for flex
+    fn default_width(&self) -> Option<usize> {
+        match self {
+            Field::Status | Field::Priority => Some(3),
+            Field::Due => Some(10),
+            Field::Description => None,
+            Field::Project | Field::Context => Some(12),
+        }
+    }
+
+    fn value(&self, task: &Task) -> String {
+        let tags = |select: fn(&orgflow::TagCollection) -> Vec<String>| {
+            task.tags().as_ref().map(select).unwrap_or_default().join(" ")
+        };
+        match self {
+            Field::Status if task.is_completed() => "[x]".to_string(),
+            Field::Status => "[ ]".to_string(),
+            Field::Priority => {
+                task.priority_level().as_ref().map(|p| p.to_string()).unwrap_or_default()
+            }
+            Field::Due => task.due_date().map(|due| due.to_string()).unwrap_or_default(),
+            Field::Description => task.description().to_string(),
+            Field::Project => tags(orgflow::TagCollection::project_tags),
+            Field::Context => tags(orgflow::TagCollection::context_tags),
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            Field::Status | Field::Description => Style::default(),
+            Field::Priority => Style::default().fg(Color::Yellow).bold(),
+            Field::Due => Style::default().fg(Color::Magenta),
+            Field::Project => Style::default().fg(Color::Blue),
+            Field::Context => Style::default().fg(Color::Cyan),
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(Field::Status),
+            "pri" => Ok(Field::Priority),
+            "due" => Ok(Field::Due),
+            "desc" => Ok(Field::Description),
+            "project" => Ok(Field::Project),
+            "context" => Ok(Field::Context),
+            _ => Err(format!(
+                "Unknown placeholder '{{{s}}}', use status, pri, due, desc, project or context"
+            )),
+        }
+    }
+}
+
+/// Part of a format string, literal text or a column
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cell {
+    Text(String),
+    Column {
+        field: Field,
+        /// Fixed width, `None` for a column absorbing the remaining space
+        width: Option<usize>,
+        right_aligned: bool,
+    },
+}
+
+/// Parsed column layout of the Tasks list, e.g. `{pri} {desc} {project:>12}`
+///
+/// A placeholder takes an optional width after a colon, `>` aligns it to
+/// the right. `{desc}` grows with the pane unless it is given a width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskFormat {
+    cells: Vec<Cell>,
+}
+
+impl Default for TaskFormat {
+    fn default() -> Self {
+        DEFAULT_TASK_FORMAT.parse().expect("default task format is valid")
+    }
+}
+
+impl FromStr for TaskFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(format!("Unexpected '}}' in task format '{s}'"));
+            }
+            if start > 0 {
+                cells.push(Cell::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Missing '}}' in task format '{s}'"))?;
+            let placeholder = &rest[start + 1..start + end];
+            let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+            let field = Field::from_str(name.trim())?;
+            let (right_aligned, width) = match spec.strip_prefix('>') {
+                Some(width) => (true, width),
+                None => (false, spec),
+            };
+            let width = match width {
+                "" => field.default_width(),
+                width => Some(
+                    width
+                        .parse()
+                        .map_err(|_| format!("Width '{width}' of {{{name}}} is no number"))?,
+                ),
+            };
+            cells.push(Cell::Column { field, width, right_aligned });
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            cells.push(Cell::Text(rest.to_string()));
+        }
+        if !cells.iter().any(|cell| matches!(cell, Cell::Column { .. })) {
+            return Err(format!("Task format '{s}' has no placeholder"));
+        }
+        Ok(Self { cells })
+    }
+}
+
+impl TaskFormat {
+    /// Width of every cell for a line of `width` characters
+    ///
+    /// Flex columns share what the fixed cells leave, but keep at least
+    /// `MIN_FLEX_WIDTH` characters each.
+    fn widths(&self, width: usize) -> Vec<usize> {
+        let fixed: usize = self
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                Cell::Text(text) => text.chars().count(),
+                Cell::Column { width, .. } => width.unwrap_or(0),
+            })
+            .sum();
+        let flex_count = self
+            .cells
+            .iter()
+            .filter(|cell| matches!(cell, Cell::Column { width: None, .. }))
+            .count();
+        let remaining = width.saturating_sub(fixed);
+        let mut extra = remaining % flex_count.max(1);
+        self.cells
+            .iter()
+            .map(|cell| match cell {
+                Cell::Text(text) => text.chars().count(),
+                Cell::Column { width: Some(width), .. } => *width,
+                Cell::Column { width: None, .. } => {
+                    let share = remaining / flex_count + usize::from(extra > 0);
+                    extra = extra.saturating_sub(1);
+                    share.max(MIN_FLEX_WIDTH)
+                }
+            })
+            .collect()
+    }
+
+    /// Styled cells of `task` for a line of `width` characters
+    pub fn spans(&self, task: &Task, width: usize) -> Vec<Span<'static>> {
+        self.cells
+            .iter()
+            .zip(self.widths(width))
+            .map(|(cell, width)| match cell {
+                Cell::Text(text) => Span::raw(text.clone()),
+                Cell::Column { field, right_aligned, .. } => {
+                    Span::styled(fit(&field.value(task), width, *right_aligned), field.style())
+                }
+            })
+            .collect()
+    }
+}
+
+/// Pad `text` to exactly `width` characters, cutting it with an ellipsis
+fn fit(text: &str, width: usize, right_aligned: bool) -> String {
+    let len = text.chars().count();
+    if len > width {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        if width > 0 {
+            cut.push('…');
+        }
+        cut
+    } else if right_aligned {
+        format!("{text:>width$}")
+    } else {
+        format!("{text:<width$}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(format: &str, task: &str, width: usize) -> String {
+        let format = TaskFormat::from_str(format).unwrap();
+        let task = Task::from_str(task).unwrap();
+        format.spans(&task, width).iter().map(|span| span.content.to_string()).collect()
+    }
+
+    #[test]
+    fn test_flex_column_absorbs_the_remainder() {
+        let format = TaskFormat::from_str("{pri} {due} {desc} {project:>8}").unwrap();
+        assert_eq!(format.widths(40), [3, 1, 10, 1, 16, 1, 8]);
+        // Narrow panes keep a minimum for the description
+        assert_eq!(format.widths(20), [3, 1, 10, 1, 10, 1, 8]);
+        assert_eq!(format.widths(0)[4], MIN_FLEX_WIDTH);
+
+        let twice = TaskFormat::from_str("{desc}|{desc}").unwrap();
+        assert_eq!(twice.widths(25), [12, 1, 12]);
+    }
+
+    #[test]
+    fn test_cells_are_aligned_and_truncated() {
+        let task = "(A) 2024-01-10 Write the quarterly report +work due:2024-02-01";
+        assert_eq!(
+            line("{pri} {due} {desc} {project:>8}", task, 40),
+            "(A) 2024-02-01 Write the quart…    +work"
+        );
+        assert_eq!(line("{status} {desc:6}|", "x 2024-01-15 Done it all", 0), "[x] Done …|");
+        assert_eq!(line("{due} {desc}", "Nothing due", 22), "           Nothing due");
+    }
+
+    #[test]
+    fn test_invalid_formats() {
+        assert!(TaskFormat::from_str("{pri} {deadline}").unwrap_err().contains("deadline"));
+        assert!(TaskFormat::from_str("{pri").is_err());
+        assert!(TaskFormat::from_str("pri}").is_err());
+        assert!(TaskFormat::from_str("{desc:wide}").is_err());
+        assert!(TaskFormat::from_str("no columns").is_err());
+        assert_eq!(TaskFormat::from_str(DEFAULT_TASK_FORMAT), Ok(TaskFormat::default()));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of stashed drafts kept, older ones are dropped
+pub const DRAFT_HISTORY_CAPACITY: usize = 5;
+
+/// Title and content of a note that was cleared from the Editor
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteDraft {
+    pub title: Vec<String>,
+    pub content: Vec<String>,
+}
+
+impl NoteDraft {
+    pub fn new(title: &[String], content: &[String]) -> Self {
+        Self {
+            title: title.to_vec(),
+            content: content.to_vec(),
+        }
+    }
+
+    /// Whether there is nothing but whitespace in title and content
+    pub fn is_empty(&self) -> bool {
+        self.title
+            .iter()
+            .chain(self.content.iter())
+            .all(|line| line.trim().is_empty())
+    }
+}
+
+/// Small ring of stashed note drafts, most recent last
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DraftHistory {
+    drafts: VecDeque<NoteDraft>,
+}
+
+impl DraftHistory {
+    /// Stash a draft, dropping the oldest one when the ring is full
+    ///
+    /// Empty drafts are not stored, returns whether the draft was kept.
+    pub fn push(&mut self, draft: NoteDraft) -> bool {
+        if draft.is_empty() {
+            return false;
+        }
+        while self.drafts.len() >= DRAFT_HISTORY_CAPACITY {
+            self.drafts.pop_front();
+        }
+        self.drafts.push_back(draft);
+        true
+    }
+
+    /// Take the most recently stashed draft
+    pub fn pop(&mut self) -> Option<NoteDraft> {
+        self.drafts.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.drafts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drafts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(title: &str) -> NoteDraft {
+        NoteDraft::new(&[title.to_string()], &["content".to_string()])
+    }
+
+    #[test]
+    fn test_pop_returns_most_recent() {
+        let mut history = DraftHistory::default();
+        history.push(draft("first"));
+        history.push(draft("second"));
+        assert_eq!(history.pop(), Some(draft("second")));
+        assert_eq!(history.pop(), Some(draft("first")));
+        assert_eq!(history.pop(), None);
+    }
+
+    #[test]
+    fn test_capacity_drops_oldest() {
+        let mut history = DraftHistory::default();
+        for i in 0..DRAFT_HISTORY_CAPACITY + 2 {
+            history.push(draft(&i.to_string()));
+        }
+        assert_eq!(history.len(), DRAFT_HISTORY_CAPACITY);
+        let mut remaining = Vec::new();
+        while let Some(d) = history.pop() {
+            remaining.push(d.title[0].clone());
+        }
+        assert_eq!(remaining, vec!["6", "5", "4", "3", "2"]);
+    }
+
+    #[test]
+    fn test_empty_drafts_are_not_stashed() {
+        let mut history = DraftHistory::default();
+        let blank = NoteDraft::new(&["".to_string()], &["   ".to_string()]);
+        assert!(blank.is_empty());
+        assert!(!history.push(blank));
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut history = DraftHistory::default();
+        history.push(draft("kept"));
+        let json = serde_json::to_string(&history).unwrap();
+        let restored: DraftHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, history);
+    }
+}
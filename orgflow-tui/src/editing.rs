@@ -0,0 +1,95 @@
+use orgflow::{Note, OrgDocument};
+use serde::{Deserialize, Serialize};
+
+/// Note of the document the Editor draft is an edit of
+///
+/// Kept in the session together with the draft, so that a restored draft is
+/// still saved into its note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditingNote {
+    pub guid: String,
+    /// Modification date of the note when editing started, the draft is
+    /// based on that version
+    pub base_modified: String,
+}
+
+/// How the note being edited relates to the draft
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Unchanged since editing started, at the index
+    Fresh(usize),
+    /// Modified since editing started, e.g. on another machine
+    Changed(usize),
+    /// No longer in the document
+    Missing,
+}
+
+impl EditingNote {
+    pub fn start(note: &Note) -> Self {
+        Self {
+            guid: note.guid().to_string(),
+            base_modified: note.modification_date().to_string(),
+        }
+    }
+
+    /// Compare the note in `document` against the version the draft is based on
+    ///
+    /// Modification dates are days, an edit made elsewhere on the same day
+    /// goes unnoticed.
+    pub fn freshness(&self, document: &OrgDocument) -> Freshness {
+        match document.find_note(&self.guid) {
+            Some(index)
+                if document.notes[index].modification_date().to_string()
+                    == self.base_modified =>
+            {
+                Freshness::Fresh(index)
+            }
+            Some(index) => Freshness::Changed(index),
+            None => Freshness::Missing,
+        }
+    }
+}
+
+/// Editor lines showing `note`: the title followed by its tags, and the content
+pub fn note_lines(note: &Note) -> (String, Vec<String>) {
+    let tags = note.tags().to_string();
+    let title = if tags.is_empty() {
+        note.title().to_string()
+    } else {
+        format!("{} {}", note.title(), tags)
+    };
+    (title, note.content().to_vec())
+}
+
+/// A restored draft whose note changed since, waiting for the user to pick
+/// which version to go on with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DraftConflict {
+    pub note_index: usize,
+    /// Both versions are shown side by side
+    pub show_both: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(modified: &str) -> OrgDocument {
+        let mut document = OrgDocument::default();
+        document.push_note(Note::from(vec![
+            "### Groceries".to_string(),
+            format!("> cre:2024-01-01 mod:{modified} guid:1d7a2c3e-0000-4000-8000-000000000001"),
+            "Milk".to_string(),
+        ]));
+        document
+    }
+
+    #[test]
+    fn test_freshness_compares_modification_dates() {
+        let editing = EditingNote::start(&document("2024-01-02").notes[0]);
+        assert_eq!(editing.base_modified, "2024-01-02");
+        assert_eq!(editing.freshness(&document("2024-01-02")), Freshness::Fresh(0));
+        assert_eq!(editing.freshness(&document("2024-01-05")), Freshness::Changed(0));
+        assert_eq!(editing.freshness(&OrgDocument::default()), Freshness::Missing);
+    }
+}
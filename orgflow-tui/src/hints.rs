@@ -0,0 +1,234 @@
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+
+use crate::app::{App, AppTab, NoteFocus, UiMode};
+use crate::scratchpad::ScratchpadMode;
+
+/// Most actions shown in a hint bar at once
+const MAX_HINTS: usize = 5;
+
+/// Where the keyboard is, deciding which actions a key hint is given for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintContext {
+    EditorTitle,
+    EditorContent,
+    Viewer,
+    Tasks,
+    Selecting,
+    TaskScratchpad,
+    NoteScratchpad,
+}
+
+/// Contexts where keys type text, help is asked for with Alt+H there
+const TYPING: [HintContext; 4] = [
+    HintContext::EditorTitle,
+    HintContext::EditorContent,
+    HintContext::TaskScratchpad,
+    HintContext::NoteScratchpad,
+];
+
+impl HintContext {
+    /// Context of the layer that has the keyboard, popups have their own hints
+    pub fn of(app: &App) -> Self {
+        match (app.mode.base(), app.scratchpad_mode) {
+            (UiMode::Scratchpad, ScratchpadMode::Task) => HintContext::TaskScratchpad,
+            (UiMode::Scratchpad, ScratchpadMode::Note) => HintContext::NoteScratchpad,
+            _ => Self::of_tab(app),
+        }
+    }
+
+    /// Context of the current tab, whatever covers it
+    pub fn of_tab(app: &App) -> Self {
+        match (app.selection(), &app.current_tab, &app.note_focus) {
+            (Some(_), _, _) => HintContext::Selecting,
+            (None, AppTab::Editor, NoteFocus::Title) => HintContext::EditorTitle,
+            (None, AppTab::Editor, NoteFocus::Content) => HintContext::EditorContent,
+            (None, AppTab::Viewer, _) => HintContext::Viewer,
+            (None, AppTab::Tasks, _) => HintContext::Tasks,
+        }
+    }
+
+    /// Keys opening the list of all actions
+    pub fn help_keys(self) -> &'static str {
+        if TYPING.contains(&self) {
+            "<ALT>+<H>"
+        } else {
+            "<?>"
+        }
+    }
+}
+
+/// A key binding as it is explained to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Action {
+    pub keys: &'static str,
+    pub label: &'static str,
+    /// Hint bars show the actions with the lowest numbers first
+    pub priority: u8,
+    pub contexts: &'static [HintContext],
+}
+
+impl Action {
+    const fn new(
+        keys: &'static str,
+        label: &'static str,
+        priority: u8,
+        contexts: &'static [HintContext],
+    ) -> Self {
+        Self {
+            keys,
+            label,
+            priority,
+            contexts,
+        }
+    }
+}
+
+const TITLE: &[HintContext] = &[HintContext::EditorTitle];
+const CONTENT: &[HintContext] = &[HintContext::EditorContent];
+const EDITOR: &[HintContext] = &[HintContext::EditorTitle, HintContext::EditorContent];
+const VIEWER: &[HintContext] = &[HintContext::Viewer];
+const TASKS: &[HintContext] = &[HintContext::Tasks];
+const READ_ONLY: &[HintContext] = &[HintContext::Viewer, HintContext::Tasks];
+const TABS: &[HintContext] = &[
+    HintContext::EditorTitle,
+    HintContext::EditorContent,
+    HintContext::Viewer,
+    HintContext::Tasks,
+];
+const SELECTING: &[HintContext] = &[HintContext::Selecting];
+const TASK_PAD: &[HintContext] = &[HintContext::TaskScratchpad];
+const NOTE_PAD: &[HintContext] = &[HintContext::NoteScratchpad];
+const SCRATCHPAD: &[HintContext] = &[HintContext::TaskScratchpad, HintContext::NoteScratchpad];
+
+/// Every key binding, in the order they are listed in
+pub const ACTIONS: &[Action] = &[
+    Action::new("<CTRL>+<S>", "Save Note", 1, EDITOR),
+    Action::new("<TAB>", "Content", 2, TITLE),
+    Action::new("<SHIFT>+<TAB>", "Title", 2, CONTENT),
+    Action::new("<←→>", "Navigate", 1, VIEWER),
+    Action::new("<E>", "Edit", 2, VIEWER),
+    Action::new("<SHIFT>+<↑↓>", "Move Note", 7, VIEWER),
+    Action::new("<↑↓>", "Navigate", 1, TASKS),
+    Action::new("<X>", "Complete", 1, TASKS),
+    Action::new("<D>", "Details", 2, TASKS),
+    Action::new("<T>", "Tags", 5, TASKS),
+    Action::new("<C>", "Due Date", 4, TASKS),
+    Action::new("<P>", "Project", 3, TASKS),
+    Action::new("<W>", "Sections", 6, TASKS),
+    Action::new("<SHIFT>+<W>", "Move to Section", 7, TASKS),
+    Action::new("<A>", "Waiting", 6, TASKS),
+    Action::new("<SHIFT>+<D>", "Duplicates", 7, TASKS),
+    Action::new("<SHIFT>+<E>", "Export", 8, TASKS),
+    Action::new("<V>", "Select", 4, READ_ONLY),
+    Action::new("<↑↓>", "Extend", 1, SELECTING),
+    Action::new("<Y>", "Copy", 1, SELECTING),
+    Action::new("<ESC>", "Cancel", 1, SELECTING),
+    Action::new("<ENTER>", "Add Task", 1, TASK_PAD),
+    Action::new("<ENTER>", "Save", 1, NOTE_PAD),
+    Action::new("<ALT>+<ENTER>", "New Line", 2, NOTE_PAD),
+    Action::new("<TAB>", "Complete Tag", 3, SCRATCHPAD),
+    Action::new("<CTRL>+<T>", "Note", 2, TASK_PAD),
+    Action::new("<CTRL>+<G>", "Go to Duplicate", 6, TASK_PAD),
+    Action::new("<ESC>", "Close", 1, SCRATCHPAD),
+    Action::new("<CTRL>+<T>", "Enter Task", 3, TABS),
+    Action::new("<CTRL>+<N>", "New", 4, EDITOR),
+    Action::new("<CTRL>+<O>", "Restore", 6, EDITOR),
+    Action::new("<CTRL>+<R>/<E>", "Switch", 3, TABS),
+    Action::new("<ALT>+<F>", "Find", 8, TABS),
+    Action::new("<ALT>+<R>", "Rename", 9, TABS),
+    Action::new("<ALT>+<P>", "File Header", 9, TABS),
+    Action::new("<CTRL>+<L>", "Problems", 9, TABS),
+    Action::new("<ESC>", "Quit", 5, TABS),
+];
+
+/// All actions of `context`, in table order
+pub fn actions(context: HintContext) -> impl Iterator<Item = &'static Action> {
+    ACTIONS
+        .iter()
+        .filter(move |action| action.contexts.contains(&context))
+}
+
+/// Columns an action takes in a hint bar, " Label <KEY>" with a space after
+fn hint_width(label: &str, keys: &str) -> usize {
+    label.chars().count() + keys.chars().count() + 2
+}
+
+/// Hint bar for `context` fitting into `width` columns
+///
+/// The most important actions that fit are shown in table order, up to
+/// `MAX_HINTS` of them. Leaving any out points to the help instead.
+pub fn hint_line(context: HintContext, width: u16) -> Line<'static> {
+    let mut candidates: Vec<(usize, &Action)> = actions(context).enumerate().collect();
+    candidates.sort_by_key(|(index, action)| (action.priority, *index));
+
+    let elision = hint_width("… Help", context.help_keys());
+    let mut room = (width as usize).saturating_sub(1);
+    let mut shown = Vec::new();
+    for (i, (index, action)) in candidates.iter().enumerate() {
+        let needed = hint_width(action.label, action.keys);
+        let rest = candidates.len() - i - 1;
+        let reserve = if rest > 0 { elision } else { 0 };
+        if shown.len() == MAX_HINTS || needed + reserve > room {
+            break;
+        }
+        room -= needed;
+        shown.push((*index, *action));
+    }
+    shown.sort_by_key(|(index, _)| *index);
+
+    let mut spans: Vec<Span<'static>> = vec![" ".into()];
+    for (_, action) in &shown {
+        spans.push(format!("{} ", action.label).into());
+        spans.push(format!("{} ", action.keys).blue().bold());
+    }
+    if shown.len() < candidates.len() && elision <= (width as usize).saturating_sub(1) {
+        spans.push("… Help ".into());
+        spans.push(format!("{} ", context.help_keys()).blue().bold());
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_hint_line_keeps_the_most_important_actions() {
+        let wide = hint_line(HintContext::Tasks, 200);
+        assert_eq!(
+            text(&wide),
+            " Navigate <↑↓> Complete <X> Details <D> Project <P> Enter Task <CTRL>+<T> \
+             … Help <?> "
+        );
+        assert!(wide.width() <= 200);
+
+        let narrow = hint_line(HintContext::Tasks, 60);
+        assert_eq!(
+            text(&narrow),
+            " Navigate <↑↓> Complete <X> Details <D> … Help <?> "
+        );
+        assert!(narrow.width() <= 60);
+    }
+
+    #[test]
+    fn test_hint_line_without_elision_when_everything_fits() {
+        let line = hint_line(HintContext::Selecting, 80);
+        assert_eq!(text(&line), " Extend <↑↓> Copy <Y> Cancel <ESC> ");
+        // Too narrow even for the pointer to the help
+        assert_eq!(text(&hint_line(HintContext::EditorTitle, 10)), " ");
+    }
+
+    #[test]
+    fn test_help_key_depends_on_typing() {
+        assert!(text(&hint_line(HintContext::EditorContent, 40)).ends_with("… Help <ALT>+<H> "));
+        assert_eq!(HintContext::Viewer.help_keys(), "<?>");
+    }
+}
@@ -0,0 +1,975 @@
+use orgflow::{
+    Configuration, Date, ReplaceOptions, Tag, TagCollection, is_long_capture, validate_preamble,
+    visible_position,
+};
+use std::str::FromStr;
+use std::io;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::app::{App, AppTab, Modal, NoteFocus, UiMode};
+use crate::clipboard;
+use crate::editing::{EditingNote, note_lines};
+use crate::hints::HintContext;
+use crate::selection::LineSelection;
+use crate::session::SessionManager;
+use crate::widgets::autocompletion::AutocompletionWidget;
+use crate::widgets::calendar::{Calendar, CalendarAction};
+use crate::widgets::projects::ProjectPicker;
+use crate::widgets::waiting::WaitingList;
+use crate::scratchpad::ScratchpadMode;
+
+impl App {
+    /// Look for key presses and handle event
+    ///
+    /// Keys go to the topmost layer only: a popup, the line selection, the
+    /// scratchpad or else the current tab.
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        if key_event.kind == KeyEventKind::Press {
+            self.alerts.clear();
+        }
+        // Anything but a second Ctrl+O cancels a pending restore
+        let is_restore = key_event.code == KeyCode::Char('o')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL);
+        if key_event.kind == KeyEventKind::Press && !is_restore {
+            self.confirm_restore = false;
+        }
+        if key_event.kind == KeyEventKind::Press && key_event.code != KeyCode::Enter {
+            self.confirm_duplicate = false;
+        }
+
+        match self.mode {
+            UiMode::Modal { .. } if key_event.kind != KeyEventKind::Press => Ok(()),
+            UiMode::Modal { .. } => self.handle_modal_key(key_event),
+            UiMode::Selecting(_) => {
+                self.handle_selection_key(key_event);
+                Ok(())
+            }
+            UiMode::Scratchpad => self.handle_scratchpad_key(key_event),
+            UiMode::Normal => self.handle_tab_key(key_event),
+        }
+    }
+
+    /// Keys of the current tab, nothing covers it
+    fn handle_tab_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        match (
+            key_event.kind,
+            key_event.code,
+            &self.current_tab,
+            &self.note_focus,
+        ) {
+            // Start selecting lines in the read-only panes
+            (KeyEventKind::Press, KeyCode::Char('v'), AppTab::Viewer | AppTab::Tasks, _) => {
+                let len = self.selectable_lines().len();
+                if len > 0 {
+                    self.mode = UiMode::Selecting(LineSelection::new(0, len));
+                }
+            }
+            // List every key, '?' is typed as text in the Editor
+            (KeyEventKind::Press, KeyCode::Char('?'), AppTab::Viewer | AppTab::Tasks, _) => {
+                self.open_modal(Modal::Help(HintContext::of(self)));
+            }
+            (KeyEventKind::Press, KeyCode::Char('h'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.open_modal(Modal::Help(HintContext::of(self)));
+            }
+            // Tab switching (cycling and direct jumps)
+            (KeyEventKind::Press, KeyCode::Char('r'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.switch_to(self.current_tab.next());
+            }
+            (KeyEventKind::Press, KeyCode::Char('e'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.switch_to(self.current_tab.previous());
+            }
+            (KeyEventKind::Press, KeyCode::F(n @ 1..=3), _, _) => {
+                self.switch_to(AppTab::ALL[n as usize - 1].clone());
+            }
+            (KeyEventKind::Press, KeyCode::Char(c @ '1'..='3'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.switch_to(AppTab::ALL[c as usize - '1' as usize].clone());
+            }
+            // Rename the document file
+            (KeyEventKind::Press, KeyCode::Char('r'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                let name = std::path::Path::new(&self.document_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let mut prompt = TextArea::from([name]);
+                prompt.move_cursor(CursorMove::End);
+                self.open_modal(Modal::Rename(prompt));
+            }
+            // Edit the lines before the Tasks section
+            (KeyEventKind::Press, KeyCode::Char('p'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                let mut editor = TextArea::from(self.document.preamble().to_vec());
+                editor.move_cursor(CursorMove::Bottom);
+                editor.move_cursor(CursorMove::End);
+                self.open_modal(Modal::Header {
+                    editor,
+                    error: None,
+                });
+            }
+            // Find and replace in task descriptions and note content
+            (KeyEventKind::Press, KeyCode::Char('f'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                self.open_modal(Modal::Replace {
+                    prompt: TextArea::default(),
+                    confirm: false,
+                });
+            }
+            // Show the lines the parser could not read
+            (KeyEventKind::Press, KeyCode::Char('l'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                if self.document.lint().is_empty() {
+                    self.alerts.info("No problems found");
+                } else {
+                    self.open_modal(Modal::Problems);
+                }
+            }
+            // Edit the shown note in the Editor
+            (KeyEventKind::Press, KeyCode::Char('e'), AppTab::Viewer, _)
+                if key_event.modifiers.is_empty() =>
+            {
+                self.edit_current_note();
+            }
+            // Move the shown note up or down among its siblings
+            (KeyEventKind::Press, KeyCode::Up | KeyCode::Down, AppTab::Viewer, _)
+                if key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                self.move_current_note(key_event.code == KeyCode::Up)?;
+            }
+            // Arrow navigation in viewer tab
+            (KeyEventKind::Press, KeyCode::Left, AppTab::Viewer, _) => {
+                if self.current_note_index > 0 {
+                    self.current_note_index -= 1;
+                }
+            }
+            (KeyEventKind::Press, KeyCode::Right, AppTab::Viewer, _) => {
+                if self.current_note_index < self.document.notes.len().saturating_sub(1) {
+                    self.current_note_index += 1;
+                }
+            }
+            // Arrow navigation in tasks tab, over the tasks the filter shows
+            (KeyEventKind::Press, KeyCode::Up, AppTab::Tasks, _) => {
+                let visible = self.visible_tasks();
+                if let Some(row) = visible_position(&visible, self.current_task_index)
+                    && row > 0
+                {
+                    self.current_task_index = visible[row - 1];
+                }
+            }
+            (KeyEventKind::Press, KeyCode::Down, AppTab::Tasks, _) => {
+                let visible = self.visible_tasks();
+                if let Some(row) = visible_position(&visible, self.current_task_index)
+                    && row + 1 < visible.len()
+                {
+                    self.current_task_index = visible[row + 1];
+                }
+            }
+            // Pick the project to filter the tasks by
+            (KeyEventKind::Press, KeyCode::Char('p'), AppTab::Tasks, _)
+                if key_event.modifiers.is_empty() =>
+            {
+                let progress = self.document.project_progress();
+                let picker = ProjectPicker::new(progress, self.project_filter.as_deref());
+                self.open_modal(Modal::ProjectPicker(picker));
+            }
+            // Tasks waiting on someone, to follow them up
+            (KeyEventKind::Press, KeyCode::Char('a'), AppTab::Tasks, _)
+                if key_event.modifiers.is_empty() =>
+            {
+                let waiting = WaitingList::new(self.document.waiting_by_person());
+                self.open_modal(Modal::Waiting(waiting));
+            }
+            // Edit the detail lines of the selected task
+            (KeyEventKind::Press, KeyCode::Char('d'), AppTab::Tasks, _) => {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                if let Some(task) = self.document.tasks.get(self.current_task_index) {
+                    let mut editor = TextArea::from(task.details().to_vec());
+                    editor.move_cursor(CursorMove::Bottom);
+                    editor.move_cursor(CursorMove::End);
+                    self.open_modal(Modal::Details(editor));
+                }
+            }
+            // Edit the tags of the selected task in one line
+            (KeyEventKind::Press, KeyCode::Char('t'), AppTab::Tasks, _)
+                if key_event.modifiers.is_empty() =>
+            {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                if let Some(task) = self.document.tasks.get(self.current_task_index) {
+                    let tags = task.tags().as_ref().map(|t| t.to_string()).unwrap_or_default();
+                    let mut prompt = TextArea::from([tags]);
+                    prompt.move_cursor(CursorMove::End);
+                    self.open_modal(Modal::Tags {
+                        prompt,
+                        completion: AutocompletionWidget::new(),
+                        error: None,
+                    });
+                }
+            }
+            // Pick the due date of the selected task
+            (KeyEventKind::Press, KeyCode::Char('c'), AppTab::Tasks, _)
+                if key_event.modifiers.is_empty() =>
+            {
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                if let Some(task) = self.document.tasks.get(self.current_task_index) {
+                    let today = Date::now();
+                    let due = task.due_date().copied().unwrap_or(today);
+                    let locale = Configuration::locale();
+                    let calendar = Calendar::new(due, today, Configuration::week_start(), locale);
+                    self.open_modal(Modal::Calendar(calendar));
+                }
+            }
+            // Show or collapse the sections between Tasks and Notes
+            (KeyEventKind::Press, KeyCode::Char('w'), AppTab::Tasks, _) => {
+                self.show_between = !self.show_between;
+            }
+            (KeyEventKind::Press, KeyCode::Char('W'), AppTab::Tasks, _) => {
+                self.move_task_to_section()?;
+            }
+            // Keep, merge or delete tasks that look like duplicates
+            (KeyEventKind::Press, KeyCode::Char('D'), AppTab::Tasks, _) => {
+                self.review_duplicates();
+            }
+            // Write what the Tasks list shows to a text file
+            (KeyEventKind::Press, KeyCode::Char('E'), AppTab::Tasks, _) => {
+                self.export_task_view();
+            }
+            // Complete the selected task
+            (KeyEventKind::Press, KeyCode::Char('x'), AppTab::Tasks, _) => {
+                self.complete_current_task()?;
+            }
+            // Open the scratchpad, in task mode
+            (KeyEventKind::Press, KeyCode::Char('t'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.mode = UiMode::Scratchpad;
+                self.scratchpad_mode = ScratchpadMode::Task;
+                self.refresh_scratchpad_autocompletion();
+            }
+            (KeyEventKind::Press, KeyCode::Char('s'), _, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.save_note()?;
+            }
+            (KeyEventKind::Press, KeyCode::Esc, AppTab::Editor, NoteFocus::Title) if self.title_autocompletion.is_visible() => {
+                // Hide title autocompletion
+                self.title_autocompletion.hide();
+            }
+            (KeyEventKind::Press, KeyCode::Esc, _, _) => self.exit = true,
+            // Editor tab specific key handling
+            (KeyEventKind::Press, KeyCode::Char('n'), AppTab::Editor, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.new_note();
+            }
+            (KeyEventKind::Press, KeyCode::Char('o'), AppTab::Editor, _)
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.restore_previous_draft();
+            }
+            (KeyEventKind::Press, KeyCode::BackTab, AppTab::Editor, NoteFocus::Content) => {
+                self.note_focus = NoteFocus::Title;
+                self.refresh_title_autocompletion();
+            }
+            (KeyEventKind::Press, KeyCode::BackTab, AppTab::Editor, NoteFocus::Title) => {
+                self.note_focus = NoteFocus::Content;
+                self.title_autocompletion.hide();
+            }
+            (KeyEventKind::Press, KeyCode::Enter, AppTab::Editor, NoteFocus::Title) => {
+                self.note_focus = NoteFocus::Content;
+                self.title_autocompletion.hide();
+            }
+            // Title autocompletion handling
+            (KeyEventKind::Press, KeyCode::Up, AppTab::Editor, NoteFocus::Title) if self.title_autocompletion.is_visible() => {
+                self.title_autocompletion.select_previous();
+            }
+            (KeyEventKind::Press, KeyCode::Down, AppTab::Editor, NoteFocus::Title) if self.title_autocompletion.is_visible() => {
+                self.title_autocompletion.select_next();
+            }
+            (KeyEventKind::Press, KeyCode::Tab, AppTab::Editor, NoteFocus::Title) if self.title_autocompletion.is_visible() => {
+                // Apply the selected suggestion
+                if let Some((new_text, _cursor_pos)) = self.title_autocompletion.apply_selected(&self.title.lines().join(" ")) {
+                    self.title = TextArea::from(vec![new_text]);
+                    self.title.move_cursor(tui_textarea::CursorMove::End);
+                    self.title_autocompletion.hide();
+                }
+            }
+            (KeyEventKind::Press, KeyCode::Tab, AppTab::Editor, NoteFocus::Title) => {
+                self.note_focus = NoteFocus::Content;
+                self.title_autocompletion.hide();
+            }
+            (_, _, AppTab::Editor, NoteFocus::Content) => _ = self.note.input(key_event),
+            (_, _, AppTab::Editor, NoteFocus::Title) => {
+                self.input_title(key_event);
+                // Update autocompletion suggestions after input
+                let current_text = self.title.lines().join(" ");
+                self.title_autocompletion.update_suggestions(&current_text, &self.tag_suggestions);
+            }
+            // Ignore other inputs in viewer mode
+            (_, _, AppTab::Viewer, _) => {}
+            // Ignore other inputs in tasks mode
+            (_, _, AppTab::Tasks, _) => {}
+        }
+        Ok(())
+    }
+
+    /// Keys while the scratchpad is open, none of them reach the tab below
+    fn handle_scratchpad_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let popup = self.autocompletion.is_visible();
+        match (key_event.kind, key_event.code) {
+            (KeyEventKind::Press, KeyCode::Char('h'))
+                if key_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.open_modal(Modal::Help(HintContext::of(self)));
+            }
+            // Cycle closed -> task -> note -> closed
+            (KeyEventKind::Press, KeyCode::Char('t'))
+                if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                match self.scratchpad_mode {
+                    ScratchpadMode::Task => {
+                        self.scratchpad_mode = ScratchpadMode::Note;
+                        self.refresh_scratchpad_autocompletion();
+                    }
+                    ScratchpadMode::Note => self.close_scratchpad(),
+                }
+            }
+            (KeyEventKind::Press, KeyCode::Char('g'))
+                if key_event.modifiers.contains(KeyModifiers::CONTROL)
+                    && self.scratchpad_mode == ScratchpadMode::Task =>
+            {
+                self.go_to_oneoff_conflict();
+            }
+            // Hide autocompletion but don't close scratchpad
+            (KeyEventKind::Press, KeyCode::Esc) if popup => self.autocompletion.hide(),
+            (KeyEventKind::Press, KeyCode::Esc) => self.close_scratchpad(),
+            (KeyEventKind::Press, KeyCode::Enter)
+                if self.scratchpad_mode == ScratchpadMode::Note =>
+            {
+                if key_event.modifiers.contains(KeyModifiers::ALT) {
+                    self.note_scratchpad.insert_newline();
+                    self.autocompletion.hide();
+                } else {
+                    self.capture_note()?;
+                }
+            }
+            // The task stays in the scratchpad
+            (KeyEventKind::Press, KeyCode::Enter) if self.read_only.is_some() => {
+                self.refuse_read_only();
+            }
+            (KeyEventKind::Press, KeyCode::Enter)
+                if !self.confirm_duplicate
+                    && let Some(warning) = self.duplicate_warning() =>
+            {
+                // Ask first, a second Enter adds the task anyway
+                self.confirm_duplicate = true;
+                self.alerts.warn(warning);
+            }
+            (KeyEventKind::Press, KeyCode::Enter) => {
+                self.confirm_duplicate = false;
+                self.add_draft_task()?;
+            }
+            // Autocompletion handling in scratchpad
+            (KeyEventKind::Press, KeyCode::Up) if popup => self.autocompletion.select_previous(),
+            (KeyEventKind::Press, KeyCode::Down) if popup => self.autocompletion.select_next(),
+            (KeyEventKind::Press, KeyCode::Tab) if popup => {
+                // Apply the selected suggestion
+                match self.scratchpad_mode {
+                    ScratchpadMode::Task => {
+                        if let Some((new_text, _cursor_pos)) =
+                            self.autocompletion.apply_selected(self.draft_task.text())
+                        {
+                            // Replace the text content
+                            self.scratchpad = TextArea::from(vec![new_text]);
+                            // Move cursor to the end of the inserted tag
+                            self.scratchpad.move_cursor(tui_textarea::CursorMove::End);
+                            self.draft_task.update(&self.scratchpad.lines().join(" "));
+                        }
+                    }
+                    ScratchpadMode::Note => {
+                        // Only the line under the cursor is completed
+                        let (row, _) = self.note_scratchpad.cursor();
+                        let line = self.note_scratchpad.lines()[row].clone();
+                        if let Some((new_text, _cursor_pos)) =
+                            self.autocompletion.apply_selected(&line)
+                        {
+                            let mut lines = self.note_scratchpad.lines().to_vec();
+                            let col = new_text.chars().count();
+                            lines[row] = new_text;
+                            self.note_scratchpad =
+                                SessionManager::restore_textarea_with_cursor(&lines, (row, col));
+                        }
+                    }
+                }
+                self.autocompletion.hide();
+            }
+            _ => {
+                // Update autocompletion suggestions (and the task parse) after input
+                match self.scratchpad_mode {
+                    ScratchpadMode::Task => {
+                        self.scratchpad.input(key_event);
+                        let current_text = self.scratchpad.lines().join(" ");
+                        self.draft_task.update(&current_text);
+                    }
+                    ScratchpadMode::Note => _ = self.note_scratchpad.input(key_event),
+                }
+                let current_text = self.scratchpad_completion_text();
+                self.autocompletion.update_suggestions(&current_text, &self.tag_suggestions);
+            }
+        }
+        Ok(())
+    }
+
+    fn close_scratchpad(&mut self) {
+        self.mode = UiMode::Normal;
+        self.autocompletion.hide();
+    }
+
+    /// Key presses while a popup is open, each popup has its own keys
+    fn handle_modal_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal { kind, .. } = &mut self.mode else {
+            return Ok(());
+        };
+        match kind {
+            Modal::Problems => {
+                let is_toggle = key_event.code == KeyCode::Char('l')
+                    && key_event.modifiers.contains(KeyModifiers::CONTROL);
+                if key_event.code == KeyCode::Esc || is_toggle {
+                    self.close_modal();
+                } else if key_event.code == KeyCode::Char('f') && !self.refuse_read_only() {
+                    self.repair_guids()?;
+                    if self.document.lint().is_empty() {
+                        self.close_modal();
+                    }
+                }
+                Ok(())
+            }
+            Modal::PendingPaste(text) => {
+                let text = std::mem::take(text);
+                self.close_modal();
+                match key_event.code {
+                    KeyCode::Enter => self.capture_paste_as_note(&text),
+                    _ => {
+                        self.paste_into_task(&text);
+                        Ok(())
+                    }
+                }
+            }
+            Modal::Help(_) => {
+                self.close_modal();
+                Ok(())
+            }
+            Modal::Details(_) => self.handle_details_key(key_event),
+            Modal::Rename(_) => self.handle_rename_key(key_event),
+            Modal::Header { .. } => self.handle_header_key(key_event),
+            Modal::Replace { .. } => self.handle_replace_key(key_event),
+            Modal::Conflict(_) => {
+                self.handle_conflict_key(key_event);
+                Ok(())
+            }
+            Modal::ProjectPicker(_) => {
+                self.handle_picker_key(key_event);
+                Ok(())
+            }
+            Modal::Calendar(_) => self.handle_calendar_key(key_event),
+            Modal::Waiting(_) => self.handle_waiting_key(key_event),
+            Modal::Duplicates(_) => self.handle_duplicates_key(key_event),
+            Modal::Tags { .. } => self.handle_tags_key(key_event),
+        }
+    }
+
+    /// Insert pasted text into whatever has the focus
+    ///
+    /// Text for the task scratchpad is sanitized into a single line. Long or
+    /// multi-paragraph text is held back first, offering to capture it as a
+    /// note instead.
+    pub fn handle_paste(&mut self, text: &str) {
+        self.alerts.clear();
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        // A second paste replaces the one waiting for a decision
+        if let UiMode::Modal {
+            kind: Modal::PendingPaste(_),
+            ..
+        } = self.mode
+        {
+            self.close_modal();
+        }
+        match (&self.mode, self.scratchpad_mode) {
+            (UiMode::Scratchpad, ScratchpadMode::Task) => {
+                if is_long_capture(&text, Configuration::capture_max_len()) {
+                    self.alerts.warn(format!(
+                        "Pasted {} characters, press Enter to capture them as a note \
+                         or any other key to paste them into the task",
+                        text.chars().count()
+                    ));
+                    self.open_modal(Modal::PendingPaste(text));
+                } else {
+                    self.paste_into_task(&text);
+                }
+            }
+            (UiMode::Scratchpad, ScratchpadMode::Note) => {
+                _ = self.note_scratchpad.insert_str(&text)
+            }
+            (UiMode::Normal, _) if matches!(self.current_tab, AppTab::Editor) => {
+                match self.note_focus {
+                    NoteFocus::Title => self.paste_into_title(&text),
+                    NoteFocus::Content => _ = self.note.insert_str(&text),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Keys while the detail lines of a task are edited: Ctrl+S stores them,
+    /// ESC discards the changes
+    fn handle_details_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Details(editor),
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key_event.code {
+            KeyCode::Esc => self.close_modal(),
+            KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let details = editor.lines().to_vec();
+                self.close_modal();
+                self.ensure_document_loaded();
+                if let Some(task) = self.document.tasks.get_mut(self.current_task_index) {
+                    task.set_details(details);
+                    self.persistence.mark_dirty("set_details");
+                    self.save_document()?;
+                }
+            }
+            _ => _ = editor.input(key_event),
+        }
+        Ok(())
+    }
+
+    /// Keys while the file header is edited: Ctrl+S saves it, ESC discards
+    /// the changes. A line starting a section keeps the editor open with the
+    /// error
+    fn handle_header_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Header { editor, error },
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key_event.code {
+            KeyCode::Esc => self.close_modal(),
+            KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let lines = editor.lines().to_vec();
+                if let Err(e) = validate_preamble(&lines) {
+                    *error = Some(e);
+                    return Ok(());
+                }
+                self.close_modal();
+                self.save_preamble(lines)?;
+            }
+            _ => {
+                *error = None;
+                _ = editor.input(key_event);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys while the new document name is typed: Enter renames the file,
+    /// ESC keeps the current name
+    fn handle_rename_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Rename(prompt),
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key_event.code {
+            KeyCode::Esc => self.close_modal(),
+            KeyCode::Enter => {
+                let name = prompt.lines().join("");
+                self.close_modal();
+                self.rename_document(&name)?;
+            }
+            _ => _ = prompt.input(key_event),
+        }
+        Ok(())
+    }
+
+    /// Keys while the tags of a task are edited: Enter replaces them (an
+    /// empty line removes them), ESC keeps them. Tags that do not parse
+    /// keep the prompt open with the error
+    fn handle_tags_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Tags {
+                prompt,
+                completion,
+                error,
+            },
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        let popup = completion.is_visible();
+        let text = prompt.lines().join(" ");
+        match key_event.code {
+            KeyCode::Esc if popup => completion.hide(),
+            KeyCode::Esc => self.close_modal(),
+            KeyCode::Up if popup => completion.select_previous(),
+            KeyCode::Down if popup => completion.select_next(),
+            KeyCode::Tab if popup => {
+                if let Some((new_text, _cursor_pos)) = completion.apply_selected(&text) {
+                    *prompt = TextArea::from([new_text]);
+                    prompt.move_cursor(CursorMove::End);
+                }
+                completion.hide();
+            }
+            KeyCode::Enter => {
+                let tags = match text.trim() {
+                    "" => Ok(TagCollection::new()),
+                    text => TagCollection::from_str(text),
+                };
+                let tags = match tags {
+                    Ok(tags) => tags,
+                    Err(e) => {
+                        *error = Some(e);
+                        completion.hide();
+                        return Ok(());
+                    }
+                };
+                self.close_modal();
+                self.ensure_document_loaded();
+                let Some(task) = self.document.tasks.get_mut(self.current_task_index) else {
+                    return Ok(());
+                };
+                task.set_tags(tags);
+                let message = match task.tags() {
+                    Some(tags) => format!("Tagged '{}' {tags}", task.description()),
+                    None => format!("Removed the tags of '{}'", task.description()),
+                };
+                self.persistence.mark_dirty("set_tags");
+                self.save_document()?;
+                self.alerts.info(message);
+                self.tag_suggestions =
+                    self.document.collect_unique_tags(Configuration::suggest_from());
+            }
+            _ => {
+                *error = None;
+                prompt.input(key_event);
+                let text = prompt.lines().join(" ");
+                completion.update_suggestions(&text, &self.tag_suggestions);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys while find and replace is open: Enter moves from the text to find
+    /// to its replacement, then shows the number of changes and applies them
+    /// on a second Enter. ESC leaves the document as it is
+    fn handle_replace_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Replace { prompt, confirm },
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key_event.code {
+            KeyCode::Esc => self.close_modal(),
+            KeyCode::Enter if prompt.lines().len() < 2 => {
+                prompt.move_cursor(CursorMove::Bottom);
+                prompt.move_cursor(CursorMove::End);
+                prompt.insert_newline();
+            }
+            KeyCode::Enter => {
+                let (find, replace) = (prompt.lines()[0].clone(), prompt.lines()[1].clone());
+                let confirmed = *confirm;
+                if find.is_empty() {
+                    self.alerts.warn("Nothing to find");
+                    return Ok(());
+                }
+                self.ensure_document_loaded();
+                let options = ReplaceOptions::default();
+                if !confirmed {
+                    let count = self.document.preview_replace(&find, &replace, options).len();
+                    if count == 0 {
+                        self.alerts.info(format!("'{find}' not found"));
+                    } else {
+                        self.alerts.warn(format!("{count} change(s), press Enter again to apply"));
+                        self.confirm_replace();
+                    }
+                    return Ok(());
+                }
+                self.close_modal();
+                let changes = self.document.replace_text(&find, &replace, options);
+                self.persistence.mark_dirty("replace_text");
+                self.save_document()?;
+                self.alerts.info(format!("Replaced '{find}' in {} place(s)", changes.len()));
+            }
+            _ => {
+                *confirm = false;
+                _ = prompt.input(key_event);
+            }
+        }
+        Ok(())
+    }
+
+    /// The change count of the replacement was shown, the next Enter applies it
+    fn confirm_replace(&mut self) {
+        if let UiMode::Modal {
+            kind: Modal::Replace { confirm, .. },
+            ..
+        } = &mut self.mode
+        {
+            *confirm = true;
+        }
+    }
+
+    /// Keys while a selection is active: movement extends it, 'y' copies the
+    /// selected lines and ESC cancels
+    fn handle_selection_key(&mut self, key_event: KeyEvent) {
+        if key_event.kind != KeyEventKind::Press {
+            return;
+        }
+        let lines = self.selectable_lines();
+        let UiMode::Selecting(selection) = &mut self.mode else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => selection.up(),
+            KeyCode::Down | KeyCode::Char('j') => selection.down(lines.len()),
+            KeyCode::Char('y') => {
+                let selected = selection.selected(&lines);
+                match clipboard::copy(&selected.join("\n")) {
+                    Ok(()) => self.alerts.info(format!("Copied {} line(s)", selected.len())),
+                    Err(e) => self.alerts.error(format!("Copy failed: {e}")),
+                }
+                self.mode = UiMode::Normal;
+            }
+            KeyCode::Esc => self.mode = UiMode::Normal,
+            _ => {}
+        }
+    }
+
+    /// Move through the project picker, Enter filters the Tasks list by the
+    /// selected project
+    fn handle_picker_key(&mut self, key_event: KeyEvent) {
+        let UiMode::Modal {
+            kind: Modal::ProjectPicker(picker),
+            ..
+        } = &mut self.mode
+        else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => picker.up(),
+            KeyCode::Down | KeyCode::Char('j') => picker.down(),
+            KeyCode::Enter => {
+                self.project_filter = picker.selected().map(str::to_string);
+                self.close_modal();
+                self.select_visible_task();
+            }
+            KeyCode::Esc | KeyCode::Char('p') => self.close_modal(),
+            _ => {}
+        }
+    }
+
+    /// Keys while the date picker is open, a picked date becomes the due
+    /// date of the selected task
+    fn handle_calendar_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Calendar(calendar),
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match calendar.handle_key(key_event.code) {
+            CalendarAction::Open => {}
+            CalendarAction::Cancel => self.close_modal(),
+            CalendarAction::Pick(date) => {
+                self.close_modal();
+                self.ensure_document_loaded();
+                let Some(task) = self.document.tasks.get_mut(self.current_task_index) else {
+                    return Ok(());
+                };
+                task.add_tag(Tag::Due(date));
+                self.persistence.mark_dirty("set_due");
+                self.save_document()?;
+                self.alerts.info(format!("Due on {date}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys of the waiting list: 'f' notes a follow-up today, Enter stops
+    /// the selected task from waiting
+    fn handle_waiting_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Waiting(waiting),
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        let selected = waiting.selected();
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => waiting.up(),
+            KeyCode::Down | KeyCode::Char('j') => waiting.down(),
+            KeyCode::Esc | KeyCode::Char('a') => self.close_modal(),
+            KeyCode::Char('f') | KeyCode::Enter => {
+                let Some(index) = selected else {
+                    return Ok(());
+                };
+                if self.refuse_read_only() {
+                    return Ok(());
+                }
+                self.ensure_document_loaded();
+                let Some(task) = self.document.tasks.get_mut(index) else {
+                    return Ok(());
+                };
+                let message = if key_event.code == KeyCode::Enter {
+                    task.stop_waiting();
+                    format!("'{}' is no longer waiting", task.description())
+                } else {
+                    task.follow_up(&Date::now());
+                    format!("Followed up '{}' today", task.description())
+                };
+                self.persistence.mark_dirty("waiting");
+                self.save_document()?;
+                self.alerts.info(message);
+                let groups = self.document.waiting_by_person();
+                if let UiMode::Modal {
+                    kind: Modal::Waiting(waiting),
+                    ..
+                } = &mut self.mode
+                {
+                    waiting.refresh(groups);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keys of the duplicate review: 'k' keeps both tasks of the pair, 'm'
+    /// merges the second into the first, '1' or '2' deletes that one
+    fn handle_duplicates_key(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        let UiMode::Modal {
+            kind: Modal::Duplicates(review),
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(());
+        };
+        let Some((first, second)) = review.current() else {
+            return Ok(());
+        };
+        let removed = match key_event.code {
+            KeyCode::Char('k') => {
+                review.skip();
+                None
+            }
+            KeyCode::Char('m') => match self.document.merge_tasks(first, second) {
+                Ok(_) => {
+                    review.merged += 1;
+                    Some(second)
+                }
+                Err(error) => {
+                    self.alerts.warn(error);
+                    return Ok(());
+                }
+            },
+            KeyCode::Char('1') | KeyCode::Char('2') => {
+                let index = if key_event.code == KeyCode::Char('1') { first } else { second };
+                self.document.remove_task(index);
+                review.deleted += 1;
+                Some(index)
+            }
+            KeyCode::Esc => {
+                review.stop();
+                None
+            }
+            _ => return Ok(()),
+        };
+        if let Some(index) = removed {
+            review.removed(index);
+            self.persistence.mark_dirty("duplicates");
+            self.save_document()?;
+        }
+        if let UiMode::Modal {
+            kind: Modal::Duplicates(review),
+            ..
+        } = &self.mode
+            && review.current().is_none()
+        {
+            let summary = review.summary();
+            self.close_modal();
+            self.select_visible_task();
+            self.alerts.info(summary);
+        }
+        Ok(())
+    }
+
+    /// Resolve a changed note under an edit draft: keep the draft ('m'), take
+    /// the note from the document ('t') or look at both first ('b')
+    fn handle_conflict_key(&mut self, key_event: KeyEvent) {
+        let UiMode::Modal {
+            kind: Modal::Conflict(conflict),
+            ..
+        } = &mut self.mode
+        else {
+            return;
+        };
+        let note = &self.document.notes[conflict.note_index];
+        match key_event.code {
+            KeyCode::Char('m') => {
+                // Saving the draft now knowingly replaces the newer note
+                self.editing = Some(EditingNote::start(note));
+                self.close_modal();
+                self.alerts.info("Kept your draft, saving it replaces the newer note");
+            }
+            KeyCode::Char('t') => {
+                let editing = EditingNote::start(note);
+                let (title, content) = note_lines(note);
+                self.load_into_editor(title, &content);
+                self.editing = Some(editing);
+                self.close_modal();
+                self.alerts.info("Took the newer note, your draft was dropped");
+            }
+            KeyCode::Char('b') => conflict.show_both = !conflict.show_both,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Optional log file for looking into problems after the fact, like a task
+//! that went missing
+//!
+//! Records go to `LOG_FILE` in the base folder, one line each:
+//! `<unix seconds> <LEVEL> <target> <event> key=value ...`. Nothing is ever
+//! written to the terminal, which belongs to the interface.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use orgflow::Configuration;
+
+/// Log file in the base folder
+pub const LOG_FILE: &str = "orgflow.log";
+
+/// Size at which the log file is moved to `orgflow.log.1` and started anew
+pub const LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Appends the records of orgflow's own modules to a file
+pub struct FileLogger {
+    path: PathBuf,
+    level: LevelFilter,
+    max_bytes: u64,
+    /// Keeps the lines of concurrent records apart, e.g. of the writer thread
+    lock: Mutex<()>,
+}
+
+impl FileLogger {
+    pub fn new(path: PathBuf, level: LevelFilter, max_bytes: u64) -> Self {
+        Self {
+            path,
+            level,
+            max_bytes,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Where the previous log goes once the log file is full
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".1");
+        self.path.with_file_name(name)
+    }
+
+    fn append(&self, line: &str) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let size = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            fs::rename(&self.path, self.rotated_path())?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("orgflow")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let line = format!("{seconds} {} {} {}\n", record.level(), record.target(), record.args());
+        // A log that cannot be written must not disturb the work itself
+        let _ = self.append(&line);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Send the records to `LOG_FILE` in `basefolder` at the level of
+/// `Configuration::log_level`, with `off` nothing is set up at all
+pub fn init(basefolder: &str) {
+    let level = Configuration::log_level();
+    if level == LevelFilter::Off {
+        return;
+    }
+    let logger = FileLogger::new(Path::new(basefolder).join(LOG_FILE), level, LOG_MAX_BYTES);
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::Level;
+
+    use super::*;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("orgflow-log-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn record(logger: &FileLogger, level: Level, target: &str, message: &str) {
+        let args = format_args!("{message}");
+        logger.log(&Record::builder().level(level).target(target).args(args).build());
+    }
+
+    /// Lines of the log without their timestamps
+    fn logged(path: &Path) -> Vec<String> {
+        let text = fs::read_to_string(path).unwrap_or_default();
+        text.lines().map(|line| line.split_once(' ').unwrap().1.to_string()).collect()
+    }
+
+    #[test]
+    fn test_levels_decide_what_is_written() {
+        let path = temp_log("levels.log");
+        let logger = FileLogger::new(path.clone(), LevelFilter::Info, LOG_MAX_BYTES);
+        record(&logger, Level::Info, "orgflow::io", "load path=a.org tasks=2 notes=1");
+        record(&logger, Level::Debug, "orgflow::app", "add_task line=\"Buy milk\"");
+        record(&logger, Level::Warn, "orgflow::persistence", "write_failed path=a.org");
+        record(&logger, Level::Info, "crossterm", "not ours");
+        assert_eq!(
+            logged(&path),
+            [
+                "INFO orgflow::io load path=a.org tasks=2 notes=1",
+                "WARN orgflow::persistence write_failed path=a.org",
+            ]
+        );
+
+        let path = temp_log("off.log");
+        let logger = FileLogger::new(path.clone(), LevelFilter::Off, LOG_MAX_BYTES);
+        record(&logger, Level::Error, "orgflow::io", "load_failed");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_full_log_is_rotated() {
+        let path = temp_log("rotated.log");
+        let logger = FileLogger::new(path.clone(), LevelFilter::Debug, 60);
+        let _ = fs::remove_file(logger.rotated_path());
+        record(&logger, Level::Info, "orgflow::app", "mutation op=add_task revision=1");
+        record(&logger, Level::Info, "orgflow::app", "mutation op=set_due revision=2");
+        let rotated = logged(&logger.rotated_path());
+        assert_eq!(rotated, ["INFO orgflow::app mutation op=add_task revision=1"]);
+        assert_eq!(logged(&path), ["INFO orgflow::app mutation op=set_due revision=2"]);
+    }
+}
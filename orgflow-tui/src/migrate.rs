@@ -0,0 +1,268 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use orgflow::{DOCUMENT_POINTER, PROJECT_DEFAULTS};
+
+use crate::persistence::{AtomicFiles, Storage};
+
+/// File names in the base folder a document must not be renamed to
+const RESERVED_NAMES: [&str; 4] = ["session.json", "done.org", DOCUMENT_POINTER, PROJECT_DEFAULTS];
+
+/// Rename the document in `basefolder` from `from` to `to`
+///
+/// The document is copied to the new name first, then the new name is
+/// remembered in the base folder and only then the old file (and its
+/// backup) is moved away. Interrupting it at any point leaves a readable
+/// document behind, running it again with the same names picks up where
+/// it stopped. Returns the path of the renamed document.
+pub fn rename_document(basefolder: &Path, from: &str, to: &str) -> io::Result<PathBuf> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let to = to.trim();
+    if to.is_empty() || to.starts_with('.') || to.contains(['/', '\\']) {
+        return Err(invalid(format!("'{to}' is not a plain file name")));
+    }
+    if RESERVED_NAMES.contains(&to) || to.ends_with(".bak") || to.ends_with(".tmp") {
+        return Err(invalid(format!("'{to}' is used by orgflow itself")));
+    }
+    if to == from {
+        return Err(invalid(format!("The document is already called '{to}'")));
+    }
+
+    let old = basefolder.join(from);
+    let new = basefolder.join(to);
+    if let Some(contents) = AtomicFiles.read(&old)? {
+        match AtomicFiles.read(&new)? {
+            // Left over from an interrupted rename
+            Some(existing) if existing == contents => {}
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", new.display()),
+                ));
+            }
+            None => AtomicFiles.write(&new, &contents)?,
+        }
+        if AtomicFiles.read(&new)?.as_deref() != Some(contents.as_slice()) {
+            return Err(io::Error::other(format!("Copy to {} differs", new.display())));
+        }
+    }
+
+    AtomicFiles.write(&basefolder.join(DOCUMENT_POINTER), format!("{to}\n").as_bytes())?;
+
+    let old_backup = basefolder.join(crate::cli::backup_path(from));
+    let new_backup = basefolder.join(crate::cli::backup_path(to));
+    if old_backup.exists() && !new_backup.exists() {
+        fs::rename(&old_backup, &new_backup)?;
+    }
+    if old.exists() {
+        fs::remove_file(&old)?;
+    }
+    Ok(new)
+}
+
+/// Outcome of moving a base folder
+#[derive(Debug, Default, PartialEq)]
+pub struct Migration {
+    /// Files copied by this run
+    pub copied: usize,
+    /// Files found already copied, e.g. by an interrupted earlier run
+    pub resumed: usize,
+}
+
+/// Move every file below `from` (documents, session, backups, archive) to `to`
+///
+/// All files are copied and compared by count and content hash before the
+/// first original is deleted. A file in `to` with different contents stops
+/// the migration with the originals untouched. Running it again after an
+/// interruption skips the files that are already in place.
+pub fn migrate(from: &Path, to: &Path) -> io::Result<Migration> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if !from.is_dir() {
+        return Err(invalid(format!("{} is not a folder", from.display())));
+    }
+    fs::create_dir_all(to)?;
+    let (source, target) = (from.canonicalize()?, to.canonicalize()?);
+    if source.starts_with(&target) || target.starts_with(&source) {
+        // Only goes away if it was created just now
+        let _ = fs::remove_dir(&target);
+        return Err(invalid(format!(
+            "{} and {} must not contain each other",
+            source.display(),
+            target.display()
+        )));
+    }
+
+    let files = list_files(&source)?;
+    let mut migration = Migration::default();
+    for file in &files {
+        let (original, copy) = (source.join(file), target.join(file));
+        let contents = fs::read(&original)?;
+        match AtomicFiles.read(&copy)? {
+            Some(existing) if existing == contents => migration.resumed += 1,
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists with other contents", copy.display()),
+                ));
+            }
+            None => {
+                if let Some(parent) = copy.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                AtomicFiles.write(&copy, &contents)?;
+                migration.copied += 1;
+            }
+        }
+    }
+
+    // Verify everything before the first original goes away
+    let copied = files.iter().filter(|file| target.join(file).is_file()).count();
+    if copied != files.len() {
+        return Err(io::Error::other(format!(
+            "Only {copied} of {} files arrived in {}, nothing was deleted",
+            files.len(),
+            target.display()
+        )));
+    }
+    for file in &files {
+        if hash_file(&source.join(file))? != hash_file(&target.join(file))? {
+            return Err(io::Error::other(format!(
+                "{} differs after copying, nothing was deleted",
+                target.join(file).display()
+            )));
+        }
+    }
+
+    for file in &files {
+        fs::remove_file(source.join(file))?;
+    }
+    remove_empty_dirs(&source)?;
+    Ok(migration)
+}
+
+/// Paths of all files below `dir`, relative to it and sorted
+fn list_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&fs::read(path)?);
+    Ok(hasher.finish())
+}
+
+/// Remove `dir` and the folders below it, as long as they are empty
+fn remove_empty_dirs(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dirs(&entry.path())?;
+        }
+    }
+    if fs::read_dir(dir)?.next().is_none() {
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn basefolder(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("orgflow-migrate-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_rename_moves_document_and_backup() {
+        let dir = basefolder("rename");
+        fs::write(dir.join("refile.org"), "## Tasks\n").unwrap();
+        fs::write(dir.join("refile.org.bak"), "old").unwrap();
+
+        let path = rename_document(&dir, "refile.org", "inbox.org").unwrap();
+        assert_eq!(path, dir.join("inbox.org"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "## Tasks\n");
+        assert_eq!(fs::read_to_string(dir.join("inbox.org.bak")).unwrap(), "old");
+        assert!(!dir.join("refile.org").exists());
+        assert!(!dir.join("refile.org.bak").exists());
+        assert_eq!(fs::read_to_string(dir.join(DOCUMENT_POINTER)).unwrap(), "inbox.org\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rename_resumes_and_refuses_to_overwrite() {
+        let dir = basefolder("rename-resume");
+        fs::write(dir.join("refile.org"), "## Tasks\n").unwrap();
+        fs::write(dir.join("other.org"), "## Notes\n").unwrap();
+        assert!(rename_document(&dir, "refile.org", "other.org").is_err());
+        assert!(rename_document(&dir, "refile.org", "session.json").is_err());
+        assert!(rename_document(&dir, "refile.org", "../inbox.org").is_err());
+        assert!(dir.join("refile.org").exists());
+
+        // Interrupted after the copy, both files are still there
+        fs::write(dir.join("inbox.org"), "## Tasks\n").unwrap();
+        rename_document(&dir, "refile.org", "inbox.org").unwrap();
+        assert!(!dir.join("refile.org").exists());
+        assert_eq!(fs::read_to_string(dir.join("inbox.org")).unwrap(), "## Tasks\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_moves_everything() {
+        let from = basefolder("from");
+        let to = basefolder("to").join("nested");
+        fs::write(from.join("refile.org"), "## Tasks\n").unwrap();
+        fs::write(from.join("session.json"), "{}").unwrap();
+        fs::create_dir_all(from.join("trash")).unwrap();
+        fs::write(from.join("trash").join("old.org"), "gone").unwrap();
+
+        let migration = migrate(&from, &to).unwrap();
+        assert_eq!(migration, Migration { copied: 3, resumed: 0 });
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(to.join("session.json")).unwrap(), "{}");
+        assert_eq!(fs::read_to_string(to.join("trash").join("old.org")).unwrap(), "gone");
+        fs::remove_dir_all(to.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_resumes_and_keeps_originals_on_conflict() {
+        let from = basefolder("resume-from");
+        let to = basefolder("resume-to");
+        fs::write(from.join("refile.org"), "## Tasks\n").unwrap();
+        fs::write(from.join("done.org"), "[x] Done\n").unwrap();
+        fs::write(to.join("done.org"), "[x] Something else\n").unwrap();
+
+        assert!(migrate(&from, &to).is_err());
+        assert!(from.join("refile.org").exists());
+        assert!(from.join("done.org").exists());
+        assert!(migrate(&from, &from.join("inner")).is_err());
+
+        // Same contents count as already copied by an earlier run
+        fs::write(to.join("done.org"), "[x] Done\n").unwrap();
+        let migration = migrate(&from, &to).unwrap();
+        assert_eq!(migration, Migration { copied: 1, resumed: 1 });
+        assert!(!from.exists());
+        fs::remove_dir_all(&to).unwrap();
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// Where the persistence coordinator reads and writes whole files
+pub trait Storage: Send + Sync + 'static {
+    /// Current contents of `path`, `None` if it does not exist
+    fn read(&self, path: &Path) -> io::Result<Option<Vec<u8>>>;
+    /// Replace the contents of `path`
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+}
+
+/// Files on disk, replaced atomically by writing a temp file next to the
+/// target and renaming it over the target
+///
+/// Readers (like the background document loader) therefore never see a half
+/// written file.
+#[derive(Debug, Default)]
+pub struct AtomicFiles;
+
+impl AtomicFiles {
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        path.with_file_name(name)
+    }
+}
+
+impl Storage for AtomicFiles {
+    fn read(&self, path: &Path) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let temp_path = Self::temp_path(path);
+        {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temp_path, path)
+    }
+}
+
+/// Whether the in-memory document has changes that are not on disk yet
+///
+/// Every mutation bumps the revision, a successful write of a revision marks
+/// everything up to it as saved. A failed write keeps the state dirty and its
+/// error around until the next successful write of the same file, so that a
+/// failed archive write is not hidden by the document write that follows it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirtyState {
+    revision: u64,
+    saved: u64,
+    failed: BTreeMap<PathBuf, String>,
+}
+
+impl DirtyState {
+    /// Record a change of the document, returns the new revision
+    pub fn mark_dirty(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn write_succeeded(&mut self, path: &Path, revision: u64) {
+        self.saved = self.saved.max(revision);
+        self.failed.remove(path);
+    }
+
+    pub fn write_failed(&mut self, path: &Path, error: &io::Error) {
+        self.failed.insert(path.to_path_buf(), error.to_string());
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.saved < self.revision || !self.failed.is_empty()
+    }
+
+    /// Error of a failed write that was not followed by a successful one
+    pub fn error(&self) -> Option<&str> {
+        self.failed.values().next().map(String::as_str)
+    }
+}
+
+type Update = Box<dyn FnOnce(Option<Vec<u8>>) -> io::Result<Vec<u8>> + Send>;
+
+enum Operation {
+    /// Replace the file, later replacements of the same path supersede it
+    Replace(Vec<u8>),
+    /// Read-modify-write of the file, never coalesced
+    Update(Update),
+}
+
+struct Job {
+    path: PathBuf,
+    operation: Operation,
+    /// Document revision this write persists, `None` for untracked files
+    revision: Option<u64>,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// The worker is executing a job that is no longer in `jobs`
+    busy: bool,
+    /// Failed untracked writes since the last `flush`
+    errors: Vec<io::Error>,
+    dirty: DirtyState,
+    shutdown: bool,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Single owner of all file writes of the application
+///
+/// Session and document writes are queued and executed one after another by
+/// a background thread, so writes to the same path can never interleave and
+/// are applied in the order they were submitted. A replacement that is still
+/// waiting in the queue is overwritten by a newer one for the same path, which
+/// keeps rapid session saves from piling up behind a slow disk.
+///
+/// Document writes are tracked in a `DirtyState`, the single source for
+/// whether there are unsaved changes, including their errors. Errors of other
+/// writes (e.g. the session) are collected and reported by the next `flush`.
+pub struct Persistence {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Persistence {
+    pub fn new() -> Self {
+        Self::with_storage(AtomicFiles)
+    }
+
+    pub fn with_storage<S: Storage>(storage: S) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || Self::work(&shared, &storage))
+        };
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue replacing the contents of `path`
+    pub fn save(&self, path: impl Into<PathBuf>, contents: Vec<u8>) {
+        self.enqueue_replace(path.into(), contents, None);
+    }
+
+    /// Queue a write of the document that persists all changes so far
+    pub fn save_document(&self, path: impl Into<PathBuf>, contents: Vec<u8>) {
+        let revision = self.shared.lock().dirty.revision();
+        self.enqueue_replace(path.into(), contents, Some(revision));
+    }
+
+    fn enqueue_replace(&self, path: PathBuf, contents: Vec<u8>, revision: Option<u64>) {
+        let mut queue = self.shared.lock();
+        // Only the last queued job of a path may be coalesced, an update
+        // in between has to see the contents that were saved before it
+        let last = queue.jobs.iter_mut().rev().find(|job| job.path == path);
+        if let Some(Job {
+            operation: Operation::Replace(queued),
+            revision: queued_revision,
+            ..
+        }) = last
+        {
+            *queued = contents;
+            *queued_revision = revision.or(*queued_revision);
+        } else {
+            queue.jobs.push_back(Job {
+                path,
+                operation: Operation::Replace(contents),
+                revision,
+            });
+        }
+        self.shared.changed.notify_all();
+    }
+
+    /// Queue a read-modify-write of `path` that persists all document
+    /// changes so far
+    ///
+    /// `update` receives the contents after all previously queued writes of
+    /// the path were applied (`None` if the file does not exist) and returns
+    /// the new contents.
+    pub fn update_document<F>(&self, path: impl Into<PathBuf>, update: F)
+    where
+        F: FnOnce(Option<Vec<u8>>) -> io::Result<Vec<u8>> + Send + 'static,
+    {
+        let mut queue = self.shared.lock();
+        let revision = Some(queue.dirty.revision());
+        queue.jobs.push_back(Job {
+            path: path.into(),
+            operation: Operation::Update(Box::new(update)),
+            revision,
+        });
+        self.shared.changed.notify_all();
+    }
+
+    /// Record that the in-memory document changed by `operation`
+    pub fn mark_dirty(&self, operation: &str) {
+        let revision = self.shared.lock().dirty.mark_dirty();
+        log::info!("mutation op={operation} revision={revision}");
+    }
+
+    /// Snapshot of the unsaved changes state
+    pub fn dirty_state(&self) -> DirtyState {
+        self.shared.lock().dirty.clone()
+    }
+
+    /// Block until every queued write is on disk
+    ///
+    /// Returns the first error of the untracked writes since the last flush,
+    /// failed document writes show up in `dirty_state` instead.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut queue = self.shared.lock();
+        while !queue.jobs.is_empty() || queue.busy {
+            queue = self
+                .shared
+                .changed
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        let mut errors = std::mem::take(&mut queue.errors);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.swap_remove(0)),
+        }
+    }
+
+    fn work(shared: &Shared, storage: &dyn Storage) {
+        loop {
+            let job = {
+                let mut queue = shared.lock();
+                loop {
+                    if let Some(job) = queue.jobs.pop_front() {
+                        queue.busy = true;
+                        break job;
+                    }
+                    if queue.shutdown {
+                        return;
+                    }
+                    queue = shared.changed.wait(queue).unwrap_or_else(|e| e.into_inner());
+                }
+            };
+
+            let result = match job.operation {
+                Operation::Replace(contents) => storage.write(&job.path, &contents),
+                Operation::Update(update) => storage
+                    .read(&job.path)
+                    .and_then(update)
+                    .and_then(|contents| storage.write(&job.path, &contents)),
+            };
+
+            match &result {
+                Ok(()) => log::debug!("write path={}", job.path.display()),
+                Err(e) => log::warn!(
+                    "write_failed path={} error={:?}",
+                    job.path.display(),
+                    e.to_string()
+                ),
+            }
+            let mut queue = shared.lock();
+            queue.busy = false;
+            match (result, job.revision) {
+                (Ok(()), Some(revision)) => queue.dirty.write_succeeded(&job.path, revision),
+                (Ok(()), None) => {}
+                (Err(e), Some(_)) => queue.dirty.write_failed(&job.path, &e),
+                (Err(e), None) => queue.errors.push(e),
+            }
+            shared.changed.notify_all();
+        }
+    }
+}
+
+impl Default for Persistence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Persistence {
+    fn drop(&mut self) {
+        // The worker drains the queue before it stops
+        self.shared.lock().shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl fmt::Debug for Persistence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queue = self.shared.lock();
+        f.debug_struct("Persistence")
+            .field("queued", &queue.jobs.len())
+            .field("busy", &queue.busy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::time::Duration;
+
+    /// Signals the start of a write and receives the go ahead to finish it
+    type Gate = (Sender<()>, Arc<Mutex<Receiver<()>>>);
+
+    /// In-memory storage that records every write and sleeps while writing
+    #[derive(Clone, Default)]
+    struct SlowStorage {
+        files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+        log: Arc<Mutex<Vec<(PathBuf, String)>>>,
+        gate: Option<Gate>,
+    }
+
+    impl SlowStorage {
+        fn gated() -> (Self, Receiver<()>, Sender<()>) {
+            let (started_tx, started_rx) = mpsc::channel();
+            let (release_tx, release_rx) = mpsc::channel();
+            let storage = Self {
+                gate: Some((started_tx, Arc::new(Mutex::new(release_rx)))),
+                ..Default::default()
+            };
+            (storage, started_rx, release_tx)
+        }
+
+        fn log(&self) -> Vec<(String, String)> {
+            let log = self.log.lock().unwrap();
+            log.iter()
+                .map(|(p, c)| (p.to_str().unwrap().to_string(), c.clone()))
+                .collect()
+        }
+    }
+
+    impl Storage for SlowStorage {
+        fn read(&self, path: &Path) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.files.lock().unwrap().get(path).cloned())
+        }
+
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            if let Some((started, release)) = &self.gate {
+                started.send(()).unwrap();
+                release.lock().unwrap().recv().unwrap();
+            } else {
+                thread::sleep(Duration::from_millis(5));
+            }
+            if path.starts_with("fail") {
+                return Err(io::Error::other("disk full"));
+            }
+            let text = String::from_utf8(contents.to_vec()).unwrap();
+            self.log.lock().unwrap().push((path.to_path_buf(), text));
+            self.files.lock().unwrap().insert(path.to_path_buf(), contents.to_vec());
+            Ok(())
+        }
+    }
+
+    fn entries(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(p, c)| (p.to_string(), c.to_string())).collect()
+    }
+
+    #[test]
+    fn test_writes_keep_submission_order() {
+        let storage = SlowStorage::default();
+        let persistence = Persistence::with_storage(storage.clone());
+        persistence.save("a", b"1".to_vec());
+        persistence.save("b", b"1".to_vec());
+        persistence.update_document("a", |old| {
+            let mut contents = old.unwrap_or_default();
+            contents.extend_from_slice(b"2");
+            Ok(contents)
+        });
+        persistence.flush().unwrap();
+        assert_eq!(storage.log(), entries(&[("a", "1"), ("b", "1"), ("a", "12")]));
+    }
+
+    #[test]
+    fn test_queued_saves_are_coalesced() {
+        let (storage, started, release) = SlowStorage::gated();
+        let persistence = Persistence::with_storage(storage.clone());
+
+        // Hold the worker inside the first write while more saves come in
+        persistence.save("session", b"1".to_vec());
+        started.recv().unwrap();
+        persistence.save("session", b"2".to_vec());
+        persistence.save("document", b"x".to_vec());
+        persistence.save("session", b"3".to_vec());
+
+        for _ in 0..3 {
+            release.send(()).unwrap();
+        }
+        persistence.flush().unwrap();
+        assert_eq!(
+            storage.log(),
+            entries(&[("session", "1"), ("session", "3"), ("document", "x")])
+        );
+    }
+
+    #[test]
+    fn test_update_is_not_coalesced_away() {
+        let (storage, started, release) = SlowStorage::gated();
+        let persistence = Persistence::with_storage(storage.clone());
+
+        persistence.save("doc", b"a".to_vec());
+        started.recv().unwrap();
+        persistence.save("doc", b"b".to_vec());
+        persistence.update_document("doc", |old| {
+            let mut contents = old.unwrap_or_default();
+            contents.extend_from_slice(b"+");
+            Ok(contents)
+        });
+        // Must not replace "b", the update has to see it first
+        persistence.save("doc", b"c".to_vec());
+
+        for _ in 0..4 {
+            release.send(()).unwrap();
+        }
+        persistence.flush().unwrap();
+        assert_eq!(
+            storage.log(),
+            entries(&[("doc", "a"), ("doc", "b"), ("doc", "b+"), ("doc", "c")])
+        );
+    }
+
+    #[test]
+    fn test_flush_reports_errors_once() {
+        let storage = SlowStorage::default();
+        let persistence = Persistence::with_storage(storage.clone());
+        persistence.save("fail", b"1".to_vec());
+        persistence.save("ok", b"1".to_vec());
+        assert!(persistence.flush().is_err());
+        assert_eq!(storage.log(), entries(&[("ok", "1")]));
+        assert!(persistence.flush().is_ok());
+    }
+
+    #[test]
+    fn test_drop_drains_queue() {
+        let storage = SlowStorage::default();
+        {
+            let persistence = Persistence::with_storage(storage.clone());
+            for i in 0..3 {
+                persistence.update_document("counter", move |_| Ok(i.to_string().into_bytes()));
+            }
+        }
+        assert_eq!(
+            storage.log(),
+            entries(&[("counter", "0"), ("counter", "1"), ("counter", "2")])
+        );
+    }
+
+    #[test]
+    fn test_dirty_state_transitions() {
+        let doc = Path::new("doc");
+        let archive = Path::new("archive");
+        let mut state = DirtyState::default();
+        assert!(!state.is_dirty());
+
+        // Clean -> dirty -> clean
+        let first = state.mark_dirty();
+        assert!(state.is_dirty());
+        state.write_succeeded(doc, first);
+        assert!(!state.is_dirty());
+
+        // A write of an older revision does not cover newer changes
+        let second = state.mark_dirty();
+        let third = state.mark_dirty();
+        state.write_succeeded(doc, second);
+        assert!(state.is_dirty());
+        state.write_succeeded(doc, third);
+        assert!(!state.is_dirty());
+
+        // A failed write keeps it dirty until the file is written again
+        let fourth = state.mark_dirty();
+        state.write_failed(archive, &io::Error::other("disk full"));
+        state.write_succeeded(doc, fourth);
+        assert!(state.is_dirty());
+        assert_eq!(state.error(), Some("disk full"));
+        state.write_succeeded(archive, fourth);
+        assert!(!state.is_dirty());
+        assert_eq!(state.error(), None);
+    }
+
+    #[test]
+    fn test_document_writes_clear_dirty_state() {
+        let (storage, started, release) = SlowStorage::gated();
+        let persistence = Persistence::with_storage(storage.clone());
+
+        persistence.mark_dirty("test");
+        persistence.save_document("doc", b"1".to_vec());
+        started.recv().unwrap();
+        // Changed again while the first write is in flight
+        persistence.mark_dirty("test");
+        release.send(()).unwrap();
+        persistence.save("session", b"s".to_vec());
+        release.send(()).unwrap();
+        persistence.flush().unwrap();
+        assert!(persistence.dirty_state().is_dirty());
+
+        persistence.save_document("doc", b"2".to_vec());
+        release.send(()).unwrap();
+        persistence.flush().unwrap();
+        assert!(!persistence.dirty_state().is_dirty());
+    }
+
+    #[test]
+    fn test_failed_document_write_stays_dirty() {
+        let persistence = Persistence::with_storage(SlowStorage::default());
+        persistence.mark_dirty("test");
+        persistence.save_document("fail", b"1".to_vec());
+        // Reported through the dirty state rather than by flush
+        persistence.flush().unwrap();
+        let state = persistence.dirty_state();
+        assert!(state.is_dirty());
+        assert_eq!(state.error(), Some("disk full"));
+
+        // Untracked writes do not touch the document state
+        persistence.save("session", b"s".to_vec());
+        persistence.flush().unwrap();
+        assert!(persistence.dirty_state().is_dirty());
+
+        persistence.update_document("doc", |_| Ok(b"2".to_vec()));
+        persistence.flush().unwrap();
+        assert!(persistence.dirty_state().is_dirty());
+        assert_eq!(persistence.dirty_state().error(), Some("disk full"));
+    }
+
+    #[test]
+    fn test_atomic_files_replace_and_read() {
+        let dir = std::env::temp_dir().join(format!("orgflow-persist-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(AtomicFiles.read(&path).unwrap(), None);
+        AtomicFiles.write(&path, b"first").unwrap();
+        AtomicFiles.write(&path, b"second").unwrap();
+        assert_eq!(AtomicFiles.read(&path).unwrap(), Some(b"second".to_vec()));
+        assert!(!AtomicFiles::temp_path(&path).exists());
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::persistence::{AtomicFiles, Storage};
+use crate::session::SessionState;
+
+/// Folder in the base folder the drafts are recovered to
+pub const RECOVERED_DIR: &str = "recovered";
+
+/// Draft fields of the session file and the name of their recovered file
+const DRAFT_FIELDS: [(&str, &str); 4] = [
+    ("title_content", "title"),
+    ("note_content", "note"),
+    ("scratchpad_content", "scratchpad"),
+    ("note_scratchpad_content", "note-scratchpad"),
+];
+
+/// Non-empty drafts of a session file by name, including stashed drafts
+///
+/// The JSON is only read as far as the draft fields go, so a session that
+/// no longer loads because of other fields still gives up its drafts.
+/// Fields of an unexpected shape and lines that are not text are skipped.
+pub fn salvage_drafts(json: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let session: Value =
+        serde_json::from_str(json).map_err(|e| format!("Session file is not JSON: {e}"))?;
+    let mut drafts: Vec<(String, Vec<String>)> = DRAFT_FIELDS
+        .iter()
+        .map(|(field, name)| (name.to_string(), lines(&session[field])))
+        .collect();
+
+    if let Some(stashed) = session["draft_history"]["drafts"].as_array() {
+        for (i, draft) in stashed.iter().enumerate() {
+            drafts.push((format!("stash-{}-title", i + 1), lines(&draft["title"])));
+            drafts.push((format!("stash-{}-note", i + 1), lines(&draft["content"])));
+        }
+    }
+    drafts.retain(|(_, lines)| lines.iter().any(|line| !line.trim().is_empty()));
+    Ok(drafts)
+}
+
+/// Text lines of a JSON array, empty for anything else
+fn lines(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|lines| lines.iter().filter_map(|line| line.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// Write the drafts of the session file at `session` into `dir` as text
+/// files named after the draft and prefixed with `stamp`
+///
+/// Returns the paths of the written files.
+pub fn dump(session: &Path, dir: &Path, stamp: u64) -> io::Result<Vec<PathBuf>> {
+    let json = fs::read_to_string(session)?;
+    let drafts =
+        salvage_drafts(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_drafts(dir, stamp, &drafts)
+}
+
+fn write_drafts(
+    dir: &Path,
+    stamp: u64,
+    drafts: &[(String, Vec<String>)],
+) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for (name, lines) in drafts {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{stamp}-{name}.txt"));
+        AtomicFiles.write(&path, format!("{}\n", lines.join("\n")).as_bytes())?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Load the text file at `file` into the note draft of the session file at
+/// `session`, keeping the rest of the session
+///
+/// A note draft that is already there is written to `dir` first and its
+/// path returned. A session that cannot be loaded is moved aside like on
+/// startup and the text lands in a fresh one.
+pub fn restore(
+    session: &Path,
+    file: &Path,
+    dir: &Path,
+    stamp: u64,
+) -> io::Result<Option<PathBuf>> {
+    let text = fs::read_to_string(file)?;
+    let mut state = match AtomicFiles.read(session)? {
+        None => SessionState::default(),
+        Some(json) => match serde_json::from_slice::<SessionState>(&json) {
+            Ok(state) => state,
+            Err(_) => {
+                fs::rename(session, crate::session::corrupt_path(session))?;
+                SessionState::default()
+            }
+        },
+    };
+
+    let mut kept = None;
+    if state.note_content.iter().any(|line| !line.trim().is_empty()) {
+        let previous = [("note".to_string(), state.note_content.clone())];
+        kept = write_drafts(dir, stamp, &previous)?.pop();
+    }
+
+    state.note_content = text.lines().map(String::from).collect();
+    state.note_cursor_pos = (0, 0);
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    AtomicFiles.write(session, json.as_bytes())?;
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/corrupted_session.json");
+
+    #[test]
+    fn test_salvage_drafts_from_a_session_that_no_longer_loads() {
+        assert!(serde_json::from_str::<SessionState>(FIXTURE).is_err());
+
+        let drafts = salvage_drafts(FIXTURE).unwrap();
+        let names: Vec<&str> = drafts.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["title", "note", "scratchpad", "stash-1-title", "stash-1-note"]);
+        assert_eq!(drafts[1].1, ["Agreed on the release date", "Alex sends the slides"]);
+        assert_eq!(drafts[4].1, ["milk", "bread"]);
+
+        assert!(salvage_drafts("{\"title_content\": [").is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// What Enter in the scratchpad creates, Ctrl+T cycles through the modes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScratchpadMode {
+    #[default]
+    Task,
+    Note,
+}
+
+impl ScratchpadMode {
+    /// Title of the scratchpad block
+    pub fn title(&self) -> &'static str {
+        match self {
+            ScratchpadMode::Task => "Task",
+            ScratchpadMode::Note => "Note",
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::ops::RangeInclusive;
+
+/// Line-wise selection in a read-only pane
+///
+/// The anchor stays where the selection was started, the cursor moves with
+/// the movement keys. The selection spans both, whichever comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineSelection {
+    anchor: usize,
+    cursor: usize,
+}
+
+impl LineSelection {
+    /// Start a selection at line `at` of a pane with `len` lines
+    pub fn new(at: usize, len: usize) -> Self {
+        let at = at.min(len.saturating_sub(1));
+        Self {
+            anchor: at,
+            cursor: at,
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn down(&mut self, len: usize) {
+        self.cursor = (self.cursor + 1).min(len.saturating_sub(1));
+    }
+
+    /// Selected line indices, first to last
+    pub fn range(&self) -> RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.range().contains(&line)
+    }
+
+    /// The selected lines, ignoring the part beyond the end of `lines`
+    pub fn selected<'a>(&self, lines: &'a [String]) -> &'a [String] {
+        let start = (*self.range().start()).min(lines.len());
+        let end = (*self.range().end() + 1).min(lines.len());
+        &lines[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("line {i}")).collect()
+    }
+
+    #[test]
+    fn test_extend_downwards_from_anchor() {
+        let mut selection = LineSelection::new(1, 5);
+        assert_eq!(selection.range(), 1..=1);
+        selection.down(5);
+        selection.down(5);
+        assert_eq!(selection.range(), 1..=3);
+        assert_eq!(selection.selected(&lines(5)), ["line 1", "line 2", "line 3"]);
+    }
+
+    #[test]
+    fn test_extend_upwards_past_anchor() {
+        let mut selection = LineSelection::new(2, 5);
+        selection.down(5);
+        selection.up();
+        selection.up();
+        selection.up();
+        assert_eq!(selection.range(), 0..=2);
+        assert!(selection.contains(0) && selection.contains(2));
+        assert!(!selection.contains(3));
+    }
+
+    #[test]
+    fn test_clamping() {
+        let mut selection = LineSelection::new(9, 3);
+        assert_eq!(selection.range(), 2..=2);
+        selection.down(3);
+        assert_eq!(selection.range(), 2..=2);
+        selection.up();
+        selection.up();
+        selection.up();
+        assert_eq!(selection.range(), 0..=2);
+
+        // Lines went away underneath the selection
+        let selection = LineSelection::new(4, 6);
+        assert!(selection.selected(&lines(2)).is_empty());
+        assert_eq!(LineSelection::new(0, 0).range(), 0..=0);
+    }
+}
@@ -1,11 +1,27 @@
+use orgflow::Date;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tui_textarea::TextArea;
 
-use crate::{AppTab, NoteFocus};
+use crate::drafts::DraftHistory;
+use crate::editing::EditingNote;
+use crate::persistence::Persistence;
+use crate::scratchpad::ScratchpadMode;
+use crate::app::{AppTab, NoteFocus};
+
+/// Name of the session file in the base folder
+pub const SESSION_FILE: &str = "session.json";
+
+/// Where a session file that cannot be loaded is moved to
+pub fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".corrupt");
+    PathBuf::from(name)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionState {
@@ -15,21 +31,36 @@ pub struct SessionState {
     pub current_task_index: usize,
     pub note_focus: NoteFocus,
     pub scratchpad_visible: bool,
+    #[serde(default)]
+    pub scratchpad_mode: ScratchpadMode,
     
     // Draft Content (unsaved work)
     pub title_content: Vec<String>,
     pub note_content: Vec<String>,
     pub scratchpad_content: Vec<String>,
+    #[serde(default)]
+    pub note_scratchpad_content: Vec<String>,
+    /// Drafts stashed with Ctrl+N, missing in sessions of older versions
+    #[serde(default)]
+    pub draft_history: DraftHistory,
+    /// Note of the document the title and content draft is an edit of
+    #[serde(default)]
+    pub editing: Option<EditingNote>,
     
     // Cursor positions for text areas
     pub title_cursor_pos: (usize, usize),
     pub note_cursor_pos: (usize, usize),
     pub scratchpad_cursor_pos: (usize, usize),
+    #[serde(default)]
+    pub note_scratchpad_cursor_pos: (usize, usize),
     
     // File metadata
     pub document_path: String,
     pub last_save_timestamp: u64,
     pub has_unsaved_changes: bool,
+    /// Day the app was last started on (`YYYY-MM-DD`), for the daily rollover
+    #[serde(default)]
+    pub last_opened_date: Option<String>,
 }
 
 impl Default for SessionState {
@@ -40,15 +71,21 @@ impl Default for SessionState {
             current_task_index: 0,
             note_focus: NoteFocus::Title,
             scratchpad_visible: false,
+            scratchpad_mode: ScratchpadMode::default(),
             title_content: Vec::new(),
             note_content: Vec::new(),
             scratchpad_content: Vec::new(),
+            note_scratchpad_content: Vec::new(),
+            draft_history: DraftHistory::default(),
+            editing: None,
             title_cursor_pos: (0, 0),
             note_cursor_pos: (0, 0),
             scratchpad_cursor_pos: (0, 0),
+            note_scratchpad_cursor_pos: (0, 0),
             document_path: String::new(),
             last_save_timestamp: 0,
             has_unsaved_changes: false,
+            last_opened_date: None,
         }
     }
 }
@@ -78,6 +115,9 @@ impl SessionManager {
     }
 
     /// Load session state from file, or create default if file doesn't exist
+    ///
+    /// A corrupted session file is moved aside to `corrupt_path` and reported
+    /// as an error, the session starts fresh from the default state then.
     pub fn load_session(&mut self) -> io::Result<SessionState> {
         // Always start with a valid default state
         self.state = SessionState::default();
@@ -89,11 +129,22 @@ impl SessionManager {
                         Ok(state) => {
                             // Only use loaded state if it's valid
                             self.state = state.clone();
+                            log::info!("session_restore path={}", self.session_file_path);
                         }
                         Err(e) => {
-                            // If JSON parsing fails, delete corrupted file and start fresh
-                            eprintln!("Warning: Corrupted session file, starting fresh: {}", e);
-                            let _ = fs::remove_file(&self.session_file_path);
+                            // Keep the corrupted file for `orgflow session dump`
+                            let path = Path::new(&self.session_file_path);
+                            let _ = fs::rename(path, corrupt_path(path));
+                            let error = e.to_string();
+                            log::warn!("session_corrupt path={} error={error:?}", path.display());
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "Corrupted session file, moved aside, recover its drafts \
+                                     with `orgflow session dump`: {}",
+                                    e
+                                ),
+                            ));
                         }
                     }
                 }
@@ -106,15 +157,20 @@ impl SessionManager {
     }
 
     /// Update session state from current app state
+    #[allow(clippy::too_many_arguments)]
     pub fn update_state(&mut self, 
         current_tab: &AppTab,
         current_note_index: usize,
         current_task_index: usize,
         note_focus: &NoteFocus,
         scratchpad_visible: bool,
+        scratchpad_mode: ScratchpadMode,
         title: &TextArea<'static>,
         note: &TextArea<'static>,
         scratchpad: &TextArea<'static>,
+        note_scratchpad: &TextArea<'static>,
+        draft_history: &DraftHistory,
+        editing: Option<&EditingNote>,
         document_path: &str,
         has_unsaved_changes: bool,
     ) {
@@ -124,16 +180,22 @@ impl SessionManager {
         self.state.current_task_index = current_task_index;
         self.state.note_focus = note_focus.clone();
         self.state.scratchpad_visible = scratchpad_visible;
+        self.state.scratchpad_mode = scratchpad_mode;
 
         // Update draft content
         self.state.title_content = title.lines().iter().map(|s| s.to_string()).collect();
         self.state.note_content = note.lines().iter().map(|s| s.to_string()).collect();
         self.state.scratchpad_content = scratchpad.lines().iter().map(|s| s.to_string()).collect();
+        self.state.note_scratchpad_content =
+            note_scratchpad.lines().iter().map(|s| s.to_string()).collect();
+        self.state.draft_history = draft_history.clone();
+        self.state.editing = editing.cloned();
         
         // Update cursor positions
         self.state.title_cursor_pos = title.cursor();
         self.state.note_cursor_pos = note.cursor();
         self.state.scratchpad_cursor_pos = scratchpad.cursor();
+        self.state.note_scratchpad_cursor_pos = note_scratchpad.cursor();
 
         // Update metadata
         self.state.document_path = document_path.to_string();
@@ -145,6 +207,18 @@ impl SessionManager {
         self.needs_save = true;
     }
 
+    /// Day the app was last started on, `None` for a new or older session
+    pub fn last_opened(&self) -> Option<Date> {
+        let day = self.state.last_opened_date.as_deref()?;
+        Date::from_str(day).ok()
+    }
+
+    /// Remember `day` as the day the app was last started on
+    pub fn set_last_opened(&mut self, day: &Date) {
+        self.state.last_opened_date = Some(day.to_string());
+        self.needs_save = true;
+    }
+
     /// Check if session should be saved based on debounce logic
     pub fn should_save(&self) -> bool {
         if !self.needs_save {
@@ -165,8 +239,8 @@ impl SessionManager {
         false
     }
 
-    /// Save session state to file (async-safe)
-    pub fn save_session(&mut self) -> io::Result<()> {
+    /// Hand the session state to the persistence coordinator for saving
+    pub fn save_session(&mut self, persistence: &Persistence) -> io::Result<()> {
         if !self.needs_save {
             return Ok(());
         }
@@ -177,21 +251,11 @@ impl SessionManager {
             .unwrap_or_default()
             .as_secs();
 
-        // Serialize to JSON
+        // Serialize to JSON, the coordinator takes care of the atomic write
         let json_content = serde_json::to_string_pretty(&self.state)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        // Atomic write: write to temp file first, then rename
-        let temp_path = format!("{}.tmp", self.session_file_path);
-        
-        {
-            let mut file = fs::File::create(&temp_path)?;
-            file.write_all(json_content.as_bytes())?;
-            file.flush()?;
-        }
-
-        // Atomic rename
-        fs::rename(&temp_path, &self.session_file_path)?;
+        persistence.save(&self.session_file_path, json_content.into_bytes());
+        log::info!("session_save path={}", self.session_file_path);
 
         // Reset counters
         self.needs_save = false;
@@ -201,33 +265,11 @@ impl SessionManager {
     }
 
     /// Force save session (for app exit)
-    pub fn force_save(&mut self) -> io::Result<()> {
+    pub fn force_save(&mut self, persistence: &Persistence) -> io::Result<()> {
         self.needs_save = true;
-        self.save_session()
-    }
-
-    /// Get current session state
-    pub fn get_state(&self) -> &SessionState {
-        &self.state
+        self.save_session(persistence)
     }
 
-    /// Check if there are unsaved drafts that would be lost
-    pub fn has_unsaved_drafts(&self) -> bool {
-        !self.state.title_content.is_empty() || 
-        !self.state.note_content.is_empty() || 
-        !self.state.scratchpad_content.is_empty() ||
-        self.state.has_unsaved_changes
-    }
-
-    /// Create TextArea from saved content
-    pub fn restore_textarea(content: &[String]) -> TextArea<'static> {
-        if content.is_empty() {
-            TextArea::default()
-        } else {
-            TextArea::from(content.to_vec())
-        }
-    }
-    
     /// Create TextArea from saved content and restore cursor position
     pub fn restore_textarea_with_cursor(content: &[String], cursor_pos: (usize, usize)) -> TextArea<'static> {
         let mut textarea = if content.is_empty() {
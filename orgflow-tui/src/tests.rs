@@ -0,0 +1,1456 @@
+use orgflow::{
+    Configuration, Date, DefaultsMap, Note, OnComplete, OrgDocument, ReadOnlyReason, Rollover,
+    TITLE_MAX_LEN, Task,
+};
+use std::str::FromStr;
+
+use orgflow::testing::pin_today;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Rect;
+use ratatui::prelude::Color;
+use ratatui::style::Style;
+use ratatui::widgets::Widget;
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::alerts::AlertLevel;
+use crate::app::{App, AppTab, Modal, UiMode};
+use crate::editing::{DraftConflict, EditingNote};
+use crate::hints::HintContext;
+use crate::scratchpad::ScratchpadMode;
+use crate::session::{SessionManager, SessionState};
+use crate::views::tasks::{stale_style, task_metadata_lines};
+
+fn app_with(tasks: usize, notes: usize) -> App {
+    let mut document = OrgDocument::default();
+    for i in 0..tasks {
+        document.push_task(Task::from_str(&format!("Task {i}")).unwrap());
+    }
+    for i in 0..notes {
+        document.push_note(Note::with(format!("Note {i}"), vec![]));
+    }
+    let session_path = std::env::temp_dir().join("orgflow-tui-test-session.json");
+    let session_manager = SessionManager::new(session_path.to_str().unwrap().to_string());
+    App::from_state(
+        document,
+        String::new(),
+        session_manager,
+        SessionState::default(),
+    )
+}
+
+/// Point the app to a throwaway document file so that saving works
+fn with_temp_document(mut app: App, name: &str) -> App {
+    let dir = std::env::temp_dir().join(format!("orgflow-tui-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    app.document_path = dir.join(name).to_str().unwrap().to_string();
+    app
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        press(app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+}
+
+fn press(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    app.handle_key_event(KeyEvent::new(code, modifiers)).unwrap();
+}
+
+/// Popup on top, if any
+fn modal(app: &App) -> Option<&Modal> {
+    match &app.mode {
+        UiMode::Modal { kind, .. } => Some(kind),
+        _ => None,
+    }
+}
+
+fn conflict(app: &App) -> Option<&DraftConflict> {
+    match modal(app) {
+        Some(Modal::Conflict(conflict)) => Some(conflict),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_cycle_tabs_both_ways() {
+    let mut app = app_with(1, 1);
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(matches!(app.current_tab, AppTab::Viewer));
+    press(&mut app, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+    press(&mut app, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    assert!(matches!(app.current_tab, AppTab::Tasks));
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+}
+
+#[test]
+fn test_direct_tab_jumps() {
+    let mut app = app_with(1, 1);
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    assert!(matches!(app.current_tab, AppTab::Tasks));
+    press(&mut app, KeyCode::Char('2'), KeyModifiers::ALT);
+    assert!(matches!(app.current_tab, AppTab::Viewer));
+    press(&mut app, KeyCode::F(1), KeyModifiers::NONE);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+}
+
+#[test]
+fn test_shift_arrows_move_the_shown_note() {
+    let mut app = with_temp_document(app_with(0, 3), "move_note.md");
+    press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+    let titles = |app: &App| -> Vec<String> {
+        app.document.notes.iter().map(|note| note.title().to_string()).collect()
+    };
+    press(&mut app, KeyCode::Down, KeyModifiers::SHIFT);
+    press(&mut app, KeyCode::Down, KeyModifiers::SHIFT);
+    assert_eq!(titles(&app), ["Note 1", "Note 2", "Note 0"]);
+    // The Viewer follows the note, which cannot go further down
+    assert_eq!(app.current_note_index, 2);
+    press(&mut app, KeyCode::Down, KeyModifiers::SHIFT);
+    press(&mut app, KeyCode::Up, KeyModifiers::SHIFT);
+    assert_eq!(titles(&app), ["Note 1", "Note 0", "Note 2"]);
+    assert_eq!(app.current_note_index, 1);
+
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    let order: Vec<&str> = saved.lines().filter(|line| line.starts_with("### ")).collect();
+    assert_eq!(order, ["### Note 1", "### Note 0", "### Note 2"]);
+}
+
+#[test]
+fn test_saving_wraps_note_content_at_the_configured_width() {
+    let mut app = with_temp_document(app_with(0, 0), "wrapped_note.md");
+    let long = "Ask the landlord about the heating before the winter starts";
+    app.document.push_note(Note::with("Flat".to_string(), vec![long.to_string()]));
+    app.wrap_width = 30;
+    app.save_document().unwrap();
+
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("Ask the landlord about the\nheating before the winter\nstarts\n"));
+    assert_eq!(app.document.notes[0].content(), [long]);
+}
+
+#[test]
+fn test_switch_clamps_stale_indices() {
+    let mut app = app_with(2, 3);
+    // As if tasks and notes were removed since the indices were set
+    app.current_task_index = 5;
+    app.current_note_index = 7;
+
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    assert_eq!(app.current_task_index, 1);
+    press(&mut app, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    assert_eq!(app.current_note_index, 2);
+
+    let mut empty = app_with(0, 0);
+    empty.current_task_index = 3;
+    press(&mut empty, KeyCode::F(3), KeyModifiers::NONE);
+    assert_eq!(empty.current_task_index, 0);
+}
+
+#[test]
+fn test_no_tab_switch_while_scratchpad_open() {
+    let mut app = app_with(1, 1);
+    app.mode = UiMode::Scratchpad;
+    press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+}
+
+#[test]
+fn test_ctrl_t_cycles_scratchpad_modes() {
+    let mut app = app_with(0, 0);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(app.scratchpad_visible());
+    assert_eq!(app.scratchpad_mode, ScratchpadMode::Task);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(app.scratchpad_visible());
+    assert_eq!(app.scratchpad_mode, ScratchpadMode::Note);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(!app.scratchpad_visible());
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert_eq!(app.scratchpad_mode, ScratchpadMode::Task);
+}
+
+#[test]
+fn test_enter_in_task_mode_adds_task() {
+    let mut app = with_temp_document(app_with(0, 0), "task_mode.md");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Buy milk @errands");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.len(), (1, 0));
+    assert_eq!(app.document.tasks[0].description(), "Buy milk");
+}
+
+#[test]
+fn test_enter_in_note_mode_adds_note() {
+    let mut app = with_temp_document(app_with(0, 0), "note_mode.md");
+    type_text(&mut app, "Editor draft");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Call back +work");
+    press(&mut app, KeyCode::Enter, KeyModifiers::ALT);
+    type_text(&mut app, "about the offer");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+
+    assert_eq!(app.document.len(), (0, 1));
+    let note = &app.document.notes[0];
+    assert_eq!(note.title(), "Call back");
+    assert_eq!(note.content(), ["about the offer"]);
+    assert_eq!(note.tags().to_string(), "+work");
+    assert_eq!(app.note_scratchpad.lines(), [""]);
+
+    // The Editor draft is left alone
+    assert_eq!(app.title.lines(), ["Editor draft"]);
+    assert!(app.scratchpad_visible());
+}
+
+#[test]
+fn test_modes_keep_separate_drafts() {
+    let mut app = app_with(0, 0);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "half a task");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "a note");
+    assert_eq!(app.scratchpad.lines(), ["half a task"]);
+    assert_eq!(app.note_scratchpad.lines(), ["a note"]);
+    assert_eq!(app.draft_task.text(), "half a task");
+}
+
+/// Whole rendered screen, to check which popups and panes are shown
+fn screen(app: &App) -> String {
+    rendered_rows(app, 100, 30).join("\n")
+}
+
+/// App over a document with a tagged task, for the tag popups
+fn app_with_tags() -> App {
+    let mut app = app_with(0, 0);
+    app.document.push_task(Task::from_str("Weed the beds +garden").unwrap());
+    app.tag_suggestions = app.document.collect_unique_tags(Configuration::suggest_from());
+    app
+}
+
+#[test]
+fn test_esc_closes_the_scratchpad_before_quitting() {
+    let mut app = app_with(1, 1);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(screen(&app).contains("┌Task─"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!app.exit);
+    assert!(!screen(&app).contains("┌Task─"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.exit);
+}
+
+#[test]
+fn test_esc_hides_tag_popups_first() {
+    // Scratchpad: popup, then the scratchpad, then nothing is left to close
+    let mut app = app_with_tags();
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Buy seeds +g");
+    assert!(screen(&app).contains("+garden"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!screen(&app).contains("+garden"));
+    assert!(screen(&app).contains("┌Task─"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!screen(&app).contains("┌Task─"));
+    assert!(!app.exit);
+
+    // Editor title: popup, then the app
+    let mut app = app_with_tags();
+    type_text(&mut app, "Plan +g");
+    assert!(screen(&app).contains("+garden"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!screen(&app).contains("+garden"));
+    assert!(!app.exit);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.exit);
+}
+
+#[test]
+fn test_popups_take_every_key() {
+    let alt = |c| (KeyCode::Char(c), KeyModifiers::ALT);
+    let plain = |c| (KeyCode::Char(c), KeyModifiers::NONE);
+    for ((code, modifiers), shown) in [
+        (plain('p'), "Filter by project"),
+        (plain('c'), "PgUp/PgDn month"),
+        (plain('d'), "Details: Task 0"),
+        (plain('v'), "Copy <Y>"),
+        (alt('r'), "Rename document"),
+        (alt('f'), "Find (first line)"),
+    ] {
+        let mut app = app_with(2, 1);
+        press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+        press(&mut app, code, modifiers);
+        assert!(screen(&app).contains(shown), "{shown}");
+
+        // Neither tab switches nor the keys of the Tasks tab get through
+        press(&mut app, KeyCode::F(1), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+        assert!(matches!(app.current_tab, AppTab::Tasks), "{shown}");
+        assert!(!app.document.tasks[0].is_completed(), "{shown}");
+        assert!(!screen(&app).contains("┌Task─"), "{shown}");
+
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!screen(&app).contains(shown), "{shown}");
+        assert!(!app.exit, "{shown}");
+        assert!(matches!(app.current_tab, AppTab::Tasks), "{shown}");
+        // Nothing was opened behind the popup
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(app.exit, "{shown}");
+    }
+}
+
+#[test]
+fn test_keys_go_to_the_topmost_layer() {
+    let mut app = with_temp_document(app_with(0, 0), "topmost.md");
+    type_text(&mut app, "Title");
+
+    // Typing and Ctrl+S in the scratchpad leave the Editor alone
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, " more");
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.title.lines(), ["Title"]);
+    assert_eq!(app.document.notes.len(), 0);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+
+    // So does typing into a prompt
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::ALT);
+    type_text(&mut app, "abc");
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert_eq!(app.title.lines(), ["Title"]);
+
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes.len(), 1);
+    assert_eq!(app.document.notes[0].title(), "Title");
+}
+
+#[test]
+fn test_scratchpad_floats_over_every_tab() {
+    let mut app = app_with(2, 2);
+    press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(screen(&app).contains("┌Task─"));
+
+    // The arrows move the cursor of the scratchpad, not the note below
+    type_text(&mut app, "ab");
+    press(&mut app, KeyCode::Left, KeyModifiers::NONE);
+    type_text(&mut app, "c");
+    assert_eq!(app.draft_task.text(), "acb");
+    assert_eq!(app.current_note_index, 1);
+
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(screen(&app).contains("┌Task─"));
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    assert_eq!(app.current_task_index, 0);
+}
+
+#[test]
+fn test_help_lists_every_key_of_the_layer_below() {
+    let mut app = app_with(2, 0);
+    // The hint bar only has room for the most important keys
+    assert!(screen(&app).contains("… Help <ALT>+<H>"));
+    assert!(!screen(&app).contains("Rename"));
+
+    // '?' is typed into the title, Alt+H asks for help
+    type_text(&mut app, "?");
+    assert_eq!(app.title.lines(), ["?"]);
+    press(&mut app, KeyCode::Char('h'), KeyModifiers::ALT);
+    assert!(matches!(modal(&app), Some(Modal::Help(HintContext::EditorTitle))));
+    assert!(screen(&app).contains("<ALT>+<R>  Rename"));
+    press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.title.lines(), ["?"]);
+
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('?'), KeyModifiers::NONE);
+    assert!(screen(&app).contains("<SHIFT>+<W>  Move to Section"));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!app.exit);
+
+    // The scratchpad has hints of its own
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    assert!(screen(&app).contains("Add Task <ENTER>"));
+    press(&mut app, KeyCode::Char('h'), KeyModifiers::ALT);
+    assert!(matches!(modal(&app), Some(Modal::Help(HintContext::TaskScratchpad))));
+}
+
+/// Runs of cells rendered in reversed style, row by row and trimmed,
+/// leaving out the single cell cursors of text areas
+fn reversed_runs(buf: &ratatui::buffer::Buffer) -> Vec<String> {
+    let area = buf.area;
+    let mut runs = Vec::new();
+    for y in area.top()..area.bottom() {
+        let mut run = String::new();
+        for x in area.left()..=area.right() {
+            let cell = (x < area.right()).then(|| &buf[(x, y)]);
+            match cell {
+                Some(cell) if cell.modifier.contains(ratatui::style::Modifier::REVERSED) => {
+                    run.push_str(cell.symbol())
+                }
+                _ => {
+                    if run.chars().count() > 1 {
+                        runs.push(run.trim().to_string());
+                    }
+                    run.clear();
+                }
+            }
+        }
+    }
+    runs
+}
+
+#[test]
+fn test_viewer_selection_spans_content_and_metadata() {
+    let mut app = app_with(0, 0);
+    app.document.push_note(Note::with(
+        "Selected".to_string(),
+        vec!["first".to_string(), "second".to_string()],
+    ));
+    press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('v'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+
+    let mut buf = ratatui::buffer::Buffer::empty(Rect::new(0, 0, 100, 20));
+    (&app).render(buf.area, &mut buf);
+    // The metadata pane starts above the content, next to the title
+    assert_eq!(reversed_runs(&buf), ["Level: 3", "first", "second"]);
+
+    // Keys go to the selection, ESC only cancels it
+    press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.selection().is_none());
+    assert!(!app.exit);
+}
+
+#[test]
+fn test_task_selection_extends_upwards() {
+    let mut app = app_with(1, 0);
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('v'), KeyModifiers::NONE);
+    for _ in 0..3 {
+        press(&mut app, KeyCode::Char('j'), KeyModifiers::NONE);
+    }
+    press(&mut app, KeyCode::Char('k'), KeyModifiers::NONE);
+    let lines = app.selectable_lines();
+    let selected = app.selection().unwrap().selected(&lines).to_vec();
+    assert_eq!(selected, ["Status: Pending", "Priority: None", "Dates: unknown"]);
+
+    let mut buf = ratatui::buffer::Buffer::empty(Rect::new(0, 0, 100, 20));
+    (&app).render(buf.area, &mut buf);
+    assert_eq!(reversed_runs(&buf), selected);
+}
+
+#[test]
+fn test_duplicate_task_needs_confirmation() {
+    let mut app = with_temp_document(app_with(0, 0), "duplicates.md");
+    app.document.push_task(Task::from_str("Water plants +garden").unwrap());
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+
+    // Same description as an open task
+    type_text(&mut app, "Water plants @home");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 1);
+    assert!(app.alerts.current().unwrap().message.contains("Water plants +garden"));
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 2);
+
+    // Re-submitting the task that was just added
+    type_text(&mut app, "Buy seeds");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    app.document.tasks.pop();
+    type_text(&mut app, "Buy seeds");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 2);
+    assert!(app.alerts.current().unwrap().message.contains("just added"));
+
+    // Typing in between cancels the confirmation
+    press(&mut app, KeyCode::Char('!'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 2);
+}
+
+#[test]
+fn test_distinct_task_is_added_right_away() {
+    let mut app = with_temp_document(app_with(0, 0), "distinct.md");
+    app.document.push_task(Task::from_str("x 2025-01-02 2025-01-01 Water plants").unwrap());
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Water plants");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 2);
+    assert!(app.alerts.current().is_none());
+}
+
+#[test]
+fn test_task_with_a_taken_oneoff_offers_the_existing_one() {
+    let mut app = with_temp_document(app_with(2, 0), "oneoffs.md");
+    app.document.push_task(Task::from_str("x 2025-01-02 Fix the logout !ticket-200").unwrap());
+    app.document.push_task(Task::from_str("Fix the login !ticket-123").unwrap());
+    app.tag_suggestions = app.document.collect_unique_tags(Configuration::suggest_from());
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+
+    // Completed tasks are no conflict
+    type_text(&mut app, "Reopen the logout fix !ticket-200");
+    assert!(app.oneoff_conflict().is_none());
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 5);
+
+    type_text(&mut app, "Reply to the customer !ticket-123");
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    let preview = screen(&app);
+    assert!(preview.contains("!ticket-123 is on an open task, Ctrl+G goes there"));
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 5);
+    let warning = &app.alerts.current().unwrap().message;
+    assert!(warning.contains("!ticket-123 is already on \"Fix the login\""), "{warning}");
+
+    // Going there instead keeps the draft
+    press(&mut app, KeyCode::Char('g'), KeyModifiers::CONTROL);
+    assert!(matches!(app.mode, UiMode::Normal));
+    assert!(matches!(app.current_tab, AppTab::Tasks));
+    assert_eq!(app.current_task_index, 3);
+    assert_eq!(app.draft_task.text(), "Reply to the customer !ticket-123");
+    assert_eq!(app.document.tasks.len(), 5);
+}
+
+#[test]
+fn test_task_links_a_note_picked_by_its_title() {
+    let mut app = app_with(0, 0);
+    app.document.push_note(Note::with("Packing list".to_string(), vec![]));
+    app.document.push_note(Note::with("Flight times".to_string(), vec![]));
+    let guid = app.document.notes[1].guid().to_string();
+    app.tag_suggestions = app.document.collect_unique_tags(Configuration::suggest_from());
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+
+    type_text(&mut app, "Check in online n:fli");
+    assert!(screen(&app).contains("Flight times"));
+    assert!(!screen(&app).contains("Packing list"));
+    press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(app.draft_task.text(), format!("Check in online n:{guid} "));
+
+    // The preview names the note, the task keeps the guid
+    let preview = screen(&app);
+    assert!(preview.contains("Check in online n:\"Flight times\""), "{preview}");
+    let task = app.draft_task.parsed().clone().unwrap();
+    assert!(task.to_string().ends_with(&format!(" Check in online n:{guid}")));
+}
+
+#[test]
+fn test_quarantined_lines_popup() {
+    let dir = std::env::temp_dir().join(format!("orgflow-tui-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("quarantine.md");
+    std::fs::write(&path, "## Tasks\nWater plants\nx (A) @phone\n\n## Notes\n").unwrap();
+    let (document, _) = OrgDocument::from_lenient(path.to_str().unwrap()).unwrap();
+    let session_path = dir.join("quarantine-session.json");
+    let session_manager = SessionManager::new(session_path.to_str().unwrap().to_string());
+    let mut app =
+        App::from_state(document, String::new(), session_manager, SessionState::default());
+
+    let area = Rect::new(0, 0, 100, 20);
+    let mut buf = ratatui::buffer::Buffer::empty(area);
+    (&app).render(area, &mut buf);
+    let header: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+    assert!(header.contains("[1 problem,"), "{header}");
+
+    press(&mut app, KeyCode::Char('l'), KeyModifiers::CONTROL);
+    assert!(matches!(modal(&app), Some(Modal::Problems)));
+    // The popup is modal
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert!(!app.exit);
+}
+
+#[test]
+fn test_edit_task_details() {
+    let mut app = with_temp_document(app_with(2, 0), "details.md");
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+    type_text(&mut app, "login: jdoe");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    type_text(&mut app, "x waiting");
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[1].details(), ["login: jdoe", "x waiting"]);
+    assert!(task_metadata_lines(&app.document.tasks[1]).contains(&"x waiting".to_string()));
+
+    // Cancelling keeps the stored details
+    press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert!(!app.exit);
+    assert_eq!(app.document.tasks[1].details().len(), 2);
+}
+
+#[test]
+fn test_edit_file_header() {
+    let mut app = with_temp_document(app_with(1, 0), "header.md");
+    press(&mut app, KeyCode::Char('p'), KeyModifiers::ALT);
+    type_text(&mut app, "# Inbox");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    type_text(&mut app, "## Links");
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    // A section header is refused in place, nothing is changed
+    assert!(matches!(modal(&app), Some(Modal::Header { error: Some(_), .. })));
+    assert!(screen(&app).contains("Line 2 starts with '## '"));
+    assert!(app.document.preamble().is_empty());
+
+    press(&mut app, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('#'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.preamble(), ["# Inbox", "### Links"]);
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.starts_with("# Inbox\n### Links\n\n## Tasks\nTask 0\n"), "{saved}");
+
+    // Cancelling keeps the saved header
+    press(&mut app, KeyCode::Char('p'), KeyModifiers::ALT);
+    press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.preamble().len(), 2);
+}
+
+#[test]
+fn test_edit_task_tags_in_one_line() {
+    let mut app = with_temp_document(app_with_tags(), "tags.md");
+    app.document.push_task(Task::from_str("(A) Pay rent +flat due:2024-03-01").unwrap());
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::NONE);
+    assert!(screen(&app).contains("+flat due:2024-03-01"));
+
+    // Replacing, with a tag completed from the popup
+    for _ in 0.."due:2024-03-01".len() {
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    }
+    type_text(&mut app, "+g");
+    assert!(screen(&app).contains("+garden"));
+    press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[1].to_string(), "(A) Pay rent +flat +garden");
+
+    // Tags that do not parse keep the prompt open
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::NONE);
+    type_text(&mut app, " Due:soon");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(matches!(modal(&app), Some(Modal::Tags { error: Some(_), .. })));
+    assert!(screen(&app).contains("'Due:soon'"));
+    assert_eq!(app.document.tasks[1].to_string(), "(A) Pay rent +flat +garden");
+
+    // An empty line removes the tags
+    let text = "+flat +garden Due:soon";
+    for _ in 0..text.len() {
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    }
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[1].to_string(), "(A) Pay rent");
+    assert!(app.alerts.current().unwrap().message.starts_with("Removed the tags"));
+}
+
+#[test]
+fn test_recurring_task_comes_back_with_its_history() {
+    let mut app = with_temp_document(app_with(0, 0), "recurring.md");
+    app.on_complete = OnComplete::InPlace;
+    app.document.push_task(Task::from_str("Water the plants rec:3d").unwrap());
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    for day in ["2025-03-01", "2025-03-04", "2025-03-07"] {
+        pin_today(Some(Date::from_str(day).unwrap()));
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    }
+    pin_today(Some(Date::from_str("2025-03-10").unwrap()));
+    // The next occurrence stays selected, above the completed ones
+    assert_eq!(app.document.tasks.len(), 4);
+    assert_eq!(app.current_task_index, 0);
+    let next = &app.document.tasks[0];
+    assert!(!next.is_completed());
+    assert!(app.document.tasks[1..].iter().all(Task::is_completed));
+    let lines = task_metadata_lines(next);
+    assert!(lines.contains(&"Last done: 3 days ago, 6 days ago, 9 days ago".to_string()));
+    pin_today(None);
+}
+
+#[test]
+fn test_checklist_progress_in_the_details() {
+    let task = Task::from_str("Plan the offsite\n  - [ ] book room\n  - [x] send invite").unwrap();
+    assert!(task_metadata_lines(&task).contains(&"Checklist: 1 of 2 done".to_string()));
+    let task = Task::from_str("Plan the offsite\n  budget: 2k").unwrap();
+    assert!(!task_metadata_lines(&task).iter().any(|line| line.starts_with("Checklist:")));
+}
+
+#[test]
+fn test_task_dates_share_one_row() {
+    pin_today(Some(Date::from_str("2025-04-11").unwrap()));
+    let dates = |line: &str| {
+        let lines = task_metadata_lines(&Task::from_str(line).unwrap());
+        lines.into_iter().find(|line| line.starts_with("Dates:")).unwrap()
+    };
+    assert_eq!(dates("2025-03-01 Plan the trip"), "Dates: created 2025-03-01, open for 41 days");
+    assert_eq!(
+        dates("x 2025-03-04 2025-03-01 Plan the trip"),
+        "Dates: 2025-03-01 → 2025-03-04, done in 3 days"
+    );
+    // A single date is the creation date, also on a done task
+    assert_eq!(dates("x 2025-03-04 Plan the trip"), "Dates: created 2025-03-04");
+    assert_eq!(dates("Plan the trip"), "Dates: unknown");
+    assert_eq!(
+        dates("x 2025-03-01 2025-03-04 Plan the trip"),
+        "Dates: 2025-03-04 → 2025-03-01, ⚠ Completed on 2025-03-01 \
+         before it was created on 2025-03-04"
+    );
+    pin_today(None);
+}
+
+#[test]
+fn test_scratchpad_without_description_is_not_added() {
+    let mut app = with_temp_document(app_with(0, 0), "no_description.md");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    for text in ["@phone +home", "(A)"] {
+        type_text(&mut app, text);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        let alert = app.alerts.current().unwrap();
+        assert_eq!(alert.level, AlertLevel::Warn);
+        assert_eq!(alert.message, "Task not added: There must be a task description!");
+        // Kept for fixing
+        assert_eq!(app.scratchpad.lines(), [text]);
+        app.scratchpad = TextArea::default();
+        app.draft_task.update("");
+        app.alerts.clear();
+    }
+    type_text(&mut app, "   ");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.alerts.current().is_none());
+    assert!(app.document.tasks.is_empty());
+}
+
+#[test]
+fn test_due_date_typed_in_the_scratchpad() {
+    pin_today(Some(Date::from_str("2025-06-28").unwrap()));
+    let mut app = with_temp_document(app_with(0, 0), "due_typed.md");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "call dentist due:2025-07-01 @phone");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    let task = &app.document.tasks[0];
+    assert_eq!(task.due_date(), Some(&Date::from_str("2025-07-01").unwrap()));
+    assert!(task_metadata_lines(task).contains(&"Due: 2025-07-01, in 3 days".to_string()));
+    pin_today(Some(Date::from_str("2025-07-03").unwrap()));
+    let due = "Due: 2025-07-01, 2 days ago, overdue".to_string();
+    assert!(task_metadata_lines(&app.document.tasks[0]).contains(&due));
+    // The date is not offered as a tag to type again
+    app.tag_suggestions = app.document.collect_unique_tags(Configuration::suggest_from());
+    assert!(app.tag_suggestions.matching_prefix("due").is_empty());
+    pin_today(None);
+}
+
+/// App over tasks marked for yesterday, last started on `last_opened`
+fn app_with_today_tags(name: &str, last_opened: &str) -> App {
+    pin_today(Some(Date::from_str("2025-03-05").unwrap()));
+    let mut app = with_temp_document(app_with(0, 0), name);
+    for line in ["Call the landlord today:1", "x 2025-03-04 Measure the hallway today:1", "Pack"] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    app.session_manager.set_last_opened(&Date::from_str(last_opened).unwrap());
+    app
+}
+
+#[test]
+fn test_new_day_clears_today_tags() {
+    let mut app = app_with_today_tags("rollover_clear.md", "2025-03-04");
+    app.roll_over_day().unwrap();
+    assert_eq!(app.document.tasks[0].to_string(), "Call the landlord");
+    // Done tasks keep what they were marked with
+    assert!(app.document.tasks[1].to_string().ends_with("today:1"));
+    let alert = &app.alerts.current().unwrap().message;
+    assert_eq!(alert, "New day, cleared the today: tags of 1 task(s)");
+    assert_eq!(app.session_manager.last_opened(), Some(Date::now()));
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("\nCall the landlord\n"));
+
+    let mut app = app_with_today_tags("rollover_carry.md", "2025-03-01");
+    app.rollover = Rollover::Carry;
+    app.roll_over_day().unwrap();
+    assert_eq!(app.document.tasks[0].to_string(), "Call the landlord carried:2025-03-01");
+    pin_today(None);
+}
+
+#[test]
+fn test_same_day_leaves_today_tags() {
+    let mut app = app_with_today_tags("rollover_same_day.md", "2025-03-05");
+    app.roll_over_day().unwrap();
+    assert_eq!(app.document.tasks[0].to_string(), "Call the landlord today:1");
+    assert!(app.alerts.current().is_none());
+    app.persistence.flush().unwrap();
+    assert!(!std::path::Path::new(&app.document_path).exists());
+
+    // Without a day in the session there is nothing to compare with yet
+    let mut app = app_with(0, 0);
+    app.document.push_task(Task::from_str("Call the landlord today:1").unwrap());
+    app.roll_over_day().unwrap();
+    assert_eq!(app.document.tasks[0].to_string(), "Call the landlord today:1");
+    assert_eq!(app.session_manager.last_opened(), Some(Date::now()));
+    pin_today(None);
+}
+
+#[test]
+fn test_stale_tasks_fade() {
+    let today = Date::from_str("2024-06-30").unwrap();
+    let style = |line: &str| stale_style(&Task::from_str(line).unwrap(), &today, (30, 90));
+    let dim = Style::default().add_modifier(ratatui::style::Modifier::DIM);
+
+    assert_eq!(style("2024-05-31 Exactly 30 days old"), Style::default());
+    assert_eq!(style("2024-05-30 Just over 30 days old"), dim);
+    assert_eq!(style("2024-04-01 Exactly 90 days old"), dim);
+    assert_eq!(style("2024-03-31 Just over 90 days old"), dim.fg(Color::DarkGray));
+    assert_eq!(style("Without a creation date"), Style::default());
+    assert_eq!(style("x 2024-06-01 2020-01-01 Completed long ago"), Style::default());
+}
+
+#[test]
+fn test_rename_document() {
+    let dir = std::env::temp_dir().join(format!("orgflow-tui-rename-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut app = app_with(1, 0);
+    app.document_path = dir.join("refile.org").to_str().unwrap().to_string();
+    app.save_document().unwrap();
+
+    // ESC keeps the name
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::ALT);
+    let Some(Modal::Rename(prompt)) = modal(&app) else {
+        panic!("the rename prompt is not open");
+    };
+    assert_eq!(prompt.lines(), ["refile.org"]);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert!(!app.exit);
+
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::ALT);
+    for _ in 0.."refile.org".len() {
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    }
+    type_text(&mut app, "inbox.org");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.document_path.ends_with("inbox.org"));
+    assert!(dir.join("inbox.org").exists());
+    assert!(!dir.join("refile.org").exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.join(orgflow::DOCUMENT_POINTER)).unwrap(),
+        "inbox.org\n"
+    );
+
+    // A failed rename is reported and keeps the document where it is
+    press(&mut app, KeyCode::Char('r'), KeyModifiers::ALT);
+    type_text(&mut app, "/x");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.document_path.ends_with("inbox.org"));
+    assert_eq!(app.alerts.current().unwrap().level, AlertLevel::Error);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Rows of the rendered app, without trailing blanks
+fn rendered_rows(app: &App, width: u16, height: u16) -> Vec<String> {
+    let mut buf = ratatui::buffer::Buffer::empty(Rect::new(0, 0, width, height));
+    app.render(buf.area, &mut buf);
+    (0..height)
+        .map(|y| {
+            let row: String = (0..width).map(|x| buf[(x, y)].symbol()).collect();
+            row.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// App on the Tasks tab over a few tasks, the same on every day
+fn app_for_snapshots() -> App {
+    pin_today(Some(Date::from_str("2025-03-05").unwrap()));
+    let mut app = app_with(0, 1);
+    for line in [
+        "(A) 2025-03-01 Send the quarterly report +work @office due:2025-03-07",
+        "2025-03-03 Sow carrots +garden",
+        "Call the plumber @phone p:mario",
+    ] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    app
+}
+
+#[test]
+fn test_layout_snapshots_at_small_sizes() {
+    let app = app_for_snapshots();
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/layout");
+    for (width, height) in [(45, 10), (60, 15), (80, 24)] {
+        let snapshot = rendered_rows(&app, width, height).join("\n") + "\n";
+        let golden = dir.join(format!("{width}x{height}.txt"));
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&golden, &snapshot).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&golden).unwrap();
+        assert_eq!(snapshot, expected, "{width}x{height} differs from {}", golden.display());
+    }
+    pin_today(None);
+}
+
+#[test]
+fn test_viewer_snapshots_adapt_the_strip() {
+    pin_today(Some(Date::from_str("2025-03-05").unwrap()));
+    let guid = "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+    let content = format!(
+        "## Tasks
+Send the agenda +work n:{guid}
+Book the room @office
+
+## Notes
+
+### Kickoff
+> cre:2025-02-20 mod:2025-03-02 guid:{guid} +work +hiring
+Agenda: budget and hiring
+Owner p:ines
+
+### Retro
+> cre:2025-03-04 mod:2025-03-04 guid:b1b2b3b4-b1b2-c1c2-d1d2-d3d4d5d6d7d8
+Went well
+"
+    );
+    let app = with_temp_document(app_with(0, 0), "viewer.md");
+    std::fs::write(&app.document_path, content).unwrap();
+    let document = OrgDocument::from(&app.document_path).unwrap();
+    let mut app = App::from_state(
+        document,
+        app.document_path.clone(),
+        app.session_manager,
+        SessionState::default(),
+    );
+    press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/layout");
+    for (width, height) in [(60, 15), (80, 16), (120, 16)] {
+        let snapshot = rendered_rows(&app, width, height).join("\n") + "\n";
+        let golden = dir.join(format!("viewer-{width}x{height}.txt"));
+        if update {
+            std::fs::write(&golden, &snapshot).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&golden).unwrap();
+        assert_eq!(snapshot, expected, "{width}x{height} differs from {}", golden.display());
+    }
+    pin_today(None);
+}
+
+#[test]
+fn test_popups_render_on_small_terminals() {
+    let popups: [&[(KeyCode, KeyModifiers)]; 5] = [
+        &[(KeyCode::Char('c'), KeyModifiers::NONE)],
+        &[(KeyCode::Char('p'), KeyModifiers::NONE)],
+        &[(KeyCode::Char('?'), KeyModifiers::NONE)],
+        &[(KeyCode::Char('t'), KeyModifiers::NONE), (KeyCode::Char(' '), KeyModifiers::NONE)],
+        &[(KeyCode::Char('t'), KeyModifiers::CONTROL), (KeyCode::Char('@'), KeyModifiers::NONE)],
+    ];
+    for keys in popups {
+        let mut app = app_for_snapshots();
+        app.tag_suggestions = app.document.collect_unique_tags(Configuration::suggest_from());
+        for &(code, modifiers) in keys {
+            press(&mut app, code, modifiers);
+        }
+        for (width, height) in [(1, 1), (45, 10), (50, 12), (60, 15)] {
+            rendered_rows(&app, width, height);
+        }
+    }
+    pin_today(None);
+}
+
+#[test]
+fn test_between_sections_panel() {
+    let mut app = with_temp_document(app_with(2, 0), "between.md");
+    app.document.append_between("## Waiting For", &["Reply from Bob".to_string()]);
+    app.document.append_between("## Someday", &["Learn Go".to_string()]);
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+
+    // Collapsed to the names below the lists
+    let rows = rendered_rows(&app, 72, 12);
+    assert_eq!(
+        rows[11],
+        "▸ Waiting For, Someday ────────── Expand <W> Move task here <SHIFT>+<W>"
+    );
+    assert!(rows[10].starts_with("└"));
+
+    press(&mut app, KeyCode::Char('w'), KeyModifiers::NONE);
+    let rows = rendered_rows(&app, 72, 12);
+    assert_eq!(
+        rows[7..],
+        [
+            "▾ Waiting For, Someday ──────── Collapse <W> Move task here <SHIFT>+<W>",
+            "Waiting For",
+            "  Reply from Bob",
+            "Someday",
+            "  Learn Go",
+        ]
+    );
+
+    // Moved into the first section, as a plain line
+    press(&mut app, KeyCode::Char('W'), KeyModifiers::SHIFT);
+    assert_eq!(app.document.tasks.len(), 1);
+    assert_eq!(app.document.between()[..3], ["## Waiting For", "Reply from Bob", "Task 0"]);
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("Reply from Bob\nTask 0\n## Someday"), "{saved}");
+
+    // Without sections there is no panel, a task starts Waiting For
+    let mut app = with_temp_document(app_with(1, 0), "waiting-for.md");
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    assert!(rendered_rows(&app, 72, 12)[11].starts_with("└"));
+    press(&mut app, KeyCode::Char('W'), KeyModifiers::SHIFT);
+    assert_eq!(app.document.between(), ["## Waiting For", "Task 0"]);
+}
+
+#[test]
+fn test_project_picker_filters_tasks() {
+    let mut app = app_with(0, 0);
+    for line in [
+        "Sow carrots +garden",
+        "x 2025-01-02 Fix the gate +garden +house",
+        "Call mum",
+        "Paint the door +house",
+    ] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('p'), KeyModifiers::NONE);
+    let rows = rendered_rows(&app, 60, 20);
+    assert_eq!(
+        rows[8..13],
+        [
+            "│     ┌Filter by project─────────────────────────────┐     │",
+            "│     │All projects                                  │     │",
+            "│     │+garden      ▇▇▇▇▇▇▇▇         1 open / 2 total│     │",
+            "│     │+house       ▇▇▇▇▇▇▇▇         1 open / 2 total│     │",
+            "│     └── Filter <ENTER> Navigate <↑↓> Close <ESC> ──┘     │",
+        ]
+    );
+
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.project_filter.as_deref(), Some("+house"));
+    assert_eq!(app.current_task_index, 1);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    assert_eq!(app.current_task_index, 3);
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    assert_eq!(app.current_task_index, 3);
+    assert!(rendered_rows(&app, 60, 20)[1].contains("Tasks (2 of 4 in +house)"));
+
+    // The first row shows all tasks again
+    press(&mut app, KeyCode::Char('p'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Up, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Up, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.project_filter, None);
+    assert_eq!(app.visible_tasks(), [0, 1, 2, 3]);
+}
+
+#[test]
+fn test_export_the_filtered_task_view() {
+    let mut app = with_temp_document(app_with(0, 0), "export.md");
+    for line in ["Sow carrots +garden", "Review the budget +work", "x Order compost +garden"] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    app.project_filter = Some("+garden".to_string());
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('E'), KeyModifiers::SHIFT);
+    let path = std::path::Path::new(&app.document_path).with_file_name("view.txt");
+    let alert = &app.alerts.current().unwrap().message;
+    assert!(alert.starts_with("Exported 2 task(s) to"), "{alert}");
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("Tasks: project +garden; in document order\n"), "{text}");
+    assert!(text.ends_with("\n\n[ ] Sow carrots +garden\n[x] Order compost +garden\n"));
+}
+
+#[test]
+fn test_captured_tasks_get_the_project_defaults() {
+    let mut app = with_temp_document(app_with(0, 0), "defaults.md");
+    app.project_defaults = DefaultsMap::from_str("+webdev est:30min @work").unwrap();
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Fix the navbar +webdev @office");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    let task = app.document.tasks[0].to_string();
+    assert!(task.ends_with("Fix the navbar +webdev @office est:30min"), "{task}");
+    let alert = &app.alerts.current().unwrap().message;
+    assert!(alert.contains("est:30min"), "{alert}");
+
+    // Submitting the same text again is still caught as a duplicate
+    type_text(&mut app, "Fix the navbar +webdev @office");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 1);
+}
+
+#[test]
+fn test_waiting_list_follows_up_and_stops_waiting() {
+    let mut app = with_temp_document(app_with(0, 0), "waiting.md");
+    for line in [
+        "Signed contract s:wait(landlord) wait_since:2024-03-04",
+        "Call the plumber",
+        "Quote for the roof @waiting p:anna",
+    ] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('a'), KeyModifiers::NONE);
+    let text = screen(&app);
+    assert!(text.contains("┌Waiting for"), "{text}");
+    assert!(text.contains("│anna"), "{text}");
+    assert!(text.contains("Quote for the roof · waiting "), "{text}");
+    assert!(text.contains("Signed contract · waiting "), "{text}");
+
+    // The cursor starts on anna's task, then moves to the landlord's
+    press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('f'), KeyModifiers::NONE);
+    let today = Date::now();
+    assert_eq!(app.document.tasks[0].followed_up(), Some(today));
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks[0].to_string(), "Signed contract");
+    assert!(!screen(&app).contains("Signed contract ·"));
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("Signed contract\n"), "{saved}");
+
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+}
+
+#[test]
+fn test_review_duplicates_merges_and_deletes() {
+    let mut app = with_temp_document(app_with(0, 0), "duplicates.md");
+    for line in [
+        "2025-03-04 Renew passport +travel",
+        "Water plants",
+        "2025-02-20 renew passport @errands",
+        "Water plants",
+        "Renew pasport",
+    ] {
+        app.document.push_task(Task::from_str(line).unwrap());
+    }
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('D'), KeyModifiers::SHIFT);
+    let text = screen(&app);
+    assert!(text.contains("Possible duplicates 1 of 4"), "{text}");
+    assert!(text.contains("1  2025-03-04 Renew passport +travel"), "{text}");
+    assert!(text.contains("2  2025-02-20 renew passport @errands"), "{text}");
+
+    // Merged, its pair with the misspelt one moves up
+    press(&mut app, KeyCode::Char('m'), KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 4);
+    assert_eq!(app.document.tasks[0].to_string(), "2025-02-20 Renew passport +travel @errands");
+    // The misspelt one is deleted, the plants are kept
+    press(&mut app, KeyCode::Char('2'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('k'), KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    let summary = &app.alerts.current().unwrap().message;
+    assert_eq!(summary, "Duplicate review done: 1 merge applied, 1 deleted");
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(!saved.contains("pasport"), "{saved}");
+    assert_eq!(saved.matches("Water plants").count(), 2, "{saved}");
+
+    press(&mut app, KeyCode::Char('D'), KeyModifiers::SHIFT);
+    assert!(modal(&app).is_some());
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+}
+
+#[test]
+fn test_pick_due_date_from_calendar() {
+    let mut app = with_temp_document(app_with(0, 0), "due-date.md");
+    app.document.push_task(Task::from_str("Pay rent due:2025-06-30").unwrap());
+    press(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('c'), KeyModifiers::NONE);
+    let rows = rendered_rows(&app, 60, 20);
+    let calendar: Vec<String> =
+        rows[6..15].iter().map(|row| row.chars().skip(19).take(23).collect()).collect();
+    assert_eq!(
+        calendar,
+        [
+            "┌───── Jun 2025 ──────┐",
+            "│ Mo Tu We Th Fr Sa Su│",
+            "│                    1│",
+            "│  2  3  4  5  6  7  8│",
+            "│  9 10 11 12 13 14 15│",
+            "│ 16 17 18 19 20 21 22│",
+            "│ 23 24 25 26 27 28 29│",
+            "│ 30                  │",
+            "└── PgUp/PgDn month ──┘",
+        ]
+    );
+
+    // Other keys go to the calendar until a date is picked
+    press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::PageDown, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[0].to_string(), "Pay rent due:2025-07-30");
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("Pay rent due:2025-07-30"), "{saved}");
+
+    press(&mut app, KeyCode::Char('c'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert!(!app.exit);
+}
+
+#[test]
+fn test_read_only_blocks_changes() {
+    let mut app = with_temp_document(app_with(2, 0), "read-only.md");
+    app.read_only = Some(ReadOnlyReason::Marked);
+
+    press(&mut app, KeyCode::Char('3'), KeyModifiers::ALT);
+    press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    assert!(!app.document.tasks[0].is_completed());
+    assert!(app.alerts.current().unwrap().message.starts_with("Read-only document"));
+    press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+
+    // The task draft stays in the scratchpad
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Not today");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks.len(), 2);
+    assert_eq!(app.draft_task.text(), "Not today");
+
+    app.persistence.flush().unwrap();
+    assert!(!std::path::Path::new(&app.document_path).exists());
+}
+
+#[test]
+fn test_replace_asks_before_applying() {
+    let mut app = with_temp_document(app_with(3, 1), "replace.md");
+    press(&mut app, KeyCode::Char('f'), KeyModifiers::ALT);
+    type_text(&mut app, "Task");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    type_text(&mut app, "Job");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.alerts.current().unwrap().message.starts_with("3 change(s)"));
+    assert_eq!(app.document.tasks[0].description(), "Task 0");
+
+    // Editing the replacement asks again
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::NONE);
+    assert!(matches!(modal(&app), Some(Modal::Replace { confirm: false, .. })));
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[2].description(), "Jobs 2");
+    app.persistence.flush().unwrap();
+    let saved = std::fs::read_to_string(&app.document_path).unwrap();
+    assert!(saved.contains("Jobs 0"));
+
+    press(&mut app, KeyCode::Char('f'), KeyModifiers::ALT);
+    type_text(&mut app, "Jobs");
+    press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert_eq!(app.document.tasks[2].description(), "Jobs 2");
+}
+
+#[test]
+fn test_paste_is_sanitized_into_the_task() {
+    let mut app = with_temp_document(app_with(0, 0), "paste.md");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    app.handle_paste("Call\tBob \u{7}back\r\n @phone");
+    assert_eq!(app.draft_task.text(), "Call Bob back @phone");
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.document.tasks[0].description(), "Call Bob back");
+}
+
+#[test]
+fn test_title_stays_a_single_line() {
+    let mut app = with_temp_document(app_with(0, 0), "title-line.md");
+    type_text(&mut app, "Weekly ");
+    // Ctrl+M breaks lines in a text area, but not in the title
+    press(&mut app, KeyCode::Char('m'), KeyModifiers::CONTROL);
+    app.handle_paste("sync\r\n\nnotes\n");
+    assert_eq!(app.title.lines(), ["Weekly sync notes"]);
+
+    // A full title takes no more characters, pasted text is cut off
+    app.title = TextArea::from(["x".repeat(TITLE_MAX_LEN - 3)]);
+    app.title.move_cursor(CursorMove::End);
+    app.handle_paste("abcdef");
+    assert_eq!(app.title_len(), TITLE_MAX_LEN);
+    assert!(app.title.lines()[0].ends_with("xabc"));
+    assert_eq!(app.alerts.current().unwrap().level, AlertLevel::Warn);
+    type_text(&mut app, "g");
+    assert_eq!(app.title_len(), TITLE_MAX_LEN);
+    assert!(screen(&app).contains(&format!("{TITLE_MAX_LEN}/{TITLE_MAX_LEN}")));
+    press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(app.title_len(), TITLE_MAX_LEN - 1);
+
+    // Drafts restored from older sessions are checked when saving
+    app.title = TextArea::from(["Restored", "draft"]);
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes[0].title(), "Restored draft");
+    app.title = TextArea::from(["y".repeat(TITLE_MAX_LEN + 1)]);
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes.len(), 1);
+    assert_eq!(app.alerts.current().unwrap().level, AlertLevel::Error);
+}
+
+#[test]
+fn test_long_paste_offers_a_note() {
+    let mut app = with_temp_document(app_with(0, 0), "long-paste.md");
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    let email = "Quarterly planning\r\nHi all,\r\n\r\nplease review the plan.\r\n";
+    app.handle_paste(email);
+    assert!(matches!(modal(&app), Some(Modal::PendingPaste(_))));
+    assert!(app.draft_task.text().is_empty());
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.document.tasks.is_empty());
+    assert_eq!(app.document.notes.len(), 1);
+    assert_eq!(app.document.notes[0].title(), "Quarterly planning");
+    assert_eq!(app.document.notes[0].content(), ["Hi all,", "please review the plan."]);
+
+    // Any other key pastes it into the task after all
+    app.handle_paste(email);
+    press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    assert!(modal(&app).is_none());
+    assert!(app.draft_task.text().starts_with("Quarterly planning Hi all,"));
+}
+
+#[test]
+fn test_repair_guids_from_problems_popup() {
+    let dir = std::env::temp_dir().join(format!("orgflow-tui-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("guids.md");
+    let guid = "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+    let note = format!("### Twin\n> cre:2024-01-01 mod:2024-01-01 guid:{guid}\n");
+    std::fs::write(&path, format!("## Tasks\n\n## Notes\n\n{note}\n{note}")).unwrap();
+    let path = path.to_str().unwrap().to_string();
+    let (document, _) = OrgDocument::from_lenient(&path).unwrap();
+    let mut app = with_temp_document(app_with(0, 0), "guids.md");
+    app.document = document;
+
+    press(&mut app, KeyCode::Char('l'), KeyModifiers::CONTROL);
+    assert!(matches!(modal(&app), Some(Modal::Problems)));
+    press(&mut app, KeyCode::Char('f'), KeyModifiers::NONE);
+    assert!(app.document.lint().is_empty());
+    assert!(modal(&app).is_none());
+    app.persistence.flush().unwrap();
+    assert!(std::fs::read_to_string(format!("{path}.bak")).unwrap().contains("Twin"));
+    assert!(OrgDocument::from(&path).unwrap().lint().is_empty());
+}
+
+#[test]
+fn test_edit_note_in_place() {
+    let mut app = with_temp_document(app_with(0, 2), "edit-in-place.md");
+    let guid = app.document.notes[1].guid().to_string();
+    press(&mut app, KeyCode::Char('2'), KeyModifiers::ALT);
+    press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('e'), KeyModifiers::NONE);
+    assert!(matches!(app.current_tab, AppTab::Editor));
+    assert_eq!(app.title.lines(), ["Note 1"]);
+
+    type_text(&mut app, " revised");
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes.len(), 2);
+    assert_eq!(app.document.notes[1].title(), "Note 1 revised");
+    assert_eq!(app.document.notes[1].guid().to_string(), guid);
+    assert!(app.editing.is_none());
+}
+
+/// App restored from a session whose draft edits the only note, based on
+/// an older version of it
+fn app_with_stale_draft() -> App {
+    let mut app = app_with(0, 1);
+    let note = &app.document.notes[0];
+    let session_state = SessionState {
+        title_content: vec!["Note 0 from yesterday".to_string()],
+        note_content: vec!["Old thoughts".to_string()],
+        editing: Some(EditingNote {
+            guid: note.guid().to_string(),
+            base_modified: "2000-01-01".to_string(),
+        }),
+        ..SessionState::default()
+    };
+    let document = std::mem::take(&mut app.document);
+    let session_manager = std::mem::replace(
+        &mut app.session_manager,
+        SessionManager::new(String::new()),
+    );
+    App::from_state(document, String::new(), session_manager, session_state)
+}
+
+#[test]
+fn test_stale_draft_asks_before_restoring() {
+    let fresh = app_with(0, 1);
+    assert!(conflict(&fresh).is_none());
+
+    let mut app = app_with_stale_draft();
+    assert_eq!(conflict(&app).unwrap().note_index, 0);
+    // Other keys, even saving, wait for the decision
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes[0].title(), "Note 0");
+    let rows = rendered_rows(&app, 80, 20).join("\n");
+    assert!(rows.contains("The note changed since"), "{rows}");
+}
+
+#[test]
+fn test_stale_draft_keep_mine() {
+    let mut app = with_temp_document(app_with_stale_draft(), "keep-mine.md");
+    press(&mut app, KeyCode::Char('m'), KeyModifiers::NONE);
+    assert!(conflict(&app).is_none());
+    assert_eq!(app.title.lines(), ["Note 0 from yesterday"]);
+    let base = app.document.notes[0].modification_date().to_string();
+    assert_eq!(app.editing.as_ref().unwrap().base_modified, base);
+
+    press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(app.document.notes.len(), 1);
+    assert_eq!(app.document.notes[0].title(), "Note 0 from yesterday");
+    assert_eq!(app.document.notes[0].content(), ["Old thoughts"]);
+}
+
+#[test]
+fn test_stale_draft_take_theirs() {
+    let mut app = app_with_stale_draft();
+    press(&mut app, KeyCode::Char('t'), KeyModifiers::NONE);
+    assert!(conflict(&app).is_none());
+    assert_eq!(app.title.lines(), ["Note 0"]);
+    assert_eq!(app.note.lines(), [""]);
+    assert!(app.editing.is_some());
+    assert_eq!(app.document.notes[0].title(), "Note 0");
+}
+
+#[test]
+fn test_stale_draft_view_both() {
+    let mut app = app_with_stale_draft();
+    press(&mut app, KeyCode::Char('b'), KeyModifiers::NONE);
+    assert!(conflict(&app).unwrap().show_both);
+    let rows = rendered_rows(&app, 80, 20);
+    assert_eq!(
+        rows[9..12],
+        [
+            "│       │Mine───────────────────────────Theirs─────────────────────────│       │",
+            "│       │Note 0 from yesterday          Note 0                         │       │",
+            "│       │Old thoughts                                                  │       │",
+        ]
+    );
+
+    // Looking at both does not decide anything
+    press(&mut app, KeyCode::Char('b'), KeyModifiers::NONE);
+    assert!(!conflict(&app).unwrap().show_both);
+    assert_eq!(app.title.lines(), ["Note 0 from yesterday"]);
+}
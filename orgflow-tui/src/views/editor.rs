@@ -0,0 +1,83 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Widget};
+
+use orgflow::TITLE_MAX_LEN;
+
+use super::{render_header, tab_hints};
+use crate::app::{App, NoteFocus};
+
+/// Characters before the title limit from which on they are counted
+const TITLE_WARN_BEFORE: usize = 20;
+
+pub fn render_note_editor(app: &App, area: Rect, buf: &mut Buffer) {
+    // Create a vertical layout via length
+    let vertical_layout = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Min(0),
+    ]);
+
+    // Split input area in above layout
+    let [appname_area, title_area, content_area] = vertical_layout.areas(area);
+
+    // Render title in the vertical area
+    render_header(app, appname_area, buf);
+
+    // The scratchpad draws over the Editor and has the focus then
+    let focused = !app.scratchpad_visible();
+
+    // Define title area and its content
+    let mut title = app.title.clone();
+    let title_block = Block::default().borders(Borders::ALL).title(match app.editing {
+        Some(_) => "Title (editing a saved note)",
+        None => "Title",
+    });
+    let title_block = match app.note_focus {
+        NoteFocus::Title if focused => title_block.style(Style::default().fg(Color::Yellow)),
+        _ => title_block,
+    };
+    // Count the characters once the title gets close to its limit
+    let title_len = app.title_len();
+    let title_block = match title_len {
+        len if len >= TITLE_MAX_LEN => title_block
+            .title(Line::from(format!(" {len}/{TITLE_MAX_LEN} ").red()).right_aligned()),
+        len if len + TITLE_WARN_BEFORE >= TITLE_MAX_LEN => title_block
+            .title(Line::from(format!(" {len}/{TITLE_MAX_LEN} ")).right_aligned()),
+        _ => title_block,
+    };
+
+    // Define content for the note inputs: content (text_area), border (block)
+    let mut text_area = app.note.clone();
+    let note_block = Block::default()
+        .borders(Borders::ALL)
+        .title("Content")
+        .title_bottom(tab_hints(app, content_area));
+
+    let note_block = match app.note_focus {
+        NoteFocus::Content if focused => note_block.style(Style::default().fg(Color::Yellow)),
+        _ => note_block,
+    };
+
+    // Render each of the contents
+    text_area.set_block(note_block);
+    text_area.render(content_area, buf);
+
+    title.set_block(title_block);
+    title.render(title_area, buf);
+
+    // Render title autocompletion popup if visible
+    if app.title_autocompletion.is_visible() && app.note_focus == NoteFocus::Title && focused {
+        // Calculate cursor position within the title
+        let cursor_line = title.cursor().0;
+        let cursor_col = title.cursor().1;
+        let cursor_pos = (
+            title_area.x.saturating_add(1 + cursor_col as u16), // +1 for border
+            title_area.y.saturating_add(1 + cursor_line as u16), // +1 for border
+        );
+        app.title_autocompletion.render(area, buf, cursor_pos);
+    }
+}
@@ -0,0 +1,311 @@
+use orgflow::Date;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap};
+
+use crate::alerts::AlertLevel;
+use crate::app::{App, AppTab, Modal, UiMode};
+use crate::hints::{HintContext, hint_line};
+use crate::scratchpad::ScratchpadMode;
+use crate::selection::LineSelection;
+use crate::widgets::autocompletion::note_labels;
+use crate::widgets::centered_rect;
+use crate::widgets::conflict::render_conflict;
+use crate::widgets::help::render_help;
+use crate::widgets::problems::render_problems;
+use crate::widgets::prompt::render_prompt;
+
+pub mod editor;
+pub mod tasks;
+pub mod viewer;
+
+/// Application name line shown on top of every tab
+const HEADER: &str = "Orgflow - Editor | Viewer | Tasks (Ctrl+R/Ctrl+E or F1-F3 to switch)";
+/// The header on terminals too narrow for `HEADER`
+const SHORT_HEADER: &str = "Orgflow - Editor | Viewer | Tasks";
+
+/// Smallest terminal the tabs are drawn in, a notice is shown below it
+pub const MIN_WIDTH: u16 = 50;
+pub const MIN_HEIGHT: u16 = 12;
+
+/// Narrower tabs leave out their metadata pane, the list or note gets it all
+const SIDE_PANE_MIN_WIDTH: u16 = 70;
+
+/// Give App itself the ability to be a Widget (if there is only one widget )
+impl Widget for &App {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            render_too_small(area, buf);
+            return;
+        }
+        match self.current_tab {
+            AppTab::Editor => editor::render_note_editor(self, area, buf),
+            AppTab::Viewer => viewer::render_note_viewer(self, area, buf),
+            AppTab::Tasks => tasks::render_task_viewer(self, area, buf),
+        }
+        render_mode(self, &self.mode, area, buf);
+    }
+}
+
+/// Notice instead of the tabs, whose borders would leave no room for text
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let text = format!(
+        "Terminal too small (need {MIN_WIDTH}x{MIN_HEIGHT}, have {}x{})",
+        area.width, area.height
+    );
+    let width = area.width.max(1);
+    let rows = match text.len() as u16 {
+        len if len <= width => 1,
+        // Wrapping at words may take a row more
+        len => len.div_ceil(width) + 1,
+    };
+    let [rows] = Layout::vertical([Constraint::Length(rows)]).flex(Flex::Center).areas(area);
+    Paragraph::new(text).centered().wrap(Wrap { trim: true }).render(rows, buf);
+}
+
+/// Split `area` into the main pane with `percent` of the width and the
+/// metadata pane, which is left out on narrow terminals
+fn with_side_pane(area: Rect, percent: u16) -> (Rect, Option<Rect>) {
+    if area.width < SIDE_PANE_MIN_WIDTH {
+        return (area, None);
+    }
+    let [main, side] =
+        Layout::horizontal([Constraint::Percentage(percent), Constraint::Percentage(100 - percent)])
+            .areas(area);
+    (main, Some(side))
+}
+
+/// Draw what covers the tab in `mode`, popups over the modes they were
+/// opened in
+fn render_mode(app: &App, mode: &UiMode, area: Rect, buf: &mut Buffer) {
+    match mode {
+        // Selections are highlighted by the panes themselves
+        UiMode::Normal | UiMode::Selecting(_) => {}
+        UiMode::Scratchpad => render_scratchpad(app, area, buf),
+        UiMode::Modal { kind, under } => {
+            render_mode(app, under, area, buf);
+            render_modal(app, kind, area, buf);
+        }
+    }
+}
+
+fn render_modal(app: &App, modal: &Modal, area: Rect, buf: &mut Buffer) {
+    match modal {
+        // Explained in the header, the scratchpad stays as it is
+        Modal::PendingPaste(_) => {}
+        Modal::Problems => render_problems(&app.document.lint(), area, buf),
+        Modal::Conflict(conflict) => {
+            let note = &app.document.notes[conflict.note_index];
+            let (title, content) = (app.title.lines(), app.note.lines());
+            render_conflict(note, title, content, conflict.show_both, area, buf);
+        }
+        Modal::ProjectPicker(picker) => picker.render(area, buf),
+        Modal::Calendar(calendar) => calendar.render(area, buf),
+        Modal::Help(context) => render_help(*context, area, buf),
+        Modal::Waiting(waiting) => waiting.render(&app.document.tasks, &Date::now(), area, buf),
+        Modal::Duplicates(review) => review.render(&app.document.tasks, area, buf),
+        Modal::Details(editor) => {
+            let Some(task) = app.document.tasks.get(app.current_task_index) else {
+                return;
+            };
+            let hint = Line::from(vec![
+                " Save ".into(),
+                "<CTRL>+<S> ".blue().bold(),
+                "Cancel ".into(),
+                "<ESC> ".blue().bold(),
+            ]);
+            let title = format!("Details: {}", task.description());
+            render_prompt(editor, title, hint, centered_rect(60, 40, area), buf);
+        }
+        Modal::Tags {
+            prompt,
+            completion,
+            error,
+        } => {
+            let Some(task) = app.document.tasks.get(app.current_task_index) else {
+                return;
+            };
+            let hint = Line::from(vec![
+                " Replace ".into(),
+                "<ENTER> ".blue().bold(),
+                "Complete Tag ".into(),
+                "<TAB> ".blue().bold(),
+                "Cancel ".into(),
+                "<ESC> ".blue().bold(),
+            ]);
+            let title = match error {
+                Some(error) => format!("Tags: {error}"),
+                None => format!("Tags: {}", task.description()),
+            };
+            let popup = centered_rect(60, 10, area);
+            render_prompt(prompt, title, hint, popup, buf);
+            if completion.is_visible() {
+                let (_, col) = prompt.cursor();
+                let cursor_pos = (popup.x.saturating_add(1 + col as u16), popup.y + 1);
+                completion.render(area, buf, cursor_pos);
+            }
+        }
+        Modal::Rename(prompt) => {
+            let hint = Line::from(vec![
+                " Rename ".into(),
+                "<ENTER> ".blue().bold(),
+                "Cancel ".into(),
+                "<ESC> ".blue().bold(),
+            ]);
+            let title = "Rename document".to_string();
+            render_prompt(prompt, title, hint, centered_rect(50, 20, area), buf);
+        }
+        Modal::Header { editor, error } => {
+            let hint = Line::from(vec![
+                " Save ".into(),
+                "<CTRL>+<S> ".blue().bold(),
+                "Cancel ".into(),
+                "<ESC> ".blue().bold(),
+            ]);
+            let title = match error {
+                Some(error) => format!("File header: {error}"),
+                None => "File header".to_string(),
+            };
+            render_prompt(editor, title, hint, centered_rect(60, 40, area), buf);
+        }
+        Modal::Replace { prompt, .. } => {
+            let hint = Line::from(vec![
+                " Next/Apply ".into(),
+                "<ENTER> ".blue().bold(),
+                "Cancel ".into(),
+                "<ESC> ".blue().bold(),
+            ]);
+            let title = "Find (first line) and replace with (second line)".to_string();
+            render_prompt(prompt, title, hint, centered_rect(50, 20, area), buf);
+        }
+    }
+}
+
+/// The scratchpad over the current tab, with the tag popup below its cursor
+fn render_scratchpad(app: &App, area: Rect, buf: &mut Buffer) {
+    let scratchpad_block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.scratchpad_mode.title())
+        .style(Style::default().fg(Color::Yellow));
+    let hints = HintContext::of(app);
+    let (mut scratchpad, scratchpad_block, scratchpad_area) = match app.scratchpad_mode {
+        ScratchpadMode::Task => {
+            let area = centered_rect(60, 10, area);
+            // The preview takes the bottom line, the hints go next to the title
+            let width = area.width.saturating_sub(app.scratchpad_mode.title().len() as u16 + 4);
+            let scratchpad_block = scratchpad_block.title(hint_line(hints, width).right_aligned());
+            // Preview the parsed task (or its parse error) below the input
+            let block = match (app.draft_task.parsed(), app.draft_task.error()) {
+                (_, Some(msg)) => {
+                    scratchpad_block.title_bottom(Line::from(format!(" {msg} ")).red())
+                }
+                (Ok(_), None) if let Some((oneoff, _)) = app.oneoff_conflict() => {
+                    let hint = format!(" {oneoff} is on an open task, Ctrl+G goes there ");
+                    scratchpad_block.title_bottom(Line::from(hint).yellow())
+                }
+                (Ok(task), None) => {
+                    let preview = with_note_titles(&task.to_string(), &app.tag_suggestions.notes);
+                    scratchpad_block.title_bottom(Line::from(format!(" {preview} ")).green())
+                }
+                _ => scratchpad_block,
+            };
+            (app.scratchpad.clone(), block, area)
+        }
+        ScratchpadMode::Note => {
+            let area = centered_rect(60, 30, area);
+            let hint = hint_line(hints, area.width.saturating_sub(2));
+            let block = scratchpad_block.title_bottom(hint.centered());
+            (app.note_scratchpad.clone(), block, area)
+        }
+    };
+
+    Clear.render(scratchpad_area, buf);
+    scratchpad.set_block(scratchpad_block);
+    scratchpad.render(scratchpad_area, buf);
+
+    // Render autocompletion popup if visible
+    if app.autocompletion.is_visible() {
+        // Calculate cursor position within the scratchpad
+        let cursor_line = scratchpad.cursor().0;
+        let cursor_col = scratchpad.cursor().1;
+        let cursor_pos = (
+            scratchpad_area.x.saturating_add(1 + cursor_col as u16), // +1 for border
+            scratchpad_area.y.saturating_add(1 + cursor_line as u16), // +1 for border
+        );
+        app.autocompletion.render(area, buf, cursor_pos);
+    }
+}
+
+/// The task line with each `n:` link showing the title of its note instead
+/// of the guid
+fn with_note_titles(line: &str, notes: &[(String, String)]) -> String {
+    let labels = note_labels(notes);
+    notes.iter().zip(labels).fold(line.to_string(), |line, ((_, guid), label)| {
+        line.replace(&format!("n:{guid}"), &format!("n:\"{label}\""))
+    })
+}
+
+/// Key hints of the current tab for a block drawn in `area`
+fn tab_hints(app: &App, area: Rect) -> Line<'static> {
+    hint_line(HintContext::of_tab(app), area.width.saturating_sub(2)).centered()
+}
+
+/// Lines of a pane starting at `offset` in the selectable line model, with
+/// selected lines in reversed style
+fn highlighted(
+    lines: &[String],
+    offset: usize,
+    selection: Option<&LineSelection>,
+) -> Vec<Line<'static>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match selection {
+            Some(selection) if selection.contains(offset + i) => {
+                Line::from(line.clone()).reversed()
+            }
+            _ => Line::from(line.clone()),
+        })
+        .collect()
+}
+
+/// Application name line, flagging document changes that are not on disk
+fn render_header(app: &App, area: Rect, buf: &mut Buffer) {
+    let dirty = app.persistence.dirty_state();
+    let header = if area.width as usize >= HEADER.len() { HEADER } else { SHORT_HEADER };
+    let mut spans = vec![header.bold()];
+    if app.read_only.is_some() {
+        spans.push(" READ-ONLY".red().bold());
+    }
+    match dirty.error() {
+        Some(error) => spans.push(format!(" [save failed: {error}]").red().bold()),
+        None if dirty.is_dirty() => spans.push(" [unsaved]".yellow()),
+        None => {}
+    }
+    let problems = app.document.lint().len();
+    if problems > 0 {
+        let plural = if problems == 1 { "" } else { "s" };
+        spans.push(format!(" [{problems} problem{plural}, <CTRL>+<L>]").magenta());
+    }
+    if let Some(alert) = app.alerts.current() {
+        let text = format!(" {}", alert.message);
+        spans.push(match alert.level {
+            AlertLevel::Info => text.green(),
+            AlertLevel::Warn => text.yellow(),
+            AlertLevel::Error => text.red().bold(),
+        });
+    }
+    let header = Line::from(spans).centered();
+    if app.alerts.is_flashing() {
+        header.reversed().render(area, buf);
+    } else {
+        header.render(area, buf);
+    }
+}
@@ -0,0 +1,324 @@
+use orgflow::{Configuration, Date, FilterSet, Task, TaskFilter, TaskSort, weekly_streak};
+use std::collections::BTreeMap;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+use tui_textarea::TextArea;
+
+use super::{highlighted, render_header, tab_hints, with_side_pane};
+use crate::app::App;
+
+/// Weeks considered for the completion streak, the last few are shown as bars
+const THROUGHPUT_WEEKS: usize = 52;
+const THROUGHPUT_BARS: usize = 4;
+
+pub fn render_task_viewer(app: &App, area: Rect, buf: &mut Buffer) {
+    // Create a vertical layout
+    let vertical_layout = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+
+    // Split input area in above layout
+    let [appname_area, main_area] = vertical_layout.areas(area);
+
+    // Render title in the vertical area
+    render_header(app, appname_area, buf);
+
+    // Sections between Tasks and Notes are pinned below everything else
+    let between_height = match (app.document.between().len(), app.show_between) {
+        (0, _) => 0,
+        (_, false) => 1,
+        (lines, true) => (lines as u16 + 1).min(main_area.height / 2),
+    };
+    let [main_area, between_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(between_height)])
+            .areas(main_area);
+    if between_height > 0 {
+        render_between(app, between_area, buf);
+    }
+
+    let task_count = app.document.tasks.len();
+    let current_index = app.current_task_index;
+
+    if task_count == 0 {
+        // Show empty state
+        let empty_block = Block::default()
+            .borders(Borders::ALL)
+            .title("No Tasks")
+            .title_bottom(
+                Line::from(vec![
+                    " Quit ".into(),
+                    "<ESC> ".blue().bold(),
+                    "Switch ".into(),
+                    "<CTRL>+<R>/<E> ".blue().bold(),
+                ])
+                .centered(),
+            );
+
+        let mut empty_display = TextArea::from(vec!["No tasks to display".to_string()]);
+        empty_display.set_block(empty_block);
+        empty_display.render(main_area, buf);
+        return;
+    }
+
+    // Task list and metadata side by side, the metadata goes first on narrow terminals
+    let (task_list_area, side_area) = with_side_pane(main_area, 60);
+
+    // Per project completions of the last weeks below the task details
+    let throughput = app.document.throughput(THROUGHPUT_WEEKS, Configuration::week_start());
+    let throughput_height = if throughput.is_empty() {
+        0
+    } else {
+        throughput.len() as u16 + 2
+    };
+    let metadata_area = side_area.map(|side_area| {
+        let [metadata_area, throughput_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(throughput_height)])
+                .areas(side_area);
+        if !throughput.is_empty() {
+            render_throughput(&throughput, throughput_area, buf);
+        }
+        metadata_area
+    });
+
+    // Open tasks untouched for long fade out, and are counted in the title
+    let today = Date::now();
+    let stale_after = Configuration::stale_after();
+    let stale = FilterSet {
+        filters: vec![TaskFilter::OlderThan(stale_after.0)],
+        sort: TaskSort::Document,
+    };
+    let stale_count = stale.apply_at(&app.document.tasks, &today).len();
+    let visible = app.visible_tasks();
+    let shown = match &app.project_filter {
+        Some(project) => format!("{} of {} in {project}", visible.len(), task_count),
+        None => format!("{} total", task_count),
+    };
+    let title = match stale_count {
+        0 => format!("Tasks ({shown})"),
+        n => format!("Tasks ({shown}, {n} stale)"),
+    };
+
+    // Display task list with current selection highlighted
+    let task_list_block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(tab_hints(app, task_list_area));
+
+    // Create content area for the task list
+    let inner_area = task_list_block.inner(task_list_area);
+    task_list_block.render(task_list_area, buf);
+
+    // Render each task line with appropriate styling
+    for (row, &i) in visible.iter().enumerate() {
+        if row >= inner_area.height as usize {
+            break; // Don't render beyond the available space
+        }
+
+        let task = &app.document.tasks[i];
+        let y = inner_area.y + row as u16;
+        let prefix = if i == current_index { "► " } else { "  " };
+        let width = (inner_area.width as usize).saturating_sub(prefix.chars().count());
+        let mut spans = vec![prefix.into()];
+        spans.extend(app.task_format.spans(task, width));
+
+        let style = if i == current_index {
+            Style::default().add_modifier(ratatui::style::Modifier::UNDERLINED)
+        } else {
+            Style::default()
+        };
+        let style = style.patch(stale_style(task, &today, stale_after));
+
+        Line::from(spans).style(style).render(
+            ratatui::layout::Rect {
+                x: inner_area.x,
+                y,
+                width: inner_area.width,
+                height: 1,
+            },
+            buf,
+        );
+    }
+
+    // Display metadata for current task
+    if let Some(metadata_area) = metadata_area
+        && let Some(task) = app.document.tasks.get(current_index)
+        && visible.contains(&current_index)
+    {
+        let metadata_lines = task_metadata_lines(task);
+        let metadata_block = Block::default().borders(Borders::ALL).title("Task Details");
+        Paragraph::new(highlighted(&metadata_lines, 0, app.selection()))
+            .block(metadata_block)
+            .render(metadata_area, buf);
+    }
+}
+
+/// Lines of the sections between Tasks and Notes, collapsed to a single line
+/// with their names unless `app.show_between`
+fn render_between(app: &App, area: Rect, buf: &mut Buffer) {
+    let lines = app.document.between();
+    let names: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("## "))
+        .collect();
+    let (marker, action) = if app.show_between { ("▾", "Collapse") } else { ("▸", "Expand") };
+    let hint = Line::from(vec![
+        format!(" {action} ").into(),
+        "<W> ".blue().bold(),
+        "Move task here ".into(),
+        "<SHIFT>+<W> ".blue().bold(),
+    ]);
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title(format!("{marker} {} ", names.join(", ")))
+        .title(hint.right_aligned());
+    let content: Vec<Line> = lines
+        .iter()
+        .map(|line| match line.strip_prefix("## ") {
+            Some(name) => Line::from(name.to_string()).bold(),
+            None => Line::from(format!("  {line}")),
+        })
+        .collect();
+    Paragraph::new(content).block(block).render(area, buf);
+}
+
+/// Details of a task as shown next to the task list
+/// Dimmed style for open tasks older than the first of the `stale_after`
+/// thresholds, dimmer still beyond the second one
+pub fn stale_style(task: &Task, today: &Date, stale_after: (u32, u32)) -> Style {
+    let age = match task.age_in_days(today) {
+        Some(age) if !task.is_completed() => age,
+        _ => return Style::default(),
+    };
+    if age > i64::from(stale_after.1) {
+        Style::default().fg(Color::DarkGray).add_modifier(ratatui::style::Modifier::DIM)
+    } else if age > i64::from(stale_after.0) {
+        Style::default().add_modifier(ratatui::style::Modifier::DIM)
+    } else {
+        Style::default()
+    }
+}
+
+/// Creation and completion date in one, with what they say together or
+/// what is wrong with them
+fn dates_row(task: &Task, today: &Date) -> String {
+    let dates = match (task.creation_date(), task.completion_date()) {
+        (Some(created), Some(completed)) => format!("{created} → {completed}"),
+        (Some(created), None) => format!("created {created}"),
+        (None, Some(completed)) => format!("done {completed}"),
+        (None, None) => return "unknown".to_string(),
+    };
+    match (task.date_problem(), task.date_facts(today)) {
+        (Some(problem), _) => format!("{dates}, ⚠ {problem}"),
+        (None, Some(facts)) => format!("{dates}, {facts}"),
+        (None, None) => dates,
+    }
+}
+
+pub fn task_metadata_lines(task: &Task) -> Vec<String> {
+    let mut metadata_lines = vec![format!(
+        "Status: {}",
+        if task.is_completed() {
+            "Completed"
+        } else {
+            "Pending"
+        }
+    )];
+
+    if let Some(priority) = task.priority_level() {
+        metadata_lines.push(format!("Priority: {}", priority));
+    } else {
+        metadata_lines.push("Priority: None".to_string());
+    }
+
+    metadata_lines.push(format!("Dates: {}", dates_row(task, &Date::now())));
+    if let Some(due) = task.due_date() {
+        let today = Date::now();
+        let relative = due.relative_to(&today, Configuration::locale());
+        if task.is_overdue(&today) {
+            metadata_lines.push(format!("Due: {due}, {relative}, overdue"));
+        } else {
+            metadata_lines.push(format!("Due: {due}, {relative}"));
+        }
+    }
+
+    // When a recurring task was done the last times
+    let done = task.done_history();
+    if !done.is_empty() {
+        let (today, locale) = (Date::now(), Configuration::locale());
+        let days: Vec<String> = done.iter().map(|day| day.relative_to(&today, locale)).collect();
+        metadata_lines.push(format!("Last done: {}", days.join(", ")));
+    }
+
+    if let Some(tags) = task.tags() {
+        metadata_lines.push(format!("Tags: {}", tags));
+    } else {
+        metadata_lines.push("Tags: None".to_string());
+    }
+
+    let checklist = task.checklist();
+    if !checklist.is_empty() {
+        let done = checklist.iter().filter(|item| item.done).count();
+        metadata_lines.push(format!("Checklist: {done} of {} done", checklist.len()));
+    }
+
+    metadata_lines.push("".to_string());
+    metadata_lines.push("Description:".to_string());
+    metadata_lines.push(task.description().to_string());
+    if !task.details().is_empty() {
+        metadata_lines.push("".to_string());
+        metadata_lines.push("Details:".to_string());
+        metadata_lines.extend(task.details().iter().cloned());
+    }
+    // Comment lines written above the task in the file
+    if !task.comments().is_empty() {
+        metadata_lines.push("".to_string());
+        metadata_lines.push("Comments:".to_string());
+        metadata_lines.extend(task.comments().iter().cloned());
+    }
+    metadata_lines
+}
+
+fn render_throughput(throughput: &BTreeMap<String, Vec<usize>>, area: Rect, buf: &mut Buffer) {
+    let max = throughput
+        .values()
+        .flat_map(|counts| counts.iter().rev().take(THROUGHPUT_BARS))
+        .copied()
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<String> = throughput
+        .iter()
+        .map(|(project, counts)| {
+            let recent = &counts[counts.len().saturating_sub(THROUGHPUT_BARS)..];
+            let done: usize = recent.iter().sum();
+            format!(
+                "{} {:>3} done, {}w streak  {}",
+                sparkline(recent, max),
+                done,
+                weekly_streak(counts),
+                project
+            )
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Completed (last {} weeks)", THROUGHPUT_BARS));
+    let mut display = TextArea::from(lines);
+    display.set_block(block);
+    display.render(area, buf);
+}
+
+/// Render counts as Unicode block bars scaled to `max`
+fn sparkline(counts: &[usize], max: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    counts
+        .iter()
+        .map(|&c| match c {
+            0 => ' ',
+            _ => BARS[((c * BARS.len()).div_ceil(max.max(1)) - 1).min(BARS.len() - 1)],
+        })
+        .collect()
+}
@@ -0,0 +1,208 @@
+use orgflow::{Configuration, Date, Note, OrgDocument};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+use tui_textarea::TextArea;
+
+use super::{highlighted, render_header, tab_hints, with_side_pane};
+use crate::app::App;
+
+/// Between the parts of the strip above a note
+const STRIP_SEPARATOR: &str = " │ ";
+
+pub fn render_note_viewer(app: &App, area: Rect, buf: &mut Buffer) {
+    // Create a vertical layout
+    let vertical_layout = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(0),
+    ]);
+
+    // Split input area in above layout
+    let [appname_area, strip_area, main_area] = vertical_layout.areas(area);
+
+    // Render title in the vertical area
+    render_header(app, appname_area, buf);
+
+    // Where the shown note is, and what it is about
+    let note_count = app.document.notes.len();
+    let current_index = app.current_note_index;
+    let strip = if app.document.is_partial() {
+        Line::from(" Loading notes...")
+    } else {
+        match app.document.notes.get(current_index) {
+            Some(note) => note_strip(note, current_index, note_count, strip_area.width),
+            None => Line::from(" No notes available"),
+        }
+    };
+    Paragraph::new(strip)
+        .style(Style::default().fg(Color::Yellow))
+        .render(strip_area, buf);
+
+    if note_count == 0 {
+        // Show empty state
+        let empty_block = Block::default()
+            .borders(Borders::ALL)
+            .title("No Notes")
+            .title_bottom(
+                Line::from(vec![
+                    " Quit ".into(),
+                    "<ESC> ".blue().bold(),
+                    "Switch ".into(),
+                    "<CTRL>+<R>/<E> ".blue().bold(),
+                ])
+                .centered(),
+            );
+
+        let mut empty_display = TextArea::from(vec!["No notes to display".to_string()]);
+        empty_display.set_block(empty_block);
+        empty_display.render(main_area, buf);
+        return;
+    }
+
+    // Content and metadata side by side, the metadata goes first on narrow terminals
+    let (content_area, metadata_area) = with_side_pane(main_area, 70);
+
+    // Create vertical layout for content area (title + content)
+    let content_vertical = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]);
+
+    let [title_area, note_content_area] = content_vertical.areas(content_area);
+
+    if let Some(note) = app.document.notes.get(current_index) {
+        // Display note title
+        let title_block = Block::default().borders(Borders::ALL).title("Title");
+
+        let mut title_display = TextArea::from(vec![note.title().to_string()]);
+        title_display.set_block(title_block);
+        title_display.render(title_area, buf);
+
+        // Display note content
+        let content_block = Block::default()
+            .borders(Borders::ALL)
+            .title("Content")
+            .title_bottom(tab_hints(app, note_content_area));
+
+        // Content and metadata form one line model for selections
+        let content_lines = note.content();
+        let selection = app.selection();
+        Paragraph::new(highlighted(content_lines, 0, selection))
+            .block(content_block)
+            .render(note_content_area, buf);
+
+        // Display metadata
+        let metadata_lines = note_metadata_lines(note, &app.document);
+        let metadata_block = Block::default().borders(Borders::ALL).title("Metadata");
+        if let Some(metadata_area) = metadata_area {
+            Paragraph::new(highlighted(&metadata_lines, content_lines.len(), selection))
+                .block(metadata_block)
+                .render(metadata_area, buf);
+        }
+    }
+}
+
+/// Metadata lines of a note as shown in the Viewer, its dates are in the
+/// strip above it
+pub fn note_metadata_lines(note: &Note, document: &OrgDocument) -> Vec<String> {
+    let words: usize = note.content().iter().map(|line| line.split_whitespace().count()).sum();
+    let mut lines = vec![
+        format!("Level: {}", note.level()),
+        format!("GUID: {}", note.guid()),
+        format!("Tags: {}", note.tags()),
+        format!("Size: {} line(s), {words} word(s)", note.content().len()),
+    ];
+    let linking = document.tasks_linking_to(&note.guid().to_string());
+    if linking.is_empty() {
+        lines.push("Linked from: no tasks".to_string());
+    } else {
+        lines.push(format!("Linked from {} task(s):", linking.len()));
+        for idx in linking {
+            lines.push(format!("  {}", document.tasks[idx].description()));
+        }
+    }
+    lines
+}
+
+/// The strip above a note: its position, project tags, when it was changed
+/// and created, and how to move on, as far as `width` allows
+fn note_strip(note: &Note, index: usize, count: usize, width: u16) -> Line<'static> {
+    let locale = Configuration::locale();
+    let projects = note.tags().project_tags().join(" ");
+    let parts = [
+        (0, format!("Note {}/{count}", index + 1)),
+        (1, projects),
+        (2, format!("modified {}", note.modification_date().relative_to(&Date::now(), locale))),
+        (3, format!("created {}", note.creation_date())),
+        (4, "←→ browse, e edit".to_string()),
+    ];
+    let parts: Vec<(u8, String)> =
+        parts.into_iter().filter(|(_, text)| !text.is_empty()).collect();
+    let sizes: Vec<(u8, usize)> =
+        parts.iter().map(|(rank, text)| (*rank, text.chars().count())).collect();
+
+    let mut spans: Vec<Span<'static>> = vec![" ".into()];
+    for (n, idx) in fit_strip(&sizes, width.saturating_sub(1) as usize).into_iter().enumerate() {
+        if n > 0 {
+            spans.push(STRIP_SEPARATOR.dark_gray());
+        }
+        let (rank, text) = &parts[idx];
+        match rank {
+            // The project tags as chips
+            1 => spans.push(text.clone().black().on_yellow()),
+            _ => spans.push(text.clone().into()),
+        }
+    }
+    Line::from(spans)
+}
+
+/// Indices of the strip parts that fit into `width` columns, given each
+/// part's rank and width
+///
+/// Parts are left out by rank, the highest first and among equal ranks the
+/// later one first. Rank 0 is always kept, even if it does not fit.
+pub fn fit_strip(parts: &[(u8, usize)], width: usize) -> Vec<usize> {
+    let separator = STRIP_SEPARATOR.chars().count();
+    let mut kept: Vec<usize> = (0..parts.len()).collect();
+    loop {
+        let used: usize = kept.iter().map(|&idx| parts[idx].1).sum::<usize>()
+            + separator * kept.len().saturating_sub(1);
+        let droppable = kept
+            .iter()
+            .enumerate()
+            .filter(|&(_, &idx)| parts[idx].0 > 0)
+            .max_by_key(|&(pos, &idx)| (parts[idx].0, pos));
+        match droppable {
+            Some((pos, _)) if used > width => _ = kept.remove(pos),
+            _ => return kept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_drops_parts_by_rank() {
+        let parts = [(0, 9), (1, 13), (2, 19), (3, 18), (4, 17)];
+        // All parts and four separators
+        assert_eq!(fit_strip(&parts, 88), [0, 1, 2, 3, 4]);
+        assert_eq!(fit_strip(&parts, 87), [0, 1, 2, 3]);
+        assert_eq!(fit_strip(&parts, 50), [0, 1, 2]);
+        assert_eq!(fit_strip(&parts, 25), [0, 1]);
+        assert_eq!(fit_strip(&parts, 24), [0]);
+        assert_eq!(fit_strip(&parts, 20), [0]);
+        // The position stays, whatever the width
+        assert_eq!(fit_strip(&parts, 3), [0]);
+    }
+
+    #[test]
+    fn test_strip_drops_later_parts_of_a_rank_first() {
+        let parts = [(1, 5), (0, 5), (1, 5)];
+        assert_eq!(fit_strip(&parts, 13), [0, 1]);
+        assert_eq!(fit_strip(&parts, 21), [0, 1, 2]);
+        assert!(fit_strip(&[], 0).is_empty());
+    }
+}
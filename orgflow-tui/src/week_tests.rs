@@ -0,0 +1,188 @@
+//! A week of usage driven through the app like from the terminal, restarts
+//! included, checked against golden files in `tests/golden/week`
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the golden files from the current
+//! output after an intended change.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use orgflow::testing::{pin_today, seed_guids};
+use orgflow::{Date, OnComplete};
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::{App, AppTab, UiMode};
+
+/// Files of the base folder compared with their golden versions
+const GOLDEN_FILES: [&str; 3] = ["refile.org", "done.org", "session.json"];
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/week")
+}
+
+/// Start the app on `day` over the base folder, like `orgflow` would
+fn start(basefolder: &Path, day: &str) -> App {
+    pin_today(Some(Date::from_str(day).unwrap()));
+    let document_path = basefolder.join("refile.org");
+    let mut app = App::open(
+        basefolder.to_str().unwrap(),
+        document_path.to_str().unwrap().to_string(),
+    );
+    app.on_complete = OnComplete::Archive;
+    app.ensure_document_loaded();
+    app
+}
+
+fn quit(mut app: App) {
+    app.shutdown().unwrap();
+}
+
+fn key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    app.handle_event(Event::Key(KeyEvent::new(code, modifiers))).unwrap();
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        key(app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+}
+
+/// Add tasks through the scratchpad, leaving it closed
+fn capture(app: &mut App, tasks: &[&str]) {
+    key(app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    for task in tasks {
+        type_text(app, task);
+        key(app, KeyCode::Enter, KeyModifiers::NONE);
+    }
+    close_scratchpad(app);
+}
+
+/// Esc until the scratchpad is gone, a first one may only hide suggestions
+fn close_scratchpad(app: &mut App) {
+    while matches!(app.mode, UiMode::Scratchpad) {
+        key(app, KeyCode::Esc, KeyModifiers::NONE);
+    }
+}
+
+/// Write a note in the Editor and save it
+fn write_note(app: &mut App, title: &str, content: &[&str]) {
+    key(app, KeyCode::F(1), KeyModifiers::NONE);
+    type_text(app, title);
+    key(app, KeyCode::Enter, KeyModifiers::NONE);
+    for (i, line) in content.iter().enumerate() {
+        if i > 0 {
+            key(app, KeyCode::Enter, KeyModifiers::NONE);
+        }
+        type_text(app, line);
+    }
+    key(app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+}
+
+/// Complete the task with `description` from the Tasks list
+fn complete(app: &mut App, description: &str) {
+    key(app, KeyCode::F(3), KeyModifiers::NONE);
+    let row = app
+        .visible_tasks()
+        .iter()
+        .position(|&i| app.document.tasks[i].description() == description)
+        .unwrap_or_else(|| panic!("'{description}' is not listed"));
+    for _ in 0..app.visible_tasks().len() {
+        key(app, KeyCode::Up, KeyModifiers::NONE);
+    }
+    for _ in 0..row {
+        key(app, KeyCode::Down, KeyModifiers::NONE);
+    }
+    key(app, KeyCode::Char('x'), KeyModifiers::NONE);
+}
+
+/// Content of a base folder file with what differs between runs replaced
+fn normalized(basefolder: &Path, name: &str) -> String {
+    let content = std::fs::read_to_string(basefolder.join(name)).unwrap();
+    if name != "session.json" {
+        return content;
+    }
+    let mut session: serde_json::Value = serde_json::from_str(&content).unwrap();
+    session["last_save_timestamp"] = 0.into();
+    let json = serde_json::to_string_pretty(&session).unwrap() + "\n";
+    json.replace(basefolder.to_str().unwrap(), "<basefolder>")
+}
+
+#[test]
+fn test_a_week_of_usage_matches_the_golden_files() {
+    let basefolder = std::env::temp_dir().join(format!("orgflow-week-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&basefolder);
+    std::fs::create_dir_all(&basefolder).unwrap();
+    seed_guids(Some(1));
+
+    // Monday: plan the week
+    let mut app = start(&basefolder, "2025-03-03");
+    capture(
+        &mut app,
+        &[
+            "(A) Send the quarterly report +work @office due:2025-03-07",
+            "Sow carrots +garden",
+            "Buy seed potatoes +garden @errands",
+            "Call the plumber @phone p:mario",
+            "Renew the passport @errands",
+            "(B) Review the budget +work",
+        ],
+    );
+    write_note(&mut app, "Kickoff +work", &["Agenda:", "- budget", "- hiring"]);
+    assert_eq!(app.document.notes.len(), 1);
+    // Left in the scratchpad when quitting
+    key(&mut app, KeyCode::Char('t'), KeyModifiers::CONTROL);
+    type_text(&mut app, "Book the train to Berlin");
+    quit(app);
+
+    // Wednesday: the draft is back, work gets done
+    let mut app = start(&basefolder, "2025-03-05");
+    assert!(matches!(app.mode, UiMode::Scratchpad));
+    assert_eq!(app.draft_task.text(), "Book the train to Berlin");
+    type_text(&mut app, " @travel");
+    key(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    type_text(&mut app, "Water the seedlings +garden");
+    key(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    type_text(&mut app, "Pay the electricity bill");
+    key(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    close_scratchpad(&mut app);
+    complete(&mut app, "Call the plumber");
+    complete(&mut app, "Review the budget");
+
+    // Only the garden tasks, in document order
+    key(&mut app, KeyCode::Char('p'), KeyModifiers::NONE);
+    key(&mut app, KeyCode::Down, KeyModifiers::NONE);
+    key(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.project_filter.as_deref(), Some("+garden"));
+    let garden: Vec<&str> =
+        app.visible_tasks().iter().map(|&i| app.document.tasks[i].description()).collect();
+    assert_eq!(garden, ["Sow carrots", "Buy seed potatoes", "Water the seedlings"]);
+    complete(&mut app, "Sow carrots");
+    quit(app);
+
+    // Friday: back on the Tasks tab, the week is wrapped up
+    let mut app = start(&basefolder, "2025-03-07");
+    assert!(matches!(app.current_tab, AppTab::Tasks));
+    assert_eq!(app.project_filter, None);
+    assert_eq!(app.document.notes.len(), 1);
+    complete(&mut app, "Send the quarterly report");
+    capture(&mut app, &["Plan the next sprint +work", "Return the drill to Anna p:anna"]);
+    write_note(&mut app, "Retro", &["Went well: the report", "Next time: fewer errands"]);
+    key(&mut app, KeyCode::F(3), KeyModifiers::NONE);
+    quit(app);
+    pin_today(None);
+    seed_guids(None);
+
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    for name in GOLDEN_FILES {
+        let actual = normalized(&basefolder, name);
+        let golden = golden_dir().join(name);
+        if update {
+            std::fs::create_dir_all(golden_dir()).unwrap();
+            std::fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&golden).unwrap();
+        assert_eq!(actual, expected, "{name} differs from {}", golden.display());
+    }
+    std::fs::remove_dir_all(&basefolder).unwrap();
+}
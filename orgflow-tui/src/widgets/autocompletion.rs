@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+
+use orgflow::TagSuggestions;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem},
+};
+
+/// Most notes the `n:` picker offers, enough to scroll through while typing
+/// narrows them down
+const MAX_NOTE_MATCHES: usize = 50;
+
+/// Autocompletion popup widget for tag suggestions
+#[derive(Debug, Clone)]
+pub struct AutocompletionWidget {
+    /// What is inserted for each suggestion
+    suggestions: Vec<String>,
+    /// What the popup shows for each suggestion, the note titles for `n:`
+    labels: Vec<String>,
+    selected_index: usize,
+    visible: bool,
+    current_input: String,
+    current_tag_type: TagType,
+}
+
+#[derive(Debug, Clone)]
+enum TagType {
+    Context,    // @context
+    Project,    // +project
+    Person,     // p:person
+    Custom,     // key:value
+    OneOff,     // !oneoff
+    Note,       // n:guid, picked by the note title
+    Mixed,      // Multiple types or unknown
+}
+
+impl AutocompletionWidget {
+    pub fn new() -> Self {
+        Self {
+            suggestions: Vec::new(),
+            labels: Vec::new(),
+            selected_index: 0,
+            visible: false,
+            current_input: String::new(),
+            current_tag_type: TagType::Mixed,
+        }
+    }
+
+    /// Update suggestions based on current input and available tags
+    pub fn update_suggestions(&mut self, input: &str, tag_suggestions: &TagSuggestions) {
+        self.current_input = input.to_string();
+
+        // Find the last word that looks like a tag (starts with @, +, p:, !, or contains :)
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let last_word = words.last().unwrap_or(&"");
+
+        if let Some(query) = last_word.strip_prefix("n:") {
+            // Guids mean nothing to a human, notes are picked by their title
+            let labels = note_labels(&tag_suggestions.notes);
+            let notes = tag_suggestions.notes.iter().zip(labels);
+            let matches: Vec<_> = notes
+                .filter(|(_, label)| fuzzy_match(query, label))
+                .take(MAX_NOTE_MATCHES)
+                .collect();
+            self.suggestions = matches.iter().map(|((_, guid), _)| format!("n:{guid}")).collect();
+            self.labels = matches.into_iter().map(|(_, label)| label).collect();
+            self.current_tag_type = TagType::Note;
+            self.visible = !self.suggestions.is_empty();
+            self.selected_index = 0;
+        } else if self.is_tag_prefix(last_word, tag_suggestions) {
+            self.suggestions = tag_suggestions.suggestions_for_prefix(last_word);
+            self.labels = self.suggestions.clone();
+            self.current_tag_type = self.determine_tag_type(last_word);
+            self.visible = !self.suggestions.is_empty();
+            self.selected_index = 0;
+        } else {
+            self.visible = false;
+            self.suggestions.clear();
+            self.labels.clear();
+            self.current_tag_type = TagType::Mixed;
+        }
+    }
+
+    /// Re-evaluate suggestions for the given text, e.g. when the scratchpad is
+    /// reopened or focus returns to the title, so the popup reappears for the
+    /// token under the cursor without requiring another keystroke
+    ///
+    /// Unlike typing, coming back keeps the candidate picked before if it
+    /// is still offered.
+    pub fn refresh(&mut self, input: &str, tag_suggestions: &TagSuggestions) {
+        let picked = self.get_selected().cloned();
+        self.update_suggestions(input, tag_suggestions);
+        if let Some(index) = picked.and_then(|picked| {
+            self.suggestions.iter().position(|suggestion| *suggestion == picked)
+        }) {
+            self.selected_index = index;
+        }
+    }
+
+    /// Check if a word looks like the start of a tag
+    ///
+    /// Words with a colon only count if the part before it is a known key,
+    /// so that "Meeting:" or "re:" in a title stay plain text.
+    fn is_tag_prefix(&self, word: &str, tag_suggestions: &TagSuggestions) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        if word.starts_with(['@', '+', '!']) {
+            // @context, +project, !oneoff
+            return true;
+        }
+        match word.split_once(':') {
+            // p:person, est:1h, custom:value
+            Some((key, _)) => tag_suggestions.knows_key(key),
+            None => word.starts_with('p') && word.len() >= 2,
+        }
+    }
+
+    /// Determine the tag type based on the prefix
+    fn determine_tag_type(&self, word: &str) -> TagType {
+        if word.starts_with('@') {
+            TagType::Context
+        } else if word.starts_with('+') {
+            TagType::Project
+        } else if word.starts_with('!') {
+            TagType::OneOff
+        } else if word.starts_with("p:") {
+            // Person tags must start with exactly "p:"
+            TagType::Person
+        } else if word.contains(':') && word.len() > 1 {
+            // Any other tag containing ':' is a custom tag
+            TagType::Custom
+        } else {
+            TagType::Mixed
+        }
+    }
+
+    /// Get the display name for the current tag type
+    fn get_tag_type_display(&self) -> &'static str {
+        match self.current_tag_type {
+            TagType::Context => "Context",
+            TagType::Project => "Project",
+            TagType::Person => "Person",
+            TagType::Custom => "Custom",
+            TagType::OneOff => "OneOff",
+            TagType::Note => "Note",
+            TagType::Mixed => "Tags",
+        }
+    }
+
+    /// Move selection up in the suggestions list
+    pub fn select_previous(&mut self) {
+        if !self.suggestions.is_empty() {
+            self.selected_index = if self.selected_index == 0 {
+                self.suggestions.len() - 1
+            } else {
+                self.selected_index - 1
+            };
+        }
+    }
+
+    /// Move selection down in the suggestions list
+    pub fn select_next(&mut self) {
+        if !self.suggestions.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.suggestions.len();
+        }
+    }
+
+    /// Get the currently selected suggestion
+    pub fn get_selected(&self) -> Option<&String> {
+        self.suggestions.get(self.selected_index)
+    }
+
+    /// Apply the selected suggestion to the input
+    /// Returns (new_text, cursor_position) where cursor_position is the character index
+    pub fn apply_selected(&self, input: &str) -> Option<(String, usize)> {
+        if let Some(selected) = self.get_selected() {
+            let mut words: Vec<&str> = input.split_whitespace().collect();
+            // Only the word the suggestion was made for gets replaced, for a
+            // note that is the `n:` with the start of its title
+            if let Some(last_word) = words.last_mut()
+                && (selected.to_lowercase().starts_with(&last_word.to_lowercase())
+                    || matches!(self.current_tag_type, TagType::Note)
+                        && last_word.starts_with("n:"))
+            {
+                // Replace the last word with the selected suggestion
+                words.pop();
+                words.push(selected);
+                let new_text = words.join(" ") + " ";
+                let cursor_pos = new_text.len(); // Position cursor at the end
+                return Some((new_text, cursor_pos));
+            }
+        }
+        None
+    }
+
+    /// Check if the autocompletion popup is visible
+    pub fn is_visible(&self) -> bool {
+        self.visible && !self.suggestions.is_empty()
+    }
+
+    /// Hide the autocompletion popup
+    ///
+    /// The suggestions are kept so that a later `refresh` or further typing on
+    /// the same token can bring the popup back with its candidates.
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Render the autocompletion popup at a specific position
+    pub fn render(&self, area: Rect, buf: &mut Buffer, cursor_pos: (u16, u16)) {
+        if !self.is_visible() {
+            return;
+        }
+
+        // Below the cursor if there is room, shrunk to fit small terminals
+        // Max 6 suggestions + borders
+        let popup_height = (self.suggestions.len() as u16 + 2).min(8).min(area.height);
+        let popup_width = self.labels
+            .iter()
+            .map(|s| s.chars().count() as u16)
+            .max()
+            .unwrap_or(20)
+            .clamp(20, 40) // Min 20, max 40 chars wide
+            .min(area.width);
+        if popup_height < 3 || popup_width < 3 {
+            return; // No room for a single suggestion inside the borders
+        }
+
+        let popup_area = Rect {
+            x: cursor_pos.0.clamp(area.x, area.right() - popup_width),
+            y: cursor_pos.1.saturating_add(1).clamp(area.y, area.bottom() - popup_height),
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Create list items
+        let items: Vec<ListItem> = self
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(i, suggestion)| {
+                let style = if i == self.selected_index {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(suggestion.as_str()).style(style)
+            })
+            .collect();
+
+        // Create the list widget
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.get_tag_type_display())
+                    .style(Style::default().bg(Color::DarkGray)),
+            );
+
+        // Render the popup
+        ratatui::widgets::Clear.render(popup_area, buf);
+        ratatui::prelude::Widget::render(list, popup_area, buf);
+    }
+}
+
+/// Titles of the notes as they are shown for picking, notes sharing a title
+/// get the start of their guid added
+pub fn note_labels(notes: &[(String, String)]) -> Vec<String> {
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for (title, _) in notes {
+        *uses.entry(title).or_default() += 1;
+    }
+    notes
+        .iter()
+        .map(|(title, guid)| {
+            if uses[title.as_str()] > 1 {
+                format!("{title} ({})", &guid[..guid.len().min(8)])
+            } else {
+                title.clone()
+            }
+        })
+        .collect()
+}
+
+/// Whether the characters of `query` appear in `text` in the same order,
+/// ignoring case, so "mtng" finds "Meeting notes"
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query.chars().flat_map(char::to_lowercase).all(|q| text.any(|c| c == q))
+}
+
+impl Default for AutocompletionWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use orgflow::{Tag, TagSuggestions};
+
+    const SHOPPING: &str = "5f0c6f1e-3a51-4f0e-9a0b-1c2d3e4f5a6b";
+    const MEETING_1: &str = "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+    const MEETING_2: &str = "0b1c2d3e-4f5a-4b6c-8d7e-9f0a1b2c3d4e";
+
+    fn create_test_suggestions() -> TagSuggestions {
+        TagSuggestions {
+            context: vec!["@work".to_string(), "@home".to_string(), "@phone".to_string()].into(),
+            project: vec!["+project1".to_string(), "+urgent".to_string()].into(),
+            person: vec!["p:john".to_string(), "p:alice".to_string()].into(),
+            custom: vec!["priority:high".to_string(), "status:done".to_string()].into(),
+            oneoff: vec!["!important".to_string(), "!reminder".to_string()].into(),
+            open_oneoffs: Default::default(),
+            notes: vec![
+                ("Shopping list".to_string(), SHOPPING.to_string()),
+                ("Meeting notes".to_string(), MEETING_1.to_string()),
+                ("Meeting notes".to_string(), MEETING_2.to_string()),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_context_tag_suggestions() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("This is a task @w", &suggestions);
+
+        assert!(widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["@work"]);
+    }
+
+    #[test]
+    fn test_project_tag_suggestions() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Task +p", &suggestions);
+
+        assert!(widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["+project1"]);
+    }
+
+    #[test]
+    fn test_no_suggestions_for_regular_text() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("This is regular text", &suggestions);
+
+        assert!(!widget.is_visible());
+    }
+
+    #[test]
+    fn test_apply_selected() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Task @w", &suggestions);
+        let result = widget.apply_selected("Task @w");
+
+        assert_eq!(result, Some(("Task @work ".to_string(), 11))); // 11 is the length "Task @work "
+    }
+
+    #[test]
+    fn test_apply_selected_cursor_position() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        // Test with context tag
+        widget.update_suggestions("Do something @h", &suggestions);
+        let result = widget.apply_selected("Do something @h");
+        let expected_text = "Do something @home ";
+        assert_eq!(result, Some((expected_text.to_string(), expected_text.len())));
+
+        // Test with project tag
+        widget.update_suggestions("Fix bug +p", &suggestions);
+        let result = widget.apply_selected("Fix bug +p");
+        let expected_text = "Fix bug +project1 ";
+        assert_eq!(result, Some((expected_text.to_string(), expected_text.len())));
+
+        // Test with person tag
+        widget.update_suggestions("Ask p:a", &suggestions);
+        let result = widget.apply_selected("Ask p:a");
+        let expected_text = "Ask p:alice ";
+        assert_eq!(result, Some((expected_text.to_string(), expected_text.len())));
+    }
+
+    #[test]
+    fn test_hide_then_keep_typing() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Task @w", &suggestions);
+        assert!(widget.is_visible());
+
+        // ESC hides the popup but keeps the candidates around
+        widget.hide();
+        assert!(!widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["@work"]);
+
+        // Coming back and typing on the same token brings it back
+        widget.refresh("Task @w", &suggestions);
+        assert!(widget.is_visible());
+        widget.update_suggestions("Task @wo", &suggestions);
+        assert!(widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["@work"]);
+    }
+
+    #[test]
+    fn test_close_and_reopen() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Call p:", &suggestions);
+        assert!(widget.is_visible());
+        widget.select_next();
+
+        // Closing the scratchpad hides the popup
+        widget.hide();
+        assert!(!widget.is_visible());
+
+        // Reopening refreshes from the restored draft text, the pick stays
+        widget.refresh("Call p:", &suggestions);
+        assert!(widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["p:john", "p:alice"]);
+        assert_eq!(widget.get_selected(), Some(&"p:alice".to_string()));
+        // Typing starts from the top again
+        widget.update_suggestions("Call p:", &suggestions);
+        assert_eq!(widget.get_selected(), Some(&"p:john".to_string()));
+    }
+
+    #[test]
+    fn test_refresh_without_tag_token() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Task @w", &suggestions);
+        widget.hide();
+        widget.refresh("Task done", &suggestions);
+
+        assert!(!widget.is_visible());
+        assert!(widget.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_ordinary_colons_stay_text() {
+        let mut widget = AutocompletionWidget::new();
+        let mut suggestions = create_test_suggestions();
+        let mut custom = suggestions.custom.tags().to_vec();
+        custom.extend(["meeting:weekly".to_string(), "effort:high".to_string()]);
+        suggestions.custom = custom.into();
+
+        // Keys must be written as they are, and only known keys complete
+        widget.update_suggestions("Meeting:", &suggestions);
+        assert!(!widget.is_visible());
+        widget.update_suggestions("re: thing", &suggestions);
+        assert!(!widget.is_visible());
+        widget.update_suggestions("Lunch re:", &suggestions);
+        assert!(!widget.is_visible());
+
+        widget.update_suggestions("Budget review effort:h", &suggestions);
+        assert!(widget.is_visible());
+        assert_eq!(widget.suggestions, vec!["effort:high"]);
+        assert_eq!(widget.get_tag_type_display(), "Custom");
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("@", &suggestions);
+
+        assert_eq!(widget.selected_index, 0);
+
+        widget.select_next();
+        assert_eq!(widget.selected_index, 1);
+
+        widget.select_previous();
+        assert_eq!(widget.selected_index, 0);
+    }
+
+    #[test]
+    fn test_tag_type_detection() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        // Test context tag type
+        widget.update_suggestions("Task @w", &suggestions);
+        assert_eq!(widget.get_tag_type_display(), "Context");
+
+        // Test project tag type
+        widget.update_suggestions("Fix +p", &suggestions);
+        assert_eq!(widget.get_tag_type_display(), "Project");
+
+        // Test person tag type
+        widget.update_suggestions("Ask p:a", &suggestions);
+        assert_eq!(widget.get_tag_type_display(), "Person");
+
+        // Test oneoff tag type
+        widget.update_suggestions("Note !i", &suggestions);
+        assert_eq!(widget.get_tag_type_display(), "OneOff");
+
+        // Test custom tag type (would need custom suggestions to actually show)
+        widget.determine_tag_type("priority:"); // Direct test of method
+        // Since update_suggestions filters, we test the method directly
+        assert!(matches!(widget.determine_tag_type("priority:high"), TagType::Custom));
+        assert!(matches!(widget.determine_tag_type("@work"), TagType::Context));
+        assert!(matches!(widget.determine_tag_type("+project"), TagType::Project));
+        assert!(matches!(widget.determine_tag_type("p:john"), TagType::Person));
+        assert!(matches!(widget.determine_tag_type("!urgent"), TagType::OneOff));
+    }
+
+    #[test]
+    fn test_note_picker_inserts_the_guid() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Buy bread n:", &suggestions);
+        assert!(widget.is_visible());
+        assert_eq!(widget.get_tag_type_display(), "Note");
+        assert_eq!(widget.labels.len(), 3);
+
+        widget.update_suggestions("Buy bread n:shlst", &suggestions);
+        assert_eq!(widget.labels, ["Shopping list"]);
+        let (text, _) = widget.apply_selected("Buy bread n:shlst").unwrap();
+        assert_eq!(text, format!("Buy bread n:{SHOPPING} "));
+
+        // The inserted link is a note tag that is written back as it was
+        let word = text.split_whitespace().last().unwrap();
+        let tag = Tag::from_str(word).unwrap();
+        assert!(matches!(tag, Tag::Note(_)));
+        assert_eq!(tag.to_string(), word);
+
+        // A complete link has nothing left to pick
+        widget.update_suggestions(&text, &suggestions);
+        assert!(!widget.is_visible());
+    }
+
+    #[test]
+    fn test_note_picker_tells_same_titles_apart() {
+        let mut widget = AutocompletionWidget::new();
+        let suggestions = create_test_suggestions();
+
+        widget.update_suggestions("Prepare n:meet", &suggestions);
+        assert_eq!(widget.labels, ["Meeting notes (a1a2a3a4)", "Meeting notes (0b1c2d3e)"]);
+        widget.select_next();
+        let (text, _) = widget.apply_selected("Prepare n:meet").unwrap();
+        assert_eq!(text, format!("Prepare n:{MEETING_2} "));
+
+        // The start of the guid picks one of them too
+        widget.update_suggestions("Prepare n:meet0b", &suggestions);
+        assert_eq!(widget.labels, ["Meeting notes (0b1c2d3e)"]);
+    }
+}
@@ -0,0 +1,227 @@
+use orgflow::{Date, Locale, WeekStart};
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use super::centered_box;
+
+/// Width of the popup, seven days of three characters and the borders
+const WIDTH: u16 = 23;
+/// Height of the popup, weekday names and the most weeks a month spans
+const HEIGHT: u16 = 9;
+
+/// What a key press in the calendar leads to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarAction {
+    /// The calendar stays open
+    Open,
+    /// The selected date was picked with Enter
+    Pick(Date),
+    /// Closed with ESC without picking a date
+    Cancel,
+}
+
+/// Popup to pick a date from a month grid
+///
+/// Arrow keys move the selection by days and weeks, PgUp and PgDn by months.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    selected: Date,
+    today: Date,
+    week_start: WeekStart,
+    locale: Locale,
+}
+
+impl Calendar {
+    pub fn new(selected: Date, today: Date, week_start: WeekStart, locale: Locale) -> Self {
+        Self {
+            selected,
+            today,
+            week_start,
+            locale,
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> CalendarAction {
+        match code {
+            KeyCode::Left => self.selected = self.selected.add_days(-1),
+            KeyCode::Right => self.selected = self.selected.add_days(1),
+            KeyCode::Up => self.selected = self.selected.add_days(-7),
+            KeyCode::Down => self.selected = self.selected.add_days(7),
+            KeyCode::PageUp => self.shift_months(-1),
+            KeyCode::PageDown => self.shift_months(1),
+            KeyCode::Enter => return CalendarAction::Pick(self.selected),
+            KeyCode::Esc => return CalendarAction::Cancel,
+            _ => {}
+        }
+        CalendarAction::Open
+    }
+
+    /// Same day `months` later or earlier, the last day for shorter months
+    fn shift_months(&mut self, months: i32) {
+        let index = self.selected.year() * 12 + self.selected.month() as i32 - 1 + months;
+        let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+        let day = self.selected.day().min(days_in_month(year, month));
+        if let Some(date) = Date::from_ymd(year, month, day) {
+            self.selected = date;
+        }
+    }
+
+    /// Draw the popup in the middle of `area`
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let (year, month) = (self.selected.year(), self.selected.month());
+        let title = format!(" {} {year} ", self.locale.month(month as usize));
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(title).centered())
+            .title_bottom(Line::from(" PgUp/PgDn month ").centered())
+            .style(Style::default().fg(Color::Yellow));
+        let popup = centered_box(Constraint::Length(WIDTH), HEIGHT, area);
+        Clear.render(popup, buf);
+
+        let header: Vec<Span> = (0..7)
+            .map(|column| {
+                let weekday = match self.week_start {
+                    WeekStart::Monday => column,
+                    WeekStart::Sunday => (column + 6) % 7,
+                };
+                let name: String = self.locale.weekday(weekday).chars().take(2).collect();
+                Span::from(format!(" {name:>2}")).bold()
+            })
+            .collect();
+        let mut lines = vec![Line::from(header)];
+        for week in month_grid(year, month, self.week_start) {
+            let cells: Vec<Span> = week
+                .iter()
+                .flat_map(|day| {
+                    let date = day.and_then(|day| Date::from_ymd(year, month, day));
+                    let text = day.map_or("  ".to_string(), |day| format!("{day:>2}"));
+                    let text = Span::from(text);
+                    let text = match date {
+                        Some(date) if date == self.selected => text.reversed(),
+                        Some(date) if date == self.today => text.bold().underlined(),
+                        _ => text,
+                    };
+                    [Span::from(" "), text]
+                })
+                .collect();
+            lines.push(Line::from(cells));
+        }
+        Paragraph::new(lines).block(block).render(popup, buf);
+    }
+}
+
+/// Number of days of `month` in `year`
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    (28..=31).rev().find(|&day| Date::from_ymd(year, month, day).is_some()).unwrap_or(28)
+}
+
+/// Weeks of `month` in `year` as rows of seven days, the first column being
+/// `week_start`; cells of the neighbouring months are `None`
+pub fn month_grid(year: i32, month: u32, week_start: WeekStart) -> Vec<[Option<u32>; 7]> {
+    let Some(first) = Date::from_ymd(year, month, 1) else {
+        return Vec::new();
+    };
+    let offset = first.start_of_week(week_start).days_until(&first) as usize;
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    for day in 1..=days_in_month(year, month) {
+        let column = (offset + day as usize - 1) % 7;
+        week[column] = Some(day);
+        if column == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Days of a grid as text, `..` for cells of other months
+    fn grid(year: i32, month: u32, week_start: WeekStart) -> Vec<String> {
+        month_grid(year, month, week_start)
+            .iter()
+            .map(|week| {
+                week.iter()
+                    .map(|day| day.map_or("..".to_string(), |day| format!("{day:>2}")))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_grid_respects_the_week_start() {
+        // June 2025 starts on a Sunday
+        assert_eq!(
+            grid(2025, 6, WeekStart::Monday),
+            [
+                ".. .. .. .. .. ..  1",
+                " 2  3  4  5  6  7  8",
+                " 9 10 11 12 13 14 15",
+                "16 17 18 19 20 21 22",
+                "23 24 25 26 27 28 29",
+                "30 .. .. .. .. .. ..",
+            ]
+        );
+        assert_eq!(
+            grid(2025, 6, WeekStart::Sunday),
+            [
+                " 1  2  3  4  5  6  7",
+                " 8  9 10 11 12 13 14",
+                "15 16 17 18 19 20 21",
+                "22 23 24 25 26 27 28",
+                "29 30 .. .. .. .. ..",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_of_february() {
+        // Leap years every fourth year, but not every hundredth unless every
+        // four hundredth
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2024, 12), 31);
+
+        // February 2021 fits four weeks exactly
+        assert_eq!(month_grid(2021, 2, WeekStart::Monday).len(), 4);
+        let leap = grid(2024, 2, WeekStart::Monday);
+        assert_eq!(leap[0], ".. .. ..  1  2  3  4");
+        assert_eq!(leap[4], "26 27 28 29 .. .. ..");
+        assert!(month_grid(2024, 13, WeekStart::Monday).is_empty());
+    }
+
+    #[test]
+    fn test_keys_move_the_selection() {
+        let date = |s: &str| s.parse::<Date>().unwrap();
+        let today = date("2024-01-31");
+        let mut calendar = Calendar::new(today, today, WeekStart::Monday, Locale::En);
+        assert_eq!(calendar.handle_key(KeyCode::PageDown), CalendarAction::Open);
+        assert_eq!(calendar.selected, date("2024-02-29"));
+        calendar.handle_key(KeyCode::PageDown);
+        assert_eq!(calendar.selected, date("2024-03-29"));
+        calendar.handle_key(KeyCode::Down);
+        calendar.handle_key(KeyCode::Right);
+        assert_eq!(calendar.selected, date("2024-04-06"));
+        for _ in 0..4 {
+            calendar.handle_key(KeyCode::PageUp);
+        }
+        calendar.handle_key(KeyCode::Up);
+        calendar.handle_key(KeyCode::Left);
+        assert_eq!(calendar.selected, date("2023-11-28"));
+        assert_eq!(calendar.handle_key(KeyCode::Enter), CalendarAction::Pick(date("2023-11-28")));
+        assert_eq!(calendar.handle_key(KeyCode::Esc), CalendarAction::Cancel);
+    }
+}
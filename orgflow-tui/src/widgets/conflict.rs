@@ -0,0 +1,67 @@
+use orgflow::Note;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap};
+
+use super::centered_box;
+use crate::editing::note_lines;
+
+/// Popup asking which version of a changed note to go on with, the draft
+/// (`title` and `content` of the Editor) or `note`
+///
+/// With `show_both` the two versions are shown side by side.
+pub fn render_conflict(
+    note: &Note,
+    title: &[String],
+    content: &[String],
+    show_both: bool,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let hint = Line::from(vec![
+        " Keep mine ".into(),
+        "<M> ".blue().bold(),
+        "Take theirs ".into(),
+        "<T> ".blue().bold(),
+        "View both ".into(),
+        "<B> ".blue().bold(),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" The note changed since this draft was started ")
+        .title_bottom(hint.centered())
+        .style(Style::default().fg(Color::Magenta));
+    let (their_title, their_content) = note_lines(note);
+    let height = if show_both {
+        content.len().max(their_content.len()) as u16 + 4
+    } else {
+        4
+    };
+    let popup = centered_box(Constraint::Percentage(80), height, area);
+    Clear.render(popup, buf);
+    let inner = block.inner(popup);
+    block.render(popup, buf);
+
+    if !show_both {
+        let text = format!(
+            "\"{}\" was modified on {}, after your draft of it was started.",
+            note.title(),
+            note.modification_date()
+        );
+        Paragraph::new(text).wrap(Wrap { trim: true }).render(inner, buf);
+        return;
+    }
+    let mine: Vec<String> = title.iter().chain(content).cloned().collect();
+    let theirs: Vec<String> = [their_title].into_iter().chain(their_content).collect();
+    let [mine_area, theirs_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(inner);
+    for (lines, title, area) in [(mine, "Mine", mine_area), (theirs, "Theirs", theirs_area)] {
+        let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::TOP).title(title))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,127 @@
+use orgflow::Task;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap};
+
+use super::centered_box;
+
+/// Pairs of tasks that look like duplicates, decided on one after the other
+/// (see `OrgDocument::find_duplicate_candidates`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateReview {
+    /// Pairs still to decide on, the current one first
+    pairs: Vec<(usize, usize)>,
+    /// Number of pairs the review started with
+    total: usize,
+    /// Pairs merged so far
+    pub merged: usize,
+    /// Tasks deleted so far
+    pub deleted: usize,
+}
+
+impl DuplicateReview {
+    pub fn new(pairs: Vec<(usize, usize)>) -> Self {
+        Self {
+            total: pairs.len(),
+            pairs,
+            merged: 0,
+            deleted: 0,
+        }
+    }
+
+    /// Document indices of the pair to decide on, `None` once all are done
+    pub fn current(&self) -> Option<(usize, usize)> {
+        self.pairs.first().copied()
+    }
+
+    /// Leave the current pair as it is
+    pub fn skip(&mut self) {
+        if !self.pairs.is_empty() {
+            self.pairs.remove(0);
+        }
+    }
+
+    /// Leave the pairs not decided on yet as they are
+    pub fn stop(&mut self) {
+        self.pairs.clear();
+    }
+
+    /// Go on after the task at `index` left the document: pairs with it
+    /// are dropped, the tasks below it move up
+    pub fn removed(&mut self, index: usize) {
+        self.skip();
+        let shift = |task: usize| if task > index { task - 1 } else { task };
+        self.pairs.retain(|&(first, second)| first != index && second != index);
+        for pair in &mut self.pairs {
+            *pair = (shift(pair.0), shift(pair.1));
+        }
+    }
+
+    /// What the review did, for the alert when it ends
+    pub fn summary(&self) -> String {
+        let merges = match self.merged {
+            1 => "1 merge".to_string(),
+            merged => format!("{merged} merges"),
+        };
+        format!("Duplicate review done: {merges} applied, {} deleted", self.deleted)
+    }
+
+    /// Draw the current pair in the middle of `area`
+    pub fn render(&self, tasks: &[Task], area: Rect, buf: &mut Buffer) {
+        let Some((first, second)) = self.current() else {
+            return;
+        };
+        let hint = Line::from(vec![
+            " Keep Both ".into(),
+            "<K> ".blue().bold(),
+            "Merge ".into(),
+            "<M> ".blue().bold(),
+            "Delete ".into(),
+            "<1>/<2> ".blue().bold(),
+            "Stop ".into(),
+            "<ESC> ".blue().bold(),
+        ]);
+        let done = self.total - self.pairs.len();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Possible duplicates {} of {} ", done + 1, self.total))
+            .title_bottom(hint.centered())
+            .style(Style::default().fg(Color::Magenta));
+        let line = |label: &str, index: usize| match tasks.get(index) {
+            Some(task) => Line::from(format!("{label}  {task}")),
+            None => Line::from(format!("{label}  (gone)")).italic(),
+        };
+        let lines = vec![
+            line("1", first),
+            line("2", second),
+            Line::from("Merging keeps 1 with the tags and earlier date of 2").italic(),
+        ];
+        let popup = centered_box(Constraint::Percentage(80), lines.len() as u16 + 2, area);
+        Clear.render(popup, buf);
+        let inner = block.inner(popup);
+        block.render(popup, buf);
+        Paragraph::new(lines).wrap(Wrap { trim: false }).render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removed_tasks_shift_the_pairs_below() {
+        let mut review = DuplicateReview::new(vec![(0, 2), (0, 3), (2, 3), (4, 5)]);
+        // Task 2 merged into 0
+        review.removed(2);
+        review.merged += 1;
+        assert_eq!(review.current(), Some((0, 2)));
+        review.skip();
+        assert_eq!(review.current(), Some((3, 4)));
+        review.skip();
+        assert_eq!(review.current(), None);
+        assert_eq!(review.summary(), "Duplicate review done: 1 merge applied, 0 deleted");
+    }
+}
@@ -0,0 +1,31 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use super::centered_box;
+use crate::hints::{HintContext, actions};
+
+/// Popup listing every key of `context`, the keys right-aligned before
+/// what they do
+pub fn render_help(context: HintContext, area: Rect, buf: &mut Buffer) {
+    let keys_width = actions(context).map(|a| a.keys.chars().count()).max().unwrap_or(0);
+    let lines: Vec<Line> = actions(context)
+        .map(|action| {
+            Line::from(vec![
+                format!(" {:>keys_width$}  ", action.keys).blue().bold(),
+                action.label.into(),
+            ])
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Keys ")
+        .title_bottom(Line::from(" Close <ANY KEY> ").centered())
+        .style(Style::default().fg(Color::Cyan));
+    let popup = centered_box(Constraint::Length(48), lines.len() as u16 + 2, area);
+    Clear.render(popup, buf);
+    Paragraph::new(lines).block(block).render(popup, buf);
+}
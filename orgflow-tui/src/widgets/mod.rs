@@ -0,0 +1,40 @@
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+
+pub mod autocompletion;
+pub mod calendar;
+pub mod conflict;
+pub mod duplicates;
+pub mod help;
+pub mod problems;
+pub mod projects;
+pub mod prompt;
+pub mod waiting;
+
+/// Popup `width` wide and `height` rows high, as far as `area` allows
+pub fn centered_box(width: Constraint, height: u16, area: Rect) -> Rect {
+    let [row] = Layout::vertical([Constraint::Length(height.min(area.height))])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([width]).flex(Flex::Center).areas(row);
+    popup
+}
+
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Length(3),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
@@ -0,0 +1,32 @@
+use orgflow::LintIssue;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use super::centered_rect;
+
+/// Popup listing the problems of the document, with line numbers and reasons
+pub fn render_problems(issues: &[LintIssue], area: Rect, buf: &mut Buffer) {
+    let mut lines = Vec::new();
+    for issue in issues {
+        lines.push(Line::from(format!("line {}: {}", issue.line, issue.message)).yellow());
+        lines.extend(issue.content.lines().map(|l| Line::from(format!("  {l}"))));
+    }
+    let hint = Line::from(vec![
+        " Repair guids ".into(),
+        "<F> ".blue().bold(),
+        "Close ".into(),
+        "<ESC> ".blue().bold(),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Problems: quarantined lines are kept in the file, fix them by hand ")
+        .title_bottom(hint.centered())
+        .style(Style::default().fg(Color::Magenta));
+    let popup = centered_rect(80, 60, area);
+    Clear.render(popup, buf);
+    Paragraph::new(lines).block(block).render(popup, buf);
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use super::centered_box;
+
+/// First row of the picker, clears the project filter
+pub const ALL_PROJECTS: &str = "All projects";
+
+/// Projects to filter the Tasks list by, with how far along each one is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectPicker {
+    /// Projects with their open and completed tasks, shown after the
+    /// `ALL_PROJECTS` row
+    projects: Vec<(String, (usize, usize))>,
+    /// Row of the cursor, 0 being `ALL_PROJECTS`
+    selected: usize,
+}
+
+impl ProjectPicker {
+    /// Picker over `progress` (see `OrgDocument::project_progress`), with
+    /// the cursor on the `current` project filter
+    pub fn new(progress: BTreeMap<String, (usize, usize)>, current: Option<&str>) -> Self {
+        let projects: Vec<_> = progress.into_iter().collect();
+        let selected = current
+            .and_then(|current| projects.iter().position(|(name, _)| name == current))
+            .map_or(0, |idx| idx + 1);
+        Self { projects, selected }
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1).min(self.projects.len());
+    }
+
+    /// Number of rows, `ALL_PROJECTS` included
+    pub fn len(&self) -> usize {
+        self.projects.len() + 1
+    }
+
+    /// Project of the selected row, `None` for all projects
+    pub fn selected(&self) -> Option<&str> {
+        let idx = self.selected.checked_sub(1)?;
+        self.projects.get(idx).map(|(name, _)| name.as_str())
+    }
+
+    /// Rows of `width` characters: the name, a bar growing with the share of
+    /// completed tasks and the counts, right-aligned
+    pub fn rows(&self, width: usize) -> Vec<String> {
+        let counts: Vec<String> = self
+            .projects
+            .iter()
+            .map(|(_, (open, done))| format!("{open} open / {} total", open + done))
+            .collect();
+        let name_width = self
+            .projects
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .chain([ALL_PROJECTS.chars().count()])
+            .max()
+            .unwrap_or(0);
+        let count_width = counts.iter().map(String::len).max().unwrap_or(0);
+        let bar_width = width.saturating_sub(name_width + count_width + 2);
+
+        let mut rows = vec![ALL_PROJECTS.to_string()];
+        for ((name, (open, done)), count) in self.projects.iter().zip(counts) {
+            let total = open + done;
+            let filled = (done * bar_width + total / 2) / total.max(1);
+            let bar = "▇".repeat(filled);
+            rows.push(format!(
+                "{name:<name_width$} {bar:<bar_width$} {count:>count_width$}"
+            ));
+        }
+        rows
+    }
+
+    /// Draw the picker in the middle of `area`
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let hint = Line::from(vec![
+            " Filter ".into(),
+            "<ENTER> ".blue().bold(),
+            "Navigate ".into(),
+            "<↑↓> ".blue().bold(),
+            "Close ".into(),
+            "<ESC> ".blue().bold(),
+        ]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Filter by project")
+            .title_bottom(hint.centered())
+            .style(Style::default().fg(Color::Yellow));
+        let popup = centered_box(Constraint::Percentage(80), self.len() as u16 + 2, area);
+        Clear.render(popup, buf);
+        let inner = block.inner(popup);
+        block.render(popup, buf);
+        let lines: Vec<Line> = self
+            .rows(inner.width as usize)
+            .into_iter()
+            .enumerate()
+            .map(|(row, text)| {
+                if row == self.selected {
+                    Line::from(text).reversed()
+                } else {
+                    Line::from(text)
+                }
+            })
+            .collect();
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker() -> ProjectPicker {
+        let progress =
+            BTreeMap::from([("+garden".to_string(), (3, 1)), ("+house".to_string(), (0, 2))]);
+        ProjectPicker::new(progress, Some("+house"))
+    }
+
+    #[test]
+    fn test_rows_show_counts_and_bars() {
+        let rows = picker().rows(40);
+        assert_eq!(rows[0], "All projects");
+        assert_eq!(rows[1], "+garden      ▇▇▇        3 open / 4 total");
+        assert_eq!(rows[2], "+house       ▇▇▇▇▇▇▇▇▇▇ 0 open / 2 total");
+        // Too narrow for a bar
+        assert_eq!(picker().rows(10)[2], "+house        0 open / 2 total");
+    }
+
+    #[test]
+    fn test_selection_starts_at_the_current_filter() {
+        let mut picker = picker();
+        assert_eq!(picker.selected(), Some("+house"));
+        picker.down();
+        assert_eq!(picker.selected(), Some("+house"));
+        picker.up();
+        picker.up();
+        assert_eq!(picker.selected(), None);
+        picker.up();
+        assert_eq!(picker.selected, 0);
+    }
+}
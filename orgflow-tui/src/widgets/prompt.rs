@@ -0,0 +1,26 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::Color;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Widget};
+use tui_textarea::TextArea;
+
+/// Text input in a popup at `popup`, with its keys in the bottom border
+pub fn render_prompt(
+    prompt: &TextArea<'static>,
+    title: String,
+    hint: Line<'static>,
+    popup: Rect,
+    buf: &mut Buffer,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(hint.centered())
+        .style(Style::default().fg(Color::Yellow));
+    let mut prompt = prompt.clone();
+    prompt.set_block(block);
+    Clear.render(popup, buf);
+    prompt.render(popup, buf);
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use orgflow::{Date, Task};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::prelude::Color;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+
+use super::centered_box;
+
+/// Open tasks waiting on someone, grouped by person, to follow them up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitingList {
+    /// Persons with the tasks waiting on them (see
+    /// `OrgDocument::waiting_by_person`)
+    groups: Vec<(String, Vec<usize>)>,
+    /// Task row of the cursor, counted over all groups
+    selected: usize,
+}
+
+impl WaitingList {
+    pub fn new(groups: BTreeMap<String, Vec<usize>>) -> Self {
+        Self {
+            groups: groups.into_iter().collect(),
+            selected: 0,
+        }
+    }
+
+    /// Take the groups of a changed document, the cursor stays on its row
+    pub fn refresh(&mut self, groups: BTreeMap<String, Vec<usize>>) {
+        self.groups = groups.into_iter().collect();
+        self.selected = self.selected.min(self.task_rows().saturating_sub(1));
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1).min(self.task_rows().saturating_sub(1));
+    }
+
+    /// Number of task rows, a task waiting on two persons counts twice
+    fn task_rows(&self) -> usize {
+        self.groups.iter().map(|(_, tasks)| tasks.len()).sum()
+    }
+
+    /// Document index of the task under the cursor
+    pub fn selected(&self) -> Option<usize> {
+        self.groups
+            .iter()
+            .flat_map(|(_, tasks)| tasks)
+            .nth(self.selected)
+            .copied()
+    }
+
+    /// Lines of the list: each person, then their tasks with how long they
+    /// have been waiting by `today`; the flag marks the cursor's row
+    pub fn rows(&self, tasks: &[Task], today: &Date) -> Vec<(String, bool)> {
+        let mut rows = Vec::new();
+        let mut row = 0;
+        for (person, indices) in &self.groups {
+            rows.push((person.clone(), false));
+            for &index in indices {
+                let Some(task) = tasks.get(index) else {
+                    continue;
+                };
+                let mut text = format!("  {}", task.description());
+                match task.waiting_days(today) {
+                    Some(1) => text.push_str(" · waiting 1 day"),
+                    Some(days) => text.push_str(&format!(" · waiting {days} days")),
+                    None => text.push_str(" · waiting"),
+                }
+                if let Some(date) = task.followed_up() {
+                    text.push_str(&format!(", followed up {date}"));
+                }
+                rows.push((text, row == self.selected));
+                row += 1;
+            }
+        }
+        rows
+    }
+
+    /// Draw the list in the middle of `area`
+    pub fn render(&self, tasks: &[Task], today: &Date, area: Rect, buf: &mut Buffer) {
+        let hint = Line::from(vec![
+            " Followed Up ".into(),
+            "<F> ".blue().bold(),
+            "Stop Waiting ".into(),
+            "<ENTER> ".blue().bold(),
+            "Close ".into(),
+            "<ESC> ".blue().bold(),
+        ]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Waiting for")
+            .title_bottom(hint.centered())
+            .style(Style::default().fg(Color::Yellow));
+        let mut lines: Vec<Line> = self
+            .rows(tasks, today)
+            .into_iter()
+            .map(|(text, selected)| match (selected, text.starts_with(' ')) {
+                (true, _) => Line::from(text).reversed(),
+                (false, true) => Line::from(text),
+                (false, false) => Line::from(text).bold(),
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("Nothing is waiting on anyone").italic());
+        }
+        let popup = centered_box(Constraint::Percentage(80), lines.len() as u16 + 2, area);
+        Clear.render(popup, buf);
+        let inner = block.inner(popup);
+        block.render(popup, buf);
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_rows_group_tasks_by_person() {
+        let tasks: Vec<Task> = [
+            "Contract s:wait(landlord) wait_since:2024-03-04 followup:2024-03-08",
+            "Roof quote @waiting p:anna p:ben wait_since:2024-03-10",
+        ]
+        .iter()
+        .map(|line| Task::from_str(line).unwrap())
+        .collect();
+        let groups = BTreeMap::from([
+            ("anna".to_string(), vec![1]),
+            ("ben".to_string(), vec![1]),
+            ("landlord".to_string(), vec![0]),
+        ]);
+        let mut list = WaitingList::new(groups);
+        list.down();
+        list.down();
+        list.down();
+        assert_eq!(list.selected(), Some(0));
+
+        let today = Date::from_str("2024-03-11").unwrap();
+        let rows: Vec<String> = list.rows(&tasks, &today).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            rows,
+            [
+                "anna",
+                "  Roof quote · waiting 1 day",
+                "ben",
+                "  Roof quote · waiting 1 day",
+                "landlord",
+                "  Contract · waiting 7 days, followed up 2024-03-08",
+            ]
+        );
+
+        list.refresh(BTreeMap::from([("anna".to_string(), vec![1])]));
+        assert_eq!(list.selected(), Some(1));
+    }
+}
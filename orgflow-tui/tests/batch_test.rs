@@ -0,0 +1,172 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const DOCUMENT: &str = "## Tasks
+(A) Call the plumber @phone
+Write the report +work
+Water the plants
+
+## Notes
+";
+
+/// Fresh base folder holding a refile document with three open tasks
+fn basefolder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-batch-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    dir
+}
+
+fn run_batch(dir: &Path, script: &str) -> Output {
+    run_batch_with(dir, script, &[])
+}
+
+fn run_batch_with(dir: &Path, script: &str, vars: &[(&str, &str)]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("batch")
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .envs(vars.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn task_lines(dir: &Path) -> Vec<String> {
+    let content = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+    content
+        .lines()
+        .skip_while(|line| *line != "## Tasks")
+        .skip(1)
+        .take_while(|line| !line.starts_with("##"))
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn good_batch_is_applied() {
+    let dir = basefolder("good");
+    let script = "add Buy milk @errands\n\n# comments are skipped\ndone 1\ntag 2 +q3 @desk\n";
+    let output = run_batch(&dir, script);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let lines = task_lines(&dir);
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("x ") && lines[0].contains("Call the plumber"));
+    assert!(lines[1].contains("+work") && lines[1].contains("+q3") && lines[1].contains("@desk"));
+    assert!(lines[3].contains("Buy milk @errands"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn indices_refer_to_the_start_of_the_batch() {
+    let dir = basefolder("indices");
+    let output = run_batch(&dir, "del 1\ndone 3\ndel 2\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let lines = task_lines(&dir);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("x ") && lines[0].contains("Water the plants"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failing_batch_leaves_file_untouched() {
+    let dir = basefolder("failing");
+    let path = dir.join("refile.org");
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    let output = run_batch(&dir, "add Buy milk\ndel 1\ndone 1\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 3"), "{stderr}");
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), DOCUMENT);
+    assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), modified);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn read_only_document_is_refused() {
+    let dir = basefolder("read-only");
+    let path = dir.join("refile.org");
+    let marked = format!("#orgflow:readonly\n{DOCUMENT}");
+    std::fs::write(&path, &marked).unwrap();
+
+    let output = run_batch(&dir, "add Buy milk\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("read-only"), "{stderr}");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), marked);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_uses_the_configured_document() {
+    let dir = basefolder("refile-var");
+    std::fs::rename(dir.join("refile.org"), dir.join("inbox.org")).unwrap();
+    let output = run_batch_with(&dir, "add Buy milk\n", &[("ORGFLOW_REFILE", "inbox.org")]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let content = std::fs::read_to_string(dir.join("inbox.org")).unwrap();
+    assert!(content.contains("Buy milk"));
+    assert!(!dir.join("refile.org").exists());
+
+    // A document outside the base folder has to be allowed explicitly
+    let outside = std::env::temp_dir().join(format!("orgflow-outside-{}.org", std::process::id()));
+    std::fs::write(&outside, DOCUMENT).unwrap();
+    let vars = [("ORGFLOW_REFILE", outside.to_str().unwrap())];
+    let output = run_batch_with(&dir, "add Buy milk\n", &vars);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ORGFLOW_ALLOW_OUTSIDE=1"));
+    assert_eq!(std::fs::read_to_string(&outside).unwrap(), DOCUMENT);
+
+    let vars = [("ORGFLOW_REFILE", outside.to_str().unwrap()), ("ORGFLOW_ALLOW_OUTSIDE", "1")];
+    let output = run_batch_with(&dir, "add Buy milk\n", &vars);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(std::fs::read_to_string(&outside).unwrap().contains("Buy milk"));
+
+    std::fs::remove_file(&outside).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn added_tasks_get_the_project_defaults() {
+    let dir = basefolder("defaults");
+    std::fs::write(dir.join("project-defaults"), "+webdev est:30min @work\n").unwrap();
+    let output = run_batch(&dir, "add Fix the navbar +webdev\nadd Deploy +webdev @home\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let lines = task_lines(&dir);
+    assert!(lines[3].ends_with("Fix the navbar +webdev est:30min @work"), "{lines:?}");
+    assert!(lines[4].ends_with("Deploy +webdev @home est:30min"), "{lines:?}");
+
+    // Nothing is added with defaults that cannot be read
+    std::fs::write(dir.join("project-defaults"), "webdev @work\n").unwrap();
+    let output = run_batch(&dir, "add Fix the footer +webdev\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("project-defaults, line 1"), "{stderr}");
+    assert_eq!(task_lines(&dir).len(), 5);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn recurring_task_done_in_a_batch_comes_back() {
+    let dir = basefolder("recurring");
+    run_batch(&dir, "tag 3 rec:1w\n");
+    // Task numbers still refer to the tasks before the batch
+    let output = run_batch(&dir, "done 3\ntag 3 @balcony\ndel 1\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let lines = task_lines(&dir);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("Water the plants rec:1w t:"), "{lines:?}");
+    assert!(lines[1].contains(" done:"), "{lines:?}");
+    assert!(lines[2].starts_with("x "), "{lines:?}");
+    assert!(lines[2].ends_with("Water the plants rec:1w @balcony"), "{lines:?}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
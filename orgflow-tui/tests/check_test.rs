@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const GUID: &str = "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+
+/// Fresh base folder holding a refile document with the given notes
+fn basefolder(name: &str, notes: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-check-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let document = format!("## Tasks\nRead the minutes n:{GUID}\n\n## Notes\n\n{notes}");
+    std::fs::write(dir.join("refile.org"), document).unwrap();
+    dir
+}
+
+fn note(title: &str, guid: &str) -> String {
+    format!("### {title}\n> cre:2024-01-01 mod:2024-01-01 guid:{guid}\nContent\n\n")
+}
+
+fn run_check(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("check")
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn fix_repairs_guids_with_backup() {
+    let notes = note("Minutes", GUID) + &note("Minutes (copy)", GUID);
+    let dir = basefolder("fix", &notes);
+    let before = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+
+    let output = run_check(&dir, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Duplicate guid"));
+
+    let output = run_check(&dir, &["--fix"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("Replaced guid"), "{stdout}");
+
+    let after = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+    assert_eq!(after.matches(GUID).count(), 2, "task link and first note keep it");
+    assert_eq!(std::fs::read_to_string(dir.join("refile.org.bak")).unwrap(), before);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn clean_document_is_untouched() {
+    let dir = basefolder("clean", &note("Minutes", GUID));
+    let path = dir.join("refile.org");
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    let output = run_check(&dir, &["--fix"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No problems found"));
+    assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), modified);
+    assert!(!dir.join("refile.org.bak").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
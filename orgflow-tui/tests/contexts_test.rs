@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DOCUMENT: &str = "## Tasks
+Buy stamps @town @errands
+(A) Call the dentist @phone
+Pick up the parcel @errands
+x 2025-01-02 Return the books @town
+Think about the garden
+
+## Notes
+";
+
+fn basefolder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-contexts-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    dir
+}
+
+fn run_contexts(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("contexts")
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn errands_list_as_text() {
+    let dir = basefolder("text");
+    let output = run_contexts(&dir, &["--only", "@errands,@town"]);
+    assert_eq!(
+        output,
+        "@errands\n[ ] Buy stamps\n[ ] Pick up the parcel\n\n@town\n[ ] Buy stamps\n"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn all_contexts_as_json() {
+    let dir = basefolder("json");
+    let output = run_contexts(&dir, &["--format", "json"]);
+    let groups: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let groups = groups.as_object().unwrap();
+    let names: Vec<&str> = groups.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["(no context)", "@errands", "@phone", "@town"]);
+    assert_eq!(groups["@phone"][0]["description"], "Call the dentist");
+    assert_eq!(groups["@phone"][0]["priority"], "(A)");
+    assert_eq!(groups["@phone"][0]["line"], 3);
+    assert_eq!(groups["@town"][0]["line"], 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
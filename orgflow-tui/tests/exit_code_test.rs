@@ -0,0 +1,95 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const DOCUMENT: &str = "## Tasks
+Sow carrots +garden
+(A) Buy seed potatoes +garden @errands
+
+## Notes
+";
+
+/// Fresh base folder holding a refile document with `document`
+fn basefolder(name: &str, document: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-exit-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), document).unwrap();
+    dir
+}
+
+fn run(basefolder: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", basefolder)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn nothing_matched_is_one() {
+    let dir = basefolder("nothing", DOCUMENT);
+    let output = run(&dir, &["list", "--project", "+nowhere"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No tasks in this view"));
+
+    let output = run(&dir, &["list", "--project", "+garden"], "");
+    assert_eq!(output.status.code(), Some(0));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn usage_errors_are_two() {
+    let dir = basefolder("usage", DOCUMENT);
+    for args in [&["list", "--sort", "urgency"][..], &["frobnicate"], &["--quiet"]] {
+        let output = run(&dir, args, "");
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+    }
+    // So is a batch line that makes no sense, nothing is written
+    let output = run(&dir, &["batch"], "done 9\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(std::fs::read_to_string(dir.join("refile.org")).unwrap(), DOCUMENT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unwritable_basefolder_is_three() {
+    let dir = basefolder("unwritable", DOCUMENT);
+    // A file where the base folder should be created
+    let blocked = dir.join("refile.org").join("orgflow");
+    let output = run(&blocked, &["batch"], "add Order seeds\n");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!output.stderr.is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn malformed_document_is_three() {
+    let dir = basefolder("malformed", "## Tasks\nSow carrots\n\n## Notes\n\n### No metadata\n");
+    let output = run(&dir, &["batch"], "add Order seeds\n");
+    assert_eq!(output.status.code(), Some(3));
+    let document = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+    assert!(!document.contains("Order seeds"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn quiet_add_prints_nothing() {
+    let dir = basefolder("quiet", DOCUMENT);
+    let output = run(&dir, &["batch", "--quiet"], "add Order seeds +garden\n");
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+    let document = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+    assert!(document.contains("Order seeds +garden"));
+
+    // What was asked for is still printed
+    let output = run(&dir, &["-q", "list", "--open"], "");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[ ] Order seeds +garden"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const DOCUMENT: &str = "## Tasks
+Sow carrots +garden
+(B) Review the budget +work
+(A) Buy seed potatoes +garden @errands
+x 2025-03-04 Order compost +garden
+
+## Notes
+";
+
+fn basefolder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-list-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    dir
+}
+
+fn run_list(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("list")
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn view_is_written_to_a_file() {
+    let dir = basefolder("file");
+    let file = dir.join("view.txt");
+    let args = ["--project", "+garden", "--sort", "priority", "--output", file.to_str().unwrap()];
+    let output = run_list(&dir, &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Wrote 3 task(s)"));
+
+    let text = std::fs::read_to_string(&file).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Tasks: project +garden; sorted by priority");
+    assert!(lines[1].starts_with("Generated "));
+    assert_eq!(
+        lines[3..],
+        [
+            "[ ] (A) Buy seed potatoes +garden @errands",
+            "[ ] Sow carrots +garden",
+            "[x] Order compost +garden",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn open_tasks_are_printed() {
+    let dir = basefolder("print");
+    let output = run_list(&dir, &["--open"]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("Tasks: open; in document order\n"), "{text}");
+    assert!(text.ends_with("[ ] Sow carrots +garden\n[ ] (B) Review the budget +work\n\
+                            [ ] (A) Buy seed potatoes +garden @errands\n"));
+
+    let output = run_list(&dir, &["--sort", "urgency"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown sort 'urgency'"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn view_as_json() {
+    let dir = basefolder("json");
+    let args = ["--project", "+garden", "--sort", "priority", "--format", "json"];
+    let output = run_list(&dir, &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let tasks: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tasks = tasks.as_array().unwrap();
+    let lines: Vec<_> = tasks.iter().map(|task| task["line"].as_u64().unwrap()).collect();
+    assert_eq!(lines, [4, 2, 5]);
+    assert_eq!(tasks[0]["description"], "Buy seed potatoes");
+    assert_eq!(tasks[0]["priority"], "(A)");
+    assert_eq!(tasks[2]["completed"], true);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,82 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const DOCUMENT: &str = "## Tasks
+(A) Call the plumber @phone
+Fix the login !ticket-123
+x 2025-03-04 Send the invoice !ticket-123
+
+## Notes
+";
+
+/// Fresh base folder holding a refile document with three tasks
+fn basefolder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-log-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str], level: Option<&str>, stdin: &str) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_orgflow"));
+    command.args(args).env("ORGFLOW_BASEFOLDER", dir).env_remove("ORGFLOW_LOG");
+    if let Some(level) = level {
+        command.env("ORGFLOW_LOG", level);
+    }
+    command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = command.spawn().unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap();
+}
+
+/// Lines of the log without their timestamps and the base folder
+fn logged(dir: &Path) -> Vec<String> {
+    let text = std::fs::read_to_string(dir.join("orgflow.log")).unwrap();
+    text.lines()
+        .map(|line| line.split_once(' ').unwrap().1.replace(dir.to_str().unwrap(), "<dir>"))
+        .collect()
+}
+
+#[test]
+fn info_logs_events_without_content() {
+    let dir = basefolder("info");
+    let broken = DOCUMENT.replace("(A) Call", "x (A) @phone\n(A) Call");
+    std::fs::write(dir.join("refile.org"), broken).unwrap();
+    run(&dir, &["check"], Some("info"), "");
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    run(&dir, &["batch"], Some("info"), "add Buy milk @errands\ndone 1\n");
+    assert_eq!(
+        logged(&dir),
+        [
+            "INFO orgflow::io load path=<dir>/refile.org tasks=3 notes=0 quarantined=1 \
+             partial=false",
+            "WARN orgflow::io parse_warning path=<dir>/refile.org line=2 \
+             message=\"There must be a task description!\"",
+            "INFO orgflow::io load path=<dir>/refile.org tasks=3 notes=0 quarantined=0 \
+             partial=false",
+            "INFO orgflow::batch mutation op=batch_add line=1",
+            "INFO orgflow::batch mutation op=batch_done line=2",
+            "INFO orgflow::io::transaction save path=<dir>/refile.org tasks=4 notes=0",
+        ]
+    );
+    let log = std::fs::read_to_string(dir.join("orgflow.log")).unwrap();
+    assert!(!log.contains("Buy milk") && !log.contains("plumber"));
+
+    // Debug adds what was changed
+    run(&dir, &["batch"], Some("debug"), "add Buy bread @errands\n");
+    assert!(logged(&dir).contains(
+        &"DEBUG orgflow::batch batch line=1: \"add Buy bread @errands\"".to_string()
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn off_writes_nothing() {
+    let dir = basefolder("off");
+    run(&dir, &["batch"], None, "add Buy milk @errands\n");
+    run(&dir, &["batch"], Some("off"), "done 1\n");
+    assert!(!dir.join("orgflow.log").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn folder(name: &str) -> PathBuf {
+    let name = format!("orgflow-migrate-cli-{}-{}", name, std::process::id());
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn run_migrate(from: &Path, to: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .args(["migrate", "--to"])
+        .arg(to)
+        .env("ORGFLOW_BASEFOLDER", from)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn migrate_moves_the_basefolder() {
+    let (from, to) = (folder("from"), folder("to"));
+    std::fs::create_dir_all(from.join("trash")).unwrap();
+    std::fs::write(from.join("refile.org"), "## Tasks\n[ ] Pack boxes\n").unwrap();
+    std::fs::write(from.join("session.json"), "{}").unwrap();
+    std::fs::write(from.join("refile.org.bak"), "## Tasks\n").unwrap();
+    std::fs::write(from.join("trash").join("deleted.org"), "### Old\n").unwrap();
+
+    let output = run_migrate(&from, &to);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("Moved 4 file(s)"), "{stdout}");
+    assert!(stdout.contains("export ORGFLOW_BASEFOLDER="), "{stdout}");
+    assert!(!from.exists());
+    let document = std::fs::read_to_string(to.join("refile.org")).unwrap();
+    assert_eq!(document, "## Tasks\n[ ] Pack boxes\n");
+    assert!(to.join("trash").join("deleted.org").exists());
+    std::fs::remove_dir_all(&to).unwrap();
+}
+
+#[test]
+fn conflicting_target_keeps_the_originals() {
+    let (from, to) = (folder("conflict-from"), folder("conflict-to"));
+    std::fs::create_dir_all(&from).unwrap();
+    std::fs::create_dir_all(&to).unwrap();
+    std::fs::write(from.join("refile.org"), "## Tasks\n[ ] Mine\n").unwrap();
+    std::fs::write(to.join("refile.org"), "## Tasks\n[ ] Theirs\n").unwrap();
+
+    let output = run_migrate(&from, &to);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+    let document = std::fs::read_to_string(from.join("refile.org")).unwrap();
+    assert_eq!(document, "## Tasks\n[ ] Mine\n");
+    std::fs::remove_dir_all(&from).unwrap();
+    std::fs::remove_dir_all(&to).unwrap();
+}
@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const DOCUMENT: &str = "## Tasks
+Move tickets from Jira +jira
+Ask about the jira export
+
+## Notes
+";
+
+/// Fresh base folder holding a refile document mentioning Jira
+fn basefolder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orgflow-sed-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("refile.org"), DOCUMENT).unwrap();
+    dir
+}
+
+fn run_sed(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("sed")
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn dry_run_does_not_write() {
+    let dir = basefolder("dry-run");
+    let output = run_sed(&dir, &["jira", "Linear", "--ignore-case", "--dry-run"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+ Move tickets from Linear"), "{stdout}");
+    assert!(stdout.contains("2 change(s) would be made"), "{stdout}");
+    assert_eq!(std::fs::read_to_string(dir.join("refile.org")).unwrap(), DOCUMENT);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn replacement_is_saved() {
+    let dir = basefolder("saved");
+    let output = run_sed(&dir, &["jira", "Linear", "--whole-word"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let content = std::fs::read_to_string(dir.join("refile.org")).unwrap();
+    assert!(content.contains("Move tickets from Jira +jira"), "{content}");
+    assert!(content.contains("Ask about the Linear export"), "{content}");
+
+    let output = run_sed(&dir, &["only-one-word"]);
+    assert!(!output.status.success());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
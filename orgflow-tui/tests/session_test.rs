@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const FIXTURE: &str = include_str!("corrupted_session.json");
+
+/// Fresh base folder holding the session file that no longer loads
+fn basefolder(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("orgflow-session-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("session.json"), FIXTURE).unwrap();
+    dir
+}
+
+fn run_session(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_orgflow"))
+        .arg("session")
+        .args(args)
+        .env("ORGFLOW_BASEFOLDER", dir)
+        .output()
+        .unwrap()
+}
+
+/// Recovered files of the draft `name`, whatever their time stamp
+fn recovered(dir: &Path, name: &str) -> Vec<PathBuf> {
+    std::fs::read_dir(dir.join("recovered"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let file = path.file_name().unwrap().to_string_lossy().into_owned();
+            file.split_once('-').is_some_and(|(_, rest)| rest == format!("{name}.txt"))
+        })
+        .collect()
+}
+
+#[test]
+fn dump_salvages_drafts_of_a_broken_session() {
+    let dir = basefolder("dump");
+    let output = run_session(&dir, &["dump"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 5);
+
+    let note = recovered(&dir, "note");
+    assert_eq!(note.len(), 1);
+    let content = std::fs::read_to_string(&note[0]).unwrap();
+    assert_eq!(content, "Agreed on the release date\nAlex sends the slides\n");
+    let stash = std::fs::read_to_string(&recovered(&dir, "stash-1-note")[0]).unwrap();
+    assert_eq!(stash, "milk\nbread\n");
+    // Dumping leaves the session file alone
+    assert_eq!(std::fs::read_to_string(dir.join("session.json")).unwrap(), FIXTURE);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn restore_loads_a_text_file_into_the_note_draft() {
+    let dir = basefolder("restore");
+    let text = dir.join("draft.txt");
+    std::fs::write(&text, "First line\nSecond line\n").unwrap();
+
+    let output = run_session(&dir, &["restore", text.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    // The broken session is moved aside, its drafts stay recoverable
+    assert_eq!(std::fs::read_to_string(dir.join("session.json.corrupt")).unwrap(), FIXTURE);
+    let session = std::fs::read_to_string(dir.join("session.json")).unwrap();
+    assert!(session.contains("First line") && session.contains("Second line"));
+
+    // A note draft already there is kept before it is replaced
+    std::fs::write(&text, "Replacement\n").unwrap();
+    let output = run_session(&dir, &["restore", text.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let kept = std::fs::read_to_string(&recovered(&dir, "note")[0]).unwrap();
+    assert_eq!(kept, "First line\nSecond line\n");
+    let session = std::fs::read_to_string(dir.join("session.json")).unwrap();
+    assert!(session.contains("Replacement") && !session.contains("First line"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,72 @@
+//! A frontend in a few lines: load a document, filter its tasks, capture a
+//! task and a note, complete a task and save, all without a terminal UI.
+//!
+//! Run it with `cargo run -p orgflow --example minimal_frontend`.
+
+use orgflow::{
+    CodeBlocks, DraftTask, FilterSet, OnComplete, OrgDocument, TaskFilter, TaskSort,
+    note_from_capture, visible_position,
+};
+
+const DOCUMENT: &str = "## Tasks
+(B) 2024-01-10 Call the plumber @phone @home
+2024-01-12 Water the plants @home
+(A) 2024-01-11 Book flights +travel
+
+## Notes
+";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("orgflow-example-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("refile.org");
+    std::fs::write(&path, DOCUMENT)?;
+    let path = path.to_str().ok_or("temp path is not UTF-8")?;
+
+    // Load
+    let mut document = OrgDocument::from(path)?;
+
+    // Filter: open tasks at home, most important first
+    let filters = FilterSet {
+        filters: vec![TaskFilter::Open, TaskFilter::Context("@home".to_string())],
+        sort: TaskSort::Priority,
+    };
+    let visible = filters.apply(&document.tasks);
+    for &idx in &visible {
+        println!("{}", document.tasks[idx]);
+    }
+    assert_eq!(visible, [0, 1]);
+
+    // Mutate: capture a task the way a text field would, complete the
+    // selected row and write down a note with tags
+    let mut draft = DraftTask::new();
+    draft.update("Fix the  leaking tap\t@home");
+    document.push_task(draft.take()?);
+    let selected_row = 0;
+    document.complete_tasks(&[visible[selected_row]], OnComplete::InPlace);
+    document.push_note(note_from_capture(
+        "Plumber +house",
+        &["Comes on Monday @home".to_string()],
+        CodeBlocks::Fenced,
+    ));
+
+    let visible = filters.apply(&document.tasks);
+    assert_eq!(visible, [1, 3]);
+    assert_eq!(visible_position(&visible, 0), None, "completed tasks are hidden");
+
+    // Save and read back
+    document.to(path)?;
+    let reloaded = OrgDocument::from(path)?;
+    assert_eq!(reloaded.len(), (4, 1));
+    assert!(reloaded.tasks[0].is_completed());
+    assert_eq!(reloaded.tasks[3].description(), "Fix the leaking tap");
+    assert_eq!(reloaded.notes[0].title(), "Plumber");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn smoke() {
+    main().unwrap();
+}
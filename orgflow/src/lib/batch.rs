@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use crate::core::dates::Date;
+use crate::{DefaultsMap, OrgDocument, TagCollection, Task, apply_project_defaults};
+
+/// A single line of a batch script
+#[derive(Debug, PartialEq)]
+enum Operation {
+    /// `add <task>`
+    Add(Task),
+    /// `done <n>`
+    Done(usize),
+    /// `tag <n> <tags>`
+    Tag(usize, TagCollection),
+    /// `del <n>`
+    Delete(usize),
+}
+
+impl Operation {
+    /// Command of the operation as written in scripts
+    fn command(&self) -> &'static str {
+        match self {
+            Operation::Add(_) => "add",
+            Operation::Done(_) => "done",
+            Operation::Tag(..) => "tag",
+            Operation::Delete(_) => "del",
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, rest) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        let rest = rest.trim();
+        let number = |arg: &str| -> Result<usize, String> {
+            match arg.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("'{arg}' is not a task number (counting from 1)")),
+            }
+        };
+        match command {
+            "add" => Ok(Operation::Add(Task::from_str(rest)?)),
+            "done" => Ok(Operation::Done(number(rest)?)),
+            "del" => Ok(Operation::Delete(number(rest)?)),
+            "tag" => {
+                let (n, tags) = rest.split_once(' ').ok_or("Usage: tag <n> <tags>")?;
+                Ok(Operation::Tag(number(n)?, TagCollection::from_str(tags)?))
+            }
+            _ => Err(format!("Unknown operation '{command}'")),
+        }
+    }
+}
+
+/// Apply a batch script to `document`, returning the number of operations
+///
+/// The script has one operation per line: `add <task>`, `done <n>`,
+/// `tag <n> <tags>` or `del <n>`. Empty lines and lines starting with `#` are
+/// skipped. Tasks are numbered from 1 in the order of the Tasks section *as
+/// it was when the batch started*: deleting task 1 does not renumber task 2,
+/// and added tasks cannot be referenced. Referring to a deleted task is an
+/// error. Completed tasks stay where they are, regardless of `OnComplete`,
+/// with the next occurrence of a recurring task added right before them.
+/// Added tasks get the `defaults` of their projects.
+///
+/// The first failing line aborts the batch with an error naming the line.
+/// The document is left partially modified then and should be discarded,
+/// so that a file is written either with all operations or not at all.
+pub fn apply_batch(
+    document: &mut OrgDocument,
+    script: &str,
+    defaults: &DefaultsMap,
+) -> Result<usize, String> {
+    // Current index of each task present at the start, `None` once deleted
+    let mut positions: Vec<Option<usize>> = (0..document.tasks.len()).map(Some).collect();
+    let mut applied = 0;
+    for (idx, line) in script.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let result = Operation::from_str(line).and_then(|operation| {
+            let command = operation.command();
+            apply(document, &mut positions, operation, defaults).map(|()| command)
+        });
+        let command = result.map_err(|msg| format!("line {}: {}", idx + 1, msg))?;
+        log::info!("mutation op=batch_{command} line={}", idx + 1);
+        log::debug!("batch line={}: {line:?}", idx + 1);
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+fn apply(
+    document: &mut OrgDocument,
+    positions: &mut [Option<usize>],
+    operation: Operation,
+    defaults: &DefaultsMap,
+) -> Result<(), String> {
+    let resolve = |n: usize| -> Result<usize, String> {
+        match positions.get(n - 1) {
+            Some(Some(index)) => Ok(*index),
+            Some(None) => Err(format!("Task {n} was deleted earlier in the batch")),
+            None => Err(format!("There is no task {n}")),
+        }
+    };
+    match operation {
+        Operation::Add(mut task) => {
+            if task.creation_date().is_none() {
+                task = Task::with_today(&task.to_string())?;
+            }
+            apply_project_defaults(&mut task, defaults);
+            document.push_task(task);
+        }
+        Operation::Done(n) => {
+            let index = resolve(n)?;
+            let task = &mut document.tasks[index];
+            if task.is_completed() {
+                return Err(format!("Task {n} is already completed"));
+            }
+            // The next occurrence of a recurring task goes before it
+            if let Some(next) = task.complete(&Date::now()) {
+                document.tasks.insert(index, next);
+                for position in positions.iter_mut().flatten() {
+                    if *position >= index {
+                        *position += 1;
+                    }
+                }
+            }
+        }
+        Operation::Tag(n, tags) => document.tasks[resolve(n)?].add_tags(tags),
+        Operation::Delete(n) => {
+            let index = resolve(n)?;
+            document.remove_task(index);
+            for position in positions.iter_mut() {
+                *position = match *position {
+                    Some(i) if i == index => None,
+                    Some(i) if i > index => Some(i - 1),
+                    other => other,
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let cases = [
+            ("add Buy milk @errands", Operation::Add(Task::from_str("Buy milk @errands").unwrap())),
+            ("done 2", Operation::Done(2)),
+            ("  del 1 ", Operation::Delete(1)),
+            ("tag 3 +home @phone", Operation::Tag(3, TagCollection::from_str("+home @phone").unwrap())),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(Operation::from_str(line).unwrap(), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn roundtrip_bad() {
+        let cases = ["add", "done", "done 0", "done two", "tag 1", "tag 1 nope", "rm 1", ""];
+        for line in cases {
+            assert!(Operation::from_str(line).is_err(), "{line}");
+        }
+    }
+}
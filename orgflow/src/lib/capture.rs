@@ -0,0 +1,396 @@
+use std::str::FromStr;
+
+use crate::core::tags::tag_words;
+use crate::{Note, Tag, TagCollection};
+
+/// Turn captured input into a single clean line for a task
+///
+/// Control characters (including line breaks and tabs) become spaces and
+/// runs of whitespace are collapsed into one space.
+pub fn sanitize_line(input: &str) -> String {
+    input
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Longest note title in characters
+pub const TITLE_MAX_LEN: usize = 200;
+
+/// `input` as a single clean line of at most `max_len` characters, and
+/// whether anything had to be cut off
+pub fn single_line(input: &str, max_len: usize) -> (String, bool) {
+    let line = sanitize_line(input);
+    match line.char_indices().nth(max_len) {
+        Some((end, _)) => (line[..end].trim_end().to_string(), true),
+        None => (line, false),
+    }
+}
+
+/// Whether `input` is too much for a task line: longer than `max_len`
+/// characters once sanitized, or made of several paragraphs
+pub fn is_long_capture(input: &str, max_len: usize) -> bool {
+    let paragraphs = input
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .count();
+    paragraphs > 1 || sanitize_line(input).chars().count() > max_len
+}
+
+/// Split captured input into the title and content of a note
+///
+/// The first non-blank line is the title, the following lines are kept as
+/// they are without control characters and trailing blank lines. Returns
+/// `None` if there is nothing but whitespace.
+pub fn note_parts(input: &str) -> Option<(String, Vec<String>)> {
+    let mut lines = input
+        .lines()
+        .map(|line| line.replace('\t', "    ").replace(|c: char| c.is_control(), ""))
+        .skip_while(|line| line.trim().is_empty());
+    let title = sanitize_line(&lines.next()?);
+    let mut content: Vec<String> = lines.map(|line| line.trim_end().to_string()).collect();
+    while content.last().is_some_and(|line| line.is_empty()) {
+        content.pop();
+    }
+    Some((title, content))
+}
+
+/// Tags written in free text, like `@phone` in "Call Bob @phone"
+pub fn extract_tags(text: &str) -> Vec<Tag> {
+    tag_words(text)
+        .into_iter()
+        .filter_map(|word| Tag::from_str(word).ok())
+        .collect()
+}
+
+/// Free text without the words that are tags
+pub fn strip_tags(text: &str) -> String {
+    tag_words(text)
+        .into_iter()
+        .filter(|word| Tag::from_str(word).is_err())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Which lines of note content count as code, kept verbatim on capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeBlocks {
+    /// Lines between ``` fences
+    #[default]
+    Fenced,
+    /// Fenced lines and blocks indented by four spaces or a tab
+    FencedAndIndented,
+}
+
+/// Whether a line opens or closes a fenced code block
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+/// Whether a line would be code in an indented block
+fn is_indented(line: &str) -> bool {
+    (line.starts_with("    ") || line.starts_with('\t')) && !line.trim().is_empty()
+}
+
+/// For each line of `content`, whether it belongs to a code block
+///
+/// A fence line with or without a language hint (```sh) opens a block and
+/// the next fence line closes it. Both fence lines belong to the block, text
+/// after a closing fence included. A block that is never closed runs to the
+/// end. Indented blocks start after a blank line and keep the blank lines
+/// between their indented lines.
+pub fn code_lines(content: &[String], code: CodeBlocks) -> Vec<bool> {
+    let mut flags = vec![false; content.len()];
+    let mut fenced = false;
+    for (idx, line) in content.iter().enumerate() {
+        if is_fence(line) {
+            flags[idx] = true;
+            fenced = !fenced;
+        } else {
+            flags[idx] = fenced;
+        }
+    }
+    if code == CodeBlocks::FencedAndIndented {
+        let mut indented = false;
+        for idx in 0..content.len() {
+            let line = &content[idx];
+            if flags[idx] {
+                indented = false;
+            } else if is_indented(line) {
+                let after_blank = idx == 0 || content[idx - 1].trim().is_empty();
+                indented = indented || after_blank;
+                flags[idx] = indented;
+            } else if line.trim().is_empty() {
+                let next = content[idx..].iter().position(|line| !line.trim().is_empty());
+                flags[idx] = indented && next.is_some_and(|n| is_indented(&content[idx + n]));
+            } else {
+                indented = false;
+            }
+        }
+    }
+    flags
+}
+
+/// Whether a wrapped line may start with `word` without turning into a
+/// heading, a fence, a quote, a table row, a checkbox or a list item
+fn may_start_line(word: &str) -> bool {
+    !word.starts_with(['#', '`', '>', '|', '[']) && !matches!(word, "-" | "*" | "+")
+}
+
+/// Note content with its paragraphs hard-wrapped at `width` characters,
+/// unchanged for a width of 0
+///
+/// Only lines longer than `width` are broken, between words, so a word
+/// longer than the width (like a URL) keeps a line of its own. Fenced code,
+/// indented lines, headings, quotes, tables and checkboxes stay as they are,
+/// list items continue indented below their text. Wrapping the result again
+/// changes nothing.
+pub fn reflow(content: &[String], width: usize) -> Vec<String> {
+    if width == 0 {
+        return content.to_vec();
+    }
+    let code = code_lines(content, CodeBlocks::Fenced);
+    let mut wrapped = Vec::with_capacity(content.len());
+    for (line, code) in content.iter().zip(code) {
+        let starts_text = line.chars().next().is_some_and(|c| !c.is_whitespace());
+        let keep = code || !starts_text || line.starts_with(['#', '>', '|', '[']);
+        if keep || line.chars().count() <= width {
+            wrapped.push(line.clone());
+            continue;
+        }
+        let indent = match line.starts_with("- ") || line.starts_with("* ") {
+            true => "  ",
+            false => "",
+        };
+        let mut words = line.split_whitespace();
+        let mut current = words.next().unwrap_or_default().to_string();
+        for word in words {
+            let len = current.chars().count() + 1 + word.chars().count();
+            if len <= width || !may_start_line(word) {
+                current.push(' ');
+                current.push_str(word);
+            } else {
+                wrapped.push(current);
+                current = format!("{indent}{word}");
+            }
+        }
+        wrapped.push(current);
+    }
+    wrapped
+}
+
+/// Create a note from a typed title and content, moving the tags found in
+/// either into the note's tag collection
+///
+/// Lines left empty by removing the tags are dropped, a title without
+/// anything but tags becomes "Untitled Note". Lines in code blocks (see
+/// `code_lines`) are kept as they are, tags and blank lines included.
+pub fn note_from_capture(title: &str, content: &[String], code: CodeBlocks) -> Note {
+    let code = code_lines(content, code);
+    let mut tags = extract_tags(title);
+    for (line, _) in content.iter().zip(&code).filter(|(_, code)| !**code) {
+        tags.extend(extract_tags(line));
+    }
+
+    let clean_title = strip_tags(title);
+    let title = if clean_title.trim().is_empty() {
+        "Untitled Note".to_string()
+    } else {
+        clean_title
+    };
+    let content: Vec<String> = content
+        .iter()
+        .zip(code)
+        .filter_map(|(line, code)| {
+            let line = if code { line.clone() } else { strip_tags(line) };
+            (code || !line.trim().is_empty()).then_some(line)
+        })
+        .collect();
+
+    if tags.is_empty() {
+        Note::with(title, content)
+    } else {
+        Note::with_tags(title, content, TagCollection::from_tags(tags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize() {
+        let cases = [
+            ("Buy milk @errands", "Buy milk @errands"),
+            ("  Buy\tmilk \n\n @errands  ", "Buy milk @errands"),
+            ("Call\u{7}\u{1b}[31m Bob", "Call [31m Bob"),
+            ("\r\n", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(sanitize_line(input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn single_line_within_limit() {
+        assert_eq!(single_line("Weekly\nsync  notes", 200), ("Weekly sync notes".into(), false));
+        assert_eq!(single_line("Weekly sync", 6), ("Weekly".into(), true));
+        assert_eq!(single_line("Weekly sync", 7), ("Weekly".into(), true));
+        assert_eq!(single_line("Größe", 3), ("Grö".into(), true));
+        assert_eq!(single_line("Größe", 5), ("Größe".into(), false));
+    }
+
+    #[test]
+    fn long_captures() {
+        assert!(!is_long_capture("Buy milk", 200));
+        assert!(!is_long_capture("Dear Bob,\nplease call back", 200));
+        assert!(is_long_capture("Dear Bob,\n\nplease call back", 200));
+        assert!(is_long_capture(&"word ".repeat(50), 200));
+        assert!(!is_long_capture(&format!("{}\n\n\n", "word ".repeat(20)), 200));
+    }
+
+    #[test]
+    fn split_into_note() {
+        let input = "\n  Re: Quarterly\tplanning \nHi all,\n\n\tplease review\u{7}\n\n";
+        let (title, content) = note_parts(input).unwrap();
+        assert_eq!(title, "Re: Quarterly planning");
+        assert_eq!(content, ["Hi all,", "", "    please review"]);
+        assert_eq!(note_parts(" \n\t\n"), None);
+    }
+
+    #[test]
+    fn tags_in_free_text() {
+        let tags = extract_tags("Call Bob @phone about +garden");
+        assert_eq!(tags, [Tag::Context("phone".into()), Tag::Project("garden".into())]);
+        assert_eq!(strip_tags("Call Bob @phone  about +garden"), "Call Bob about");
+
+        let content = ["Agenda +work".to_string(), "@x".to_string()];
+        let note = note_from_capture("@meeting", &content, CodeBlocks::Fenced);
+        assert_eq!(note.title(), "Untitled Note");
+        assert_eq!(note.content(), ["Agenda"]);
+        assert_eq!(note.tags().all_tags(), ["@meeting", "+work", "@x"]);
+    }
+
+    #[test]
+    fn colons_in_titles_are_text() {
+        let note = note_from_capture("Meeting: budget review effort:high", &[], CodeBlocks::Fenced);
+        assert_eq!(note.title(), "Meeting: budget review");
+        assert_eq!(note.tags().all_tags(), ["effort:high"]);
+        assert_eq!(strip_tags("re: thing"), "re: thing");
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn fenced_code_is_verbatim() {
+        let content = lines(
+            "Reboot job @ops\n\
+             ```sh\n\
+             chmod +x run.sh\n\
+             \n\
+             echo '@reboot ./run.sh' | crontab key:value\n\
+             ``` +notatag\n\
+             done +infra",
+        );
+        let note = note_from_capture("Cron", &content, CodeBlocks::Fenced);
+        assert_eq!(note.tags().all_tags(), ["@ops", "+infra"]);
+        assert_eq!(
+            note.content(),
+            [
+                "Reboot job",
+                "```sh",
+                "chmod +x run.sh",
+                "",
+                "echo '@reboot ./run.sh' | crontab key:value",
+                "``` +notatag",
+                "done",
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_fence_runs_to_the_end() {
+        let content = lines("Setup +work\n```\nchmod +x a\n\n@x");
+        assert_eq!(code_lines(&content, CodeBlocks::Fenced), [false, true, true, true, true]);
+        let note = note_from_capture("Setup", &content, CodeBlocks::Fenced);
+        assert_eq!(note.tags().all_tags(), ["+work"]);
+        assert_eq!(note.content(), ["Setup", "```", "chmod +x a", "", "@x"]);
+    }
+
+    #[test]
+    fn indented_code_is_optional() {
+        let content = lines("Run it @ops\n\n    chmod +x a\n\n    ./a @x\n\nThen +b\n    not code");
+        assert_eq!(
+            code_lines(&content, CodeBlocks::FencedAndIndented),
+            [false, false, true, true, true, false, false, false]
+        );
+        let note = note_from_capture("Run", &content, CodeBlocks::FencedAndIndented);
+        assert_eq!(note.tags().all_tags(), ["@ops", "+b"]);
+        assert_eq!(
+            note.content(),
+            ["Run it", "    chmod +x a", "", "    ./a @x", "Then", "not code"]
+        );
+        let note = note_from_capture("Run", &content, CodeBlocks::Fenced);
+        assert_eq!(note.tags().all_tags(), ["@ops", "+x", "@x", "+b"]);
+    }
+
+    #[test]
+    fn reflow_is_stable() {
+        let content = lines(
+            "A paragraph that goes on for quite a bit longer than thirty characters\n\n\
+             - a list item that is also longer than the width\nshort line",
+        );
+        let wrapped = reflow(&content, 30);
+        assert_eq!(
+            wrapped,
+            [
+                "A paragraph that goes on for",
+                "quite a bit longer than thirty",
+                "characters",
+                "",
+                "- a list item that is also",
+                "  longer than the width",
+                "short line",
+            ]
+        );
+        assert_eq!(reflow(&wrapped, 30), wrapped);
+        assert_eq!(reflow(&content, 0), content);
+    }
+
+    #[test]
+    fn reflow_keeps_long_words_and_structure() {
+        let url = "https://example.com/a/very/long/path/that/does/not/fit?at=all";
+        let content = lines(&format!(
+            "{url}\nSee {url} for details\n#### A heading that is longer than the width\n\
+             Nothing starts a line with ### or `code` or > here"
+        ));
+        let wrapped = reflow(&content, 20);
+        assert_eq!(
+            wrapped,
+            [
+                url,
+                "See",
+                url,
+                "for details",
+                "#### A heading that is longer than the width",
+                "Nothing starts a",
+                // Longer than the width rather than starting with a fence
+                "line with ### or `code`",
+                "or > here",
+            ]
+        );
+        assert_eq!(reflow(&wrapped, 20), wrapped);
+    }
+
+    #[test]
+    fn reflow_leaves_code_alone() {
+        let content = lines(concat!(
+            "```sh\necho 'a command that is longer than the width' | tee out\n```\n",
+            "    indented code that is longer than the width",
+        ));
+        assert_eq!(reflow(&content, 20), content);
+    }
+}
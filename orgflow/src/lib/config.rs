@@ -1,7 +1,88 @@
 use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{CodeBlocks, DefaultsMap, Locale, PROJECT_DEFAULTS, WeekStart};
 
 pub struct Configuration;
 
+/// File in the base folder remembering the document name after a rename
+pub const DOCUMENT_POINTER: &str = ".document";
+
+/// Variables naming the document, the first one set wins
+const DOCUMENT_VARS: [&str; 2] = ["ORGFLOW_REFILE", "ORGFLOW_DOCUMENT"];
+
+/// Where the document called `name` lives for `basefolder`
+///
+/// Relative names stay inside the base folder. Absolute paths elsewhere are
+/// refused unless `allow_outside`, since the session and backups still end
+/// up in the base folder.
+pub fn resolve_document(
+    basefolder: &Path,
+    name: &str,
+    allow_outside: bool,
+) -> Result<PathBuf, String> {
+    let name = name.trim();
+    let path = Path::new(name);
+    if path.is_absolute() {
+        let base = basefolder.canonicalize().unwrap_or_else(|_| basefolder.to_path_buf());
+        if allow_outside || path.starts_with(&base) || path.starts_with(basefolder) {
+            return Ok(path.to_path_buf());
+        }
+        return Err(format!(
+            "The document {name} is outside the base folder {}, \
+             set ORGFLOW_ALLOW_OUTSIDE=1 to use it anyway",
+            basefolder.display()
+        ));
+    }
+    if name.is_empty() || path.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(format!(
+            "The document name {name:?} does not name a file in the base folder {}",
+            basefolder.display()
+        ));
+    }
+    Ok(basefolder.join(path))
+}
+
+/// What happens to a task when it gets completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnComplete {
+    /// Keep the task where it is
+    #[default]
+    InPlace,
+    /// Move the task right after the last open task
+    MoveToBottom,
+    /// Remove the task from the document and append it to the archive file
+    Archive,
+}
+
+/// What happens to the `today:` tags of open tasks on the first start of a
+/// new day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rollover {
+    /// Remove them
+    #[default]
+    Clear,
+    /// Replace them by `carried:` with the day they were set
+    Carry,
+    /// Leave them alone
+    Off,
+}
+
+/// Which tasks feed the tag suggestions of the autocompletion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuggestFrom {
+    /// Tags of all tasks and notes
+    #[default]
+    All,
+    /// Tags of completed tasks are left out
+    OpenOnly,
+    /// Tags of tasks completed more than this many days ago are left out
+    Recent(u32),
+}
+
 impl Configuration {
     pub fn basefolder() -> String {
         env::var("ORGFLOW_BASEFOLDER").unwrap_or_else(|_| {
@@ -14,4 +95,179 @@ impl Configuration {
             }
         })
     }
+
+    /// Variable naming the document and its value, `ORGFLOW_REFILE` wins over
+    /// the older `ORGFLOW_DOCUMENT`
+    pub fn document_var() -> Option<(&'static str, String)> {
+        DOCUMENT_VARS.iter().find_map(|var| {
+            let name = env::var(var).ok().filter(|name| !name.trim().is_empty())?;
+            Some((*var, name))
+        })
+    }
+
+    /// File name of the document in `basefolder`, set via `ORGFLOW_REFILE`,
+    /// otherwise the name remembered by the last rename, defaults to `refile.org`
+    pub fn document_name(basefolder: &str) -> String {
+        if let Some((_, name)) = Self::document_var() {
+            return name;
+        }
+        fs::read_to_string(Path::new(basefolder).join(DOCUMENT_POINTER))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "refile.org".to_string())
+    }
+
+    /// Path of the document named by `document_name`, an absolute path outside
+    /// `basefolder` needs `ORGFLOW_ALLOW_OUTSIDE=1`
+    pub fn document_path(basefolder: &str) -> Result<PathBuf, String> {
+        let allow_outside = env::var("ORGFLOW_ALLOW_OUTSIDE").is_ok_and(|v| v == "1");
+        resolve_document(Path::new(basefolder), &Self::document_name(basefolder), allow_outside)
+    }
+
+    /// Behaviour on task completion, set via `ORGFLOW_ON_COMPLETE`
+    /// (`in_place`, `move_to_bottom` or `archive`), defaults to `in_place`
+    pub fn on_complete() -> OnComplete {
+        match env::var("ORGFLOW_ON_COMPLETE").as_deref() {
+            Ok("move_to_bottom") => OnComplete::MoveToBottom,
+            Ok("archive") => OnComplete::Archive,
+            _ => OnComplete::InPlace,
+        }
+    }
+
+    /// Daily rollover of the `today:` tags, set via `ORGFLOW_ROLLOVER`
+    /// (`clear`, `carry` or `off`), defaults to `clear`
+    pub fn rollover() -> Rollover {
+        match env::var("ORGFLOW_ROLLOVER").as_deref() {
+            Ok("carry") => Rollover::Carry,
+            Ok("off") => Rollover::Off,
+            _ => Rollover::Clear,
+        }
+    }
+
+    /// Source of tag suggestions, set via `ORGFLOW_SUGGEST_FROM` (`all`,
+    /// `open_only` or `recent`), defaults to `all`
+    ///
+    /// With `recent`, `ORGFLOW_SUGGEST_RECENT_DAYS` (default 90) decides how
+    /// long the tags of a completed task are still suggested.
+    pub fn suggest_from() -> SuggestFrom {
+        match env::var("ORGFLOW_SUGGEST_FROM").as_deref() {
+            Ok("open_only") => SuggestFrom::OpenOnly,
+            Ok("recent") => SuggestFrom::Recent(
+                env::var("ORGFLOW_SUGGEST_RECENT_DAYS")
+                    .ok()
+                    .and_then(|days| days.parse().ok())
+                    .unwrap_or(90),
+            ),
+            _ => SuggestFrom::All,
+        }
+    }
+
+    /// How much goes into the log file in the base folder, set via
+    /// `ORGFLOW_LOG` (`off`, `info` or `debug`), defaults to `off`
+    ///
+    /// `info` records events with counts and paths, only `debug` adds the
+    /// text of tasks and notes.
+    pub fn log_level() -> log::LevelFilter {
+        match env::var("ORGFLOW_LOG").as_deref() {
+            Ok("info") => log::LevelFilter::Info,
+            Ok("debug") => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Off,
+        }
+    }
+
+    /// Code blocks in notes whose lines are not searched for tags, set via
+    /// `ORGFLOW_INDENTED_CODE=1` to add indented blocks to the fenced ones
+    pub fn code_blocks() -> CodeBlocks {
+        match env::var("ORGFLOW_INDENTED_CODE").as_deref() {
+            Ok("1") => CodeBlocks::FencedAndIndented,
+            _ => CodeBlocks::Fenced,
+        }
+    }
+
+    /// Default tags of new tasks per project, from the `project-defaults`
+    /// file in `basefolder` (see `DefaultsMap`), none without that file
+    pub fn project_defaults(basefolder: &str) -> Result<DefaultsMap, String> {
+        let path = Path::new(basefolder).join(PROJECT_DEFAULTS);
+        match fs::read_to_string(&path) {
+            Ok(text) => DefaultsMap::from_str(&text)
+                .map_err(|e| format!("{PROJECT_DEFAULTS}, {e}")),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DefaultsMap::default()),
+            Err(e) => Err(format!("Cannot read {}: {e}", path.display())),
+        }
+    }
+
+    /// Path of the archive file for completed tasks inside the base folder
+    pub fn archive_path() -> String {
+        Self::archive_path_in(&Self::basefolder())
+    }
+
+    /// Path of the archive in `basefolder`
+    pub fn archive_path_in(basefolder: &str) -> String {
+        format!("{basefolder}/done.org")
+    }
+
+    /// Language of weekday and month names and relative dates, set via
+    /// `ORGFLOW_LOCALE` (e.g. `de` or `en`), defaults to English
+    pub fn locale() -> Locale {
+        env::var("ORGFLOW_LOCALE")
+            .ok()
+            .and_then(|code| Locale::from_str(&code).ok())
+            .unwrap_or_default()
+    }
+
+    /// Length above which pasted text is offered to be captured as a note
+    /// instead of a task, set via `ORGFLOW_CAPTURE_MAX_LEN`, defaults to 200
+    pub fn capture_max_len() -> usize {
+        env::var("ORGFLOW_CAPTURE_MAX_LEN")
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(200)
+    }
+
+    /// Width at which the app hard-wraps the paragraphs of note content
+    /// when saving, set via `ORGFLOW_WRAP_WIDTH`, defaults to 0 for no
+    /// wrapping (see `reflow`)
+    pub fn wrap_width() -> usize {
+        env::var("ORGFLOW_WRAP_WIDTH")
+            .ok()
+            .and_then(|width| width.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Ages in days after which open tasks look stale and then very stale,
+    /// set via `ORGFLOW_STALE_DAYS` (e.g. `30,90`), defaults to 30 and 90
+    pub fn stale_after() -> (u32, u32) {
+        env::var("ORGFLOW_STALE_DAYS")
+            .ok()
+            .and_then(|days| {
+                let (stale, very_stale) = days.split_once(',')?;
+                Some((stale.trim().parse().ok()?, very_stale.trim().parse().ok()?))
+            })
+            .filter(|(stale, very_stale)| stale <= very_stale)
+            .unwrap_or((30, 90))
+    }
+
+    /// Column layout of the Tasks list, set via `ORGFLOW_TASK_FORMAT` (e.g.
+    /// `{pri} {due} {desc} {project}`), `None` keeps the default layout
+    pub fn task_format() -> Option<String> {
+        env::var("ORGFLOW_TASK_FORMAT").ok().filter(|format| !format.trim().is_empty())
+    }
+
+    /// Documents that are opened read-only, set via `ORGFLOW_READONLY` as a
+    /// list of paths separated like `PATH` (`:` on Unix), empty by default
+    pub fn readonly_paths() -> Vec<PathBuf> {
+        env::var_os("ORGFLOW_READONLY")
+            .map(|paths| env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// First day of the week for weekly statistics and relative weeks, set
+    /// via `ORGFLOW_WEEK_STARTS` (`monday` or `sunday`), defaults to Monday
+    pub fn week_start() -> WeekStart {
+        env::var("ORGFLOW_WEEK_STARTS")
+            .ok()
+            .and_then(|day| WeekStart::from_str(&day).ok())
+            .unwrap_or_default()
+    }
 }
@@ -1,13 +1,172 @@
 use std::{fmt::Display, str::FromStr};
 
-use chrono::{Datelike, Local, NaiveDate};
-#[derive(PartialEq, Debug)]
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+
+use super::locale::Locale;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub struct Date(NaiveDate);
 
+/// First day of the week for everything grouped by weeks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl FromStr for WeekStart {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "monday" => Ok(WeekStart::Monday),
+            "sunday" => Ok(WeekStart::Sunday),
+            _ => Err(format!("Week can start on 'monday' or 'sunday', not '{s}'")),
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+thread_local! {
+    /// Day `Date::now` returns on this thread instead of the system date
+    pub(crate) static PINNED_TODAY: std::cell::Cell<Option<Date>> =
+        const { std::cell::Cell::new(None) };
+}
+
 impl Date {
     pub fn now() -> Self {
+        #[cfg(feature = "testing")]
+        if let Some(today) = PINNED_TODAY.get() {
+            return today;
+        }
         Date(Local::now().date_naive())
     }
+
+    /// Date of the day, `None` if there is no such day
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Date> {
+        NaiveDate::from_ymd_opt(year, month, day).map(Date)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.0.year()
+    }
+
+    /// Month from January (1) to December (12)
+    pub fn month(&self) -> u32 {
+        self.0.month()
+    }
+
+    /// Day of the month, starting at 1
+    pub fn day(&self) -> u32 {
+        self.0.day()
+    }
+
+    /// Date `days` later, or earlier for negative `days`, staying put at the
+    /// ends of the supported range
+    pub fn add_days(&self, days: i64) -> Date {
+        self.0.checked_add_signed(chrono::Duration::days(days)).map_or(*self, Date)
+    }
+
+    /// Same day `years` later, the 28th for the 29th of February in a year
+    /// without one, staying put at the end of the supported range
+    pub fn add_years(&self, years: u32) -> Date {
+        self.0.checked_add_months(Months::new(years * 12)).map_or(*self, Date)
+    }
+
+    /// First day (Monday or Sunday) of the week this date belongs to
+    pub fn start_of_week(&self, week_start: WeekStart) -> Date {
+        let offset = match week_start {
+            WeekStart::Monday => self.0.weekday().num_days_from_monday(),
+            WeekStart::Sunday => self.0.weekday().num_days_from_sunday(),
+        };
+        Date(self.0 - Days::new(offset as u64))
+    }
+
+    /// Number of days from `self` to `other`, negative if `other` is earlier
+    pub fn days_until(&self, other: &Date) -> i64 {
+        (other.0 - self.0).num_days()
+    }
+
+    /// Number of whole weeks between the week of `self` and the week of
+    /// `other`, e.g. 0 for dates within the same week
+    pub fn weeks_until(&self, other: &Date, week_start: WeekStart) -> i64 {
+        let start = self.start_of_week(week_start);
+        start.days_until(&other.start_of_week(week_start)) / 7
+    }
+
+    /// Short human-friendly form with localized names, e.g. "Sat, 01 Jun"
+    /// or "Sa, 01. Jun"
+    pub fn format_short(&self, locale: Locale) -> String {
+        let weekday = locale.weekday(self.0.weekday().num_days_from_monday() as usize);
+        let month = locale.month(self.0.month() as usize);
+        match locale {
+            Locale::En => format!("{}, {:02} {}", weekday, self.0.day(), month),
+            Locale::De => format!("{}, {:02}. {}", weekday, self.0.day(), month),
+        }
+    }
+
+    /// Distance to `today` in words, e.g. "tomorrow" or "vor 3 Tagen"
+    pub fn relative_to(&self, today: &Date, locale: Locale) -> String {
+        locale.relative(today.days_until(self))
+    }
+
+    /// Date of a relative word like "tomorrow" (or "morgen" for German)
+    ///
+    /// Weeks like "next week" resolve to the first day of that week.
+    pub fn from_relative(
+        word: &str,
+        today: &Date,
+        locale: Locale,
+        week_start: WeekStart,
+    ) -> Option<Date> {
+        let (start, days) = match locale.parse_relative_week(word) {
+            Some(weeks) => (today.start_of_week(week_start), weeks * 7),
+            None => (*today, locale.parse_relative(word)?),
+        };
+        start.0.checked_add_signed(chrono::Duration::days(days)).map(Date)
+    }
+
+    /// Date of what is typed into a `due:` or `t:` tag, counted from `today`
+    ///
+    /// Besides `YYYY-MM-DD` this takes `today`, `tomorrow`, days or weeks
+    /// ahead like `+3d` or `+2w` and weekdays like `mon` or `friday`, which
+    /// stand for the next such day after `today`. The words are English
+    /// whatever the locale, so a task line reads the same everywhere.
+    pub fn parse_relative(token: &str, today: &Date) -> Result<Date, String> {
+        if let Ok(date) = Date::from_str(token) {
+            return Ok(date);
+        }
+        let word = token.to_lowercase();
+        let ahead = |count: &str, unit: i64| count.parse::<u32>().ok().map(|n| i64::from(n) * unit);
+        let days = match word.as_str() {
+            "today" => Some(0),
+            "tomorrow" => Some(1),
+            _ => match word.strip_prefix('+') {
+                Some(count) if count.ends_with('d') => ahead(&count[..count.len() - 1], 1),
+                Some(count) if count.ends_with('w') => ahead(&count[..count.len() - 1], 7),
+                Some(_) => None,
+                None => weekday_index(&word).map(|weekday| {
+                    let from_monday = i64::from(today.0.weekday().num_days_from_monday());
+                    (weekday - from_monday - 1).rem_euclid(7) + 1
+                }),
+            },
+        };
+        days.and_then(|days| today.0.checked_add_signed(chrono::Duration::days(days)))
+            .map(Date)
+            .ok_or(format!(
+                "'{token}' is no date, use YYYY-MM-DD, today, tomorrow, +3d, +2w or a weekday"
+            ))
+    }
+}
+
+/// Day of the week of an English weekday name like `mon` or `Monday`,
+/// counting from Monday (0)
+fn weekday_index(word: &str) -> Option<i64> {
+    const WEEKDAYS: [&str; 7] =
+        ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+    let word = word.to_lowercase();
+    let position = WEEKDAYS.iter().position(|day| word.len() >= 3 && day.starts_with(&word))?;
+    Some(position as i64)
 }
 
 impl Default for Date {
@@ -41,6 +200,30 @@ impl FromStr for Date {
 mod tests {
     use super::*;
 
+    #[test]
+    fn relative_tokens() {
+        // A Wednesday
+        let today = Date::from_str("2025-03-05").unwrap();
+        let parse = |token: &str| Date::parse_relative(token, &today).map(|d| d.to_string());
+        for (token, expected) in [
+            ("2025-04-01", "2025-04-01"),
+            ("today", "2025-03-05"),
+            ("Tomorrow", "2025-03-06"),
+            ("+3d", "2025-03-08"),
+            ("+2w", "2025-03-19"),
+            ("+0d", "2025-03-05"),
+            ("thu", "2025-03-06"),
+            ("mon", "2025-03-10"),
+            ("wed", "2025-03-12"),
+            ("Friday", "2025-03-07"),
+        ] {
+            assert_eq!(parse(token).as_deref(), Ok(expected), "{token}");
+        }
+        for token in ["soon", "+3", "+d", "-3d", "+3m", "mo", "2025-13-01", ""] {
+            assert!(parse(token).is_err(), "{token}");
+        }
+    }
+
     #[test]
     fn roundtrip() {
         let expected = [
@@ -55,6 +238,70 @@ mod tests {
             assert_eq!(result, val)
         }
     }
+    #[test]
+    fn parts_and_arithmetic() {
+        let date = Date::from_ymd(2024, 2, 29).unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2024, 2, 29));
+        assert_eq!(Date::from_ymd(2023, 2, 29), None);
+        assert_eq!(date.add_days(1).to_string(), "2024-03-01");
+        assert_eq!(date.add_days(-60).to_string(), "2023-12-31");
+    }
+
+    #[test]
+    fn week_helpers() {
+        // 2025-06-01 is a Sunday, 2025-06-02 the following Monday
+        let sunday = Date::from_str("2025-06-01").unwrap();
+        let monday = Date::from_str("2025-06-02").unwrap();
+        let next_sunday = Date::from_str("2025-06-08").unwrap();
+        assert_eq!(sunday.start_of_week(WeekStart::Monday).to_string(), "2025-05-26");
+        assert_eq!(monday.start_of_week(WeekStart::Monday), monday);
+        assert_eq!(sunday.days_until(&monday), 1);
+        assert_eq!(monday.days_until(&sunday), -1);
+        assert_eq!(sunday.weeks_until(&monday, WeekStart::Monday), 1);
+        assert_eq!(monday.weeks_until(&next_sunday, WeekStart::Monday), 0);
+        assert!(sunday < monday);
+
+        // Weeks starting on Sunday put both days into the same week
+        assert_eq!(sunday.start_of_week(WeekStart::Sunday), sunday);
+        assert_eq!(monday.start_of_week(WeekStart::Sunday), sunday);
+        assert_eq!(sunday.weeks_until(&monday, WeekStart::Sunday), 0);
+        assert_eq!(monday.weeks_until(&next_sunday, WeekStart::Sunday), 1);
+    }
+
+    #[test]
+    fn next_week() {
+        // A Saturday
+        let today = Date::from_str("2025-06-07").unwrap();
+        let next = |locale, start| Date::from_relative("next week", &today, locale, start);
+        assert_eq!(next(Locale::En, WeekStart::Monday), Date::from_str("2025-06-09").ok());
+        assert_eq!(next(Locale::En, WeekStart::Sunday), Date::from_str("2025-06-08").ok());
+        assert_eq!(
+            Date::from_relative("nächste Woche", &today, Locale::De, WeekStart::Sunday),
+            Date::from_str("2025-06-08").ok()
+        );
+        assert_eq!(
+            Date::from_relative("last week", &today, Locale::En, WeekStart::Monday),
+            Date::from_str("2025-05-26").ok()
+        );
+        assert_eq!(WeekStart::from_str("Sunday"), Ok(WeekStart::Sunday));
+        assert!(WeekStart::from_str("friday").is_err());
+    }
+
+    #[test]
+    fn localized_formats() {
+        let saturday = Date::from_str("2024-06-01").unwrap();
+        let today = Date::from_str("2024-06-04").unwrap();
+        assert_eq!(saturday.format_short(Locale::En), "Sat, 01 Jun");
+        assert_eq!(saturday.format_short(Locale::De), "Sa, 01. Jun");
+        assert_eq!(saturday.relative_to(&today, Locale::En), "3 days ago");
+        assert_eq!(saturday.relative_to(&today, Locale::De), "vor 3 Tagen");
+        assert_eq!(
+            Date::from_relative("morgen", &today, Locale::De, WeekStart::Monday),
+            Some(Date::from_str("2024-06-05").unwrap())
+        );
+        assert_eq!(Date::from_relative("morgen", &today, Locale::En, WeekStart::Monday), None);
+    }
+
     #[test]
     fn roundtrip_bad() {
         let expected = [
@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+/// Language of weekday and month names and relative dates
+///
+/// Only the handful of strings used for displaying dates are translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+const WEEKDAYS_EN: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const WEEKDAYS_DE: [&str; 7] = ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"];
+const MONTHS_EN: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const MONTHS_DE: [&str; 12] = [
+    "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+];
+
+impl Locale {
+    /// Abbreviated weekday name, counting from Monday (0) to Sunday (6)
+    pub fn weekday(&self, days_from_monday: usize) -> &'static str {
+        match self {
+            Locale::En => WEEKDAYS_EN[days_from_monday % 7],
+            Locale::De => WEEKDAYS_DE[days_from_monday % 7],
+        }
+    }
+
+    /// Abbreviated month name, counting from January (1) to December (12)
+    pub fn month(&self, month: usize) -> &'static str {
+        let idx = (month + 11) % 12;
+        match self {
+            Locale::En => MONTHS_EN[idx],
+            Locale::De => MONTHS_DE[idx],
+        }
+    }
+
+    /// Describe a date `days` away from today, e.g. "tomorrow" or "3 days ago"
+    pub fn relative(&self, days: i64) -> String {
+        match (self, days) {
+            (Locale::En, 0) => "today".to_string(),
+            (Locale::En, 1) => "tomorrow".to_string(),
+            (Locale::En, -1) => "yesterday".to_string(),
+            (Locale::En, d) if d > 0 => format!("in {d} days"),
+            (Locale::En, d) => format!("{} days ago", -d),
+            (Locale::De, 0) => "heute".to_string(),
+            (Locale::De, 1) => "morgen".to_string(),
+            (Locale::De, -1) => "gestern".to_string(),
+            (Locale::De, d) if d > 0 => format!("in {d} Tagen"),
+            (Locale::De, d) => format!("vor {} Tagen", -d),
+        }
+    }
+
+    /// Day offset of a relative date word like "tomorrow"
+    ///
+    /// English words are always understood, the words of the locale itself
+    /// in addition.
+    pub fn parse_relative(&self, word: &str) -> Option<i64> {
+        let word = word.trim().to_lowercase();
+        let english = match word.as_str() {
+            "today" => Some(0),
+            "tomorrow" => Some(1),
+            "yesterday" => Some(-1),
+            _ => None,
+        };
+        let local = match (self, word.as_str()) {
+            (Locale::De, "heute") => Some(0),
+            (Locale::De, "morgen") => Some(1),
+            (Locale::De, "gestern") => Some(-1),
+            _ => None,
+        };
+        english.or(local)
+    }
+
+    /// Week offset of a relative week like "next week", see `parse_relative`
+    pub fn parse_relative_week(&self, word: &str) -> Option<i64> {
+        let word = word.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let english = match word.as_str() {
+            "this week" => Some(0),
+            "next week" => Some(1),
+            "last week" => Some(-1),
+            _ => None,
+        };
+        let local = match (self, word.as_str()) {
+            (Locale::De, "diese woche") => Some(0),
+            (Locale::De, "nächste woche") => Some(1),
+            (Locale::De, "letzte woche") => Some(-1),
+            _ => None,
+        };
+        english.or(local)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Accepts language codes like "de" as well as "de_DE.UTF-8"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lang = s.split(['_', '-', '.']).next().unwrap_or_default();
+        match lang.to_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            _ => Err(format!("Unsupported locale '{s}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let cases = [("en", Locale::En), ("de", Locale::De), ("de_DE.UTF-8", Locale::De)];
+        for (code, expected) in cases {
+            assert_eq!(Locale::from_str(code).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn roundtrip_bad() {
+        for code in ["", "fr", "english"] {
+            assert!(Locale::from_str(code).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Locale::En.weekday(5), "Sat");
+        assert_eq!(Locale::De.weekday(5), "Sa");
+        assert_eq!(Locale::En.month(3), "Mar");
+        assert_eq!(Locale::De.month(3), "Mär");
+        assert_eq!(Locale::De.month(12), "Dez");
+    }
+
+    #[test]
+    fn relative() {
+        let cases = [
+            (0, "today", "heute"),
+            (1, "tomorrow", "morgen"),
+            (-1, "yesterday", "gestern"),
+            (4, "in 4 days", "in 4 Tagen"),
+            (-3, "3 days ago", "vor 3 Tagen"),
+        ];
+        for (days, en, de) in cases {
+            assert_eq!(Locale::En.relative(days), en);
+            assert_eq!(Locale::De.relative(days), de);
+        }
+    }
+
+    #[test]
+    fn parse_relative() {
+        assert_eq!(Locale::En.parse_relative("Tomorrow"), Some(1));
+        assert_eq!(Locale::En.parse_relative("morgen"), None);
+        assert_eq!(Locale::De.parse_relative("morgen"), Some(1));
+        assert_eq!(Locale::De.parse_relative("gestern"), Some(-1));
+        assert_eq!(Locale::De.parse_relative("tomorrow"), Some(1));
+        assert_eq!(Locale::De.parse_relative("übermorgen"), None);
+    }
+}
@@ -1,5 +1,7 @@
-mod dates;
+pub(crate) mod dates;
+pub(crate) mod locale;
 pub mod note;
-mod priority;
+pub(crate) mod priority;
 pub mod tags;
 pub mod task;
+mod text_serde;
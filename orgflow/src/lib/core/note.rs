@@ -15,6 +15,7 @@ pub struct Note {
     guid: Guid,
     tags: TagCollection,
     content: Vec<String>,
+    source_line: Option<usize>,
 }
 
 impl Note {
@@ -49,6 +50,44 @@ impl Note {
     pub fn level(&self) -> usize {
         self.lvl
     }
+
+    /// 1-based line of the note's title in the file it was parsed from
+    ///
+    /// This reflects the last parse of the document and goes stale once the
+    /// document is written with changes; reload the document to refresh it.
+    /// Notes created programmatically return `None`.
+    pub fn source_line(&self) -> Option<usize> {
+        self.source_line
+    }
+
+    pub(crate) fn set_source_line(&mut self, line: usize) {
+        self.source_line = Some(line);
+    }
+
+    pub(crate) fn set_guid(&mut self, guid: Guid) {
+        self.guid = guid;
+    }
+
+    /// Replace a content line, marking the note as modified today
+    pub(crate) fn set_content_line(&mut self, index: usize, line: String) {
+        self.content[index] = line;
+        self.modification_date = Date::now();
+    }
+
+    /// Replace the tags, marking the note as modified today
+    pub(crate) fn set_tags(&mut self, tags: TagCollection) {
+        self.tags = tags;
+        self.modification_date = Date::now();
+    }
+
+    /// Take title, content and tags of `edited`, keeping the identity of this
+    /// note (guid, creation date, level) and marking it as modified today
+    pub(crate) fn revise(&mut self, edited: Note) {
+        self.title = edited.title;
+        self.content = edited.content;
+        self.tags = edited.tags;
+        self.modification_date = Date::now();
+    }
     pub fn with(title: String, content: Vec<String>) -> Self {
         Self {
             title,
@@ -84,24 +123,25 @@ impl Default for Note {
             tags: TagCollection::new(),
             guid: Guid::new(),
             content: Vec::new(),
+            source_line: None,
         }
     }
 }
 
-impl Into<Vec<String>> for &Note {
-    fn into(self) -> Vec<String> {
-        let lvl = '#'.to_string().repeat(self.lvl);
-        let title = format!("{} {}", lvl, self.title.trim());
+impl From<&Note> for Vec<String> {
+    fn from(val: &Note) -> Self {
+        let lvl = '#'.to_string().repeat(val.lvl);
+        let title = format!("{} {}", lvl, val.title.trim());
         let metadata = format!(
             "> cre:{} mod:{} guid:{} {}",
-            self.creation_date.to_string(),
-            self.modification_date.to_string(),
-            self.guid.to_string(),
-            self.tags.to_string()
+            val.creation_date,
+            val.modification_date,
+            val.guid,
+            val.tags
         )
         .trim()
         .to_string();
-        let mut content = self.content.clone();
+        let mut content = val.content.clone();
         let mut result = vec![title, metadata];
         result.append(&mut content);
         result
@@ -109,7 +149,9 @@ impl Into<Vec<String>> for &Note {
 }
 
 impl Note {
-    fn from_vec(value: Vec<String>) -> Result<Self, String> {
+    /// Parse a note block: a title line, a metadata line and any number of
+    /// content lines (including none, for title-only notes)
+    pub(crate) fn from_vec(value: Vec<String>) -> Result<Self, String> {
         if value.len() < 2 {
             return Err(format!(
                 "There should be at least a title and metadata [{:?}]",
@@ -132,13 +174,11 @@ impl Note {
 
         let (metadata, remainder) = body.split_first().unwrap();
 
-        // Second element is the metadata
-        assert!(
-            metadata.starts_with("> "),
-            "Wrong metadata start for [{:?}]",
-            value
-        );
-        let metadata = metadata.to_string().replace("> ", "");
+        // Second element is the metadata, decided by position only so that
+        // content lines starting with "> " (blockquotes) are left alone
+        let metadata = metadata
+            .strip_prefix("> ")
+            .ok_or(format!("Wrong metadata start for [{:?}]", value))?;
 
         // First metadata is creation date
         let (creation_date_str, metadata) =
@@ -153,13 +193,13 @@ impl Note {
         let modification_date = Date::from_str(&modification_date_str.replace("mod:", ""))?;
 
         // Third metadata is note id
-        let (guid_str, tag_str) = metadata.split_once(" ").unwrap_or((&metadata.trim(), ""));
+        let (guid_str, tag_str) = metadata.split_once(" ").unwrap_or((metadata.trim(), ""));
         let guid = Guid::from_str(&guid_str.replace("guid:", ""))?;
 
         let tags = if tag_str.is_empty() {
             TagCollection::new()
         } else {
-            TagCollection::from_str(&tag_str.trim())?
+            TagCollection::from_str(tag_str.trim())?
         };
 
         // The remainder is the content
@@ -173,6 +213,7 @@ impl Note {
             guid,
             tags,
             content,
+            source_line: None,
         };
         Ok(result)
     }
@@ -215,6 +256,19 @@ mod tests {
                 "> cre:2022-03-03 mod:2021-03-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
                 "- This is the content",
             ],
+            // Content starting with a blockquote
+            vec![
+                "### Quote",
+                "> cre:2022-03-03 mod:2021-03-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
+                "> quoted text",
+                "back to normal",
+            ],
+            // Content line that looks exactly like a metadata line
+            vec![
+                "### Metadata lookalike",
+                "> cre:2022-03-03 mod:2021-03-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 @aid",
+                "> cre:2023-04-04 mod:2023-04-05 guid:b1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 +other",
+            ],
             // Note without content (just title and metadata)
             vec![
                 "## Note Without Content",
@@ -232,8 +286,10 @@ mod tests {
     #[test]
     fn roundtrip_bad() {
         let cases = vec![
-            // No content
+            // No guid (content itself is optional)
             vec!["# Title", "> cre:2022-03-03 mod:2021-03-01 @aid +project"],
+            // Only a title
+            vec!["# Title"],
             // no creation date
             vec![
                 "# Title",
@@ -261,6 +317,14 @@ mod tests {
                 "- This is the content",
                 "- A lot of data",
             ],
+            // blockquote where the metadata should be
+            vec!["# Title", "> quoted text", "- This is the content"],
+            // metadata not in second position
+            vec![
+                "# Title",
+                "- This is the content",
+                "> cre:2022-03-03 mod:2021-03-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
+            ],
             // wrong guid format
             vec![
                 "# Title",
@@ -1,12 +1,31 @@
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+/// Priority of a task, `A` is the highest and orders first
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
     A,
     B,
     C,
 }
 
+impl Priority {
+    /// The next higher priority, `A` stays `A`
+    pub fn higher(&self) -> Priority {
+        match self {
+            Priority::A | Priority::B => Priority::A,
+            Priority::C => Priority::B,
+        }
+    }
+
+    /// The next lower priority, `C` stays `C`
+    pub fn lower(&self) -> Priority {
+        match self {
+            Priority::A => Priority::B,
+            Priority::B | Priority::C => Priority::C,
+        }
+    }
+}
+
 impl Display for Priority {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let output = match self {
@@ -34,6 +53,16 @@ impl FromStr for Priority {
 mod tests {
     use super::*;
 
+    #[test]
+    fn higher_and_lower_saturate() {
+        assert_eq!(Priority::C.higher(), Priority::B);
+        assert_eq!(Priority::B.higher(), Priority::A);
+        assert_eq!(Priority::A.higher(), Priority::A);
+        assert_eq!(Priority::A.lower(), Priority::B);
+        assert_eq!(Priority::B.lower(), Priority::C);
+        assert_eq!(Priority::C.lower(), Priority::C);
+    }
+
     #[test]
     fn roundtrip() {
         let expected = ["(A)".to_string(), "(C)".to_string(), "(B)".to_string()];
@@ -1,12 +1,20 @@
 use std::{fmt::Display, str::FromStr, time::Duration};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct TaskEstimate(Duration);
 
 impl TaskEstimate {
-    fn new(minutes: u64) -> Self {
-        let d = Duration::from_secs(minutes * 60);
-        TaskEstimate(d)
+    /// Estimate of `minutes`, refused if they do not fit a `Duration` in
+    /// seconds
+    pub(crate) fn new(minutes: u64) -> Result<Self, String> {
+        let seconds = minutes
+            .checked_mul(60)
+            .ok_or(format!("{minutes} minutes are too long for an estimate"))?;
+        Ok(TaskEstimate(Duration::from_secs(seconds)))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.0
     }
 }
 
@@ -23,7 +31,7 @@ impl FromStr for TaskEstimate {
             Err("String has to end with `min`".to_string())
         } else {
             match s.replace("min", "").parse() {
-                Ok(min) => Ok(TaskEstimate::new(min)),
+                Ok(min) => TaskEstimate::new(min),
                 Err(msg) => Err(format!("Could not convert number to u64: {msg}")),
             }
         }
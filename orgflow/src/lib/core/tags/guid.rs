@@ -1,18 +1,41 @@
 use std::{fmt::Display, str::FromStr};
 
 use uuid::Uuid;
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Guid(Uuid);
 
+#[cfg(feature = "testing")]
+thread_local! {
+    /// Seed of the next guid made on this thread, random guids without one
+    pub(crate) static NEXT_SEED: std::cell::Cell<Option<u128>> =
+        const { std::cell::Cell::new(None) };
+}
+
 impl Guid {
     pub fn new() -> Self {
+        #[cfg(feature = "testing")]
+        if let Some(seed) = NEXT_SEED.get() {
+            NEXT_SEED.set(Some(seed + 1));
+            return Self::from_seed(seed);
+        }
         Guid(Uuid::new_v4())
     }
+
+    /// Guid always the same for the same `seed`, for predictable test output
+    pub fn from_seed(seed: u128) -> Self {
+        Guid(Uuid::from_u128(seed))
+    }
+}
+
+impl Default for Guid {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Display for Guid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0.to_string())
+        write!(f, "{}", self.0)
     }
 }
 
@@ -41,6 +64,11 @@ mod tests {
         }
     }
     #[test]
+    fn seeded() {
+        assert_eq!(Guid::from_seed(1).to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(Guid::from_seed(1), Guid::from_seed(1));
+    }
+    #[test]
     fn roundtrip_bad() {
         let expected = [
             "2029-14-09".to_string(),
@@ -3,15 +3,15 @@ pub mod guid;
 mod recurrence;
 mod state;
 
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, time::Duration};
 
 use super::dates::Date;
-use estimate::TaskEstimate;
+pub(crate) use estimate::TaskEstimate;
 use guid::Guid;
 use recurrence::TaskRecurrence;
-use state::TaskState;
+pub use state::TaskState;
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Tag {
     /// Prefix `s:`
     Status(TaskState),
@@ -23,8 +23,14 @@ pub enum Tag {
     LooseRecurrence(TaskRecurrence),
     /// Prefix `t:`
     Threshold(Date),
+    /// Prefix `due:`
+    Due(Date),
     /// Prefix `n:`
     Note(Guid),
+    /// Prefix `id:`, the identity of a task, see `Task::ensure_id`
+    Id(Guid),
+    /// Prefix `blocked:`, the id of a task to be done first
+    BlockedBy(Guid),
     /// Prefix `p:`
     Person(String),
     /// Prefix `!`
@@ -37,6 +43,70 @@ pub enum Tag {
     Custom(String, String),
 }
 
+/// Keys of `key:value` tags that are not custom tags
+pub const RESERVED_KEYS: [&str; 9] =
+    ["s", "est", "rec", "t", "due", "n", "id", "blocked", "p"];
+
+/// Longest key of a custom `key:value` tag, in characters
+pub const CUSTOM_KEY_MAX_LEN: usize = 32;
+
+impl Tag {
+    /// Whether `key` can start a custom `key:value` tag
+    ///
+    /// Keys start with a lowercase ASCII letter, followed by lowercase
+    /// letters, digits, `-` or `_`, up to `CUSTOM_KEY_MAX_LEN` in total.
+    /// Words like `Meeting:` or `10:30` are text.
+    pub fn is_custom_key(key: &str) -> bool {
+        Self::check_custom_key(key).is_ok()
+    }
+
+    /// Whether both tags say the same sort of thing about a task, like two
+    /// contexts, two recurrences or two custom tags with the same key
+    pub fn same_kind(&self, other: &Tag) -> bool {
+        match (self, other) {
+            (Tag::Custom(key, _), Tag::Custom(other_key, _)) => key == other_key,
+            (
+                Tag::StrictRecurrence(_) | Tag::LooseRecurrence(_),
+                Tag::StrictRecurrence(_) | Tag::LooseRecurrence(_),
+            ) => true,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    /// Whether a task has at most one tag of this kind, see `same_kind`
+    pub(crate) fn is_single(&self) -> bool {
+        matches!(
+            self,
+            Tag::Status(_)
+                | Tag::Estimate(_)
+                | Tag::Threshold(_)
+                | Tag::Due(_)
+                | Tag::Id(_)
+                | Tag::StrictRecurrence(_)
+                | Tag::LooseRecurrence(_)
+                | Tag::Custom(..)
+        )
+    }
+
+    /// Why `key` cannot start a custom `key:value` tag, see `is_custom_key`
+    pub fn check_custom_key(key: &str) -> Result<(), String> {
+        let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+        let Some(first) = key.chars().next() else {
+            return Err("the key is empty".to_string());
+        };
+        if !first.is_ascii_lowercase() {
+            return Err(format!("key '{key}' has to start with a letter a-z"));
+        }
+        if let Some(c) = key.chars().find(|&c| !valid(c)) {
+            return Err(format!("key '{key}' has '{c}', keys only use a-z, 0-9, - and _"));
+        }
+        if key.chars().count() > CUSTOM_KEY_MAX_LEN {
+            return Err(format!("key '{key}' is longer than {CUSTOM_KEY_MAX_LEN} characters"));
+        }
+        Ok(())
+    }
+}
+
 impl Display for Tag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let output = match self {
@@ -45,12 +115,15 @@ impl Display for Tag {
             Tag::StrictRecurrence(rec) => format!("rec:+{rec}"),
             Tag::LooseRecurrence(rec) => format!("rec:{rec}"),
             Tag::Threshold(date) => format!("t:{date}"),
+            Tag::Due(date) => format!("due:{date}"),
             Tag::Note(note) => format!("n:{note}"),
-            Tag::Person(p) => format!("p:{p}"),
+            Tag::Id(id) => format!("id:{id}"),
+            Tag::BlockedBy(id) => format!("blocked:{id}"),
+            Tag::Person(p) => format!("p:{}", quoted(p)),
             Tag::OneOff(source) => format!("!{source}"),
             Tag::Context(ctx) => format!("@{ctx}"),
             Tag::Project(project) => format!("+{project}"),
-            Tag::Custom(key, value) => format!("{key}:{value}"),
+            Tag::Custom(key, value) => format!("{key}:{}", quoted(value)),
         };
         write!(f, "{}", output)
     }
@@ -76,32 +149,124 @@ impl FromStr for Tag {
             Ok(Tag::LooseRecurrence(r))
         } else if s.starts_with("t:") {
             let s = s.replace("t:", "");
-            Ok(Tag::Threshold(Date::from_str(&s)?))
+            Ok(Tag::Threshold(Date::parse_relative(&s, &Date::now())?))
+        } else if let Some(date) = s.strip_prefix("due:") {
+            Ok(Tag::Due(Date::parse_relative(date, &Date::now())?))
         } else if s.starts_with("n:") {
             let s = s.replace("n:", "");
             let n = Guid::from_str(&s)?;
             Ok(Tag::Note(n))
-        } else if s.starts_with("p:") {
-            Ok(Tag::Person(s.replace("p:", "").to_string()))
-        } else if s.starts_with("!") {
-            Ok(Tag::OneOff(s.replace("!", "")))
-        } else if s.starts_with("@") {
-            Ok(Tag::Context(s.replace("@", "")))
-        } else if s.starts_with("+") {
-            Ok(Tag::Project(s.replace("+", "")))
-        } else if s.contains(":") {
-            let (key, val) = s.split_once(":").unwrap();
-            Ok(Tag::Custom(
-                key.to_string().to_lowercase(),
-                val.to_string().to_lowercase(),
-            ))
+        } else if let Some(id) = s.strip_prefix("id:") {
+            Ok(Tag::Id(Guid::from_str(id)?))
+        } else if let Some(id) = s.strip_prefix("blocked:") {
+            Ok(Tag::BlockedBy(Guid::from_str(id)?))
+        } else if let Some(person) = s.strip_prefix("p:") {
+            Ok(Tag::Person(tag_value(person)?))
+        } else if let Some(name) = s.strip_prefix('!') {
+            Ok(Tag::OneOff(tag_name(s, name)?))
+        } else if let Some(name) = s.strip_prefix('@') {
+            Ok(Tag::Context(tag_name(s, name)?))
+        } else if let Some(name) = s.strip_prefix('+') {
+            Ok(Tag::Project(tag_name(s, name)?))
+        } else if let Some((key, val)) = s.split_once(":") {
+            if let Err(e) = Tag::check_custom_key(key) {
+                return Err(format!("'{s}' is no custom tag, {e}"));
+            }
+            if val.is_empty() {
+                return Err(format!("'{s}' is no custom tag, the value is missing"));
+            }
+            Ok(Tag::Custom(key.to_string(), tag_value(val)?.to_lowercase()))
         } else {
             Err("No tag found".to_string())
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// Name of a `!`, `@` or `+` tag, a lone sigil like the `+` in `A + B` is
+/// text
+fn tag_name(tag: &str, name: &str) -> Result<String, String> {
+    match name.is_empty() {
+        true => Err(format!("'{tag}' has no name")),
+        false => Ok(name.to_string()),
+    }
+}
+
+/// Value of a `p:` or custom tag as written, in quotes if it has spaces
+fn quoted(value: &str) -> String {
+    match value.contains(char::is_whitespace) {
+        true => format!("\"{value}\""),
+        false => value.to_string(),
+    }
+}
+
+/// Value of a `p:` or custom tag, `"John Smith"` without its quotes
+///
+/// Values with spaces need the quotes, quotes within them are refused.
+fn tag_value(value: &str) -> Result<String, String> {
+    let Some(opened) = value.strip_prefix('"') else {
+        if value.is_empty() {
+            return Err("The value is missing".to_string());
+        }
+        if value.contains(char::is_whitespace) {
+            return Err(format!("'{value}' has spaces, put it in quotes"));
+        }
+        return Ok(value.to_string());
+    };
+    let inner = opened
+        .strip_suffix('"')
+        .ok_or(format!("The quote of '{value}' is not closed"))?;
+    if inner.contains('"') {
+        return Err(format!("'{value}' has quotes within its quotes"));
+    }
+    if inner.trim().is_empty() {
+        return Err(format!("'{value}' is an empty value"));
+    }
+    Ok(inner.to_string())
+}
+
+/// Words of `text` split at whitespace, where a tag value in quotes like
+/// `p:"John Smith"` stays one word with its spaces
+///
+/// A quote that is not closed starts no value, its word ends at the next
+/// whitespace like any other.
+pub(crate) fn tag_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let value_end = rest[..word_end]
+            .find(':')
+            .filter(|&colon| rest[colon + 1..].starts_with('"'))
+            .and_then(|colon| rest[colon + 2..].find('"').map(|quote| colon + 3 + quote));
+        let end = match value_end {
+            Some(end) => end + rest[end..].find(char::is_whitespace).unwrap_or(rest.len() - end),
+            None => word_end,
+        };
+        words.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    words
+}
+
+/// Tags of a task or note, in the order they were written
+///
+/// ```
+/// use orgflow::{Tag, Task};
+/// use std::str::FromStr;
+///
+/// let task = Task::from_str("Call the bank @work +taxes @phone").unwrap();
+/// let mut tags = task.tags().iter().flatten();
+/// assert!(tags.any(|t| matches!(t, Tag::Context(c) if c == "work")));
+/// let contexts: Vec<String> = task
+///     .tags()
+///     .iter()
+///     .flatten()
+///     .filter(|t| matches!(t, Tag::Context(_)))
+///     .map(Tag::to_string)
+///     .collect();
+/// assert_eq!(contexts, ["@work", "@phone"]);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
 pub struct TagCollection(Vec<Tag>);
 
 impl TagCollection {
@@ -111,53 +276,206 @@ impl TagCollection {
     
     /// Create a TagCollection from a Vec<Tag>
     pub fn from_tags(tags: Vec<Tag>) -> Self {
-        TagCollection(tags)
+        tags.into_iter().collect()
     }
 
-    /// Extract all unique context tags (@context) as strings
-    pub fn context_tags(&self) -> Vec<String> {
+    /// Add `tag` at the end unless the collection has it already
+    ///
+    /// A second status, estimate or recurrence takes the place of the
+    /// first one instead. See `upsert` for the other single tags.
+    pub fn push(&mut self, tag: Tag) {
+        let per_kind = matches!(
+            tag,
+            Tag::Status(_) | Tag::Estimate(_) | Tag::StrictRecurrence(_) | Tag::LooseRecurrence(_)
+        );
+        let existing = match per_kind {
+            true => self.0.iter().position(|existing| existing.same_kind(&tag)),
+            false => self.0.iter().position(|existing| *existing == tag),
+        };
+        match existing {
+            Some(at) => self.0[at] = tag,
+            None => self.0.push(tag),
+        }
+    }
+
+    /// Drop repeated tags, as `push` would have, keeping the order
+    pub fn dedup(&mut self) {
+        *self = std::mem::take(&mut self.0).into_iter().collect();
+    }
+
+    /// Remove every copy of `tag`, returns whether there was one
+    pub fn remove(&mut self, tag: &Tag) -> bool {
+        let before = self.0.len();
+        self.0.retain(|existing| existing != tag);
+        self.0.len() != before
+    }
+
+    /// Whether the collection has `tag`
+    pub fn contains(&self, tag: &Tag) -> bool {
+        self.0.contains(tag)
+    }
+
+    /// Add the tags of `other` that are not part of the collection yet
+    pub(crate) fn merge(&mut self, other: TagCollection) {
+        for tag in other.0 {
+            if !self.contains(&tag) {
+                self.push(tag);
+            }
+        }
+    }
+
+    /// Set the custom tag `key:value`, replacing any value the key had
+    pub(crate) fn set_custom(&mut self, key: &str, value: &str) {
+        self.upsert(Tag::Custom(key.to_string(), value.to_string()));
+    }
+
+    /// Add `tag` or replace the tag it takes the place of
+    ///
+    /// A task has one status, estimate, recurrence, threshold and due date
+    /// and one value per custom key, so those are replaced where they are.
+    /// Other tags are only added if the collection does not have them yet.
+    pub fn upsert(&mut self, tag: Tag) {
+        let single = tag.is_single();
+        let replaces = |existing: &Tag| match single {
+            true => existing.same_kind(&tag),
+            false => *existing == tag,
+        };
+        let replaced: Vec<usize> = (0..self.0.len()).filter(|&i| replaces(&self.0[i])).collect();
+        match replaced.split_first() {
+            Some((&at, duplicates)) => {
+                for &i in duplicates.iter().rev() {
+                    self.0.remove(i);
+                }
+                self.0[at] = tag;
+            }
+            None => self.0.push(tag),
+        }
+    }
+
+    /// Keep only the tags `keep` returns true for
+    pub(crate) fn retain(&mut self, keep: impl FnMut(&Tag) -> bool) {
+        self.0.retain(keep);
+    }
+
+    /// The tags in the order they were written
+    pub fn iter(&self) -> impl Iterator<Item = &Tag> {
+        self.0.iter()
+    }
+
+    /// Number of tags in the collection
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the collection has no tags at all
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// State from the `s:` tag, if there is one
+    pub fn status(&self) -> Option<&TaskState> {
+        self.0.iter().find_map(|tag| match tag {
+            Tag::Status(state) => Some(state),
+            _ => None,
+        })
+    }
+
+    /// Time the task is estimated to take, from the `est:` tag
+    pub fn estimate(&self) -> Option<Duration> {
+        self.0.iter().find_map(|tag| match tag {
+            Tag::Estimate(estimate) => Some(estimate.duration()),
+            _ => None,
+        })
+    }
+
+    /// Identity from the `id:` tag, if there is one
+    pub fn id(&self) -> Option<&Guid> {
+        self.0.iter().find_map(|tag| match tag {
+            Tag::Id(id) => Some(id),
+            _ => None,
+        })
+    }
+
+    /// Value of the custom tag `key`, if there is one
+    pub fn custom_value(&self, key: &str) -> Option<&str> {
+        self.0.iter().find_map(|tag| match tag {
+            Tag::Custom(k, value) if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Contexts without their `@`, in the order of the tags
+    pub fn contexts(&self) -> Vec<&str> {
         self.0
             .iter()
             .filter_map(|tag| match tag {
-                Tag::Context(ctx) => Some(format!("@{}", ctx)),
+                Tag::Context(ctx) => Some(ctx.as_str()),
                 _ => None,
             })
             .collect()
     }
 
-    /// Extract all unique project tags (+project) as strings
-    pub fn project_tags(&self) -> Vec<String> {
+    /// Projects without their `+`, in the order of the tags
+    pub fn projects(&self) -> Vec<&str> {
         self.0
             .iter()
             .filter_map(|tag| match tag {
-                Tag::Project(proj) => Some(format!("+{}", proj)),
+                Tag::Project(proj) => Some(proj.as_str()),
                 _ => None,
             })
             .collect()
     }
 
-    /// Extract all unique person tags (p:person) as strings
-    pub fn person_tags(&self) -> Vec<String> {
+    /// People without their `p:`, in the order of the tags
+    pub fn persons(&self) -> Vec<&str> {
         self.0
             .iter()
             .filter_map(|tag| match tag {
-                Tag::Person(person) => Some(format!("p:{}", person)),
+                Tag::Person(person) => Some(person.as_str()),
                 _ => None,
             })
             .collect()
     }
 
-    /// Extract all unique custom tags (key:value) as strings
-    pub fn custom_tags(&self) -> Vec<String> {
+    /// Keys and values of the custom tags, in the order of the tags
+    pub fn customs(&self) -> Vec<(&str, &str)> {
         self.0
             .iter()
             .filter_map(|tag| match tag {
-                Tag::Custom(key, value) => Some(format!("{}:{}", key, value)),
+                Tag::Custom(key, value) => Some((key.as_str(), value.as_str())),
                 _ => None,
             })
             .collect()
     }
 
+    /// Day from the `t:` tag, if there is one
+    pub fn threshold(&self) -> Option<&Date> {
+        self.0.iter().find_map(|tag| match tag {
+            Tag::Threshold(date) => Some(date),
+            _ => None,
+        })
+    }
+
+    /// Extract all unique context tags (@context) as strings
+    pub fn context_tags(&self) -> Vec<String> {
+        self.contexts().into_iter().map(|ctx| format!("@{}", ctx)).collect()
+    }
+
+    /// Extract all unique project tags (+project) as strings
+    pub fn project_tags(&self) -> Vec<String> {
+        self.projects().into_iter().map(|proj| format!("+{}", proj)).collect()
+    }
+
+    /// Extract all unique person tags (p:person) as strings
+    pub fn person_tags(&self) -> Vec<String> {
+        self.persons().into_iter().map(|person| format!("p:{}", person)).collect()
+    }
+
+    /// Extract all unique custom tags (key:value) as strings
+    pub fn custom_tags(&self) -> Vec<String> {
+        self.customs().into_iter().map(|(key, value)| format!("{}:{}", key, value)).collect()
+    }
+
     /// Extract all unique one-off tags (!tag) as strings
     pub fn oneoff_tags(&self) -> Vec<String> {
         self.0
@@ -181,6 +499,34 @@ impl Default for TagCollection {
     }
 }
 
+impl FromIterator<Tag> for TagCollection {
+    fn from_iter<I: IntoIterator<Item = Tag>>(tags: I) -> Self {
+        let mut collection = TagCollection::new();
+        for tag in tags {
+            collection.push(tag);
+        }
+        collection
+    }
+}
+
+impl IntoIterator for TagCollection {
+    type Item = Tag;
+    type IntoIter = std::vec::IntoIter<Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TagCollection {
+    type Item = &'a Tag;
+    type IntoIter = std::slice::Iter<'a, Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
 impl Display for TagCollection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let result = self
@@ -196,15 +542,15 @@ impl Display for TagCollection {
 impl FromStr for TagCollection {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.replace(" ", "").len() == 0 {
+        if s.replace(" ", "").is_empty() {
             Err("Empty String error".to_string())
         } else {
-            let mut result = Vec::new();
-            for x in s.split_whitespace() {
+            let mut result = TagCollection::new();
+            for x in tag_words(s) {
                 let t = Tag::from_str(x)?;
                 result.push(t)
             }
-            Ok(TagCollection(result))
+            Ok(result)
         }
     }
 }
@@ -230,6 +576,155 @@ mod tests {
             assert_eq!(val, roundtrip);
         }
     }
+
+    #[test]
+    fn custom_keys_follow_the_grammar() {
+        let longest = "k".repeat(CUSTOM_KEY_MAX_LEN);
+        assert!(Tag::from_str(&format!("{longest}:v")).is_ok());
+        assert!(Tag::from_str("a:v").is_ok());
+        assert!(Tag::from_str("due-date_2:v").is_ok());
+        assert!(Tag::from_str("due:2024-03-01").is_ok());
+        let cases = [
+            (format!("{longest}k:v"), "is longer than 32 characters"),
+            (":v".to_string(), "the key is empty"),
+            ("straße:v".to_string(), "has 'ß'"),
+            ("🙂:v".to_string(), "has to start with a letter"),
+            ("Meeting:v".to_string(), "has to start with a letter"),
+            ("room:".to_string(), "the value is missing"),
+        ];
+        for (tag, message) in cases {
+            let error = Tag::from_str(&tag).unwrap_err();
+            assert!(error.contains(message), "{tag}: {error}");
+        }
+    }
+
+    #[test]
+    fn quoted_values_keep_their_spaces() {
+        let tags = TagCollection::from_str("p:\"John Smith\" +review topic:\"Quarterly  Review\"")
+            .unwrap();
+        let expected = TagCollection(vec![
+            Tag::Person("John Smith".to_string()),
+            Tag::Project("review".to_string()),
+            Tag::Custom("topic".to_string(), "quarterly  review".to_string()),
+        ]);
+        assert_eq!(tags, expected);
+        assert_eq!(tags.to_string(), "p:\"John Smith\" +review topic:\"quarterly  review\"");
+        // Quotes around a single word are dropped
+        assert_eq!(Tag::from_str("p:\"anna\"").unwrap().to_string(), "p:anna");
+    }
+
+    #[test]
+    fn broken_quotes_are_refused() {
+        let error = |s: &str| Tag::from_str(s).unwrap_err();
+        let nested = "'\"John \"JJ\" Smith\"' has quotes within its quotes";
+        assert_eq!(error("p:\"John \"JJ\" Smith\""), nested);
+        assert_eq!(error("p:\"John Smith"), "The quote of '\"John Smith' is not closed");
+        assert_eq!(error("p:John Smith"), "'John Smith' has spaces, put it in quotes");
+        assert_eq!(error("topic:\" \""), "'\" \"' is an empty value");
+        assert!(TagCollection::from_str("p:\"John \"JJ\" Smith\"").is_err());
+        assert!(TagCollection::from_str("+review p:\"John").is_err());
+    }
+
+    #[test]
+    fn upsert_replaces_tags_of_the_same_kind() {
+        let mut tags =
+            TagCollection::from_str("s:next +garden room:hall room:attic due:2024-03-01").unwrap();
+        tags.upsert(Tag::from_str("s:wait(bob)").unwrap());
+        tags.upsert(Tag::from_str("room:shed").unwrap());
+        tags.upsert(Tag::from_str("due:2024-05-01").unwrap());
+        tags.upsert(Tag::from_str("+garden").unwrap());
+        tags.upsert(Tag::from_str("@phone").unwrap());
+        assert_eq!(tags.to_string(), "s:wait(bob) +garden room:shed due:2024-05-01 @phone");
+        assert_eq!(tags.status(), Some(&TaskState::Wait("bob".into())));
+        assert_eq!(tags.custom_value("room"), Some("shed"));
+        assert_eq!(tags.custom_value("due"), None);
+        assert_eq!(tags.custom_value("wait_since"), None);
+    }
+
+    #[test]
+    fn push_and_remove() {
+        let mut tags = TagCollection::new();
+        tags.push(Tag::Project("garden".to_string()));
+        tags.push(Tag::Context("home".to_string()));
+        assert_eq!(tags.len(), 2);
+        assert!(tags.contains(&Tag::Context("home".to_string())));
+        assert!(tags.remove(&Tag::Context("home".to_string())));
+        assert!(!tags.remove(&Tag::Context("home".to_string())));
+        assert!(!tags.contains(&Tag::Context("home".to_string())));
+        assert_eq!(tags.to_string(), "+garden");
+
+        assert!(tags.remove(&Tag::Project("garden".to_string())));
+        assert!(tags.is_empty());
+        assert_eq!(tags, TagCollection::default());
+        assert_eq!(tags.to_string(), "");
+        // No tags have no text to parse, which is an error and not a panic
+        assert!(TagCollection::from_str(&tags.to_string()).is_err());
+    }
+
+    #[test]
+    fn collected_tags_keep_their_order() {
+        let words = ["@phone", "+taxes", "due:2025-04-01", "@bank", "+garden"];
+        let tags: TagCollection = words.iter().map(|w| Tag::from_str(w).unwrap()).collect();
+        assert_eq!(tags.to_string(), "@phone +taxes due:2025-04-01 @bank +garden");
+        let owned: Vec<String> = tags.clone().into_iter().map(|tag| tag.to_string()).collect();
+        assert_eq!(owned, words);
+        assert_eq!((&tags).into_iter().count(), words.len());
+        assert!(std::iter::empty::<Tag>().collect::<TagCollection>().is_empty());
+    }
+
+    #[test]
+    fn repeated_tags_are_kept_once() {
+        let tags = TagCollection::from_str("@work @work +proj +proj @home").unwrap();
+        assert_eq!(tags.to_string(), "@work +proj @home");
+        assert_eq!(tags.len(), 3);
+        let tags = TagCollection::from_tags(vec![
+            Tag::Project("proj".to_string()),
+            Tag::Context("work".to_string()),
+            Tag::Project("proj".to_string()),
+        ]);
+        assert_eq!(tags.to_string(), "+proj @work");
+
+        let mut tags = TagCollection(vec![
+            Tag::Context("work".to_string()),
+            Tag::Project("proj".to_string()),
+            Tag::Context("work".to_string()),
+        ]);
+        tags.dedup();
+        assert_eq!(tags.to_string(), "@work +proj");
+    }
+
+    #[test]
+    fn status_estimate_and_recurrence_are_one_per_kind() {
+        let parse = |text: &str| TagCollection::from_str(text).unwrap().to_string();
+        assert_eq!(parse("s:next +garden s:done @home"), "s:done +garden @home");
+        assert_eq!(parse("est:30min @home est:60min"), "est:60min @home");
+        assert_eq!(parse("rec:1w +garden rec:+2d"), "rec:+2d +garden");
+        // Other kinds can repeat as long as the tags differ
+        let text = "due:2025-03-01 p:ann due:2025-04-01 p:bob";
+        assert_eq!(parse(text), text);
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let tags = TagCollection::from_str(
+            "@office +q3-report p:ann s:next t:2025-03-03 due:2025-03-10 @phone topic:budget \
+             p:bob room:\"Room 4\"",
+        )
+        .unwrap();
+        assert_eq!(tags.contexts(), ["office", "phone"]);
+        assert_eq!(tags.projects(), ["q3-report"]);
+        assert_eq!(tags.persons(), ["ann", "bob"]);
+        assert_eq!(tags.customs(), [("topic", "budget"), ("room", "room 4")]);
+        assert_eq!(tags.status(), Some(&TaskState::Next));
+        assert_eq!(tags.threshold(), Some(&Date::from_str("2025-03-03").unwrap()));
+        // The string ones add the prefix back
+        assert_eq!(tags.context_tags(), ["@office", "@phone"]);
+        assert_eq!(tags.person_tags(), ["p:ann", "p:bob"]);
+        let none = TagCollection::from_str("+garden").unwrap();
+        assert!(none.contexts().is_empty() && none.customs().is_empty());
+        assert_eq!((none.status(), none.threshold()), (None, None));
+    }
+
     #[test]
     fn roundtrip_bad() {
         let expected = [
@@ -249,6 +744,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn custom_tags_need_key_and_value() {
+        assert_eq!(
+            Tag::from_str("effort:High"),
+            Ok(Tag::Custom("effort".to_string(), "high".to_string()))
+        );
+        assert_eq!(
+            Tag::from_str("due_2:2024-03-01"),
+            Ok(Tag::Custom("due_2".to_string(), "2024-03-01".to_string()))
+        );
+        for text in ["Meeting:", "re:", "effort:", ":high", "10:30", "Due:friday"] {
+            assert!(Tag::from_str(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn sigils_need_a_name() {
+        for text in ["+", "@", "!", "p:", "p:\"\""] {
+            assert!(Tag::from_str(text).is_err(), "{text}");
+        }
+        assert_eq!(Tag::from_str("+1"), Ok(Tag::Project("1".to_string())));
+        assert_eq!(Tag::from_str("@@home"), Ok(Tag::Context("@home".to_string())));
+    }
+
     #[test]
     fn empty_tag() {
         let result = Tag::from_str(" ");
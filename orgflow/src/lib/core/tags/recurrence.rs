@@ -2,7 +2,9 @@ use std::{fmt::Display, str::FromStr};
 
 use chrono::TimeDelta;
 
-#[derive(PartialEq, Debug)]
+use crate::Date;
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct TaskRecurrence(TimeDelta, char);
 
 impl Display for TaskRecurrence {
@@ -21,6 +23,14 @@ impl Display for TaskRecurrence {
 }
 
 impl TaskRecurrence {
+    /// Date one interval after `date`, years landing on the same day of the
+    /// month
+    pub(crate) fn after(&self, date: &Date) -> Date {
+        match self.1 {
+            'y' => date.add_years((self.0.num_weeks() / 52) as u32),
+            _ => date.add_days(self.0.num_days()),
+        }
+    }
     fn with_days(days: u64) -> Self {
         Self(TimeDelta::days(days as i64), 'd')
     }
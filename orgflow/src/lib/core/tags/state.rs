@@ -1,7 +1,9 @@
 use std::{fmt::Display, str::FromStr};
 
-#[derive(PartialEq, Debug)]
+/// Workflow state of a task, written as an `s:` tag
+#[derive(PartialEq, Debug, Clone, Default)]
 pub enum TaskState {
+    #[default]
     Todo,
     Next,
     Hold(String),
@@ -10,11 +12,6 @@ pub enum TaskState {
     Cancelled(String),
 }
 
-impl Default for TaskState {
-    fn default() -> Self {
-        TaskState::Todo
-    }
-}
 
 impl Display for TaskState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
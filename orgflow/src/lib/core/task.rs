@@ -1,12 +1,22 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
 use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 use super::dates::Date;
 use super::priority::Priority;
 use super::tags::Tag;
 use super::tags::TagCollection;
+use super::tags::TaskEstimate;
+use super::tags::TaskState;
+use super::tags::guid::Guid;
+use super::tags::tag_words;
+use crate::TaskSort;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TaskFields")]
 pub struct Task {
     is_completed: bool,
     priority_level: Option<Priority>,
@@ -14,8 +24,144 @@ pub struct Task {
     creation_date: Option<Date>,
     description: String,
     tags: Option<TagCollection>,
+    /// Free-form lines written indented below the task line
+    #[serde(default)]
+    details: Vec<String>,
+    /// Comment lines written right above the task line, see
+    /// `OrgDocument::write`
+    #[serde(default)]
+    comments: Vec<String>,
+    /// Only known for tasks read from a file, left out when serialized
+    /// without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_line: Option<usize>,
+    /// Task line as it was parsed, written instead of the canonical one
+    /// until the task is changed, see `is_dirty`
+    #[serde(skip)]
+    raw: Option<String>,
 }
 
+/// Fields of a serialized task, checked by `Task::try_from`
+#[derive(Deserialize)]
+struct TaskFields {
+    is_completed: bool,
+    priority_level: Option<Priority>,
+    completion_date: Option<Date>,
+    creation_date: Option<Date>,
+    description: String,
+    tags: Option<TagCollection>,
+    #[serde(default)]
+    details: Vec<String>,
+    #[serde(default)]
+    comments: Vec<String>,
+    #[serde(default)]
+    source_line: Option<usize>,
+}
+
+/// A deserialized task has to be one the document can hold: written with
+/// its detail lines and read back by `Task::from_str`, it is the same task
+impl TryFrom<TaskFields> for Task {
+    type Error = String;
+
+    fn try_from(fields: TaskFields) -> Result<Self, Self::Error> {
+        let task = Task {
+            is_completed: fields.is_completed,
+            priority_level: fields.priority_level,
+            completion_date: fields.completion_date,
+            creation_date: fields.creation_date,
+            description: fields.description,
+            tags: fields.tags.filter(|tags| !tags.is_empty()),
+            details: fields.details,
+            comments: fields.comments,
+            source_line: fields.source_line,
+            raw: None,
+        };
+        let mut read = Task::from_str(&task.to_string())?;
+        read.comments = task.comments.clone();
+        let differ = task.differences(&read);
+        match differ.is_empty() {
+            true => Ok(task),
+            false => Err(format!(
+                "'{}' does not read back as the same task, its {} would change",
+                task.canonical_line(),
+                differ.join(", ")
+            )),
+        }
+    }
+}
+
+/// Indentation marking a line as a detail of the task above it
+pub(crate) const DETAIL_INDENT: &str = "  ";
+
+/// A small step of a task, written as a detail line like `- [ ] book room`
+/// or `- [x] send invite`, see `Task::checklist`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecklistItem {
+    pub done: bool,
+    pub text: String,
+}
+
+/// Something that looks wrong with a task the parser accepted, see
+/// `Task::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskWarning {
+    /// A completion date on a task without the `x` of a done task
+    CompletionWithoutMarker(Date),
+    /// A done task without its completion date
+    CompletedWithoutDate,
+    /// Completed on the first date, before it was created on the second
+    CompletedBeforeCreated(Date, Date),
+    /// The same tag more than once, like `+house +house`
+    #[deprecated(note = "tag collections keep each tag once, so no task has repeated tags")]
+    DuplicateTag(String),
+    /// An `est:0min` tag
+    ZeroEstimate,
+}
+
+/// Why a task line could not be read, see `Task::from_str`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskParseError {
+    /// A line with nothing but whitespace
+    EmptyInput,
+    /// No words left for the description after the prefix and the tags
+    MissingDescription,
+    /// A word of the prefix (`x`, priority and dates) that does not fit
+    InvalidPrefix { token: String, reason: String },
+    /// A word written like a tag that cannot be one, with why not
+    InvalidTag { token: String, source: String },
+    /// A detail line below the task without its indentation
+    UnindentedDetail(String),
+}
+
+/// Context marking a task as waiting without a `s:wait()` state
+pub const WAITING_CONTEXT: &str = "waiting";
+/// Custom tag with the date a task started waiting
+pub const WAIT_SINCE_KEY: &str = "wait_since";
+/// Custom tag with the date of the last follow-up on a waiting task
+pub const FOLLOWUP_KEY: &str = "followup";
+/// Custom tag with the last days a recurring task was done, newest first
+pub const DONE_KEY: &str = "done";
+/// Most days kept in the `done` tag of a recurring task
+pub const DONE_HISTORY_LEN: usize = 5;
+
+/// Urgency added by priority (A), see `Task::urgency`
+pub const URGENCY_PRIORITY_A: f64 = 6.0;
+/// Urgency added by priority (B)
+pub const URGENCY_PRIORITY_B: f64 = 3.9;
+/// Urgency added by priority (C)
+pub const URGENCY_PRIORITY_C: f64 = 1.8;
+/// Urgency a task gains by its age, in full once it is `URGENCY_AGE_DAYS` old
+pub const URGENCY_AGE: f64 = 2.0;
+/// Age in days at which a task has gained all of `URGENCY_AGE`
+pub const URGENCY_AGE_DAYS: i64 = 365;
+/// Urgency of a due date, in full from a week overdue, a fifth of it two
+/// weeks ahead and further out
+pub const URGENCY_DUE: f64 = 12.0;
+/// Urgency of a threshold date that has passed
+pub const URGENCY_THRESHOLD: f64 = 1.0;
+/// Urgency of a threshold date still ahead, negative to hold the task back
+pub const URGENCY_HELD_BACK: f64 = -3.0;
+
 impl Task {
     pub fn new() -> Self {
         Self::default()
@@ -40,56 +186,981 @@ impl Task {
     pub fn description(&self) -> &str {
         &self.description
     }
-    
+
+    /// Description without the words in it that read as tags
+    ///
+    /// Tags in the task line go to `tags` when it is parsed, but a
+    /// description set in code can still hold some. Words count as tags the
+    /// way they would in a task line.
+    pub fn description_without_tags(&self) -> String {
+        split_line_tags(&self.description).0.join(" ")
+    }
+
+    /// The words of the description that read as tags, see
+    /// `description_without_tags`
+    pub fn embedded_tags(&self) -> Vec<Tag> {
+        split_line_tags(&self.description).1
+    }
+
+    /// Names of the parts that differ from `other`, like "description"
+    fn differences(&self, other: &Task) -> Vec<&'static str> {
+        let parts = [
+            ("completion", self.is_completed != other.is_completed),
+            ("priority", self.priority_level != other.priority_level),
+            ("completion date", self.completion_date != other.completion_date),
+            ("creation date", self.creation_date != other.creation_date),
+            ("description", self.description != other.description),
+            ("tags", self.tags != other.tags),
+            ("details", self.details != other.details),
+            ("comments", self.comments != other.comments),
+        ];
+        parts.into_iter().filter(|(_, differs)| *differs).map(|(part, _)| part).collect()
+    }
+
+    /// Days since the task was created, `None` without a creation date
+    pub fn age_in_days(&self, today: &Date) -> Option<i64> {
+        self.creation_date.as_ref().map(|created| created.days_until(today))
+    }
+
+    /// What is wrong with the dates of the task, `None` if they fit
+    ///
+    /// Only a done task has a completion date, and it cannot be done before
+    /// it was created. The parser accepts both, they come from editing the
+    /// file by hand.
+    pub fn date_problem(&self) -> Option<String> {
+        let warnings = self.validate().into_iter();
+        let mut problems = warnings.filter(|warning| {
+            matches!(
+                warning,
+                TaskWarning::CompletionWithoutMarker(_) | TaskWarning::CompletedBeforeCreated(..)
+            )
+        });
+        problems.next().map(|problem| problem.to_string())
+    }
+
+    /// What looks wrong with the task although it was parsed, in the order
+    /// of `TaskWarning`; empty for a task that is fine
+    pub fn validate(&self) -> Vec<TaskWarning> {
+        let mut warnings = Vec::new();
+        match (self.completion_date, self.is_completed) {
+            (Some(completed), false) => {
+                warnings.push(TaskWarning::CompletionWithoutMarker(completed))
+            }
+            (None, true) => warnings.push(TaskWarning::CompletedWithoutDate),
+            _ => {}
+        }
+        if let (Some(completed), Some(created)) = (self.completion_date, self.creation_date)
+            && completed < created
+        {
+            warnings.push(TaskWarning::CompletedBeforeCreated(completed, created));
+        }
+        if self.estimate() == Some(Duration::ZERO) {
+            warnings.push(TaskWarning::ZeroEstimate);
+        }
+        warnings
+    }
+
+    /// What the dates say together, like "open for 41 days" or "done in 3
+    /// days"; `None` if they say nothing or do not fit (see `date_problem`)
+    pub fn date_facts(&self, today: &Date) -> Option<String> {
+        if self.date_problem().is_some() {
+            return None;
+        }
+        let created = self.creation_date.as_ref()?;
+        match (&self.completion_date, self.is_completed) {
+            (Some(completed), _) => match created.days_until(completed) {
+                0 => Some("done the same day".to_string()),
+                days => Some(format!("done in {}", count_days(days))),
+            },
+            (None, false) => match created.days_until(today) {
+                0 => Some("open since today".to_string()),
+                days => Some(format!("open for {}", count_days(days))),
+            },
+            (None, true) => None,
+        }
+    }
+
     pub fn tags(&self) -> &Option<TagCollection> {
         &self.tags
     }
+
+    /// Contexts of the task without their `@`, in the order of its tags
+    pub fn contexts(&self) -> Vec<&str> {
+        self.tags.as_ref().map(TagCollection::contexts).unwrap_or_default()
+    }
+
+    /// Projects of the task without their `+`, in the order of its tags
+    pub fn projects(&self) -> Vec<&str> {
+        self.tags.as_ref().map(TagCollection::projects).unwrap_or_default()
+    }
+
+    /// Whether the task has the context, given with or without its `@` and
+    /// in any case
+    pub fn has_context(&self, context: &str) -> bool {
+        let context = context.strip_prefix('@').unwrap_or(context);
+        self.contexts().iter().any(|name| name.eq_ignore_ascii_case(context))
+    }
+
+    /// Whether the task has the project, given with or without its `+` and
+    /// in any case
+    pub fn has_project(&self, project: &str) -> bool {
+        let project = project.strip_prefix('+').unwrap_or(project);
+        self.projects().iter().any(|name| name.eq_ignore_ascii_case(project))
+    }
+
+    /// Detail lines below the task line, without their indentation
+    pub fn details(&self) -> &[String] {
+        &self.details
+    }
+
+    /// Replace the detail lines, trailing blank lines are dropped
+    pub fn set_details(&mut self, mut details: Vec<String>) {
+        self.raw = None;
+        while details.last().is_some_and(|l| l.trim().is_empty()) {
+            details.pop();
+        }
+        self.details = details;
+    }
+
+    /// Steps of the task, from its detail lines written as checklist items
+    /// (see `ChecklistItem`); the other detail lines are left out
+    pub fn checklist(&self) -> Vec<ChecklistItem> {
+        let items = self.details.iter().map(|line| ChecklistItem::from_str(line));
+        items.filter_map(Result::ok).collect()
+    }
+
+    pub(crate) fn push_detail(&mut self, line: &str) {
+        self.details.push(line.to_string());
+    }
+
+    /// Comment lines (starting with `//` or `#`) above the task line in the
+    /// Tasks section, verbatim
+    ///
+    /// They are no part of the task line and move with the task within the
+    /// Tasks section.
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub(crate) fn set_comments(&mut self, comments: Vec<String>) {
+        self.comments = comments;
+    }
+
+    pub(crate) fn take_comments(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.comments)
+    }
+
+    /// 1-based line of the task in the file it was parsed from
+    ///
+    /// This reflects the last parse of the document and goes stale once the
+    /// document is written with changes; reload the document to refresh it.
+    /// Tasks created programmatically return `None`.
+    pub fn source_line(&self) -> Option<usize> {
+        self.source_line
+    }
+
+    pub(crate) fn set_source_line(&mut self, line: usize) {
+        self.source_line = Some(line);
+    }
+
+    /// Whether the task is written in its canonical form: it was created
+    /// rather than parsed, or changed since
+    ///
+    /// A task that is not dirty is written as the line it was read from,
+    /// spacing and the order of its tags included.
+    pub fn is_dirty(&self) -> bool {
+        self.raw.is_none()
+    }
+
+    /// Task line as orgflow writes it, prefix, description and tags
+    /// separated by single spaces
+    fn canonical_line(&self) -> String {
+        let mut result = Vec::new();
+        if self.is_completed {
+            result.push("x".to_string());
+        }
+        if let Some(prio) = &self.priority_level {
+            result.push(prio.to_string());
+        }
+        if let Some(d) = &self.completion_date {
+            result.push(d.to_string());
+        }
+        if let Some(cd) = &self.creation_date {
+            result.push(cd.to_string());
+        }
+        result.push(quote_first_word(&self.description));
+        if let Some(tags) = &self.tags {
+            result.push(tags.to_string());
+        }
+        result.join(" ")
+    }
+
+    /// Replace the description, which should read back as one, see
+    /// `TaskBuilder::build` for what that takes
+    pub fn set_description(&mut self, description: String) {
+        self.raw = None;
+        self.description = description;
+    }
+
+    pub fn set_priority(&mut self, priority: Option<Priority>) {
+        self.raw = None;
+        self.priority_level = priority;
+    }
+
+    /// Raise the priority by one, a task without one gets `(C)`
+    pub fn bump_priority(&mut self) {
+        let priority = match &self.priority_level {
+            Some(priority) => priority.higher(),
+            None => Priority::C,
+        };
+        self.set_priority(Some(priority));
+    }
+
+    /// Lower the priority by one, `(C)` loses its priority
+    pub fn lower_priority(&mut self) {
+        let priority = match &self.priority_level {
+            Some(Priority::C) | None => None,
+            Some(priority) => Some(priority.lower()),
+        };
+        self.set_priority(priority);
+    }
+
+    /// Add `tag` the way `TagCollection::upsert` does, replacing e.g. the
+    /// estimate the task had
+    pub fn add_tag(&mut self, tag: Tag) {
+        self.raw = None;
+        self.tags.get_or_insert_with(TagCollection::new).upsert(tag);
+    }
+
+    /// Remove `tag`, returning whether the task had it
+    pub fn remove_tag(&mut self, tag: &Tag) -> bool {
+        let Some(tags) = &mut self.tags else {
+            return false;
+        };
+        let mut removed = false;
+        tags.retain(|existing| {
+            removed |= existing == tag;
+            existing != tag
+        });
+        if removed {
+            self.raw = None;
+        }
+        // No tags are written as none at all, not as a trailing space
+        if tags.is_empty() {
+            self.tags = None;
+        }
+        removed
+    }
+
+    /// Replace all tags of the task, an empty collection removes them
+    pub fn set_tags(&mut self, tags: TagCollection) {
+        self.raw = None;
+        self.tags = if tags.is_empty() { None } else { Some(tags) };
+    }
+
+    /// Mark the task done on `date`, a task with an `s:` tag gets `s:done`
+    pub(crate) fn mark_completed(&mut self, date: Date) {
+        self.raw = None;
+        self.is_completed = true;
+        self.completion_date = Some(date);
+        if let Some(tags) = &mut self.tags
+            && tags.status().is_some()
+        {
+            tags.upsert(Tag::Status(TaskState::Done));
+        }
+    }
+
+    /// Set the custom tag `key:value` (like `room:kitchen`), replacing the
+    /// value the key had
+    pub fn set_custom_tag(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let Tag::Custom(key, value) = Tag::from_str(&format!("{key}:{value}"))? else {
+            return Err(format!("'{key}' is reserved, not a custom tag"));
+        };
+        self.raw = None;
+        self.tags.get_or_insert_with(TagCollection::new).set_custom(&key, &value);
+        Ok(())
+    }
+
+    /// Complete the task on `today`, returning the next occurrence of a
+    /// recurring one
+    ///
+    /// The next occurrence is open again with `today` added to its `done`
+    /// history, which keeps the last `DONE_HISTORY_LEN` days. Its due and
+    /// threshold dates move on by the interval, counted from the old dates
+    /// for a strict recurrence (`rec:+1w`) and from today for a loose one
+    /// (`rec:1w`); so does its creation date, if it has one. A loose one
+    /// without a due or threshold date gets a threshold one interval after
+    /// today. It is a task of its own, without the `id:` tag of the one
+    /// done. A task that is done already is left as it is.
+    pub fn complete(&mut self, today: &Date) -> Option<Task> {
+        if self.is_completed {
+            return None;
+        }
+        let mut next = self.next_occurrence(today);
+        // Comments stay above the open occurrence
+        if let Some(next) = &mut next {
+            next.comments = self.take_comments();
+        }
+        self.mark_completed(*today);
+        next
+    }
+
+    /// Open the task again, without its completion date
+    ///
+    /// The next occurrence a recurring task got when it was completed stays
+    /// where it is. An `s:done` tag goes as well.
+    pub fn uncomplete(&mut self) {
+        self.raw = None;
+        self.is_completed = false;
+        self.completion_date = None;
+        if self.state() == Some(&TaskState::Done) {
+            self.drop_tags(|tag| matches!(tag, Tag::Status(_)));
+        }
+    }
+
+    /// Complete an open task on `today`, open a completed one again
+    ///
+    /// The completion date comes and goes with the `x`. Unlike `complete`,
+    /// a recurring task does not get its next occurrence.
+    pub fn toggle_completion(&mut self, today: Date) {
+        if self.is_completed {
+            self.uncomplete();
+        } else {
+            self.mark_completed(today);
+        }
+    }
+
+    /// The task as it recurs after being done on `completed_on`, open and
+    /// with its dates moved on as described for `complete`, `None` without
+    /// a `rec:` tag
+    ///
+    /// A loose recurrence without a due or threshold date gets a threshold
+    /// one interval after `completed_on`, so it is not there to be done
+    /// again right away.
+    pub fn next_occurrence(&self, completed_on: &Date) -> Option<Task> {
+        let (strict, rec) = self.tags.as_ref()?.iter().find_map(|tag| match tag {
+            Tag::StrictRecurrence(rec) => Some((true, rec)),
+            Tag::LooseRecurrence(rec) => Some((false, rec)),
+            _ => None,
+        })?;
+        let due = self.due_date().copied();
+        let threshold = self.threshold_date().copied();
+        // Both dates move alike, keeping the threshold as far before the due date
+        let moved = |date: Date| match due.or(threshold) {
+            Some(anchor) if !strict => date.add_days(anchor.days_until(&rec.after(completed_on))),
+            _ => rec.after(&date),
+        };
+        let mut history = vec![*completed_on];
+        history.extend(self.done_history());
+        history.truncate(DONE_HISTORY_LEN);
+        let history: Vec<String> = history.iter().map(Date::to_string).collect();
+
+        let mut next = self.clone();
+        next.source_line = None;
+        next.raw = None;
+        next.uncomplete();
+        next.creation_date = match next.creation_date {
+            Some(created) if strict => Some(rec.after(&created)),
+            Some(_) => Some(rec.after(completed_on)),
+            None => None,
+        };
+        let tags = next.tags.get_or_insert_with(TagCollection::new);
+        tags.retain(|tag| !matches!(tag, Tag::Id(_)));
+        // Without a date to move, a loose one waits for the interval to pass
+        if !strict && due.is_none() && threshold.is_none() {
+            tags.upsert(Tag::Threshold(rec.after(completed_on)));
+        }
+        tags.set_custom(DONE_KEY, &history.join(","));
+        if let Some(due) = due {
+            tags.upsert(Tag::Due(moved(due)));
+        }
+        if let Some(threshold) = threshold {
+            tags.upsert(Tag::Threshold(moved(threshold)));
+        }
+        Some(next)
+    }
+
+    /// Days a recurring task was done before, newest first, from its `done`
+    /// tag
+    pub fn done_history(&self) -> Vec<Date> {
+        let Some(value) = self.tags.as_ref().and_then(|tags| tags.custom_value(DONE_KEY)) else {
+            return Vec::new();
+        };
+        value.split(',').filter_map(|date| Date::from_str(date).ok()).collect()
+    }
+
+    /// Workflow state from the `s:` tag, none counts as `Todo`
+    pub fn state(&self) -> Option<&TaskState> {
+        self.tags.as_ref()?.status()
+    }
+
+    /// Set the workflow state, `Todo` is written as no `s:` tag at all
+    ///
+    /// `Done` completes an open task on `today`, without bringing back a
+    /// recurring one (see `complete` for that).
+    pub fn set_state(&mut self, state: TaskState, today: &Date) {
+        self.raw = None;
+        match state {
+            TaskState::Todo => self.drop_tags(|tag| matches!(tag, Tag::Status(_))),
+            state => {
+                let done = state == TaskState::Done;
+                self.tags.get_or_insert_with(TagCollection::new).upsert(Tag::Status(state));
+                if done && !self.is_completed {
+                    self.mark_completed(*today);
+                }
+            }
+        }
+    }
+
+    /// Mark the task as waiting on `on`, a person or a short reason
+    ///
+    /// The day the waiting started is kept in a `wait_since` tag, waiting on
+    /// someone else later keeps it.
+    pub fn wait_on(&mut self, on: &str, today: &Date) -> Result<(), String> {
+        if on.chars().any(char::is_whitespace) {
+            return Err(format!("'{on}' cannot go into a tag, use a single word"));
+        }
+        let since = self.waiting_since().filter(|_| self.is_waiting()).unwrap_or(*today);
+        self.set_state(TaskState::Wait(on.to_string()), today);
+        self.set_custom_tag(WAIT_SINCE_KEY, &since.to_string())
+    }
+
+    /// Whether the task waits on someone, by `s:wait()` or `@waiting`
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.state(), Some(TaskState::Wait(_)))
+            || self.contexts().contains(&WAITING_CONTEXT)
+    }
+
+    /// Who the task waits on, its persons or else the `wait()` comment
+    pub fn waiting_for(&self) -> Vec<String> {
+        let persons: Vec<String> = match &self.tags {
+            Some(tags) => tags.persons().into_iter().map(str::to_string).collect(),
+            None => Vec::new(),
+        };
+        match self.state() {
+            Some(TaskState::Wait(on)) if persons.is_empty() && !on.is_empty() => {
+                vec![on.clone()]
+            }
+            _ => persons,
+        }
+    }
+
+    /// Day the task is due, from its `due:` tag
+    pub fn due_date(&self) -> Option<&Date> {
+        self.tags.as_ref()?.iter().find_map(|tag| match tag {
+            Tag::Due(date) => Some(date),
+            _ => None,
+        })
+    }
+
+    /// Time the task is estimated to take, from its `est:` tag
+    pub fn estimate(&self) -> Option<Duration> {
+        self.tags.as_ref()?.estimate()
+    }
+
+    /// Estimate the task to take `minutes`, replacing the estimate it had
+    ///
+    /// Fails for more minutes than a `Duration` holds in seconds, the task
+    /// is left as it is then.
+    pub fn set_estimate(&mut self, minutes: u64) -> Result<(), String> {
+        self.add_tag(Tag::Estimate(TaskEstimate::new(minutes)?));
+        Ok(())
+    }
+
+    /// Identity of the task, from its `id:` tag
+    pub fn id(&self) -> Option<&Guid> {
+        self.tags.as_ref()?.id()
+    }
+
+    /// Identity of the task, giving it a new `id:` tag if it has none yet
+    pub fn ensure_id(&mut self) -> Guid {
+        if let Some(id) = self.id() {
+            return id.clone();
+        }
+        let id = Guid::new();
+        self.add_tag(Tag::Id(id.clone()));
+        id
+    }
+
+    /// Guids of the notes the task links to, from its `n:` tags
+    pub fn note_refs(&self) -> Vec<&Guid> {
+        let tags = self.tags.iter().flat_map(|tags| tags.iter());
+        tags.filter_map(|tag| match tag {
+            Tag::Note(guid) => Some(guid),
+            _ => None,
+        })
+        .collect()
+    }
+
+    /// Ids of the tasks to be done before this one, from its `blocked:` tags
+    pub fn blocked_by(&self) -> Vec<&Guid> {
+        let tags = self.tags.iter().flat_map(|tags| tags.iter());
+        tags.filter_map(|tag| match tag {
+            Tag::BlockedBy(id) => Some(id),
+            _ => None,
+        })
+        .collect()
+    }
+
+    /// Day before which the task is not to be worked on, from its `t:` tag
+    pub fn threshold_date(&self) -> Option<&Date> {
+        self.tags.as_ref()?.threshold()
+    }
+
+    /// Order of the task and `other` in a list sorted by `sort`
+    ///
+    /// Tasks without what is sorted by go last, `TaskSort::Document` leaves
+    /// the order to the positions of the tasks (all compare equal here).
+    pub fn cmp_by(&self, other: &Task, sort: TaskSort) -> Ordering {
+        fn last_without<T: Ord>(value: Option<T>) -> (bool, Option<T>) {
+            (value.is_none(), value)
+        }
+        let project = |task: &Task| task.projects().first().map(|name| name.to_lowercase());
+        match sort {
+            TaskSort::Document => Ordering::Equal,
+            TaskSort::Priority => last_without(self.priority_level.as_ref())
+                .cmp(&last_without(other.priority_level.as_ref())),
+            TaskSort::Created => {
+                last_without(self.creation_date).cmp(&last_without(other.creation_date))
+            }
+            TaskSort::Due => last_without(self.due_date()).cmp(&last_without(other.due_date())),
+            TaskSort::Project => last_without(project(self)).cmp(&last_without(project(other))),
+        }
+    }
+
+    /// Whether the task is open and its due date is before `today`
+    ///
+    /// The `t:` threshold date says when the task can be started, not when
+    /// it has to be done, so it is left out: a task with a threshold but no
+    /// due date is never overdue.
+    pub fn is_overdue(&self, today: &Date) -> bool {
+        !self.is_completed && self.due_date().is_some_and(|due| due < today)
+    }
+
+    /// Whether the task is open and can be worked on `today`, i.e. its
+    /// threshold date is not after `today`
+    pub fn is_actionable(&self, today: &Date) -> bool {
+        !self.is_completed && self.threshold_date().is_none_or(|threshold| threshold <= today)
+    }
+
+    /// How pressing the task is on `today`, higher is more urgent, 0 for a
+    /// completed task
+    ///
+    /// Adds up the weights `URGENCY_PRIORITY_A` to `URGENCY_HELD_BACK` for
+    /// the priority, the age since the creation date, the due date and the
+    /// threshold date of the task.
+    pub fn urgency(&self, today: &Date) -> f64 {
+        if self.is_completed {
+            return 0.0;
+        }
+        let priority = match self.priority_level {
+            Some(Priority::A) => URGENCY_PRIORITY_A,
+            Some(Priority::B) => URGENCY_PRIORITY_B,
+            Some(Priority::C) => URGENCY_PRIORITY_C,
+            None => 0.0,
+        };
+        let age = self.creation_date.map_or(0.0, |created| {
+            let days = created.days_until(today).clamp(0, URGENCY_AGE_DAYS);
+            URGENCY_AGE * days as f64 / URGENCY_AGE_DAYS as f64
+        });
+        // From a fifth two weeks ahead up to all of it a week overdue
+        let due = self.due_date().map_or(0.0, |due| {
+            let ahead = today.days_until(due).clamp(-7, 14) as f64;
+            URGENCY_DUE * (0.2 + 0.8 * (14.0 - ahead) / 21.0)
+        });
+        let threshold = match self.threshold_date() {
+            Some(threshold) if threshold > today => URGENCY_HELD_BACK,
+            Some(threshold) if threshold < today => URGENCY_THRESHOLD,
+            _ => 0.0,
+        };
+        priority + age + due + threshold
+    }
+
+    /// Day the task started waiting, from its `wait_since` tag
+    pub fn waiting_since(&self) -> Option<Date> {
+        self.custom_date(WAIT_SINCE_KEY)
+    }
+
+    /// Days the task has been waiting by `today`
+    pub fn waiting_days(&self, today: &Date) -> Option<i64> {
+        self.waiting_since().map(|since| since.days_until(today))
+    }
+
+    /// Day of the last follow-up, from its `followup` tag
+    pub fn followed_up(&self) -> Option<Date> {
+        self.custom_date(FOLLOWUP_KEY)
+    }
+
+    /// Note that the task was followed up on `today`
+    pub fn follow_up(&mut self, today: &Date) {
+        self.raw = None;
+        self.tags
+            .get_or_insert_with(TagCollection::new)
+            .set_custom(FOLLOWUP_KEY, &today.to_string());
+    }
+
+    /// Stop waiting, back to `Todo` without the tags kept while waiting
+    pub fn stop_waiting(&mut self) {
+        self.drop_tags(|tag| match tag {
+            Tag::Status(_) => true,
+            Tag::Context(ctx) => ctx == WAITING_CONTEXT,
+            Tag::Custom(key, _) => key == WAIT_SINCE_KEY || key == FOLLOWUP_KEY,
+            _ => false,
+        });
+    }
+
+    fn custom_date(&self, key: &str) -> Option<Date> {
+        let value = self.tags.as_ref()?.custom_value(key)?;
+        Date::from_str(value).ok()
+    }
+
+    fn drop_tags(&mut self, drop: impl Fn(&Tag) -> bool) {
+        self.raw = None;
+        if let Some(tags) = &mut self.tags {
+            tags.retain(|tag| !drop(tag));
+            if tags.is_empty() {
+                self.tags = None;
+            }
+        }
+    }
+
+    pub(crate) fn add_tags(&mut self, tags: TagCollection) {
+        self.raw = None;
+        match &mut self.tags {
+            Some(existing) => existing.merge(tags),
+            None => self.tags = Some(tags),
+        }
+    }
+
+    /// Take in a duplicate of this task, see `OrgDocument::merge_tasks`
+    ///
+    /// Tags of `other` are added unless this task already has one of their
+    /// kind where only one fits, the earlier creation date is kept, and
+    /// detail lines this task does not have yet are appended.
+    pub(crate) fn absorb(&mut self, other: Task) {
+        self.raw = None;
+        let tags = self.tags.get_or_insert_with(TagCollection::new);
+        for tag in other.tags.iter().flat_map(|other| other.iter()) {
+            let taken = tags.iter().any(|existing| {
+                *existing == *tag || (tag.is_single() && existing.same_kind(tag))
+            });
+            if !taken {
+                tags.upsert(tag.clone());
+            }
+        }
+        if tags.is_empty() {
+            self.tags = None;
+        }
+        self.creation_date = match (self.creation_date.take(), other.creation_date) {
+            (Some(own), Some(theirs)) => Some(own.min(theirs)),
+            (own, theirs) => own.or(theirs),
+        };
+        if self.priority_level.is_none() {
+            self.priority_level = other.priority_level;
+        }
+        for detail in other.details {
+            if !self.details.contains(&detail) {
+                self.details.push(detail);
+            }
+        }
+    }
     pub fn with_task(description: String) -> Self {
         Self {
             description,
             ..Default::default()
         }
     }
-    pub fn with_today(description: &str) -> Self {
-        let mut t = Self::from_str(description).unwrap();
+    /// Parse a task line (see `from_str`) and date it today
+    pub fn with_today(description: &str) -> Result<Self, String> {
+        let mut t = Self::from_str(description)?;
         t.creation_date = Some(Date::now());
-        t
+        t.raw = None;
+        Ok(t)
     }
-}
 
-fn _is_prefix(s: &str) -> bool {
-    Priority::from_str(s).is_ok() | Date::from_str(s).is_ok() | (s == "x")
+    /// A task done on `completed_on`, created on `created_on`, e.g. for
+    /// importing finished work; fails like `TaskBuilder::build`
+    pub fn completed_with(
+        description: &str,
+        completed_on: Date,
+        created_on: Date,
+    ) -> Result<Self, String> {
+        TaskBuilder::new()
+            .description(description)
+            .creation_date(created_on)
+            .completed_on(completed_on)
+            .build()
+    }
+
+    /// The task once more, open and created today
+    ///
+    /// Description, priority and tags are copied, apart from the `id:` tag
+    /// and an `s:done` state; detail lines and comments stay with the
+    /// original.
+    pub fn duplicated_for_today(&self) -> Task {
+        let mut task = Task {
+            priority_level: self.priority_level.clone(),
+            creation_date: Some(Date::now()),
+            description: self.description.clone(),
+            tags: self.tags.clone(),
+            ..Task::default()
+        };
+        task.drop_tags(|tag| matches!(tag, Tag::Id(_) | Tag::Status(TaskState::Done)));
+        task
+    }
 }
 
-fn _is_suffix(s: &str) -> bool {
-    Tag::from_str(s).is_ok()
+/// Put a task together from code, rather than writing and parsing its line
+///
+/// ```
+/// use orgflow::{Date, Priority, Tag, TaskBuilder};
+/// use std::str::FromStr;
+///
+/// let task = TaskBuilder::new()
+///     .description("Renew the passport")
+///     .priority(Priority::A)
+///     .creation_date(Date::from_str("2025-03-01").unwrap())
+///     .tag(Tag::from_str("@town").unwrap())
+///     .build()
+///     .unwrap();
+/// assert_eq!(task.to_string(), "(A) 2025-03-01 Renew the passport @town");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TaskBuilder {
+    description: String,
+    priority: Option<Priority>,
+    creation_date: Option<Date>,
+    completed: bool,
+    completion_date: Option<Date>,
+    tags: Vec<Tag>,
+    details: Vec<String>,
 }
 
-impl Display for Task {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = Vec::new();
-        if self.is_completed {
-            result.push("x".to_string());
+impl TaskBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn creation_date(mut self, date: Date) -> Self {
+        self.creation_date = Some(date);
+        self
+    }
+
+    /// Mark the task as done, without saying when
+    pub fn completed(mut self) -> Self {
+        self.completed = true;
+        self
+    }
+
+    /// Mark the task as done on `date`
+    pub fn completed_on(mut self, date: Date) -> Self {
+        self.completed = true;
+        self.completion_date = Some(date);
+        self
+    }
+
+    /// Add a tag, in the order they are written
+    pub fn tag(mut self, tag: Tag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Add a detail line, written indented below the task line
+    pub fn detail(mut self, line: impl Into<String>) -> Self {
+        self.details.push(line.into());
+        self
+    }
+
+    /// The task, if its line reads back as the same task
+    ///
+    /// Checked are what `Task::from_str` and `Task::date_problem` check: a
+    /// one-line description that neither starts like the line (with `x`, a
+    /// priority or a date) nor contains a tag, a completion date only next
+    /// to a creation date (a lone date is read as the creation date) and not
+    /// before it.
+    pub fn build(self) -> Result<Task, String> {
+        let description = self.description.trim();
+        if description.is_empty() {
+            return Err("There must be a task description!".to_string());
+        }
+        if description.contains('\n') {
+            return Err("The task description must be a single line".to_string());
         }
-        match &self.priority_level {
-            Some(prio) => result.push(prio.to_string()),
-            None => {}
+        if self.completion_date.is_some() && self.creation_date.is_none() {
+            return Err("A completion date needs a creation date as well".to_string());
         }
-        match &self.completion_date {
-            Some(d) => result.push(d.to_string()),
-            None => {}
+        let mut task = Task {
+            is_completed: self.completed,
+            priority_level: self.priority,
+            completion_date: self.completion_date,
+            creation_date: self.creation_date,
+            description: description.to_string(),
+            tags: None,
+            details: Vec::new(),
+            comments: Vec::new(),
+            source_line: None,
+            raw: None,
+        };
+        task.set_tags(TagCollection::from_tags(self.tags));
+        task.set_details(self.details);
+        if let Some(problem) = task.date_problem() {
+            return Err(problem);
+        }
+        // Words of the description that would be read as something else
+        match Task::from_str(&task.to_string()) {
+            Ok(parsed) if parsed == task => Ok(task),
+            _ => Err(format!("'{task}' would not be read back as the same task")),
         }
-        match &self.creation_date {
-            Some(cd) => result.push(cd.to_string()),
-            None => {}
+    }
+}
+
+/// Whether `word` at the start of a description would be read as part of
+/// the prefix, or is such a word in quotes, see `quote_first_word`
+fn needs_quotes(word: &str) -> bool {
+    word == "x"
+        || Priority::from_str(word).is_ok()
+        || looks_like_date(word)
+        || unquoted(word).is_some_and(needs_quotes)
+}
+
+/// `word` without the quotes around it
+fn unquoted(word: &str) -> Option<&str> {
+    word.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// The tag `word` stands for in a task line, `None` if it is text
+///
+/// Contexts, projects and tags with a reserved key count anywhere. A custom
+/// `key:value` tag only counts in the run of tags the line ends with, so
+/// words like `mailto:bob@example.com` in a sentence stay text, and links
+/// with `://` are text wherever they are.
+fn line_tag(word: &str, trailing: bool) -> Option<Tag> {
+    if word.contains("://") {
+        return None;
+    }
+    match Tag::from_str(word).ok()? {
+        Tag::Custom(..) if !trailing => None,
+        tag => Some(tag),
+    }
+}
+
+/// Words of `text` that are text and the tags of the others, see `line_tag`
+fn split_line_tags(text: &str) -> (Vec<&str>, Vec<Tag>) {
+    let words = tag_words(text);
+    let trailing = trailing_tags(&words);
+    let mut tags = Vec::new();
+    let mut rest = Vec::new();
+    for (index, word) in words.into_iter().enumerate() {
+        match line_tag(word, index >= trailing) {
+            Some(tag) => tags.push(tag),
+            None => rest.push(word),
+        }
+    }
+    (rest, tags)
+}
+
+/// Index of the first of the words a line ends with that are all tags
+fn trailing_tags(words: &[&str]) -> usize {
+    let text = words.iter().rposition(|word| line_tag(word, true).is_none());
+    text.map_or(0, |index| index + 1)
+}
+
+/// Whether `word` is written like a `key:value` tag, a lowercase key and
+/// a value, whether it is a valid one or not; `10:30` or `Note:` are text
+fn looks_like_tag(word: &str) -> bool {
+    word.split_once(':').is_some_and(|(key, value)| {
+        key.starts_with(|c: char| c.is_ascii_lowercase()) && !value.is_empty()
+    })
+}
+
+/// Digits in three parts like `2025-03-01`, whether they make a date or not
+fn looks_like_date(word: &str) -> bool {
+    let parts: Vec<&str> = word.split('-').collect();
+    parts.len() == 3
+        && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Description as written in a task line: a first word that would be read
+/// as `x`, a priority or a date goes in quotes, `"2025-01-01" party`
+fn quote_first_word(description: &str) -> String {
+    let (first, rest) = description.split_once(' ').unwrap_or((description, ""));
+    match (needs_quotes(first), rest.is_empty()) {
+        (false, _) => description.to_string(),
+        (true, true) => format!("\"{first}\""),
+        (true, false) => format!("\"{first}\" {rest}"),
+    }
+}
+
+/// First word of `line` and what follows it
+fn split_word(line: &str) -> (&str, &str) {
+    line.split_once(' ').map_or((line, ""), |(word, rest)| (word, rest.trim_start()))
+}
+
+impl Display for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.raw {
+            Some(raw) => write!(f, "{raw}")?,
+            None => write!(f, "{}", self.canonical_line())?,
         }
-        result.push(self.description.clone());
-        match &self.tags {
-            Some(tags) => result.push(tags.to_string()),
-            None => {}
+        for detail in &self.details {
+            write!(f, "\n{}{}", DETAIL_INDENT, detail)?;
         }
+        Ok(())
+    }
+}
 
-        write!(f, "{}", result.join(" "))
+/// Equal whatever lines they were read from, see `Task::is_dirty`
+impl PartialEq for Task {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_completed == other.is_completed
+            && self.priority_level == other.priority_level
+            && self.completion_date == other.completion_date
+            && self.creation_date == other.creation_date
+            && self.description == other.description
+            && self.tags == other.tags
+            && self.details == other.details
+            && self.comments == other.comments
+    }
+}
+
+impl Eq for Task {}
+
+impl Ord for Task {
+    /// Open tasks before completed ones, then by priority from `(A)` to
+    /// none, the earliest creation date (none last) and the description
+    ///
+    /// Tasks alike in all of these are told apart by their completion date,
+    /// tags, details and comments, so only equal tasks compare as equal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |task: &Task| {
+            let priority = task.priority_level.clone();
+            let created = task.creation_date;
+            (task.is_completed, priority.is_none(), priority, created.is_none(), created)
+        };
+        let rest = |task: &Task| {
+            let tags = task.tags.as_ref().map(TagCollection::to_string);
+            (task.completion_date, tags, task.details.clone(), task.comments.clone())
+        };
+        key(self)
+            .cmp(&key(other))
+            .then_with(|| self.description.cmp(&other.description))
+            .then_with(|| rest(self).cmp(&rest(other)))
+    }
+}
+
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -102,87 +1173,195 @@ impl Default for Task {
             creation_date: Some(Date::now()),
             description: String::new(),
             tags: None,
+            details: Vec::new(),
+            comments: Vec::new(),
+            source_line: None,
+            raw: None,
         }
     }
 }
 
 impl FromStr for Task {
-    type Err = String;
+    type Err = TaskParseError;
 
+    /// Parse a task line, optionally followed by indented detail lines
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, detail_lines) = s.split_once('\n').unwrap_or((s, ""));
         if s.trim().is_empty() {
-            return Err("Empty String error".to_string());
+            return Err(TaskParseError::EmptyInput);
         }
         let mut task = Task::default();
-        let mut prefix: Vec<&str> = Vec::new();
         let mut description: Vec<&str> = Vec::new();
-        let mut suffix: Vec<&str> = Vec::new();
-
-        let mut remaining = s.trim();
-        while let Some((head, tail)) = remaining.split_once(" ") {
-            if _is_prefix(head) & description.is_empty() {
-                prefix.push(head);
-                remaining = tail.trim();
-            } else if _is_suffix(head) {
-                suffix.push(head);
-                let mut remainder: Vec<&str> = tail.split(" ").collect();
-                suffix.append(&mut remainder);
-                remaining = "";
-            } else {
-                description.push(head);
-                remaining = tail.trim();
+        let mut suffix: Vec<Tag> = Vec::new();
+        let mut rewrite = false;
+
+        // Tags are taken from anywhere after the prefix, the other words
+        // make the description
+        let words = tag_words(take_prefix(s.trim(), &mut task)?);
+        let trailing = trailing_tags(&words);
+        for (index, word) in words.into_iter().enumerate() {
+            if description.is_empty()
+                && let Some(word) = unquoted(word).filter(|word| needs_quotes(word))
+            {
+                description.push(word);
+                continue;
+            }
+            let invalid = |source: String| TaskParseError::InvalidTag {
+                token: word.to_string(),
+                source,
+            };
+            if let Some(tag) = line_tag(word, index >= trailing) {
+                if matches!(tag, Tag::Estimate(_))
+                    && suffix.iter().any(|t| matches!(t, Tag::Estimate(_)))
+                {
+                    return Err(invalid("a task can only have one estimate".to_string()));
+                }
+                rewrite |=
+                    matches!(tag, Tag::Due(_) | Tag::Threshold(_)) && tag.to_string() != word;
+                suffix.push(tag);
+                continue;
+            }
+            match Tag::from_str(word) {
+                // Once tags started, a broken `key:value` one is no text
+                Err(error) if !suffix.is_empty() && looks_like_tag(word) => {
+                    return Err(invalid(error));
+                }
+                _ => description.push(word),
             }
-        }
-        if _is_prefix(remaining) {
-            prefix.push(remaining);
-        } else if _is_suffix(remaining) {
-            suffix.push(remaining);
-        } else {
-            description.push(remaining);
         }
         if description.is_empty() {
-            return Err("There must be a task description!".to_string());
+            return Err(TaskParseError::MissingDescription);
         }
-        let _ = process_prefix(&mut prefix, &mut task)?;
         task.description = description.join(" ").trim().to_string();
         if !suffix.is_empty() {
-            task.tags = Some(TagCollection::from_str(&suffix.join(" "))?);
+            let count = suffix.len();
+            let tags = TagCollection::from_tags(suffix);
+            // Repeated tags are written once
+            rewrite |= tags.len() != count;
+            task.tags = Some(tags);
+        }
+        for line in detail_lines.lines() {
+            let detail = line
+                .strip_prefix(DETAIL_INDENT)
+                .ok_or(TaskParseError::UnindentedDetail(line.to_string()))?;
+            task.push_detail(detail);
         }
+        // A date typed like `due:tomorrow` is written as the day it stands
+        // for, repeated tags once
+        task.raw = (!rewrite).then(|| s.to_string());
         Ok(task)
     }
 }
 
-fn process_prefix(prefix: &mut Vec<&str>, task: &mut Task) -> Result<(), String> {
-    let mut iter = prefix.iter();
-    let mut completion_date: Option<Date> = None;
-    let mut creation_date: Option<Date> = None;
-    let mut priority: Option<Priority> = None;
-    let mut is_done = false;
+impl Display for TaskParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskParseError::EmptyInput => write!(f, "Empty String error"),
+            TaskParseError::MissingDescription => write!(f, "There must be a task description!"),
+            TaskParseError::InvalidPrefix { token, reason } => write!(f, "'{token}' {reason}"),
+            TaskParseError::InvalidTag { token, source } => write!(f, "Tag '{token}': {source}"),
+            TaskParseError::UnindentedDetail(line) => {
+                write!(f, "Detail line must be indented: '{line}'")
+            }
+        }
+    }
+}
 
-    while let Some(val) = iter.next() {
-        if (val == &"x") & !is_done {
-            is_done = true;
-        } else if Priority::from_str(val).is_ok() {
-            priority = Priority::from_str(val).ok();
-        } else if Date::from_str(val).is_ok() & creation_date.is_some() & completion_date.is_none()
-        {
-            // one date was parsed, now we have a second date parsing
-            // the first must have been the completion date
-            completion_date = creation_date;
-            creation_date = Date::from_str(val).ok();
-        } else if Date::from_str(val).is_ok() & completion_date.is_none() {
-            creation_date = Date::from_str(val).ok();
+impl std::error::Error for TaskParseError {}
+
+/// Lets callers that report errors as text keep using `?`
+impl From<TaskParseError> for String {
+    fn from(error: TaskParseError) -> Self {
+        error.to_string()
+    }
+}
+
+#[allow(deprecated)]
+impl Display for TaskWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskWarning::CompletionWithoutMarker(completed) => {
+                write!(f, "Completion date {completed} without the x of a done task")
+            }
+            TaskWarning::CompletedWithoutDate => write!(f, "Done without a completion date"),
+            TaskWarning::CompletedBeforeCreated(completed, created) => {
+                write!(f, "Completed on {completed} before it was created on {created}")
+            }
+            TaskWarning::DuplicateTag(tag) => write!(f, "Tag {tag} is there more than once"),
+            TaskWarning::ZeroEstimate => write!(f, "Estimated to take no time at all"),
+        }
+    }
+}
+
+impl Display for ChecklistItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mark = if self.done { 'x' } else { ' ' };
+        write!(f, "- [{mark}] {}", self.text)
+    }
+}
+
+impl FromStr for ChecklistItem {
+    type Err = String;
+
+    /// Parse a detail line like `- [ ] book room`, nested ones included
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s.trim_start();
+        let (done, text) = if let Some(text) = line.strip_prefix("- [ ] ") {
+            (false, text)
+        } else if let Some(text) = ["- [x] ", "- [X] "].iter().find_map(|p| line.strip_prefix(p)) {
+            (true, text)
         } else {
-            return Err(format!("Error parsing prefix '{}'", val));
+            return Err(format!("'{s}' is no checklist item"));
+        };
+        match text.trim() {
+            "" => Err(format!("'{s}' is a checklist item without text")),
+            text => Ok(ChecklistItem { done, text: text.to_string() }),
         }
     }
+}
 
-    task.is_completed = is_done;
-    task.priority_level = priority;
-    task.completion_date = completion_date;
-    task.creation_date = creation_date;
+/// "1 day" or "N days"
+fn count_days(days: i64) -> String {
+    if days == 1 {
+        "1 day".to_string()
+    } else {
+        format!("{days} days")
+    }
+}
 
-    Ok(())
+/// Read the prefix of a task `line` into `task`, in the todo.txt order:
+/// `x`, a priority, then the completion date (completed tasks only) and the
+/// creation date; a completed task with one date has its creation date
+///
+/// The description starts at the first word out of this order. Returns the
+/// rest of the line from there.
+fn take_prefix<'a>(line: &'a str, task: &mut Task) -> Result<&'a str, TaskParseError> {
+    let mut remaining = line;
+    let (mut word, mut rest) = split_word(remaining);
+    task.is_completed = word == "x";
+    if task.is_completed {
+        remaining = rest;
+        (word, rest) = split_word(remaining);
+    }
+    task.priority_level = Priority::from_str(word).ok();
+    if task.priority_level.is_some() {
+        remaining = rest;
+        (word, rest) = split_word(remaining);
+    }
+    let slots = if task.is_completed { 2 } else { 1 };
+    let mut dates = Vec::new();
+    while dates.len() < slots && looks_like_date(word) {
+        let date = Date::from_str(word).map_err(|_| TaskParseError::InvalidPrefix {
+            token: word.to_string(),
+            reason: "is no date, but stands where a date goes".to_string(),
+        })?;
+        dates.push(date);
+        remaining = rest;
+        (word, rest) = split_word(remaining);
+    }
+    task.creation_date = dates.pop();
+    task.completion_date = dates.pop();
+    Ok(remaining)
 }
 
 #[cfg(test)]
@@ -198,9 +1377,9 @@ mod tests {
             // it cannot react to bad formatted tag if it is the first entry
             // here it will think it is part of the description
             "x 2025-11-12 2022-11-12 Task description rec:+24".to_string(),
-            // it can also not recognize badly formatted date
-            // because it will simply use it as part of the text
-            "x 2025-44-44 2022-11-12 Task description rec:+24".to_string(),
+            // a description starting with what reads as a prefix is quoted
+            "\"2025-01-01\" party planning".to_string(),
+            "(B) 2025-03-01 \"x\" marks the spot".to_string(),
         ];
 
         for val in expected {
@@ -211,21 +1390,814 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dates_only_in_the_prefix_positions() {
+        // A date out of place starts the description
+        let task = Task::from_str("2025-01-01 2025-02-02 party planning").unwrap();
+        assert_eq!(task.creation_date(), &Some(Date::from_str("2025-01-01").unwrap()));
+        assert_eq!(task.completion_date(), &None);
+        assert_eq!(task.description(), "2025-02-02 party planning");
+        assert_eq!(task.canonical_line(), "2025-01-01 \"2025-02-02\" party planning");
+        let task = Task::from_str("2025-01-01 (A) x party").unwrap();
+        assert_eq!(task.priority_level(), &None);
+        assert_eq!(task.description(), "(A) x party");
+        // Where a date belongs, it has to be one
+        let error = Task::from_str("x 2025-44-44 2022-11-12 Task description").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "'2025-44-44' is no date, but stands where a date goes"
+        );
+    }
+
+    #[test]
+    fn descriptions_starting_with_a_date_are_kept() {
+        for description in ["2025-01-01 party planning", "x", "\"(A)\" is a quote", "(C) -"] {
+            let mut task = Task::with_task(description.to_string());
+            task.creation_date = None;
+            let line = task.to_string();
+            let read = Task::from_str(&line).unwrap();
+            assert_eq!(read.description(), description, "{line}");
+            assert_eq!(read.creation_date(), &None, "{line}");
+            assert_eq!(read.priority_level(), &None, "{line}");
+        }
+        let task = Task::from_str("\"2025-01-01\" party planning +home").unwrap();
+        assert_eq!(task.description(), "2025-01-01 party planning");
+        assert_eq!(task.creation_date(), &None);
+        // Other quotes are part of the description
+        let task = Task::from_str("\"Dune\" reread").unwrap();
+        assert_eq!(task.description(), "\"Dune\" reread");
+        assert_eq!(task.to_string(), "\"Dune\" reread");
+    }
+
+    #[test]
+    fn tags_anywhere_in_the_line() {
+        let tags = |task: &Task| task.tags().as_ref().map(|tags| tags.to_string());
+        let task = Task::from_str("Fix +webdev login bug @work").unwrap();
+        assert_eq!(task.description(), "Fix login bug");
+        assert_eq!(tags(&task).as_deref(), Some("+webdev @work"));
+        assert_eq!(task.canonical_line(), "Fix login bug +webdev @work");
+        // Right after the prefix and in the middle
+        let task = Task::from_str("(A) 2025-03-01 @phone Call p:\"John Smith\" back").unwrap();
+        assert_eq!(task.description(), "Call back");
+        assert_eq!(tags(&task).as_deref(), Some("@phone p:\"John Smith\""));
+        assert_eq!(task.canonical_line(), "(A) 2025-03-01 Call back @phone p:\"John Smith\"");
+        // Before the first tag, a broken one is text
+        let task = Task::from_str("Meet at größe:m @office").unwrap();
+        assert_eq!(task.description(), "Meet at größe:m");
+        assert!(Task::from_str("Meet @office at größe:m").is_err());
+        // After it as well if it does not even look like one
+        let task = Task::from_str("Meet @office at 10:30").unwrap();
+        assert_eq!(task.description(), "Meet at 10:30");
+        let task = Task::from_str("Meet @home at 10:30 sharp").unwrap();
+        assert_eq!(task.description(), "Meet at 10:30 sharp");
+        // Links and custom tags in the middle are text, custom ones at the end tags
+        let task = Task::from_str("Check https://Example.com/Foo before lunch @work").unwrap();
+        assert_eq!(task.description(), "Check https://Example.com/Foo before lunch");
+        assert_eq!(tags(&task).as_deref(), Some("@work"));
+        let task = Task::from_str("Mail mailto:ann@example.com today topic:budget").unwrap();
+        assert_eq!(task.description(), "Mail mailto:ann@example.com today");
+        assert_eq!(tags(&task).as_deref(), Some("topic:budget"));
+        let task = Task::from_str("Read https://example.org @home").unwrap();
+        assert_eq!(task.description(), "Read https://example.org");
+        // A lone sigil is text, at the start, in the middle and at the end
+        let lines = ["+ eggs", "Buy milk + eggs", "Meet @ noon", "Fix A + B @work", "Wow !"];
+        for line in lines {
+            let task = Task::from_str(line).unwrap();
+            let kept = line.strip_suffix(" @work").unwrap_or(line);
+            assert_eq!(task.description(), kept, "{line}");
+            assert_eq!(Task::from_str(&task.canonical_line()).unwrap(), task, "{line}");
+        }
+        let task = Task::from_str("! Call back p: later").unwrap();
+        assert_eq!(task.description(), "! Call back p: later");
+        assert_eq!(task.tags(), &None);
+        // A quoted first word is still text
+        let task = Task::from_str("+home \"2025-01-01\" party").unwrap();
+        assert_eq!(task.description(), "2025-01-01 party");
+        assert_eq!(Task::from_str(&task.canonical_line()).unwrap(), task);
+    }
+
+    #[test]
+    fn ids_are_given_once() {
+        let line = "Renew passport id:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 +travel";
+        let mut task = Task::from_str(line).unwrap();
+        let id = task.id().cloned().unwrap();
+        assert_eq!(id.to_string(), "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8");
+        assert_eq!(task.ensure_id(), id);
+        assert!(!task.is_dirty());
+        assert!(Task::from_str("Renew passport +travel id:a1a2").is_err());
+
+        let mut task = Task::from_str("Water plants rec:1w").unwrap();
+        assert_eq!(task.id(), None);
+        let id = task.ensure_id();
+        assert_eq!(task.ensure_id(), id);
+        assert_eq!(task.to_string(), format!("Water plants rec:1w id:{id}"));
+        // The next occurrence is a task of its own
+        let next = task.next_occurrence(&Date::from_str("2025-03-01").unwrap()).unwrap();
+        assert_eq!(next.id(), None);
+    }
+
+    #[test]
+    fn quoted_tag_values_end_the_line() {
+        let task = Task::from_str("Prepare the agenda +work p:\"John Smith\"").unwrap();
+        assert_eq!(task.description(), "Prepare the agenda");
+        let person = Tag::Person("John Smith".to_string());
+        assert!(task.tags().as_ref().unwrap().iter().any(|tag| *tag == person));
+        assert_eq!(task.canonical_line(), "Prepare the agenda +work p:\"John Smith\"");
+        let task = Task::from_str("Book a room topic:\"quarterly review\" @office").unwrap();
+        assert_eq!(task.tags().as_ref().unwrap().custom_value("topic"), Some("quarterly review"));
+        // A quote in the description is text, tags after it are still found
+        let task = Task::from_str("Buy a 6\" pipe +home").unwrap();
+        assert_eq!(task.description(), "Buy a 6\" pipe");
+        // An unclosed value is no tag, nor text among the tags
+        assert!(Task::from_str("Call +work p:\"John Smith").is_err());
+    }
+
+    #[test]
+    fn untouched_tasks_keep_their_line() {
+        let line = "(A)  Call  mom due:2025-03-01 +family\n  ask about Sunday";
+        let mut task = Task::from_str(line).unwrap();
+        assert!(!task.is_dirty());
+        assert_eq!(task.to_string(), line);
+        // Equal to the task read from its canonical line
+        let canonical = "(A) Call mom due:2025-03-01 +family\n  ask about Sunday";
+        assert_eq!(task, Task::from_str(canonical).unwrap());
+        // Any change writes the canonical line
+        task.set_priority(Some(Priority::A));
+        assert!(task.is_dirty());
+        assert_eq!(task.to_string(), canonical);
+        let mut task = Task::from_str("Pay rent  +flat").unwrap();
+        assert!(!task.remove_tag(&Tag::from_str("+home").unwrap()));
+        assert!(!task.is_dirty());
+        assert!(task.complete(&Date::from_str("2025-03-01").unwrap()).is_none());
+        assert_eq!(task.to_string(), "x 2025-03-01 Pay rent +flat");
+        assert!(Task::with_today("Pay  rent").unwrap().is_dirty());
+    }
+
+    #[test]
+    fn with_today_refuses_lines_without_description() {
+        let task = Task::with_today("(A) Call mom @phone").unwrap();
+        assert_eq!(task.creation_date(), &Some(Date::now()));
+        for line in ["@phone +home", "   ", "(A)", "x (B) 2025-03-01"] {
+            assert!(Task::with_today(line).is_err(), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn roundtrip_details() {
+        let val = "(B) Renew the VPN access @it\n  login: jdoe\n  x not done yet\n    - nested";
+        let task = Task::from_str(val).unwrap();
+        assert_eq!(task.description(), "Renew the VPN access");
+        assert!(!task.is_completed());
+        assert_eq!(task.details(), ["login: jdoe", "x not done yet", "  - nested"]);
+        assert_eq!(task.to_string(), val);
+        assert!(Task::from_str("Task\nnot indented").is_err());
+    }
+
+    #[test]
+    fn checklist_items() {
+        let task = Task::from_str("Plan the offsite\n  budget: 2k").unwrap();
+        assert!(task.checklist().is_empty());
+
+        let val = "Plan the offsite +team\n  - [ ] book room\n  - [x] send invite\n  \
+                   agenda follows\n    - [X] ask Ann\n  - [ ]";
+        let task = Task::from_str(val).unwrap();
+        let item = |done: bool, text: &str| ChecklistItem { done, text: text.to_string() };
+        assert_eq!(
+            task.checklist(),
+            [item(false, "book room"), item(true, "send invite"), item(true, "ask Ann")]
+        );
+        assert_eq!(task.to_string(), val);
+        assert_eq!(item(false, "book room").to_string(), "- [ ] book room");
+        assert_eq!(ChecklistItem::from_str("- [x] send invite"), Ok(item(true, "send invite")));
+    }
+
+    #[test]
+    fn duplicates_for_today_are_copies() {
+        let line = "x (B) 2025-03-02 2025-03-01 Water plants +home s:done \
+                    id:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8\n  the ones upstairs";
+        let original = Task::from_str(line).unwrap();
+        let mut copy = original.duplicated_for_today();
+        assert!(!copy.is_completed());
+        assert_eq!(copy.completion_date(), &None);
+        assert_eq!(copy.creation_date(), &Some(Date::now()));
+        assert_eq!(copy.to_string(), format!("(B) {} Water plants +home", Date::now()));
+
+        // Changing the copy leaves the original alone
+        copy.add_tag(Tag::from_str("@garden").unwrap());
+        copy.remove_tag(&Tag::from_str("+home").unwrap());
+        assert_eq!(original.to_string(), line);
+    }
+
+    #[test]
+    fn suspicious_tasks_are_warned_about() {
+        let warnings = |line: &str| Task::from_str(line).unwrap().validate();
+        let date = |date: &str| Date::from_str(date).unwrap();
+        assert!(warnings("x 2025-03-02 2025-03-01 Water plants +home est:5min").is_empty());
+        assert!(warnings("Water plants").is_empty());
+
+        // Only tasks put together in code or deserialized get this far
+        let mut task = Task::from_str("(A) 2025-03-01 Water plants").unwrap();
+        task.completion_date = Some(date("2025-03-02"));
+        assert_eq!(task.validate(), [TaskWarning::CompletionWithoutMarker(date("2025-03-02"))]);
+        assert_eq!(warnings("x Water plants"), [TaskWarning::CompletedWithoutDate]);
+        assert_eq!(
+            warnings("x 2025-03-01 2025-03-02 Water plants"),
+            [TaskWarning::CompletedBeforeCreated(date("2025-03-01"), date("2025-03-02"))]
+        );
+        let zero = warnings("Water plants est:0min");
+        assert_eq!(zero, [TaskWarning::ZeroEstimate]);
+        assert_eq!(zero[0].to_string(), "Estimated to take no time at all");
+    }
+
+    #[test]
+    fn repeated_tags_are_written_once() {
+        let task = Task::from_str("Fix the gate @work @work +house +house").unwrap();
+        assert_eq!(task.to_string(), "Fix the gate @work +house");
+        assert_eq!(task.tags().as_ref().unwrap().len(), 2);
+        // Without repeats the line is kept as typed
+        let task = Task::from_str("Fix the gate  @work +house").unwrap();
+        assert_eq!(task.to_string(), "Fix the gate  @work +house");
+    }
+
+    #[test]
+    fn contexts_and_projects() {
+        let task = Task::from_str("Fix the gate +House @home +garden @Phone").unwrap();
+        assert_eq!(task.contexts(), ["home", "Phone"]);
+        assert_eq!(task.projects(), ["House", "garden"]);
+        assert!(task.has_project("+house") && task.has_project("GARDEN"));
+        assert!(task.has_context("@phone") && task.has_context("home"));
+        assert!(!task.has_project("home") && !task.has_context("+house"));
+
+        let task = Task::from_str("Fix the gate").unwrap();
+        assert!(task.contexts().is_empty() && task.projects().is_empty());
+        assert!(!task.has_project("house"));
+    }
+
+    #[test]
+    fn completion_toggles_with_its_date() {
+        let day = |date: &str| Date::from_str(date).unwrap();
+        let task = Task::completed_with("Pay rent", day("2025-03-02"), day("2025-03-01")).unwrap();
+        assert_eq!(task.to_string(), "x 2025-03-02 2025-03-01 Pay rent");
+        assert!(Task::completed_with("Pay rent", day("2025-03-01"), day("2025-03-02")).is_err());
+        assert!(Task::completed_with(" ", day("2025-03-02"), day("2025-03-01")).is_err());
+
+        let mut task = Task::from_str("(A) 2025-03-01 Pay rent +flat").unwrap();
+        task.toggle_completion(day("2025-03-04"));
+        assert!(task.is_completed());
+        assert_eq!(task.completion_date(), &Some(day("2025-03-04")));
+        assert_eq!(task.to_string(), "x (A) 2025-03-04 2025-03-01 Pay rent +flat");
+        assert_eq!(Task::from_str(&task.to_string()).unwrap(), task);
+        task.toggle_completion(day("2025-03-05"));
+        assert_eq!((task.is_completed(), task.completion_date()), (false, &None));
+        assert_eq!(task.to_string(), "(A) 2025-03-01 Pay rent +flat");
+        assert_eq!(Task::from_str(&task.to_string()).unwrap(), task);
+    }
+
+    #[test]
+    fn tasks_sort_by_their_key() {
+        let lines = [
+            "x (A) 2025-03-04 2025-03-01 Pay rent",
+            "Water plants",
+            "(B) 2025-03-02 Call Ann",
+            "(A) Book flights",
+            "(B) 2025-03-01 Call Bob",
+            "(A) 2025-03-05 Book hotel",
+            "x 2025-03-03 Pay taxes",
+            "Answer mails",
+            "(B) 2025-03-01 Call Ann",
+        ];
+        let mut tasks: Vec<Task> = lines.iter().map(|line| Task::from_str(line).unwrap()).collect();
+        tasks.sort();
+        let sorted: Vec<String> = tasks.iter().map(Task::to_string).collect();
+        assert_eq!(
+            sorted,
+            [
+                "(A) 2025-03-05 Book hotel",
+                "(A) Book flights",
+                "(B) 2025-03-01 Call Ann",
+                "(B) 2025-03-01 Call Bob",
+                "(B) 2025-03-02 Call Ann",
+                "Answer mails",
+                "Water plants",
+                "x (A) 2025-03-04 2025-03-01 Pay rent",
+                "x 2025-03-03 Pay taxes",
+            ]
+        );
+        let task = Task::from_str("(B) 2025-03-01 Call Ann +family").unwrap();
+        assert_ne!(task.cmp(&tasks[2]), Ordering::Equal);
+        assert_eq!(task.cmp(&task.clone()), Ordering::Equal);
+        assert_eq!(tasks[0].cmp_by(&tasks[1], TaskSort::Created), Ordering::Less);
+        assert_eq!(tasks[0].cmp_by(&tasks[1], TaskSort::Document), Ordering::Equal);
+    }
+
+    #[test]
+    fn tags_embedded_in_the_description() {
+        // Quoted, a tag is text
+        let mut task = Task::from_str("\"+home\" party").unwrap();
+        assert_eq!(task.description_without_tags(), "\"+home\" party");
+        assert!(task.embedded_tags().is_empty());
+
+        task.set_description("Mail bob@example.com about +1 at 10:30 @office".to_string());
+        assert_eq!(task.description_without_tags(), "Mail bob@example.com about at 10:30");
+        let embedded: Vec<String> = task.embedded_tags().iter().map(Tag::to_string).collect();
+        // Words starting with `+` are projects, even numbers like `+1`
+        assert_eq!(embedded, ["+1", "@office"]);
+        // Links and custom tags in a sentence are text, as in a task line
+        task.set_description("Read mailto:bob@example.com https://example.org".to_string());
+        assert_eq!(
+            task.description_without_tags(),
+            "Read mailto:bob@example.com https://example.org"
+        );
+        assert!(task.embedded_tags().is_empty());
+        task.set_description("Read the report topic:budget".to_string());
+        assert_eq!(task.description_without_tags(), "Read the report");
+        assert!(Task::from_str("Water plants").unwrap().embedded_tags().is_empty());
+    }
+
+    #[test]
+    fn age() {
+        let today = Date::from_str("2024-03-31").unwrap();
+        let task = Task::from_str("2024-03-01 Water the plants").unwrap();
+        assert_eq!(task.age_in_days(&today), Some(30));
+        assert_eq!(Task::from_str("Water the plants").unwrap().age_in_days(&today), None);
+    }
+
+    #[test]
+    fn overdue() {
+        let today = Date::from_str("2024-03-31").unwrap();
+        let overdue = |line: &str| Task::from_str(line).unwrap().is_overdue(&today);
+        assert!(overdue("File taxes due:2024-03-30"));
+        assert!(!overdue("File taxes due:2024-03-31"));
+        assert!(!overdue("File taxes due:2024-04-01"));
+        assert!(!overdue("x 2024-03-20 File taxes due:2024-03-30"));
+    }
+
+    #[test]
+    fn thresholds_do_not_make_tasks_overdue() {
+        let today = Date::from_str("2024-03-31").unwrap();
+        let task = Task::from_str("File taxes t:2024-03-01").unwrap();
+        assert!(task.is_actionable(&today));
+        assert!(!task.is_overdue(&today));
+        let task = Task::from_str("File taxes t:2024-03-01 due:2024-04-15").unwrap();
+        assert!(!task.is_overdue(&today));
+        let task = Task::from_str("File taxes t:2024-04-01 due:2024-03-30").unwrap();
+        assert!(task.is_overdue(&today));
+    }
+
+    #[test]
+    fn custom_tags_are_replaced() {
+        let mut task = Task::from_str("Pay rent +flat bank:giro").unwrap();
+        task.set_custom_tag("bank", "cash").unwrap();
+        assert_eq!(task.to_string(), "Pay rent +flat bank:cash");
+        let mut task = Task::from_str("Pay rent").unwrap();
+        task.set_custom_tag("bank", "cash").unwrap();
+        assert_eq!(task.to_string(), "Pay rent bank:cash");
+        assert!(task.set_custom_tag("t", "2024-04-01").is_err());
+        assert!(task.set_custom_tag("due", "2024-04-01").is_err());
+        assert!(task.set_custom_tag("Bank", "cash").is_err());
+    }
+
+    #[test]
+    fn thresholds_decide_what_is_actionable() {
+        let today = Date::from_str("2025-03-10").unwrap();
+        let actionable = |line: &str| Task::from_str(line).unwrap().is_actionable(&today);
+        assert!(actionable("Water the plants"));
+        assert!(actionable("Water the plants t:2025-03-10"));
+        assert!(actionable("Water the plants t:2025-03-01"));
+        assert!(!actionable("Water the plants t:2025-03-11"));
+        assert!(!actionable("x Water the plants"));
+        assert!(!actionable("x 2025-03-09 Water the plants t:2025-04-01"));
+
+        let task = Task::from_str("File taxes t:2025-04-01 +admin").unwrap();
+        assert_eq!(task.threshold_date(), Some(&Date::from_str("2025-04-01").unwrap()));
+        assert_eq!(Task::from_str("File taxes").unwrap().threshold_date(), None);
+    }
+
+    #[test]
+    fn due_dates_are_tags_of_their_own() {
+        let mut task = Task::from_str("call dentist due:2025-07-01 @phone").unwrap();
+        assert_eq!(task.due_date(), Some(&Date::from_str("2025-07-01").unwrap()));
+        assert_eq!(task.to_string(), "call dentist due:2025-07-01 @phone");
+        assert!(task.tags().as_ref().unwrap().custom_tags().is_empty());
+
+        task.add_tag(Tag::Due(Date::from_str("2025-07-08").unwrap()));
+        assert_eq!(task.to_string(), "call dentist due:2025-07-08 @phone");
+        assert_eq!(Task::from_str("call dentist").unwrap().due_date(), None);
+        // Like thresholds, due dates are dates or part of the description
+        let task = Task::from_str("call dentist due:soon").unwrap();
+        assert_eq!((task.description(), task.due_date()), ("call dentist due:soon", None));
+
+        // Relative dates are written as the day they stand for
+        let task = Task::from_str("call dentist due:tomorrow t:today").unwrap();
+        let today = Date::now();
+        assert_eq!(task.due_date(), Some(&today.add_days(1)));
+        assert!(task.is_dirty());
+        let line = format!("call dentist due:{} t:{today}", today.add_days(1));
+        assert_eq!(task.to_string(), line);
+        assert!(!Task::from_str(&line).unwrap().is_dirty());
+    }
+
+    #[test]
+    fn tags_are_replaced_as_a_whole() {
+        let line = "(A) 2024-03-01 Pay rent +flat due:2024-03-01\n  by transfer";
+        let mut task = Task::from_str(line).unwrap();
+        task.set_tags(TagCollection::from_str("@bank p:landlord").unwrap());
+        assert_eq!(task.to_string(), "(A) 2024-03-01 Pay rent @bank p:landlord\n  by transfer");
+        task.set_tags(TagCollection::new());
+        assert_eq!(*task.tags(), None);
+        assert_eq!(task.to_string(), "(A) 2024-03-01 Pay rent\n  by transfer");
+    }
+
+    #[test]
+    fn setters_keep_the_line_readable() {
+        let tag = |s: &str| Tag::from_str(s).unwrap();
+        let mut task = Task::from_str("(B) 2025-03-01 Call the plumber").unwrap();
+        task.set_description("Call the roofer".to_string());
+        task.set_priority(Some(Priority::A));
+        task.add_tag(tag("@phone"));
+        task.add_tag(tag("est:15min"));
+        task.add_tag(tag("est:30min"));
+        assert_eq!(task.to_string(), "(A) 2025-03-01 Call the roofer @phone est:30min");
+        assert_eq!(Task::from_str(&task.to_string()).unwrap(), task);
+
+        assert!(task.remove_tag(&tag("@phone")));
+        assert!(!task.remove_tag(&tag("@phone")));
+        assert!(task.remove_tag(&tag("est:30min")));
+        task.set_priority(None);
+        assert_eq!(*task.tags(), None);
+        assert_eq!(task.to_string(), "2025-03-01 Call the roofer");
+        assert_eq!(Task::from_str(&task.to_string()).unwrap(), task);
+        assert!(!task.remove_tag(&tag("+house")));
+    }
+
+    #[test]
+    fn built_tasks_read_back_the_same() {
+        let date = |s: &str| Date::from_str(s).unwrap();
+        let tag = |s: &str| Tag::from_str(s).unwrap();
+        let built = [
+            TaskBuilder::new().description("Buy milk").build(),
+            TaskBuilder::new()
+                .description("Renew the passport")
+                .priority(Priority::B)
+                .creation_date(date("2025-03-01"))
+                .completed_on(date("2025-03-04"))
+                .tag(tag("+admin"))
+                .tag(tag("due:2025-03-31"))
+                .detail("bring two photos")
+                .build(),
+            TaskBuilder::new().description("Water the plants").completed().build(),
+        ];
+        let lines: Vec<String> =
+            built.iter().map(|task| task.as_ref().unwrap().to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Buy milk",
+                "x (B) 2025-03-04 2025-03-01 Renew the passport +admin due:2025-03-31\n  \
+                 bring two photos",
+                "x Water the plants",
+            ]
+        );
+        for (task, line) in built.into_iter().zip(&lines) {
+            assert_eq!(task.unwrap(), Task::from_str(line).unwrap());
+        }
+    }
+
+    #[test]
+    fn builder_refuses_what_would_not_read_back() {
+        let date = |s: &str| Date::from_str(s).unwrap();
+        let error = |builder: TaskBuilder| builder.build().unwrap_err();
+        let blank = TaskBuilder::new().description("  ");
+        assert_eq!(error(blank), "There must be a task description!");
+        let lone = TaskBuilder::new().description("Pay rent").completed_on(date("2025-03-04"));
+        assert_eq!(error(lone), "A completion date needs a creation date as well");
+        let early = TaskBuilder::new()
+            .description("Pay rent")
+            .creation_date(date("2025-03-04"))
+            .completed_on(date("2025-03-01"));
+        assert_eq!(error(early), "Completed on 2025-03-01 before it was created on 2025-03-04");
+        for description in ["Call @home", "Two\nlines"] {
+            assert!(TaskBuilder::new().description(description).build().is_err(), "{description}");
+        }
+        // A first word read as a prefix is quoted instead
+        for description in ["x marks the spot", "2025-03-01 review"] {
+            let task = TaskBuilder::new().description(description).build().unwrap();
+            assert_eq!(task.description(), description);
+        }
+    }
+
+    #[test]
+    fn dates_are_checked_and_summed_up() {
+        let today = Date::from_str("2025-04-11").unwrap();
+        let facts = |line: &str| {
+            let task = Task::from_str(line).unwrap();
+            (task.date_problem(), task.date_facts(&today))
+        };
+        assert_eq!(facts("2025-03-01 Plan the trip"), (None, Some("open for 41 days".into())));
+        assert_eq!(facts("2025-04-11 Plan the trip"), (None, Some("open since today".into())));
+        let done = facts("x 2025-03-04 2025-03-01 Plan the trip");
+        assert_eq!(done, (None, Some("done in 3 days".into())));
+        let done = facts("x 2025-03-02 2025-03-01 Plan the trip");
+        assert_eq!(done, (None, Some("done in 1 day".into())));
+        assert_eq!(facts("x 2025-03-01 Plan the trip"), (None, None));
+        assert_eq!(facts("Plan the trip"), (None, None));
+
+        let (problem, derived) = facts("x 2025-03-01 2025-03-04 Plan the trip");
+        let expected = "Completed on 2025-03-01 before it was created on 2025-03-04";
+        assert_eq!(problem.as_deref(), Some(expected));
+        assert_eq!(derived, None);
+        // An open task has one date, a second one is part of the description
+        let (problem, derived) = facts("2025-03-04 2025-03-01 Plan the trip");
+        assert_eq!(problem, None);
+        assert_eq!(derived.as_deref(), Some("open for 38 days"));
+    }
+
+    #[test]
+    fn loose_tasks_without_dates_wait_for_the_interval() {
+        let today = Date::from_str("2025-03-10").unwrap();
+        let mut task = Task::from_str("2025-02-01 Cut hair rec:6w").unwrap();
+        let next = task.complete(&today).unwrap();
+        let later = Date::from_str("2025-04-21").unwrap();
+        assert_eq!(*next.creation_date(), Some(later));
+        assert_eq!(next.threshold_date(), Some(&later));
+        assert!(!next.is_completed());
+        assert_eq!(next.to_string(), "2025-04-21 Cut hair rec:6w t:2025-04-21 done:2025-03-10");
+    }
+
+    #[test]
+    fn recurring_tasks_keep_their_history() {
+        let date = |s: &str| Date::from_str(s).unwrap();
+        let mut task = Task::from_str("2025-03-01 Water the plants rec:3d").unwrap();
+        let mut days = Vec::new();
+        for day in 1..=7 {
+            let today = date(&format!("2025-03-{:02}", day * 3));
+            let next = task.complete(&today).unwrap();
+            assert!(task.is_completed());
+            assert_eq!(*task.completion_date(), Some(today));
+            // Loose, so three days on from when it was done
+            assert_eq!(*next.creation_date(), Some(today.add_days(3)));
+            assert_eq!(next.threshold_date(), Some(&today.add_days(3)));
+            days.insert(0, today);
+            task = next;
+        }
+        // Capped to the last days, newest first
+        assert_eq!(task.done_history(), days[..DONE_HISTORY_LEN]);
+        assert_eq!(
+            task.to_string(),
+            "2025-03-24 Water the plants rec:3d t:2025-03-24 \
+             done:2025-03-21,2025-03-18,2025-03-15,2025-03-12,2025-03-09"
+        );
+        assert!(Task::from_str("Water the plants").unwrap().complete(&days[0]).is_none());
+    }
+
+    #[test]
+    fn next_occurrence_moves_its_dates() {
+        let today = Date::from_str("2025-03-10").unwrap();
+        let next = |line: &str| Task::from_str(line).unwrap().complete(&today).unwrap();
+        // Strict from the old dates, even when done late
+        assert_eq!(
+            next("Pay rent rec:+4w t:2025-03-01 due:2025-03-03").to_string(),
+            "Pay rent rec:+4w t:2025-03-29 due:2025-03-31 done:2025-03-10"
+        );
+        // Loose from today, the threshold as far ahead of the due date
+        assert_eq!(
+            next("Cut hair rec:6w t:2025-03-01 due:2025-03-03").to_string(),
+            "Cut hair rec:6w t:2025-04-19 due:2025-04-21 done:2025-03-10"
+        );
+        // Without dates a loose one gets a threshold, not to be done again right away
+        assert_eq!(
+            next("Cut hair rec:6w").to_string(),
+            "Cut hair rec:6w t:2025-04-21 done:2025-03-10"
+        );
+        // The creation date moves from the old one for strict, from today for loose
+        assert_eq!(
+            next("2025-03-01 Pay rent rec:+4w").to_string(),
+            "2025-03-29 Pay rent rec:+4w done:2025-03-10"
+        );
+        assert_eq!(
+            next("2025-03-01 Cut hair rec:6w due:2025-03-05").to_string(),
+            "2025-04-21 Cut hair rec:6w due:2025-04-21 done:2025-03-10"
+        );
+    }
+
+    #[test]
+    fn recurrences_by_day_week_and_year() {
+        let done = Date::from_str("2025-03-10").unwrap();
+        let cases = [
+            (
+                "x 2025-03-10 2025-03-01 Water plants rec:3d",
+                "2025-03-13 Water plants rec:3d t:2025-03-13",
+            ),
+            ("2025-03-01 Sync rec:+1w t:2025-03-03", "2025-03-08 Sync rec:+1w t:2025-03-10"),
+            ("Clean windows rec:2w t:2025-02-01", "Clean windows rec:2w t:2025-03-24"),
+            // Years keep the day of the month, a leap day becomes the 28th
+            ("2024-02-29 Renew passport rec:+1y", "2025-02-28 Renew passport rec:+1y"),
+            ("Check smoke alarm rec:1y due:2025-01-15", "Check smoke alarm rec:1y due:2026-03-10"),
+        ];
+        for (line, expected) in cases {
+            let task = Task::from_str(line).unwrap();
+            let next = task.next_occurrence(&done).unwrap();
+            assert!(!next.is_completed(), "{line}");
+            assert_eq!(next.to_string(), format!("{expected} done:2025-03-10"), "{line}");
+            assert_eq!(Task::from_str(&next.to_string()).unwrap(), next, "{line}");
+            // Asking does not touch the task itself
+            assert_eq!(task.to_string(), line);
+        }
+        assert_eq!(Task::from_str("Water the plants").unwrap().next_occurrence(&done), None);
+    }
+
+    #[test]
+    fn urgency_orders_by_priority_dates_and_age() {
+        let today = Date::from_str("2025-03-10").unwrap();
+        let urgency = |line: &str| Task::from_str(line).unwrap().urgency(&today);
+        // Most urgent first
+        let ordered = [
+            "(A) Pay the fine due:2025-03-01",
+            "Renew the permit due:2025-03-10",
+            "(A) File taxes due:2025-04-30",
+            "(A) Call the bank",
+            "(B) 2024-03-10 Sort the receipts",
+            "(B) Tidy the shed",
+            // Held back, but not below tasks of lower priority
+            "(A) Plan the holiday t:2025-06-01",
+            "2025-01-01 Read the manual t:2025-02-01",
+            "Read the other manual",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(urgency(pair[0]) > urgency(pair[1]), "{pair:?}");
+        }
+        assert_eq!(urgency("Read the other manual"), 0.0);
+        assert_eq!(urgency("x 2025-03-09 (A) Send the forms due:2025-03-01"), 0.0);
+        // Overdue by more than a week is as urgent as it gets
+        assert_eq!(urgency("Pay due:2025-02-01"), urgency("Pay due:2025-03-03"));
+    }
+
+    #[test]
+    fn completing_and_reopening() {
+        let monday = Date::from_str("2025-03-10").unwrap();
+        let friday = Date::from_str("2025-03-14").unwrap();
+        let mut task = Task::from_str("2025-03-01 Pay rent rec:+4w").unwrap();
+        let next = task.complete(&monday).unwrap();
+        assert!(!next.is_completed());
+        assert_eq!(*next.completion_date(), None);
+        // Done twice is done once, without another occurrence
+        assert_eq!(task.complete(&friday), None);
+        assert_eq!(*task.completion_date(), Some(monday));
+
+        task.uncomplete();
+        assert!(!task.is_completed());
+        assert_eq!(task.to_string(), "2025-03-01 Pay rent rec:+4w");
+        assert!(task.complete(&friday).is_some());
+        assert_eq!(task.to_string(), "x 2025-03-14 2025-03-01 Pay rent rec:+4w");
+
+        // A stray completion date is not passed on to the next occurrence
+        let mut task = Task::from_str("2025-03-05 2025-03-01 Cut hair rec:6w").unwrap();
+        let next = task.complete(&friday).unwrap();
+        assert_eq!(*next.completion_date(), None);
+        assert_eq!(next.date_problem(), None);
+    }
+
+    #[test]
+    fn waiting_on_someone() {
+        let monday = Date::from_str("2024-03-04").unwrap();
+        let friday = Date::from_str("2024-03-08").unwrap();
+        let mut task = Task::from_str("Get the signed contract s:next +flat").unwrap();
+        assert_eq!(task.state(), Some(&TaskState::Next));
+        assert!(!task.is_waiting());
+
+        task.wait_on("landlord", &monday).unwrap();
+        assert_eq!(
+            task.to_string(),
+            "Get the signed contract s:wait(landlord) +flat wait_since:2024-03-04"
+        );
+        assert!(task.is_waiting());
+        assert_eq!(task.waiting_for(), ["landlord"]);
+        assert_eq!(task.waiting_days(&friday), Some(4));
+        assert!(task.wait_on("the landlord", &friday).is_err());
+
+        // Waiting on someone else does not restart the clock
+        task.wait_on("agent", &friday).unwrap();
+        assert_eq!(task.waiting_since(), Some(monday));
+        task.follow_up(&monday);
+        task.follow_up(&friday);
+        assert_eq!(task.followed_up(), Some(friday));
+        assert_eq!(task.to_string().matches("followup:").count(), 1);
+
+        task.stop_waiting();
+        assert_eq!(task.state(), None);
+        assert_eq!(task.to_string(), "Get the signed contract +flat");
+    }
+
+    #[test]
+    fn estimates() {
+        let mut task = Task::from_str("Water plants @home").unwrap();
+        assert_eq!(task.estimate(), None);
+        task.set_estimate(15).unwrap();
+        assert_eq!(task.estimate(), Some(Duration::from_secs(15 * 60)));
+        task.set_estimate(20).unwrap();
+        assert_eq!(task.to_string(), "Water plants @home est:20min");
+        // Too many minutes for a `Duration` leave the estimate alone
+        assert!(task.set_estimate(u64::MAX).is_err());
+        assert!(task.set_estimate(u64::MAX / 60 + 1).is_err());
+        assert_eq!(task.to_string(), "Water plants @home est:20min");
+        task.set_estimate(u64::MAX / 60).unwrap();
+        assert_eq!(task.estimate(), Some(Duration::from_secs(u64::MAX / 60 * 60)));
+        assert!(Task::from_str(&format!("Water plants @home est:{}min", u64::MAX)).is_err());
+
+        let task = Task::from_str("Draft the budget est:90min +house").unwrap();
+        assert_eq!(task.estimate(), Some(Duration::from_secs(90 * 60)));
+        let twice = Task::from_str("Draft the budget est:90min +house est:30min");
+        let error = TaskParseError::InvalidTag {
+            token: "est:30min".to_string(),
+            source: "a task can only have one estimate".to_string(),
+        };
+        assert_eq!(twice, Err(error));
+    }
+
+    #[test]
+    fn priorities_go_up_and_down() {
+        let mut task = Task::from_str("2025-03-01 Renew passport +travel").unwrap();
+        let mut lines = Vec::new();
+        for _ in 0..4 {
+            task.bump_priority();
+            lines.push(task.to_string());
+        }
+        for _ in 0..4 {
+            task.lower_priority();
+            lines.push(task.to_string());
+        }
+        for line in &lines {
+            assert_eq!(Task::from_str(line).unwrap().to_string(), *line);
+        }
+        let priorities: Vec<&str> = lines.iter().map(|line| &line[..3]).collect();
+        assert_eq!(priorities, ["(C)", "(B)", "(A)", "(A)", "(B)", "(C)", "202", "202"]);
+        assert_eq!(task.to_string(), "2025-03-01 Renew passport +travel");
+    }
+
+    #[test]
+    fn states_replace_each_other() {
+        let mut task = Task::from_str("Quote for the roof s:wait(roofer) +house").unwrap();
+        let today = Date::from_str("2024-03-04").unwrap();
+        task.set_state(TaskState::Next, &today);
+        assert_eq!(task.state(), Some(&TaskState::Next));
+        assert_eq!(task.to_string(), "Quote for the roof s:next +house");
+        assert_eq!(Task::from_str(&task.to_string()).unwrap(), task);
+
+        // Completing upgrades the state, opening again drops it
+        task.complete(&today);
+        assert_eq!(task.to_string(), "x 2024-03-04 Quote for the roof s:done +house");
+        task.uncomplete();
+        assert_eq!(task.to_string(), "Quote for the roof +house");
+        let mut task = Task::from_str("Water plants").unwrap();
+        task.complete(&today);
+        assert_eq!(task.to_string(), "x 2024-03-04 Water plants");
+
+        let mut task = Task::from_str("Call the bank s:hold(holiday)").unwrap();
+        task.set_state(TaskState::Done, &today);
+        assert!(task.is_completed());
+        assert_eq!(task.completion_date(), &Some(today));
+        assert_eq!(task.state(), Some(&TaskState::Done));
+        assert_eq!(task.to_string().matches("s:").count(), 1);
+    }
+
+    #[test]
+    fn waiting_by_context_and_persons() {
+        let mut task = Task::from_str("Quote for the roof @waiting p:anna p:ben").unwrap();
+        assert!(task.is_waiting());
+        assert_eq!(task.waiting_since(), None);
+        assert_eq!(task.waiting_for(), ["anna", "ben"]);
+        let today = Date::from_str("2024-03-04").unwrap();
+        task.set_state(TaskState::Wait("roofer".into()), &today);
+        assert_eq!(task.waiting_for(), ["anna", "ben"]);
+        task.stop_waiting();
+        assert_eq!(task.to_string(), "Quote for the roof p:anna p:ben");
+        let mut task = Task::from_str("Call back @waiting").unwrap();
+        task.stop_waiting();
+        assert_eq!(task.to_string(), "Call back");
+    }
+
     #[test]
     fn roundtrip_bad() {
         let expected = [
             "".to_string(),
             "x (A) @phone".to_string(),
+            "+aid @phone p:pes".to_string(),
             "x 2025-11-12 2022-11-12 Task description p:pes @phone +aid rec:+24".to_string(),
             "x 2025-11-12 2022-11-12 Task description p:pes rec:+24w rec:23o".to_string(),
             "x 2025-11-12 2022-11-12 Task description p:pes rec:+24".to_string(),
-            "x x x 2022-11-12 Task description rec:+24".to_string(),
-            "x 2026-11-12 2027-11-12 2028-11-12 2029-11-12 Task description rec:+24".to_string(),
+            "x 2025-13-01 Task description".to_string(),
+            "(A) 2025-02-30 Task description".to_string(),
         ];
 
         for val in expected {
             let result = Task::from_str(&val);
             assert!(result.is_err(), "\n{}\n=>\n{:?}\n", val, result);
         }
+        // Words out of the prefix order start the description
+        let task = Task::from_str("x x x 2022-11-12 Task description").unwrap();
+        assert_eq!(task.description(), "x x 2022-11-12 Task description");
+        let task = Task::from_str("x 2026-11-12 2027-11-12 2028-11-12 2029-11-12 Task").unwrap();
+        assert_eq!(task.creation_date(), &Some(Date::from_str("2027-11-12").unwrap()));
+        assert_eq!(task.description(), "2028-11-12 2029-11-12 Task");
     }
 }
@@ -0,0 +1,131 @@
+//! Serde support for the parts of a task, written as they are in the
+//! document: a date as `2025-03-01`, a priority as `(A)`, a tag as `+garden`
+//! and a tag collection as the list of its tags
+
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use super::dates::Date;
+use super::priority::Priority;
+use super::tags::{Tag, TagCollection};
+
+/// Serialize and deserialize the types via `Display` and `FromStr`
+macro_rules! serde_as_text {
+    ($($ty:ty),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let text = String::deserialize(deserializer)?;
+                    <$ty>::from_str(&text).map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+serde_as_text!(Date, Priority, Tag);
+
+impl Serialize for TagCollection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for TagCollection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Tag>::deserialize(deserializer).map(TagCollection::from_tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+
+    fn roundtrip(task: &Task) -> Task {
+        let json = serde_json::to_string(task).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn tasks_survive_json() {
+        let lines = [
+            "Water the plants",
+            "(B) 2025-03-01 Call Bob @phone p:bob",
+            "x (A) 2025-03-10 2025-03-01 Pay rent +flat rec:+4w due:2025-03-03 room:hall",
+            "x 2025-03-10 Read n:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 !ticket-7 s:wait(bob)",
+            "Review the budget est:30min t:2025-03-05 rec:1y",
+        ];
+        for line in lines {
+            let mut task = Task::from_str(line).unwrap();
+            assert_eq!(task.to_string(), line);
+            assert_eq!(roundtrip(&task), task, "{line}");
+            task.set_details(vec!["login: jdoe".to_string()]);
+            assert_eq!(roundtrip(&task), task, "{line}");
+        }
+    }
+
+    #[test]
+    fn parts_are_written_as_text() {
+        let task = Task::from_str("x (A) 2025-03-10 2025-03-01 Pay rent +flat due:2025-03-03")
+            .unwrap();
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["priority_level"], "(A)");
+        assert_eq!(json["completion_date"], "2025-03-10");
+        assert_eq!(json["tags"], serde_json::json!(["+flat", "due:2025-03-03"]));
+
+        let error = serde_json::from_str::<Date>("\"2025-13-01\"").unwrap_err();
+        assert!(error.to_string().starts_with("Only '%Y-%m-%d' format allowed"), "{error}");
+        assert!(serde_json::from_str::<Tag>("\"due:soon\"").is_err());
+    }
+
+    #[test]
+    fn source_lines_are_kept() {
+        let mut task = Task::from_str("Call Bob @phone").unwrap();
+        let json = serde_json::to_value(&task).unwrap();
+        assert!(json.get("source_line").is_none(), "{json}");
+        assert_eq!(roundtrip(&task).source_line(), None);
+
+        task.set_source_line(7);
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["source_line"], 7);
+        assert_eq!(roundtrip(&task).source_line(), Some(7));
+    }
+
+    #[test]
+    fn tasks_are_checked_like_task_lines() {
+        let task = Task::from_str("x 2025-03-10 2025-03-01 Pay rent +flat").unwrap();
+        let json = serde_json::to_value(&task).unwrap();
+        let read = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            change(&mut json);
+            serde_json::from_value::<Task>(json)
+        };
+        assert_eq!(read(&|_| {}).unwrap(), task);
+
+        let error = read(&|json| json["description"] = "  ".into()).unwrap_err();
+        assert_eq!(error.to_string(), "There must be a task description!");
+        // A completion date needs the `x`
+        let error = read(&|json| json["is_completed"] = false.into()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "'2025-03-10 2025-03-01 Pay rent +flat' does not read back as the same task, \
+             its completion date, creation date, description would change"
+        );
+        // Text the parser would take apart is refused too
+        let error = read(&|json| json["description"] = "Pay rent @home".into()).unwrap_err();
+        assert!(error.to_string().ends_with("its description, tags would change"), "{error}");
+        assert!(read(&|json| json["details"] = serde_json::json!(["a\nb"])).is_err());
+        // Of two estimates the later one counts, as in a `TagCollection`
+        let two = read(&|json| json["tags"] = serde_json::json!(["est:30min", "est:60min"]));
+        assert_eq!(two.unwrap().to_string(), "x 2025-03-10 2025-03-01 Pay rent est:60min");
+    }
+}
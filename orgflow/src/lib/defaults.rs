@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use crate::{Tag, TagCollection, Task};
+
+/// File in the base folder with the default tags of each project
+pub const PROJECT_DEFAULTS: &str = "project-defaults";
+
+/// Tags that new tasks of a project get unless they say otherwise
+///
+/// Written one project per line, the project tag first and then its default
+/// tags, like `+webdev est:30min @work`. Empty lines and lines starting with
+/// `#` are skipped.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DefaultsMap(Vec<(String, TagCollection)>);
+
+impl DefaultsMap {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Default tags of `project`, given with its `+`
+    pub fn get(&self, project: &str) -> Option<&TagCollection> {
+        self.0.iter().find(|(name, _)| name == project).map(|(_, tags)| tags)
+    }
+}
+
+impl FromStr for DefaultsMap {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut defaults = DefaultsMap::default();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fail = |msg: String| format!("line {}: {}", idx + 1, msg);
+            let (project, tags) = line.split_once(' ').unwrap_or((line, ""));
+            if !matches!(Tag::from_str(project), Ok(Tag::Project(_))) {
+                return Err(fail(format!("'{project}' is no project like +webdev")));
+            }
+            if defaults.get(project).is_some() {
+                return Err(fail(format!("{project} has defaults already")));
+            }
+            let tags = TagCollection::from_str(tags)
+                .map_err(|e| fail(format!("no default tags for {project}: {e}")))?;
+            defaults.0.push((project.to_string(), tags));
+        }
+        Ok(defaults)
+    }
+}
+
+/// Add the default tags of the task's projects, for what the task says
+/// nothing about yet
+///
+/// A default is left out if the task has a tag of the same kind (see
+/// `Tag::same_kind`): no `@home` for a task with `@office`, no estimate if
+/// one was given. Defaults of several projects are combined; where they
+/// disagree, the project written first on the task wins. Returns the tags
+/// that were added.
+pub fn apply_project_defaults(task: &mut Task, defaults: &DefaultsMap) -> Vec<String> {
+    let Some(tags) = task.tags() else {
+        return Vec::new();
+    };
+    let mut added: Vec<Tag> = Vec::new();
+    for project in tags.project_tags() {
+        let Some(project_defaults) = defaults.get(&project) else {
+            continue;
+        };
+        for default in project_defaults.iter() {
+            let known = tags.iter().chain(&added).any(|tag| tag.same_kind(default));
+            if !known {
+                added.push(default.clone());
+            }
+        }
+    }
+    let names = added.iter().map(Tag::to_string).collect();
+    if !added.is_empty() {
+        task.add_tags(TagCollection::from_tags(added));
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> DefaultsMap {
+        DefaultsMap::from_str(
+            "# Tags of new tasks\n\
+             +webdev est:30min @work\n\
+             \n\
+             +garden @home t:2025-04-01 season:spring\n",
+        )
+        .unwrap()
+    }
+
+    fn captured(line: &str) -> (String, Vec<String>) {
+        let mut task = Task::from_str(line).unwrap();
+        let added = apply_project_defaults(&mut task, &defaults());
+        (task.to_string(), added)
+    }
+
+    #[test]
+    fn defaults_fill_in_what_is_missing() {
+        let (task, added) = captured("Fix the navbar +webdev");
+        assert_eq!(task, "Fix the navbar +webdev est:30min @work");
+        assert_eq!(added, ["est:30min", "@work"]);
+        // Nothing the task says is overridden
+        let (task, _) = captured("Fix the navbar +webdev @office est:90min");
+        assert_eq!(task, "Fix the navbar +webdev @office est:90min");
+        let (task, added) = captured("Call the host +webdev p:ines");
+        assert_eq!(task, "Call the host +webdev p:ines est:30min @work");
+        assert_eq!(added.len(), 2);
+        // Tasks without a project with defaults stay as they are
+        assert_eq!(captured("Buy milk @errands").0, "Buy milk @errands");
+        assert_eq!(captured("Buy milk").0, "Buy milk");
+    }
+
+    #[test]
+    fn first_project_wins_where_defaults_disagree() {
+        let (task, _) = captured("Blog about the beds +garden +webdev");
+        assert_eq!(
+            task,
+            "Blog about the beds +garden +webdev @home t:2025-04-01 season:spring est:30min"
+        );
+        let (task, _) = captured("Blog about the beds +webdev +garden");
+        assert_eq!(
+            task,
+            "Blog about the beds +webdev +garden est:30min @work t:2025-04-01 season:spring"
+        );
+    }
+
+    #[test]
+    fn defaults_file_errors_name_the_line() {
+        let cases = [
+            ("webdev @work", "line 1: 'webdev' is no project"),
+            ("+a @x\n+a @y", "line 2: +a has defaults already"),
+            ("\n+a", "line 2: no default tags for +a"),
+            ("+a @x Work:y", "line 1: no default tags for +a"),
+        ];
+        for (text, message) in cases {
+            let error = DefaultsMap::from_str(text).unwrap_err();
+            assert!(error.starts_with(message), "{text}: {error}");
+        }
+        assert!(DefaultsMap::from_str("").unwrap().is_empty());
+    }
+}
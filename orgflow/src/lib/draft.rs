@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::{Task, sanitize_line};
+
+/// A task being typed in a frontend, e.g. the TUI's scratchpad
+///
+/// The draft text is re-parsed whenever it changes and the result is cached,
+/// so submitting, previewing and validating all read the same parse instead of
+/// each working on the raw input.
+#[derive(Debug)]
+pub struct DraftTask {
+    text: String,
+    parsed: Result<Task, String>,
+}
+
+impl DraftTask {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            parsed: Err("Empty String error".to_string()),
+        }
+    }
+
+    /// Create a draft from already existing text (e.g. a restored session)
+    pub fn with_text(text: &str) -> Self {
+        let mut draft = Self::new();
+        draft.update(text);
+        draft
+    }
+
+    /// Update the draft text, re-parsing only if the text actually changed
+    ///
+    /// Returns `true` if the cached parse was invalidated.
+    pub fn update(&mut self, text: &str) -> bool {
+        if self.text == text {
+            return false;
+        }
+        self.text = text.to_string();
+        self.parsed = Self::parse(text);
+        true
+    }
+
+    fn parse(text: &str) -> Result<Task, String> {
+        Task::with_today(&sanitize_line(text))
+    }
+
+    /// Current draft text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Cached parse result of the current draft
+    pub fn parsed(&self) -> &Result<Task, String> {
+        &self.parsed
+    }
+
+    /// Parse error of the current draft, if the draft is not empty
+    pub fn error(&self) -> Option<&str> {
+        match &self.parsed {
+            Err(msg) if !self.text.trim().is_empty() => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// First one-off tag of the draft that `open_oneoffs` already has, with
+    /// the index of the open task carrying it
+    ///
+    /// One-off tags like `!ticket-123` are meant as unique references, a
+    /// second open task with one is most likely captured twice.
+    pub fn oneoff_conflict(
+        &self,
+        open_oneoffs: &HashMap<String, usize>,
+    ) -> Option<(String, usize)> {
+        let tags = self.parsed.as_ref().ok()?.tags().as_ref()?;
+        tags.oneoff_tags().into_iter().find_map(|oneoff| {
+            let idx = *open_oneoffs.get(&oneoff)?;
+            Some((oneoff, idx))
+        })
+    }
+
+    /// Consume the cached parse, leaving an empty draft behind on success
+    ///
+    /// On a parse error the draft is kept so the user can fix it.
+    pub fn take(&mut self) -> Result<Task, String> {
+        if let Err(msg) = &self.parsed {
+            return Err(msg.clone());
+        }
+        let parsed = std::mem::replace(&mut self.parsed, Err("Empty String error".to_string()));
+        self.text.clear();
+        parsed
+    }
+}
+
+impl Default for DraftTask {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_is_cached() {
+        let mut draft = DraftTask::new();
+        assert!(draft.update("Buy milk @errands"));
+        assert!(!draft.update("Buy milk @errands"));
+        assert_eq!(draft.parsed().as_ref().unwrap().description(), "Buy milk");
+    }
+
+    #[test]
+    fn test_edit_invalidates_parse() {
+        let mut draft = DraftTask::new();
+        draft.update("@phone");
+        assert!(draft.error().is_some());
+
+        assert!(draft.update("Call mum @phone"));
+        assert!(draft.error().is_none());
+        assert_eq!(draft.parsed().as_ref().unwrap().description(), "Call mum");
+    }
+
+    #[test]
+    fn test_empty_draft_has_no_error() {
+        let mut draft = DraftTask::new();
+        assert!(draft.error().is_none());
+        draft.update("   ");
+        assert!(draft.error().is_none());
+        assert!(draft.parsed().is_err());
+    }
+
+    #[test]
+    fn test_take_consumes_draft() {
+        let mut draft = DraftTask::with_text("(A) Write report +work");
+        let task = draft.take().unwrap();
+        assert_eq!(task.description(), "Write report");
+        assert!(task.creation_date().is_some());
+        assert_eq!(draft.text(), "");
+        assert!(draft.take().is_err());
+    }
+
+    #[test]
+    fn test_take_keeps_invalid_draft() {
+        let mut draft = DraftTask::with_text("(A) @phone");
+        assert!(draft.take().is_err());
+        assert_eq!(draft.text(), "(A) @phone");
+    }
+
+    #[test]
+    fn test_oneoff_conflict_with_open_tasks() {
+        let open_oneoffs = HashMap::from([("!ticket-123".to_string(), 4)]);
+        let draft = DraftTask::with_text("Reply to the customer !ticket-9 !ticket-123");
+        assert_eq!(draft.oneoff_conflict(&open_oneoffs), Some(("!ticket-123".to_string(), 4)));
+        let draft = DraftTask::with_text("Reply to the customer !ticket-124");
+        assert_eq!(draft.oneoff_conflict(&open_oneoffs), None);
+        assert_eq!(DraftTask::with_text("!ticket-123").oneoff_conflict(&open_oneoffs), None);
+    }
+}
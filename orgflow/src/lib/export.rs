@@ -0,0 +1,27 @@
+use crate::{Date, FilterSet, Task};
+
+/// File in the base folder the TUI exports the Tasks view to
+pub const VIEW_EXPORT: &str = "view.txt";
+
+/// Plain text of a list of tasks for printing or sharing
+///
+/// `tasks` are the tasks `view` shows, in the order it shows them. The text
+/// starts with the view in words and the date it was generated on, then has
+/// one checklist line per task without its detail lines.
+pub fn export_view(tasks: &[&Task], view: &FilterSet, generated: &Date) -> String {
+    let mut text = format!("Tasks: {}\n", view.describe());
+    text.push_str(&format!("Generated {generated}, {} task(s)\n\n", tasks.len()));
+    for task in tasks {
+        let mut line = String::from(if task.is_completed() { "[x]" } else { "[ ]" });
+        if let Some(priority) = task.priority_level() {
+            line.push_str(&format!(" {priority}"));
+        }
+        line.push_str(&format!(" {}", task.description()));
+        if let Some(tags) = task.tags() {
+            line.push_str(&format!(" {tags}"));
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
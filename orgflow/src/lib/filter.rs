@@ -0,0 +1,400 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::{Date, Priority, Tag, Task};
+
+/// Condition a task has to meet to be shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskFilter {
+    /// Tasks that are not completed
+    Open,
+    /// Completed tasks
+    Completed,
+    /// Tasks with the context, given with its `@`
+    Context(String),
+    /// Tasks with the project, given with its `+`
+    Project(String),
+    /// Tasks without any project
+    NoProject,
+    /// Tasks with the priority, or without one for `None`
+    Priority(Option<Priority>),
+    /// Tasks with the tag, given as it is written like `due:2025-03-01`
+    HasTag(String),
+    /// Tasks without the tag, given as it is written
+    WithoutTag(String),
+    /// Tasks created within the two days, both included
+    CreatedBetween(Date, Date),
+    /// Tasks completed within the two days, both included
+    CompletedBetween(Date, Date),
+    /// Tasks whose description contains the text, ignoring case
+    Text(String),
+    /// Open tasks created more than this many days ago, i.e. stale ones
+    OlderThan(u32),
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.matches_at(task, &Date::now())
+    }
+
+    /// Like `matches`, with ages counted up to `today`
+    pub fn matches_at(&self, task: &Task, today: &Date) -> bool {
+        let tags = task.tags().as_ref();
+        match self {
+            TaskFilter::Open => !task.is_completed(),
+            TaskFilter::Completed => task.is_completed(),
+            TaskFilter::Context(context) => {
+                tags.is_some_and(|tags| tags.context_tags().contains(context))
+            }
+            TaskFilter::Project(project) => {
+                tags.is_some_and(|tags| tags.project_tags().contains(project))
+            }
+            TaskFilter::NoProject => tags.is_none_or(|tags| tags.project_tags().is_empty()),
+            TaskFilter::Priority(priority) => task.priority_level() == priority,
+            TaskFilter::HasTag(tag) => has_tag(task, tag),
+            TaskFilter::WithoutTag(tag) => !has_tag(task, tag),
+            TaskFilter::CreatedBetween(from, to) => {
+                task.creation_date().is_some_and(|created| (from..=to).contains(&&created))
+            }
+            TaskFilter::CompletedBetween(from, to) => {
+                task.completion_date().is_some_and(|completed| (from..=to).contains(&&completed))
+            }
+            TaskFilter::Text(text) => {
+                task.description().to_lowercase().contains(&text.to_lowercase())
+            }
+            TaskFilter::OlderThan(days) => {
+                !task.is_completed()
+                    && task.age_in_days(today).is_some_and(|age| age > i64::from(*days))
+            }
+        }
+    }
+}
+
+impl Display for TaskFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskFilter::Open => write!(f, "open"),
+            TaskFilter::Completed => write!(f, "completed"),
+            TaskFilter::Context(context) => write!(f, "context {context}"),
+            TaskFilter::Project(project) => write!(f, "project {project}"),
+            TaskFilter::NoProject => write!(f, "no project"),
+            TaskFilter::Priority(Some(priority)) => write!(f, "priority {priority}"),
+            TaskFilter::Priority(None) => write!(f, "no priority"),
+            TaskFilter::HasTag(tag) => write!(f, "tag {tag}"),
+            TaskFilter::WithoutTag(tag) => write!(f, "without tag {tag}"),
+            TaskFilter::CreatedBetween(from, to) => write!(f, "created {from} to {to}"),
+            TaskFilter::CompletedBetween(from, to) => write!(f, "completed {from} to {to}"),
+            TaskFilter::Text(text) => write!(f, "text '{text}'"),
+            TaskFilter::OlderThan(days) => write!(f, "older than {days} days"),
+        }
+    }
+}
+
+fn has_tag(task: &Task, tag: &str) -> bool {
+    task.tags().iter().flatten().any(|t| t.to_string() == tag)
+}
+
+/// Filters a task has to meet all of, put together one after the other
+///
+/// ```
+/// use orgflow::{Task, TaskQuery};
+/// use std::str::FromStr;
+///
+/// let query = TaskQuery::new().project("webdev").not_completed();
+/// assert!(Task::from_str("Fix the navbar +webdev").unwrap().matches(&query));
+/// assert!(!Task::from_str("x Fix the footer +webdev").unwrap().matches(&query));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskQuery {
+    filters: Vec<TaskFilter>,
+}
+
+impl TaskQuery {
+    /// Query every task matches
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The filters put together so far
+    pub fn filters(&self) -> &[TaskFilter] {
+        &self.filters
+    }
+
+    fn and(mut self, filter: TaskFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn completed(self) -> Self {
+        self.and(TaskFilter::Completed)
+    }
+
+    pub fn not_completed(self) -> Self {
+        self.and(TaskFilter::Open)
+    }
+
+    /// Tasks with the project, with or without its `+`
+    pub fn project(self, project: &str) -> Self {
+        let project = project.strip_prefix('+').unwrap_or(project);
+        self.and(TaskFilter::Project(format!("+{project}")))
+    }
+
+    pub fn no_project(self) -> Self {
+        self.and(TaskFilter::NoProject)
+    }
+
+    /// Tasks with the context, with or without its `@`
+    pub fn context(self, context: &str) -> Self {
+        let context = context.strip_prefix('@').unwrap_or(context);
+        self.and(TaskFilter::Context(format!("@{context}")))
+    }
+
+    /// Tasks with the priority, or without one for `None`
+    pub fn priority(self, priority: Option<Priority>) -> Self {
+        self.and(TaskFilter::Priority(priority))
+    }
+
+    pub fn with_tag(self, tag: &Tag) -> Self {
+        self.and(TaskFilter::HasTag(tag.to_string()))
+    }
+
+    pub fn without_tag(self, tag: &Tag) -> Self {
+        self.and(TaskFilter::WithoutTag(tag.to_string()))
+    }
+
+    /// Tasks whose description contains `text`, ignoring case
+    pub fn text(self, text: &str) -> Self {
+        self.and(TaskFilter::Text(text.to_string()))
+    }
+
+    /// Tasks created from `from` up to `to`, both included
+    pub fn created_between(self, from: Date, to: Date) -> Self {
+        self.and(TaskFilter::CreatedBetween(from, to))
+    }
+
+    /// Tasks completed from `from` up to `to`, both included
+    pub fn completed_between(self, from: Date, to: Date) -> Self {
+        self.and(TaskFilter::CompletedBetween(from, to))
+    }
+}
+
+impl Task {
+    /// Whether the task meets all filters of `query`
+    pub fn matches(&self, query: &TaskQuery) -> bool {
+        let today = Date::now();
+        query.filters.iter().all(|filter| filter.matches_at(self, &today))
+    }
+}
+
+/// Order of the tasks that are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskSort {
+    /// As they are in the document
+    #[default]
+    Document,
+    /// Highest priority first, tasks without one last
+    Priority,
+    /// Oldest creation date first, tasks without one last
+    Created,
+    /// Earliest due date first, tasks without one last
+    Due,
+    /// By the name of the first project, ignoring case, tasks without one last
+    Project,
+}
+
+impl Display for TaskSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskSort::Document => write!(f, "document"),
+            TaskSort::Priority => write!(f, "priority"),
+            TaskSort::Created => write!(f, "created"),
+            TaskSort::Due => write!(f, "due"),
+            TaskSort::Project => write!(f, "project"),
+        }
+    }
+}
+
+impl FromStr for TaskSort {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "document" => Ok(TaskSort::Document),
+            "priority" => Ok(TaskSort::Priority),
+            "created" => Ok(TaskSort::Created),
+            "due" => Ok(TaskSort::Due),
+            "project" => Ok(TaskSort::Project),
+            _ => Err(format!(
+                "Unknown sort '{s}', use document, priority, created, due or project"
+            )),
+        }
+    }
+}
+
+/// Filters a task must all meet, and the order of the remaining tasks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterSet {
+    pub filters: Vec<TaskFilter>,
+    pub sort: TaskSort,
+}
+
+impl FilterSet {
+    /// Indices into `tasks` of the tasks to show, in the order to show them
+    ///
+    /// The index is the final sort key, tasks that compare equal keep the
+    /// document order.
+    pub fn apply(&self, tasks: &[Task]) -> Vec<usize> {
+        self.apply_at(tasks, &Date::now())
+    }
+
+    /// The view in words, like "open, project +garden; sorted by priority"
+    pub fn describe(&self) -> String {
+        let filters = match self.filters.as_slice() {
+            [] => "all tasks".to_string(),
+            filters => filters.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", "),
+        };
+        match self.sort {
+            TaskSort::Document => format!("{filters}; in document order"),
+            sort => format!("{filters}; sorted by {sort}"),
+        }
+    }
+
+    /// Like `apply`, with ages counted up to `today`
+    pub fn apply_at(&self, tasks: &[Task], today: &Date) -> Vec<usize> {
+        let mut visible: Vec<usize> = (0..tasks.len())
+            .filter(|&idx| self.filters.iter().all(|f| f.matches_at(&tasks[idx], today)))
+            .collect();
+        visible.sort_by(|&a, &b| tasks[a].cmp_by(&tasks[b], self.sort).then(a.cmp(&b)));
+        visible
+    }
+}
+
+/// Row of the task at `index` in the document among the `visible` ones (as
+/// returned by `FilterSet::apply`), `None` if it is filtered out
+pub fn visible_position(visible: &[usize], index: usize) -> Option<usize> {
+    visible.iter().position(|&idx| idx == index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn tasks() -> Vec<Task> {
+        [
+            "2024-03-01 Water the plants @home",
+            "(B) 2024-01-01 Call the plumber @phone @home",
+            "x 2024-02-02 2024-02-01 Pay rent +flat",
+            "(A) Book flights +travel",
+        ]
+        .iter()
+        .map(|line| Task::from_str(line).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn filters_and_sorts() {
+        let tasks = tasks();
+        let open = FilterSet {
+            filters: vec![TaskFilter::Open],
+            sort: TaskSort::Priority,
+        };
+        assert_eq!(open.apply(&tasks), [3, 1, 0]);
+
+        let home = FilterSet {
+            filters: vec![TaskFilter::Context("@home".into()), TaskFilter::Text("PLUMB".into())],
+            sort: TaskSort::Document,
+        };
+        assert_eq!(home.apply(&tasks), [1]);
+
+        let created = FilterSet {
+            filters: vec![],
+            sort: TaskSort::Created,
+        };
+        assert_eq!(created.apply(&tasks), [1, 2, 0, 3]);
+        assert_eq!(FilterSet::default().apply(&tasks), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn queries_combine_filters() {
+        let tasks = tasks();
+        let matching = |query: &TaskQuery| {
+            (0..tasks.len()).filter(|&idx| tasks[idx].matches(query)).collect::<Vec<_>>()
+        };
+        assert_eq!(matching(&TaskQuery::new()), [0, 1, 2, 3]);
+        assert_eq!(matching(&TaskQuery::new().no_project()), [0, 1]);
+        assert_eq!(matching(&TaskQuery::new().no_project().context("home").text("plumb")), [1]);
+        assert!(matching(&TaskQuery::new().project("+flat").not_completed()).is_empty());
+        assert_eq!(matching(&TaskQuery::new().project("flat").completed()), [2]);
+        assert_eq!(matching(&TaskQuery::new().priority(Some(Priority::A))), [3]);
+        assert_eq!(matching(&TaskQuery::new().priority(None).not_completed()), [0]);
+
+        let home = Tag::from_str("@home").unwrap();
+        assert_eq!(matching(&TaskQuery::new().with_tag(&home)), [0, 1]);
+        assert_eq!(matching(&TaskQuery::new().without_tag(&home).not_completed()), [3]);
+
+        let day = |date: &str| Date::from_str(date).unwrap();
+        let february = TaskQuery::new().created_between(day("2024-01-01"), day("2024-02-01"));
+        assert_eq!(matching(&february), [1, 2]);
+        let done = TaskQuery::new().completed_between(day("2024-02-02"), day("2024-02-02"));
+        assert_eq!(matching(&done), [2]);
+        assert_eq!(
+            TaskFilter::CreatedBetween(day("2024-01-01"), day("2024-02-01")).to_string(),
+            "created 2024-01-01 to 2024-02-01"
+        );
+    }
+
+    #[test]
+    fn sorted_by_due_date_and_project() {
+        let tasks: Vec<Task> = [
+            "Water the plants +Garden due:2024-03-09",
+            "Call the plumber",
+            "Pay rent +flat due:2024-03-01",
+            "Fix the fence +garden",
+        ]
+        .iter()
+        .map(|line| Task::from_str(line).unwrap())
+        .collect();
+        let sorted = |sort: &str| FilterSet {
+            filters: vec![],
+            sort: TaskSort::from_str(sort).unwrap(),
+        };
+        assert_eq!(sorted("due").apply(&tasks), [2, 0, 1, 3]);
+        assert_eq!(sorted("project").apply(&tasks), [2, 0, 3, 1]);
+        assert_eq!(TaskSort::Project.to_string(), "project");
+    }
+
+    #[test]
+    fn stale_tasks() {
+        let tasks = tasks();
+        let stale = FilterSet {
+            filters: vec![TaskFilter::OlderThan(30)],
+            sort: TaskSort::Document,
+        };
+        // Watering the plants was added exactly 30 days before
+        let today = Date::from_str("2024-03-31").unwrap();
+        assert_eq!(stale.apply_at(&tasks, &today), [1]);
+        let today = Date::from_str("2024-04-01").unwrap();
+        assert_eq!(stale.apply_at(&tasks, &today), [0, 1]);
+        // Neither the completed task nor the one without creation date
+        let today = Date::from_str("2030-01-01").unwrap();
+        assert_eq!(stale.apply_at(&tasks, &today), [0, 1]);
+    }
+
+    #[test]
+    fn views_in_words() {
+        let view = FilterSet {
+            filters: vec![TaskFilter::Open, TaskFilter::Project("+garden".into())],
+            sort: TaskSort::from_str("priority").unwrap(),
+        };
+        assert_eq!(view.describe(), "open, project +garden; sorted by priority");
+        assert_eq!(FilterSet::default().describe(), "all tasks; in document order");
+        assert!(TaskSort::from_str("urgency").is_err());
+    }
+
+    #[test]
+    fn positions_of_visible_tasks() {
+        let visible = [3, 1, 0];
+        assert_eq!(visible_position(&visible, 1), Some(1));
+        assert_eq!(visible_position(&visible, 2), None);
+    }
+}
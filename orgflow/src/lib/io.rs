@@ -1,12 +1,32 @@
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Result as IoResult;
 use std::io::{self, BufRead, Seek, Write};
 use std::str::FromStr;
+use std::time::Duration;
 use std::vec;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use crate::{Note, Task};
+mod duplicates;
+mod lint;
+mod readonly;
+mod reorder;
+mod replace;
+mod transaction;
+
+pub use duplicates::DUPLICATE_DISTANCE;
+pub use readonly::{READONLY_MARKER, ReadOnlyReason};
+pub use replace::{ChangeRecord, ChangeTarget, ReplaceOptions, ReplaceScope};
+pub use transaction::Transaction;
+
+use crate::core::dates::{Date, WeekStart};
+use crate::core::tags::RESERVED_KEYS;
+use crate::core::tags::guid::Guid;
+use crate::core::task::DETAIL_INDENT;
+use crate::{
+    FilterSet, Note, OnComplete, SuggestFrom, Tag, TagCollection, Task, TaskSort, reflow,
+};
 
 #[derive(PartialEq, Debug, Default)]
 pub struct OrgDocument {
@@ -15,6 +35,13 @@ pub struct OrgDocument {
     between: Vec<String>,
     pub notes: Vec<Note>,
     post: Vec<String>,
+    /// Lines the parser rejected, written back in a `## Quarantine` section
+    quarantine: Vec<LintIssue>,
+    /// Comment lines after the last task of the Tasks section, see
+    /// `Task::comments` for those above a task
+    closing_comments: Vec<String>,
+    /// Only the Tasks section was loaded, see `from_tasks_only`
+    partial: bool,
 }
 
 impl OrgDocument {
@@ -24,62 +51,602 @@ impl OrgDocument {
     pub fn push_note(&mut self, note: Note) {
         self.notes.push(note);
     }
-    pub fn write<W: Write + Seek>(&self, mut buf: W) -> Result<(), io::Error> {
+    /// Index of the note with the guid
+    pub fn find_note(&self, guid: &str) -> Option<usize> {
+        self.notes.iter().position(|note| note.guid().to_string() == guid)
+    }
+    /// Index of the task with the `id:` tag `id`
+    pub fn find_task_by_id(&self, id: &Guid) -> Option<usize> {
+        self.tasks.iter().position(|task| task.id() == Some(id))
+    }
+    /// Whether a task that `task` is blocked by (see `Task::blocked_by`) is
+    /// still open; ids no task has do not block, `lint` lists them
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.blocked_by().into_iter().any(|id| {
+            self.find_task_by_id(id).is_some_and(|index| !self.tasks[index].is_completed())
+        })
+    }
+    /// Indices of the tasks linking to the note with the guid by an `n:` tag
+    pub fn tasks_linking_to(&self, guid: &str) -> Vec<usize> {
+        let links = |task: &Task| task.note_refs().iter().any(|note| note.to_string() == guid);
+        (0..self.tasks.len()).filter(|&idx| links(&self.tasks[idx])).collect()
+    }
+    /// The note an `n:` tag links to (see `Task::note_refs`), `None` if no
+    /// note has the guid
+    pub fn resolve_note(&self, guid: &Guid) -> Option<&Note> {
+        self.notes.iter().find(|note| note.guid() == guid)
+    }
+    /// The tasks linking to the note with the guid, in document order
+    pub fn tasks_for_note(&self, guid: &Guid) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.note_refs().contains(&guid)).collect()
+    }
+    /// Title and guid of every note, in document order, for linking to
+    /// notes by their title
+    pub fn note_titles(&self) -> Vec<(String, String)> {
+        let notes = self.notes.iter();
+        notes.map(|note| (note.title().to_string(), note.guid().to_string())).collect()
+    }
+    /// Replace title, content and tags of the note at `index` with those of
+    /// `edited`, the note keeps its guid and creation date
+    pub fn update_note(&mut self, index: usize, edited: Note) {
+        self.notes[index].revise(edited);
+    }
+    pub fn write<W: Write + Seek>(&self, buf: W) -> Result<(), io::Error> {
+        self.write_wrapped(buf, 0)
+    }
+    /// Write the document with the note content reflowed at `wrap_width`
+    /// (see `reflow`), the notes in memory keep their lines
+    pub fn write_wrapped<W: Write + Seek>(
+        &self,
+        mut buf: W,
+        wrap_width: usize,
+    ) -> Result<(), io::Error> {
+        if self.partial {
+            return Err(io::Error::other(
+                "Document was loaded partially, refusing to write it",
+            ));
+        }
         for line in self.preample.iter() {
             writeln!(buf, "{}", line)?;
         }
-        writeln!(buf, "")?;
+        writeln!(buf)?;
         writeln!(buf, "## Tasks")?;
-        for line in self.tasks.iter() {
-            writeln!(buf, "{}", line)?;
+        for task in self.tasks.iter() {
+            for comment in task.comments() {
+                writeln!(buf, "{}", comment)?;
+            }
+            writeln!(buf, "{}", task)?;
+        }
+        for comment in self.closing_comments.iter() {
+            writeln!(buf, "{}", comment)?;
         }
-        writeln!(buf, "")?;
+        writeln!(buf)?;
         if !self.between.is_empty() {
             for line in self.between.iter() {
                 writeln!(buf, "{}", line)?;
             }
-            writeln!(buf, "")?;
+            writeln!(buf)?;
         }
         writeln!(buf, "## Notes")?;
-        writeln!(buf, "")?;
+        writeln!(buf)?;
         for note in self.notes.iter() {
-            let t: Vec<String> = note.into();
+            let mut t: Vec<String> = note.into();
+            let content = t.split_off(2.min(t.len()));
+            t.extend(reflow(&content, wrap_width));
             for nline in t.iter() {
                 writeln!(buf, "{}", nline)?;
             }
-            writeln!(buf, "")?;
+            writeln!(buf)?;
+        }
+        if !self.quarantine.is_empty() {
+            writeln!(buf, "{}", QUARANTINE_HEADER)?;
+            for issue in self.quarantine.iter() {
+                writeln!(buf, "{}", issue.content)?;
+            }
+            writeln!(buf)?;
         }
         if !self.post.is_empty() {
             for line in self.post.iter() {
                 writeln!(buf, "{}", line)?;
             }
         }
-        Ok(buf.flush()?)
+        buf.flush()
     }
     pub fn to(&self, path: &str) -> Result<(), io::Error> {
-        let file = File::options().write(true).open(path)?;
+        let file = File::options().write(true).truncate(true).open(path)?;
         let buf = io::BufWriter::new(file);
-        self.write(buf)
+        self.write(buf)?;
+        log::info!("save path={path} tasks={} notes={}", self.tasks.len(), self.notes.len());
+        Ok(())
+    }
+    /// Serialize the document into memory, e.g. to hand it to a writer that
+    /// takes care of persisting it
+    pub fn to_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let mut buf = io::Cursor::new(Vec::new());
+        self.write(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+    /// `to_bytes` with the note content reflowed at `wrap_width`
+    pub fn to_bytes_wrapped(&self, wrap_width: usize) -> Result<Vec<u8>, io::Error> {
+        let mut buf = io::Cursor::new(Vec::new());
+        self.write_wrapped(&mut buf, wrap_width)?;
+        Ok(buf.into_inner())
     }
+    /// Load a document, failing on the first invalid task line or note block
     pub fn from(path: &str) -> IoResult<Self> {
+        Self::parse_file(path, false)
+    }
+    /// Load a document, setting invalid task lines and note blocks aside
+    ///
+    /// The rejected text is kept verbatim in the quarantine of the document
+    /// and returned with its line numbers. `write` puts it into a
+    /// `## Quarantine` section after the notes, so nothing is lost; lines in
+    /// that section are quarantined again on the next load until they are
+    /// fixed by hand.
+    pub fn from_lenient(path: &str) -> IoResult<(Self, Vec<LintIssue>)> {
+        let doc = Self::parse_file(path, true)?;
+        let issues = doc.quarantine.clone();
+        Ok((doc, issues))
+    }
+    fn parse_file(path: &str, lenient: bool) -> IoResult<Self> {
+        let doc = Self::parse_reader(io::BufReader::new(File::open(path)?), lenient)
+            .inspect_err(|e| log::warn!("load_failed path={path} error={:?}", e.to_string()))?;
+        doc.log_load(path);
+        Ok(doc)
+    }
+    /// Record a load with its counts, and every line set aside
+    fn log_load(&self, path: &str) {
+        let (tasks, notes) = self.len();
+        let quarantined = self.quarantine.len();
+        log::info!(
+            "load path={path} tasks={tasks} notes={notes} quarantined={quarantined} partial={}",
+            self.partial
+        );
+        for issue in &self.quarantine {
+            log::warn!("parse_warning path={path} line={} message={:?}", issue.line, issue.message);
+            log::debug!("parse_warning line={} content={:?}", issue.line, issue.content);
+        }
+    }
+    fn parse_reader<R: BufRead>(reader: R, lenient: bool) -> IoResult<Self> {
         let mut parser = OrgDocumentParser::default();
         let mut doc = OrgDocument::default();
+        for (idx, line) in reader.lines().map_while(Result::ok).enumerate() {
+            if !line.is_empty() {
+                parser.parse(&line, idx + 1, &mut doc, lenient)?;
+            }
+        }
+        parser.finish(&mut doc, lenient)?;
+        Ok(doc)
+    }
+    /// Load only the Tasks section of a document
+    ///
+    /// Parsing stops at the first section header after `## Tasks`, so notes are
+    /// not read at all. The returned document is marked as partial and refuses
+    /// to `write` until the remaining sections are merged in via
+    /// `merge_remaining`. Use `append_task` to persist tasks in the meantime.
+    /// Invalid task lines are quarantined as with `from_lenient`.
+    pub fn from_tasks_only(path: &str) -> IoResult<Self> {
+        let mut parser = OrgDocumentParser::default();
+        let mut doc = OrgDocument {
+            partial: true,
+            ..Default::default()
+        };
         let file = File::open(path)?;
         let lines = io::BufReader::new(file).lines();
-        for line in lines.map_while(Result::ok) {
+        for (idx, line) in lines.map_while(Result::ok).enumerate() {
+            if parser.leaves_tasks(&line) {
+                break;
+            }
             if !line.is_empty() {
-                parser.parse(&line, &mut doc)?;
+                parser.parse(&line, idx + 1, &mut doc, true)?;
             }
         }
-        parser.finish(&mut doc)?;
+        doc.log_load(path);
         Ok(doc)
     }
+    /// Text the parser set aside, see `from_lenient`
+    pub fn quarantine(&self) -> &[LintIssue] {
+        &self.quarantine
+    }
+    /// Whether only the Tasks section was loaded
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+    /// Complete a partial document with the sections of a fully loaded one
+    ///
+    /// The tasks of `self` are kept, as they might have been extended since
+    /// the partial load. Everything else is taken from `full`.
+    pub fn merge_remaining(&mut self, full: OrgDocument) {
+        self.preample = full.preample;
+        self.between = full.between;
+        self.notes = full.notes;
+        self.post = full.post;
+        self.quarantine = full.quarantine;
+        self.partial = false;
+    }
+    /// Add a task and persist it by inserting its line at the end of the
+    /// Tasks section of the file at `path`
+    ///
+    /// The rest of the file is copied verbatim, so this works on partially
+    /// loaded documents as well.
+    pub fn append_task(&mut self, path: &str, task: Task) -> Result<(), io::Error> {
+        let content = std::fs::read_to_string(path)?;
+        let output = Self::insert_tasks(&content, std::slice::from_ref(&task))?;
+        std::fs::write(path, output)?;
+        log::info!("append_task path={path}");
+        log::debug!("append_task line={task}");
+        self.tasks.push(task);
+        Ok(())
+    }
+    /// Insert the lines of `tasks` at the end of the Tasks section of the
+    /// document text `content`, leaving everything else untouched
+    ///
+    /// Tasks can be given as `Task`s or as already formatted task lines.
+    pub fn insert_tasks<T: Display>(content: &str, tasks: &[T]) -> Result<String, io::Error> {
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+        let header = lines
+            .iter()
+            .position(|l| l == "## Tasks")
+            .ok_or_else(|| io::Error::other("No '## Tasks' section found"))?;
+        let mut insert_at = header + 1;
+        for (i, line) in lines.iter().enumerate().skip(header + 1) {
+            if line.starts_with("## ") {
+                break;
+            }
+            // Comments closing the section stay at its end
+            if !line.is_empty() && !is_task_comment(line) {
+                insert_at = i + 1;
+            }
+        }
+        lines.splice(insert_at..insert_at, tasks.iter().map(T::to_string));
+        let mut output = lines.join("\n");
+        output.push('\n');
+        Ok(output)
+    }
+    /// Lines before the Tasks section, verbatim
+    pub fn preamble(&self) -> &[String] {
+        &self.preample
+    }
+    /// Replace the lines before the Tasks section, refusing lines that
+    /// `validate_preamble` rejects
+    ///
+    /// Blank lines are dropped, as they are when the document is loaded.
+    pub fn set_preamble(&mut self, lines: Vec<String>) -> Result<(), String> {
+        validate_preamble(&lines)?;
+        self.preample = lines.into_iter().filter(|line| !line.is_empty()).collect();
+        Ok(())
+    }
+    /// Lines of the sections between Tasks and Notes, with their `## `
+    /// headers, verbatim
+    pub fn between(&self) -> &[String] {
+        &self.between
+    }
+    /// Append `lines` to the section with the header line `header` (e.g.
+    /// `## Waiting For`) between Tasks and Notes
+    ///
+    /// The section is added after the existing ones if there is none with
+    /// that header yet.
+    pub fn append_between(&mut self, header: &str, lines: &[String]) {
+        let Some(start) = self.between.iter().position(|line| line == header) else {
+            self.between.push(header.to_string());
+            self.between.extend(lines.iter().cloned());
+            return;
+        };
+        let end = self.between[start + 1..]
+            .iter()
+            .position(|line| line.starts_with("## "))
+            .map_or(self.between.len(), |offset| start + 1 + offset);
+        self.between.splice(end..end, lines.iter().cloned());
+    }
+    /// Remove the task at `index` from the document
+    ///
+    /// Its comments stay in the Tasks section, above the task that took its
+    /// place (or at the end of the section).
+    pub fn remove_task(&mut self, index: usize) -> Option<Task> {
+        if index >= self.tasks.len() {
+            return None;
+        }
+        let mut task = self.tasks.remove(index);
+        let comments = task.take_comments();
+        let following = match self.tasks.get_mut(index) {
+            Some(next) => next.take_comments(),
+            None => std::mem::take(&mut self.closing_comments),
+        };
+        let comments = [comments, following].concat();
+        match self.tasks.get_mut(index) {
+            Some(next) => next.set_comments(comments),
+            None => self.closing_comments = comments,
+        }
+        Some(task)
+    }
+    /// Comment lines at the end of the Tasks section, after the last task
+    pub fn closing_comments(&self) -> &[String] {
+        &self.closing_comments
+    }
+    /// Turn the task at `index` into plain lines of the section `header`
+    /// between Tasks and Notes, see `append_between`
+    pub fn move_task_to_section(&mut self, index: usize, header: &str) -> Option<Task> {
+        let task = self.remove_task(index)?;
+        let lines: Vec<String> = task.to_string().lines().map(String::from).collect();
+        self.append_between(header, &lines);
+        Some(task)
+    }
+    /// Remove the custom tags with `key` (e.g. `today:1`) from all open tasks,
+    /// returning how many tasks had one
+    pub fn clear_tag_key(&mut self, key: &str) -> usize {
+        self.retag_key(key, None)
+    }
+    /// Replace the custom tags with `key` of all open tasks by
+    /// `carried:<day>`, returning how many tasks had one
+    pub fn carry_tag_key(&mut self, key: &str, day: &Date) -> usize {
+        self.retag_key(key, Some(Tag::Custom(CARRIED_KEY.to_string(), day.to_string())))
+    }
+    fn retag_key(&mut self, key: &str, replacement: Option<Tag>) -> usize {
+        let mut count = 0;
+        for task in self.tasks.iter_mut().filter(|task| !task.is_completed()) {
+            let mut found = false;
+            while let Some(value) = task.tags().as_ref().and_then(|tags| tags.custom_value(key)) {
+                let tag = Tag::Custom(key.to_string(), value.to_string());
+                task.remove_tag(&tag);
+                found = true;
+            }
+            if !found {
+                continue;
+            }
+            if let Some(replacement) = &replacement {
+                task.add_tag(replacement.clone());
+            }
+            count += 1;
+        }
+        count
+    }
+    /// Move the task at `from` so that it ends up at index `to`
+    pub fn move_task(&mut self, from: usize, to: usize) {
+        let task = self.tasks.remove(from);
+        let to = to.min(self.tasks.len());
+        self.tasks.insert(to, task);
+    }
+    /// Insert `task` so that it ends up at `index`, at the end if `index` is
+    /// past the last task
+    pub fn insert_task(&mut self, index: usize, task: Task) {
+        let index = index.min(self.tasks.len());
+        self.tasks.insert(index, task);
+    }
+    /// Indices of all tasks in the order of `sort`
+    ///
+    /// The position of a task in the document is the final tiebreaker, tasks
+    /// that compare equal keep the order they are written in. All methods
+    /// adding, moving or removing tasks keep that position in line with the
+    /// file, so it serves as the sequence number of the manual order.
+    pub fn sorted_task_indices(&self, sort: TaskSort) -> Vec<usize> {
+        FilterSet {
+            filters: Vec::new(),
+            sort,
+        }
+        .apply(&self.tasks)
+    }
+    /// Indices of all tasks in the order they are written to the file, the
+    /// view before any sorting
+    pub fn original_order(&self) -> Vec<usize> {
+        (0..self.tasks.len()).collect()
+    }
+    /// Index of an open task that `task` looks like a duplicate of
+    ///
+    /// Open tasks with the same description match, whatever their tags or
+    /// priority. Completed tasks never match.
+    pub fn find_similar_task(&self, task: &Task) -> Option<usize> {
+        let description = task.description().trim();
+        self.tasks
+            .iter()
+            .position(|t| !t.is_completed() && t.description().trim() == description)
+    }
+    /// Index right after the last open task, i.e. where a completed task is
+    /// moved with `OnComplete::MoveToBottom`
+    pub fn after_last_open_task(&self) -> usize {
+        self.tasks
+            .iter()
+            .rposition(|t| !t.is_completed())
+            .map_or(0, |i| i + 1)
+    }
+    /// Complete the open tasks at `indices` and handle them per `on_complete`
+    ///
+    /// Already completed tasks and invalid indices are ignored. The next
+    /// occurrence of a recurring task takes its place (see `Task::complete`).
+    /// With `OnComplete::Archive` the completed tasks are removed from the
+    /// document and returned in their original order, ready for
+    /// `archive_tasks`.
+    pub fn complete_tasks(&mut self, indices: &[usize], on_complete: OnComplete) -> Vec<Task> {
+        let mut indices: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&i| self.tasks.get(i).is_some_and(|t| !t.is_completed()))
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        // Going backwards keeps the remaining (lower) indices valid
+        let mut archived = Vec::new();
+        for &index in indices.iter().rev() {
+            let mut index = index;
+            if let Some(next) = self.tasks[index].complete(&Date::now()) {
+                self.tasks.insert(index, next);
+                index += 1;
+            }
+            match on_complete {
+                OnComplete::InPlace => {}
+                OnComplete::MoveToBottom => {
+                    let task = self.tasks.remove(index);
+                    let position = self.after_last_open_task();
+                    self.tasks.insert(position, task);
+                }
+                OnComplete::Archive => archived.extend(self.remove_task(index)),
+            }
+        }
+        archived.reverse();
+        archived
+    }
+    /// Append tasks to the Tasks section of the archive document at `path`,
+    /// creating it if it does not exist yet
+    ///
+    /// All tasks are inserted with a single write of the file.
+    pub fn archive_tasks(path: &str, tasks: Vec<Task>) -> Result<(), io::Error> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                String::from_utf8(OrgDocument::default().to_bytes()?).map_err(io::Error::other)?
+            }
+            Err(e) => return Err(e),
+        };
+        std::fs::write(path, Self::insert_tasks(&content, &tasks)?)
+    }
+    /// Completed tasks per project and week for the last `weeks` weeks
+    ///
+    /// See `throughput_at`, using today as the reference date.
+    pub fn throughput(&self, weeks: usize, week_start: WeekStart) -> BTreeMap<String, Vec<usize>> {
+        self.throughput_at(weeks, &Date::now(), week_start)
+    }
+    /// Completed tasks per project (`+project`) and week, for the `weeks`
+    /// weeks up to and including the week of `today`, with weeks starting on
+    /// `week_start`
+    ///
+    /// Each vector is ordered from the oldest week to the current one. Tasks
+    /// with several projects count for each of them, tasks without a project
+    /// go into the `(no project)` bucket. Completed tasks without a completion
+    /// date are not counted.
+    pub fn throughput_at(
+        &self,
+        weeks: usize,
+        today: &Date,
+        week_start: WeekStart,
+    ) -> BTreeMap<String, Vec<usize>> {
+        let mut result: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        if weeks == 0 {
+            return result;
+        }
+        for task in self.tasks.iter().filter(|t| t.is_completed()) {
+            let Some(completed) = task.completion_date() else {
+                continue;
+            };
+            let age = completed.weeks_until(today, week_start);
+            if age < 0 || age >= weeks as i64 {
+                continue;
+            }
+            let mut projects: Vec<String> =
+                task.projects().iter().map(|project| format!("+{project}")).collect();
+            if projects.is_empty() {
+                projects.push(NO_PROJECT.to_string());
+            }
+            for project in projects {
+                let counts = result.entry(project).or_insert_with(|| vec![0; weeks]);
+                counts[weeks - 1 - age as usize] += 1;
+            }
+        }
+        result
+    }
+    /// Sum of the estimates of the tasks `filter` keeps, tasks without an
+    /// `est:` tag count as nothing
+    pub fn total_estimate(&self, filter: impl Fn(&Task) -> bool) -> Duration {
+        self.tasks.iter().filter(|task| filter(task)).filter_map(Task::estimate).sum()
+    }
+    /// Number of tasks carrying each tag, completed ones included
+    pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+        self.tally_tags(TagCollection::all_tags, |count, _| *count += 1)
+    }
+    /// Open and completed tasks of each project (`+project`)
+    ///
+    /// Tasks with several projects count for each of them, tasks without a
+    /// project are left out.
+    pub fn project_progress(&self) -> BTreeMap<String, (usize, usize)> {
+        self.tally_tags(TagCollection::project_tags, |(open, done), task| {
+            if task.is_completed() {
+                *done += 1;
+            } else {
+                *open += 1;
+            }
+        })
+    }
+    /// Fold the tasks into a value per tag, for the tags `tags_of` picks
+    ///
+    /// A task is added once to each of its tags, even if a tag is repeated.
+    fn tally_tags<T: Default>(
+        &self,
+        tags_of: fn(&TagCollection) -> Vec<String>,
+        mut add: impl FnMut(&mut T, &Task),
+    ) -> BTreeMap<String, T> {
+        let mut result: BTreeMap<String, T> = BTreeMap::new();
+        for task in self.tasks.iter() {
+            let tags: BTreeSet<String> =
+                task.tags().as_ref().map(tags_of).unwrap_or_default().into_iter().collect();
+            for tag in tags {
+                add(result.entry(tag).or_default(), task);
+            }
+        }
+        result
+    }
+    /// Open tasks grouped by context (`@context`), e.g. for an errands list
+    ///
+    /// Contexts are sorted by name and the tasks of each context keep their
+    /// document order. Tasks with several contexts are listed under each of
+    /// them, tasks without one under `(no context)`.
+    pub fn tasks_by_context(&self) -> BTreeMap<String, Vec<&Task>> {
+        let mut result: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+        for task in self.tasks.iter().filter(|t| !t.is_completed()) {
+            let mut contexts: Vec<String> =
+                task.contexts().iter().map(|context| format!("@{context}")).collect();
+            contexts.sort();
+            contexts.dedup();
+            if contexts.is_empty() {
+                contexts.push(NO_CONTEXT.to_string());
+            }
+            for context in contexts {
+                result.entry(context).or_default().push(task);
+            }
+        }
+        result
+    }
+    /// Indices of open waiting tasks grouped by who they wait on
+    ///
+    /// Persons are sorted by name and the tasks of each keep their document
+    /// order. A task waiting on several persons is listed under each of
+    /// them, one waiting on no one in particular under `(no one)`.
+    pub fn waiting_by_person(&self) -> BTreeMap<String, Vec<usize>> {
+        let mut result: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            if task.is_completed() || !task.is_waiting() {
+                continue;
+            }
+            let mut persons = task.waiting_for();
+            persons.sort();
+            persons.dedup();
+            if persons.is_empty() {
+                persons.push(NO_PERSON.to_string());
+            }
+            for person in persons {
+                result.entry(person).or_default().push(index);
+            }
+        }
+        result
+    }
     pub fn len(&self) -> (usize, usize) {
         (self.tasks.len(), self.notes.len())
     }
 
+    /// Indices of the tasks, most urgent first on today (see
+    /// `Task::urgency`), tasks as urgent as each other in document order
+    pub fn tasks_by_urgency(&self) -> Vec<usize> {
+        let today = Date::now();
+        let urgency: Vec<f64> = self.tasks.iter().map(|task| task.urgency(&today)).collect();
+        let mut indices: Vec<usize> = (0..self.tasks.len()).collect();
+        indices.sort_by(|&a, &b| urgency[b].total_cmp(&urgency[a]));
+        indices
+    }
+
     /// Collect all unique tags from tasks and notes for autocompletion
-    pub fn collect_unique_tags(&self) -> TagSuggestions {
+    ///
+    /// `from` leaves out the tags of completed tasks, all of them or those
+    /// completed too long ago, unless they also occur elsewhere.
+    pub fn collect_unique_tags(&self, from: SuggestFrom) -> TagSuggestions {
+        let today = Date::now();
         let mut context_tags = HashSet::new();
         let mut project_tags = HashSet::new();
         let mut person_tags = HashSet::new();
@@ -87,7 +654,15 @@ impl OrgDocument {
         let mut oneoff_tags = HashSet::new();
 
         // Collect tags from tasks
-        for task in &self.tasks {
+        let suggested = |task: &&Task| match (from, task.completion_date()) {
+            (SuggestFrom::All, _) => true,
+            (SuggestFrom::OpenOnly, _) => !task.is_completed(),
+            (SuggestFrom::Recent(days), Some(completed)) => {
+                completed.days_until(&today) <= i64::from(days)
+            }
+            (SuggestFrom::Recent(_), None) => true,
+        };
+        for task in self.tasks.iter().filter(suggested) {
             if let Some(tag_collection) = task.tags() {
                 context_tags.extend(tag_collection.context_tags());
                 project_tags.extend(tag_collection.project_tags());
@@ -121,83 +696,184 @@ impl OrgDocument {
         oneoff.sort();
 
         TagSuggestions {
-            context,
-            project,
-            person,
-            custom,
-            oneoff,
+            context: context.into(),
+            project: project.into(),
+            person: person.into(),
+            custom: custom.into(),
+            oneoff: oneoff.into(),
+            open_oneoffs: self.open_oneoffs(),
+            notes: self.note_titles(),
+        }
+    }
+}
+
+/// Header of the section holding the lines the parser set aside
+const QUARANTINE_HEADER: &str = "## Quarantine";
+
+/// A task line or note block the parser could not make sense of
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    /// Line number in the source file, the first line for note blocks
+    pub line: usize,
+    /// The rejected text, verbatim (one line per block line for notes)
+    pub content: String,
+    /// Why the text was rejected
+    pub message: String,
+}
+
+/// Bucket name for tasks without a project in `OrgDocument::throughput`
+pub const NO_PROJECT: &str = "(no project)";
+
+/// Group name for tasks without a context in `OrgDocument::tasks_by_context`
+pub const NO_CONTEXT: &str = "(no context)";
+
+/// Group name for tasks waiting on no one in `OrgDocument::waiting_by_person`
+pub const NO_PERSON: &str = "(no one)";
+
+/// Key of the tag `OrgDocument::carry_tag_key` leaves in place of the cleared
+/// ones, with the day they were set as its value
+pub const CARRIED_KEY: &str = "carried";
+
+/// Check lines meant for the preamble of a document
+///
+/// A line starting with `## ` would begin a section of its own and is
+/// refused, naming the first such line. No lines at all are fine.
+pub fn validate_preamble(lines: &[String]) -> Result<(), String> {
+    match lines.iter().position(|line| line.starts_with("## ")) {
+        Some(idx) => Err(format!("Line {} starts with '## ', which begins a section", idx + 1)),
+        None => Ok(()),
+    }
+}
+
+/// Number of consecutive weeks with at least one completion, counted back
+/// from the last entry of `counts` (as returned by `OrgDocument::throughput`)
+///
+/// A current week without completions yet does not break the streak.
+pub fn weekly_streak(counts: &[usize]) -> usize {
+    let weeks = match counts.split_last() {
+        Some((0, previous)) => previous,
+        _ => counts,
+    };
+    weeks.iter().rev().take_while(|&&c| c > 0).count()
+}
+
+/// Tags of one kind, in their order, with their lowercase forms kept sorted
+///
+/// Finding the tags that start with a prefix is a binary search instead of
+/// lowercasing every tag on each keystroke, which matters for documents with
+/// thousands of tags (e.g. ticket ids). Matches keep the order of the tags.
+#[derive(Debug, Clone, Default)]
+pub struct TagIndex {
+    tags: Vec<String>,
+    /// Lowercase form of each tag with its position in `tags`, sorted
+    lowercase: Vec<(String, usize)>,
+}
+
+impl TagIndex {
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Tags starting with `prefix`, ignoring case
+    pub fn starting_with(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        let first = self.lowercase.partition_point(|(tag, _)| tag.as_str() < prefix.as_str());
+        let matches =
+            self.lowercase[first..].iter().take_while(|(tag, _)| tag.starts_with(&prefix));
+        let mut positions: Vec<usize> = matches.map(|(_, position)| *position).collect();
+        // Short prefixes match a good part of the tags, marking them is
+        // cheaper than sorting them back into their order
+        if positions.len() * 8 < self.tags.len() {
+            positions.sort_unstable();
+            return positions.into_iter().map(|position| self.tags[position].clone()).collect();
         }
+        let mut matched = vec![false; self.tags.len()];
+        for position in positions {
+            matched[position] = true;
+        }
+        let tags = self.tags.iter().zip(matched);
+        tags.filter(|(_, matched)| *matched).map(|(tag, _)| tag.clone()).collect()
+    }
+}
+
+impl From<Vec<String>> for TagIndex {
+    fn from(tags: Vec<String>) -> Self {
+        let mut lowercase: Vec<(String, usize)> =
+            tags.iter().enumerate().map(|(i, tag)| (tag.to_lowercase(), i)).collect();
+        lowercase.sort_unstable();
+        Self { tags, lowercase }
     }
 }
 
 /// Collection of tag suggestions for autocompletion
 #[derive(Debug, Clone)]
 pub struct TagSuggestions {
-    pub context: Vec<String>,   // @context
-    pub project: Vec<String>,   // +project
-    pub person: Vec<String>,    // p:person
-    pub custom: Vec<String>,    // key:value
-    pub oneoff: Vec<String>,    // !oneoff
+    pub context: TagIndex,   // @context
+    pub project: TagIndex,   // +project
+    pub person: TagIndex,    // p:person
+    pub custom: TagIndex,    // key:value
+    pub oneoff: TagIndex,    // !oneoff
+    /// One-off tags already used by an open task, see `open_oneoffs`
+    pub open_oneoffs: HashMap<String, usize>,
+    /// Title and guid of the notes `n:` links to, see `note_titles`
+    pub notes: Vec<(String, String)>,
 }
 
 impl TagSuggestions {
+    fn indices(&self) -> [&TagIndex; 5] {
+        [&self.context, &self.project, &self.person, &self.custom, &self.oneoff]
+    }
+
     /// Get all tags as a flat list for general autocompletion
     pub fn all_tags(&self) -> Vec<String> {
-        let mut all = Vec::new();
-        all.extend(self.context.clone());
-        all.extend(self.project.clone());
-        all.extend(self.person.clone());
-        all.extend(self.custom.clone());
-        all.extend(self.oneoff.clone());
+        let mut all: Vec<String> =
+            self.indices().iter().flat_map(|index| index.tags()).cloned().collect();
         all.sort();
         all
     }
 
+    /// Whether `key` starts a `key:value` tag worth completing: one of the
+    /// reserved keys like `p` or `est`, or the key of a known custom tag
+    pub fn knows_key(&self, key: &str) -> bool {
+        // Keys are written as they are, `Meeting:` is no `meeting:` tag
+        let prefix = format!("{key}:");
+        RESERVED_KEYS.contains(&key)
+            || self.custom.starting_with(&prefix).iter().any(|tag| tag.starts_with(&prefix))
+    }
+
     /// Get suggestions that match a given prefix
     pub fn matching_prefix(&self, prefix: &str) -> Vec<String> {
-        self.all_tags()
-            .into_iter()
-            .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-            .collect()
+        let mut matching: Vec<String> =
+            self.indices().iter().flat_map(|index| index.starting_with(prefix)).collect();
+        matching.sort();
+        matching
     }
 
     /// Get suggestions for a specific tag type based on prefix
     pub fn suggestions_for_prefix(&self, prefix: &str) -> Vec<String> {
         if prefix.starts_with('@') {
             // Context tags
-            self.context
-                .iter()
-                .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-                .cloned()
-                .collect()
+            self.context.starting_with(prefix)
         } else if prefix.starts_with('+') {
             // Project tags
-            self.project
-                .iter()
-                .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-                .cloned()
-                .collect()
+            self.project.starting_with(prefix)
         } else if prefix.starts_with('p') && prefix.contains(':') {
             // Person tags
-            self.person
-                .iter()
-                .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-                .cloned()
-                .collect()
+            self.person.starting_with(prefix)
         } else if prefix.starts_with('!') {
             // One-off tags
-            self.oneoff
-                .iter()
-                .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-                .cloned()
-                .collect()
+            self.oneoff.starting_with(prefix)
         } else if prefix.contains(':') {
             // Custom tags
-            self.custom
-                .iter()
-                .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
-                .cloned()
-                .collect()
+            self.custom.starting_with(prefix)
         } else {
             // Fallback to all tags
             self.matching_prefix(prefix)
@@ -205,72 +881,159 @@ impl TagSuggestions {
     }
 }
 
+#[derive(Default)]
 enum OrgDocumentParser {
+    #[default]
     BeforeTasks,
     InTasks,
     BetweenTasksAndNotes,
-    InNotes(Vec<String>),
+    /// Start line and lines of the note block being collected
+    InNotes(usize, Vec<String>),
+    /// Lines that were quarantined when the document was last written
+    InQuarantine,
     AfterNotes,
 }
 
-impl Default for OrgDocumentParser {
-    fn default() -> Self {
-        Self::BeforeTasks
-    }
-}
-
 impl OrgDocumentParser {
-    fn parse(&mut self, line: &str, doc: &mut OrgDocument) -> IoResult<()> {
+    /// Whether `line` ends the Tasks section
+    fn leaves_tasks(&self, line: &str) -> bool {
+        matches!(self, OrgDocumentParser::InTasks) && line.starts_with("## ")
+    }
+    fn parse(
+        &mut self,
+        line: &str,
+        line_no: usize,
+        doc: &mut OrgDocument,
+        lenient: bool,
+    ) -> IoResult<()> {
         match (&self, line) {
             (OrgDocumentParser::BeforeTasks, "## Tasks") => *self = OrgDocumentParser::InTasks,
-            (OrgDocumentParser::InTasks, "## Notes") => {
-                *self = OrgDocumentParser::InNotes(Vec::new())
+            (OrgDocumentParser::InTasks | OrgDocumentParser::InQuarantine, "## Notes") => {
+                *self = OrgDocumentParser::InNotes(0, Vec::new())
             }
+            (
+                OrgDocumentParser::InTasks
+                | OrgDocumentParser::BetweenTasksAndNotes
+                | OrgDocumentParser::AfterNotes,
+                QUARANTINE_HEADER,
+            ) => *self = OrgDocumentParser::InQuarantine,
             (OrgDocumentParser::InTasks, l) if l.starts_with("## ") => {
                 doc.between.push(line.to_string().clone());
                 *self = OrgDocumentParser::BetweenTasksAndNotes;
             }
             (OrgDocumentParser::BetweenTasksAndNotes, "## Notes") => {
-                *self = OrgDocumentParser::InNotes(Vec::new())
+                *self = OrgDocumentParser::InNotes(0, Vec::new())
             }
-            (OrgDocumentParser::InNotes(note_vec), l)
+            (OrgDocumentParser::InNotes(start, note_vec), l)
                 if (l.starts_with("## ") | l.starts_with("### ")) =>
             {
-                if !note_vec.is_empty() {
-                    doc.notes.push(Note::from(note_vec.clone()));
-                }
-                if l.starts_with("## ") {
+                push_note(doc, *start, note_vec, lenient)?;
+                if l == QUARANTINE_HEADER {
+                    *self = OrgDocumentParser::InQuarantine
+                } else if l.starts_with("## ") {
                     doc.post.push(l.to_string().clone());
                     *self = OrgDocumentParser::AfterNotes
                 } else {
-                    *self = OrgDocumentParser::InNotes(vec![line.to_string()])
+                    *self = OrgDocumentParser::InNotes(line_no, vec![line.to_string()])
                 }
             }
+            (OrgDocumentParser::InQuarantine, l) if l.starts_with("## ") => {
+                doc.post.push(l.to_string());
+                *self = OrgDocumentParser::AfterNotes
+            }
             (OrgDocumentParser::BeforeTasks, _) => doc.preample.push(line.to_string().clone()),
-            (OrgDocumentParser::InTasks, _) => doc.tasks.push(Task::from_str(line).unwrap()),
+            (OrgDocumentParser::InTasks, l) if l.starts_with(DETAIL_INDENT) => {
+                let detail = &l[DETAIL_INDENT.len()..];
+                // Details belong to the line above, which might have been rejected
+                let last_task = doc.tasks.last().and_then(|t| t.source_line()).unwrap_or(0);
+                let last_issue = doc.quarantine.last().map_or(0, |i| i.line);
+                match doc.tasks.last_mut() {
+                    Some(task) if last_task > last_issue => task.push_detail(detail),
+                    _ => {
+                        let msg = "Detail line without a valid task above it".to_string();
+                        reject(doc, line_no, vec![line.to_string()], msg, lenient)?
+                    }
+                }
+            }
+            // Kept for the next task, the section's closing comments otherwise
+            (OrgDocumentParser::InTasks, l) if is_task_comment(l) => {
+                doc.closing_comments.push(line.to_string())
+            }
+            (OrgDocumentParser::InTasks, _) => match Task::from_str(line) {
+                Ok(mut task) => {
+                    task.set_source_line(line_no);
+                    task.set_comments(std::mem::take(&mut doc.closing_comments));
+                    doc.tasks.push(task)
+                }
+                Err(error) => reject(doc, line_no, vec![line.to_string()], error.into(), lenient)?,
+            },
             (OrgDocumentParser::BetweenTasksAndNotes, _) => doc.between.push(line.to_string()),
-            (OrgDocumentParser::InNotes(notes_vec), _) => {
+            (OrgDocumentParser::InNotes(start, notes_vec), _) => {
                 let mut t = notes_vec.clone();
                 t.push(line.to_string());
-                *self = OrgDocumentParser::InNotes(t)
+                *self = OrgDocumentParser::InNotes(*start, t)
             }
+            (OrgDocumentParser::InQuarantine, _) => doc.quarantine.push(LintIssue {
+                line: line_no,
+                content: line.to_string(),
+                message: "Quarantined when the document was last written".to_string(),
+            }),
             (OrgDocumentParser::AfterNotes, _) => {
                 doc.post.push(line.to_string());
             }
         }
         Ok(())
     }
-    fn finish(&mut self, doc: &mut OrgDocument) -> IoResult<()> {
+    fn finish(&mut self, doc: &mut OrgDocument, lenient: bool) -> IoResult<()> {
         match self {
-            OrgDocumentParser::InNotes(vec) => {
-                if !vec.is_empty() {
-                    doc.notes.push(Note::from(vec.clone()));
-                    Ok(())
-                } else {
-                    Ok(())
-                }
-            }
+            OrgDocumentParser::InNotes(start, vec) => push_note(doc, *start, vec, lenient),
             _ => Ok(()),
         }
     }
 }
+
+/// Whether `line` of the Tasks section is a comment rather than a task: it
+/// starts with `//`, or with `#`s and a space without being a `## ` section
+/// header; `#42 fix login` is a task
+fn is_task_comment(line: &str) -> bool {
+    let hashes = line.trim_start_matches('#');
+    line.starts_with("//")
+        || hashes.len() < line.len()
+            && (hashes.is_empty() || hashes.starts_with(' '))
+            && !line.starts_with("## ")
+}
+
+/// Add the note block `lines` starting at line `start` to the document
+fn push_note(doc: &mut OrgDocument, start: usize, lines: &[String], lenient: bool) -> IoResult<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    match Note::from_vec(lines.to_vec()) {
+        Ok(mut note) => {
+            note.set_source_line(start);
+            doc.notes.push(note);
+            Ok(())
+        }
+        Err(msg) => reject(doc, start, lines.to_vec(), msg, lenient),
+    }
+}
+
+/// Quarantine rejected `lines` in lenient mode, fail otherwise
+fn reject(
+    doc: &mut OrgDocument,
+    line_no: usize,
+    lines: Vec<String>,
+    message: String,
+    lenient: bool,
+) -> IoResult<()> {
+    if !lenient {
+        let msg = format!("line {}: {}", line_no, message);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    }
+    doc.quarantine.push(LintIssue {
+        line: line_no,
+        content: lines.join("\n"),
+        message,
+    });
+    Ok(())
+}
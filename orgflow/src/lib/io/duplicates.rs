@@ -0,0 +1,165 @@
+use super::OrgDocument;
+
+/// Most single-character edits between the descriptions of two open tasks
+/// for `OrgDocument::find_duplicate_candidates` to pair them
+pub const DUPLICATE_DISTANCE: usize = 2;
+
+/// Descriptions shorter than this only pair when they are the same, "Call
+/// Ann" and "Call Bob" are not duplicates of each other
+const NEAR_MIN_CHARS: usize = 10;
+
+impl OrgDocument {
+    /// Pairs of open tasks that look like the same task written twice,
+    /// as task indices with the earlier one first
+    ///
+    /// Descriptions are compared lowercased, with their tags left out and
+    /// runs of whitespace collapsed. They pair when they are the same or
+    /// within `DUPLICATE_DISTANCE` edits of each other.
+    pub fn find_duplicate_candidates(&self) -> Vec<(usize, usize)> {
+        let open: Vec<(usize, Vec<char>)> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| !task.is_completed())
+            .map(|(index, task)| (index, normalized(&task.description_without_tags())))
+            .filter(|(_, description)| !description.is_empty())
+            .collect();
+        let mut pairs = Vec::new();
+        for (position, (first, description)) in open.iter().enumerate() {
+            for (second, other) in &open[position + 1..] {
+                if is_duplicate(description, other) {
+                    pairs.push((*first, *second));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Merge the task at `other` into the one at `keep` and remove it
+    ///
+    /// The kept task gains the tags and detail lines it does not have yet
+    /// and the earlier of both creation dates. Returns where the kept task
+    /// ends up once `other` is gone.
+    pub fn merge_tasks(&mut self, keep: usize, other: usize) -> Result<usize, String> {
+        if keep == other {
+            return Err(format!("Task {keep} cannot be merged into itself"));
+        }
+        for index in [keep, other] {
+            if index >= self.tasks.len() {
+                return Err(format!("There is no task {index}"));
+            }
+        }
+        let merged = self.remove_task(other).expect("checked above");
+        let keep = if other < keep { keep - 1 } else { keep };
+        self.tasks[keep].absorb(merged);
+        Ok(keep)
+    }
+}
+
+/// Description as compared for duplicates, see `find_duplicate_candidates`
+fn normalized(description: &str) -> Vec<char> {
+    description.to_lowercase().chars().collect()
+}
+
+fn is_duplicate(first: &[char], second: &[char]) -> bool {
+    if first == second {
+        return true;
+    }
+    let shorter = first.len().min(second.len());
+    shorter >= NEAR_MIN_CHARS
+        && first.len().abs_diff(second.len()) <= DUPLICATE_DISTANCE
+        && edit_distance(first, second) <= DUPLICATE_DISTANCE
+}
+
+/// Levenshtein distance, counted in characters
+fn edit_distance(first: &[char], second: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+    for (i, a) in first.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in second.iter().enumerate() {
+            let substitute = previous[j] + usize::from(a != b);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::Task;
+
+    fn with_tasks(lines: &[&str]) -> OrgDocument {
+        let mut document = OrgDocument::default();
+        for line in lines {
+            document.push_task(Task::from_str(line).unwrap());
+        }
+        document
+    }
+
+    #[test]
+    fn same_descriptions_pair() {
+        let document = with_tasks(&[
+            "Renew passport +travel",
+            "Water plants",
+            "2025-03-01 renew   Passport @errands",
+            "RENEW PASSPORT",
+        ]);
+        assert_eq!(document.find_duplicate_candidates(), [(0, 2), (0, 3), (2, 3)]);
+        // Short ones as well, as long as they are the same
+        let document = with_tasks(&["Call Ann", "Call Bob", "call ann +family"]);
+        assert_eq!(document.find_duplicate_candidates(), [(0, 2)]);
+    }
+
+    #[test]
+    fn near_descriptions_pair_within_the_distance() {
+        let document = with_tasks(&[
+            "Book dentist appointment",
+            "Book dentsit appointment",
+            "Book dentist appointments",
+            "Book the dentist appointment",
+        ]);
+        // Two edits apart pair, four apart do not
+        assert_eq!(edit_distance(&normalized("dentist"), &normalized("dentsit")), 2);
+        assert_eq!(document.find_duplicate_candidates(), [(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn completed_tasks_are_left_out() {
+        let document = with_tasks(&[
+            "x 2025-03-02 2025-03-01 Renew passport",
+            "Renew passport",
+            "x Renew passport",
+        ]);
+        assert!(document.find_duplicate_candidates().is_empty());
+    }
+
+    #[test]
+    fn merging_keeps_the_earlier_date_and_all_tags() {
+        let mut document = with_tasks(&[
+            "Water plants",
+            "2025-03-04 Renew passport +travel due:2025-04-01",
+            "(B) 2025-02-20 renew passport @errands +travel due:2025-05-01",
+        ]);
+        assert_eq!(document.merge_tasks(2, 1), Ok(1));
+        assert_eq!(document.tasks.len(), 2);
+        assert_eq!(
+            document.tasks[1].to_string(),
+            "(B) 2025-02-20 renew passport @errands +travel due:2025-05-01"
+        );
+        let mut document = with_tasks(&[
+            "2025-03-04 Renew passport +travel due:2025-04-01",
+            "2025-02-20 renew passport @errands due:2025-05-01",
+        ]);
+        assert_eq!(document.merge_tasks(0, 1), Ok(0));
+        assert_eq!(
+            document.tasks[0].to_string(),
+            "2025-02-20 Renew passport +travel due:2025-04-01 @errands"
+        );
+        assert!(document.merge_tasks(0, 0).is_err());
+        assert!(document.merge_tasks(0, 1).is_err());
+    }
+}
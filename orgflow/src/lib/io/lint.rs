@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use super::{LintIssue, OrgDocument};
+use crate::{Note, Task};
+use crate::core::tags::guid::Guid;
+
+impl OrgDocument {
+    /// Everything that needs attention: the quarantined lines, tasks whose
+    /// dates do not fit (see `Task::date_problem`), tasks blocked by an id no
+    /// task has, notes sharing a guid with an earlier note and open tasks
+    /// sharing a one-off tag with an earlier open task, ordered by line
+    pub fn lint(&self) -> Vec<LintIssue> {
+        let mut issues = self.quarantine.clone();
+        for task in self.tasks.iter() {
+            if let Some(message) = task.date_problem() {
+                issues.push(LintIssue {
+                    line: task.source_line().unwrap_or_default(),
+                    content: task.to_string(),
+                    message,
+                });
+            }
+            for id in task.blocked_by() {
+                if self.find_task_by_id(id).is_none() {
+                    issues.push(LintIssue {
+                        line: task.source_line().unwrap_or_default(),
+                        content: task.to_string(),
+                        message: format!("Blocked by {id}, but no task has this id"),
+                    });
+                }
+            }
+        }
+        let mut first_open: HashMap<String, &Task> = HashMap::new();
+        for task in self.tasks.iter().filter(|task| !task.is_completed()) {
+            for oneoff in task.tags().iter().flat_map(|tags| tags.oneoff_tags()) {
+                match first_open.get(&oneoff) {
+                    Some(first) => issues.push(LintIssue {
+                        line: task.source_line().unwrap_or_default(),
+                        content: task.to_string(),
+                        message: format!(
+                            "Duplicate one-off {oneoff}, already on '{}'",
+                            first.description()
+                        ),
+                    }),
+                    None => _ = first_open.insert(oneoff, task),
+                }
+            }
+        }
+        let mut first_use: HashMap<String, &Note> = HashMap::new();
+        for note in self.notes.iter() {
+            let guid = note.guid().to_string();
+            match first_use.get(&guid) {
+                Some(first) => issues.push(LintIssue {
+                    line: note.source_line().unwrap_or_default(),
+                    content: title_line(note),
+                    message: format!("Duplicate guid {guid}, already used by '{}'", first.title()),
+                }),
+                None => _ = first_use.insert(guid, note),
+            }
+        }
+        issues.sort_by_key(|issue| issue.line);
+        issues
+    }
+
+    /// One-off tags of the open tasks (like `!ticket-123`) with the index of
+    /// the first open task carrying each
+    pub fn open_oneoffs(&self) -> HashMap<String, usize> {
+        let mut index = HashMap::new();
+        for (idx, task) in self.tasks.iter().enumerate().filter(|(_, t)| !t.is_completed()) {
+            for oneoff in task.tags().iter().flat_map(|tags| tags.oneoff_tags()) {
+                index.entry(oneoff).or_insert(idx);
+            }
+        }
+        index
+    }
+
+    /// Give notes with a duplicate or missing guid a fresh one
+    ///
+    /// The first note using a guid keeps it, so links to it (e.g. `n:` tags
+    /// on tasks) still point to the same note. Quarantined note blocks whose
+    /// metadata lacks a (valid) guid are parsed again with a new one and move
+    /// back between the notes where they were in the file. Returns one entry
+    /// per change, an empty list means the document was left untouched.
+    pub fn repair_guids(&mut self) -> Vec<LintIssue> {
+        let mut changes = Vec::new();
+
+        let mut quarantine = Vec::new();
+        for issue in std::mem::take(&mut self.quarantine) {
+            match with_new_guid(&issue) {
+                Some(mut note) => {
+                    note.set_source_line(issue.line);
+                    changes.push(LintIssue {
+                        line: issue.line,
+                        content: title_line(&note),
+                        message: format!("Added missing guid {}", note.guid()),
+                    });
+                    let position = self
+                        .notes
+                        .iter()
+                        .position(|n| n.source_line().is_some_and(|line| line > issue.line))
+                        .unwrap_or(self.notes.len());
+                    self.notes.insert(position, note);
+                }
+                None => quarantine.push(issue),
+            }
+        }
+        self.quarantine = quarantine;
+
+        let mut seen: HashMap<String, String> = HashMap::new();
+        for note in self.notes.iter_mut() {
+            let guid = note.guid().to_string();
+            if let Some(first) = seen.get(&guid) {
+                let message = format!("Replaced guid {guid} already used by '{first}'");
+                note.set_guid(Guid::new());
+                changes.push(LintIssue {
+                    line: note.source_line().unwrap_or_default(),
+                    content: title_line(note),
+                    message: format!("{message} with {}", note.guid()),
+                });
+            } else {
+                seen.insert(guid, note.title().to_string());
+            }
+        }
+        changes.sort_by_key(|change| change.line);
+        changes
+    }
+}
+
+/// Title line of a note as written in the document
+fn title_line(note: &Note) -> String {
+    format!("{} {}", "#".repeat(note.level()), note.title())
+}
+
+/// Parse a quarantined note block again with a fresh guid in its metadata,
+/// `None` if it is not a note block or still invalid
+fn with_new_guid(issue: &LintIssue) -> Option<Note> {
+    let mut lines: Vec<String> = issue.content.lines().map(String::from).collect();
+    if !lines.first()?.starts_with('#') {
+        return None;
+    }
+    let metadata = lines.get(1)?.strip_prefix("> ")?;
+    let mut fields: Vec<String> = metadata
+        .split_whitespace()
+        .filter(|field| !field.starts_with("guid:"))
+        .map(String::from)
+        .collect();
+    if fields.len() < 2 {
+        return None;
+    }
+    fields.insert(2, format!("guid:{}", Guid::new()));
+    lines[1] = format!("> {}", fields.join(" "));
+    Note::from_vec(lines).ok()
+}
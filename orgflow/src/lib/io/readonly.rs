@@ -0,0 +1,62 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use super::OrgDocument;
+use crate::Configuration;
+
+/// Line in the preamble of a document that keeps orgflow from changing it
+pub const READONLY_MARKER: &str = "#orgflow:readonly";
+
+/// Why a document must not be changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnlyReason {
+    /// The path is listed in `ORGFLOW_READONLY`
+    Configured,
+    /// The preamble contains `READONLY_MARKER`
+    Marked,
+    /// The file is not writable
+    Permissions,
+}
+
+impl Display for ReadOnlyReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadOnlyReason::Configured => write!(f, "listed in ORGFLOW_READONLY"),
+            ReadOnlyReason::Marked => write!(f, "marked with {READONLY_MARKER}"),
+            ReadOnlyReason::Permissions => write!(f, "file is not writable"),
+        }
+    }
+}
+
+impl OrgDocument {
+    /// Whether the preamble contains the `#orgflow:readonly` line
+    pub fn is_marked_readonly(&self) -> bool {
+        self.preample.iter().any(|line| line.trim() == READONLY_MARKER)
+    }
+
+    /// Whether the permissions of the file at `path` forbid writing it,
+    /// `false` for a file that does not exist yet
+    pub fn is_readonly_file(path: &str) -> bool {
+        fs::metadata(path).is_ok_and(|meta| meta.permissions().readonly())
+    }
+
+    /// Why the document loaded from `path` must not be changed, `None` if it
+    /// may be
+    ///
+    /// Checks the configured list first, then the marker and the file
+    /// permissions last.
+    pub fn readonly_reason(&self, path: &str) -> Option<ReadOnlyReason> {
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        let path_buf = canonical(Path::new(path));
+        if Configuration::readonly_paths().iter().any(|p| canonical(p) == path_buf) {
+            Some(ReadOnlyReason::Configured)
+        } else if self.is_marked_readonly() {
+            Some(ReadOnlyReason::Marked)
+        } else if Self::is_readonly_file(path) {
+            Some(ReadOnlyReason::Permissions)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use super::OrgDocument;
+use crate::Note;
+
+impl OrgDocument {
+    /// Indices of the note at `index` and the notes nested below it, up to
+    /// the next note on its level or above
+    pub fn note_block(&self, index: usize) -> Range<usize> {
+        let Some(note) = self.notes.get(index) else {
+            return index..index;
+        };
+        let end = self.notes[index + 1..]
+            .iter()
+            .position(|next| next.level() <= note.level())
+            .map_or(self.notes.len(), |offset| index + 1 + offset);
+        index..end
+    }
+
+    /// Note before the one at `index` within the same parent, see `move_note`
+    pub fn previous_sibling_note(&self, index: usize) -> Option<usize> {
+        let siblings = self.sibling_notes(index);
+        let position = siblings.iter().position(|&sibling| sibling == index)?;
+        position.checked_sub(1).map(|before| siblings[before])
+    }
+
+    /// Note after the one at `index` within the same parent, see `move_note`
+    pub fn next_sibling_note(&self, index: usize) -> Option<usize> {
+        let siblings = self.sibling_notes(index);
+        let position = siblings.iter().position(|&sibling| sibling == index)?;
+        siblings.get(position + 1).copied()
+    }
+
+    /// Move the note at `from` together with the notes nested below it (see
+    /// `note_block`) so that it ends up at index `to`
+    ///
+    /// The note stays within the note it is nested in, and lands before or
+    /// after the block of one of its siblings, never inside it. Other moves
+    /// are refused and leave the notes as they are.
+    pub fn move_note(&mut self, from: usize, to: usize) -> Result<(), String> {
+        if from >= self.notes.len() {
+            return Err(format!("There is no note {from}"));
+        }
+        let block = self.note_block(from);
+        let siblings = self.sibling_notes(from);
+        // Where the block can start once it has been taken out
+        let taken_out = |index: usize| if index > from { index - block.len() } else { index };
+        let last_end = siblings.last().map_or(block.end, |&last| self.note_block(last).end);
+        let mut targets: Vec<usize> = siblings.iter().map(|&sibling| taken_out(sibling)).collect();
+        targets.push(taken_out(last_end));
+        if !targets.contains(&to) {
+            let title = self.notes[from].title();
+            let inside = targets[0] <= to && to <= targets[targets.len() - 1];
+            return Err(match inside {
+                true => format!("Note '{title}' would end up within another note"),
+                false => format!("Note '{title}' cannot leave the note it is nested in"),
+            });
+        }
+        let moved: Vec<Note> = self.notes.drain(block).collect();
+        self.notes.splice(to..to, moved);
+        Ok(())
+    }
+
+    /// First notes of the blocks within the note the one at `index` is
+    /// nested in (or within all notes), its own block included
+    fn sibling_notes(&self, index: usize) -> Vec<usize> {
+        let Some(note) = self.notes.get(index) else {
+            return Vec::new();
+        };
+        let parent = self.notes[..index].iter().rposition(|before| before.level() < note.level());
+        let region = match parent {
+            Some(parent) => parent + 1..self.note_block(parent).end,
+            None => 0..self.notes.len(),
+        };
+        let mut siblings = Vec::new();
+        let mut start = region.start;
+        while start < region.end {
+            siblings.push(start);
+            start = self.note_block(start).end;
+        }
+        siblings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str =
+        "> cre:2025-01-01 mod:2025-01-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+
+    /// Notes from their heading lines, `####` ones nested in the `###` above
+    fn document(headings: &[&str]) -> OrgDocument {
+        let mut document = OrgDocument::default();
+        for heading in headings {
+            let note = Note::from_vec(vec![heading.to_string(), METADATA.to_string()]).unwrap();
+            document.push_note(note);
+        }
+        document
+    }
+
+    fn titles(document: &OrgDocument) -> Vec<&str> {
+        document.notes.iter().map(|note| note.title()).collect()
+    }
+
+    const TRIPS: [&str; 5] =
+        ["### Reference", "### Trips", "#### Flights", "#### Hotels", "### Shopping"];
+
+    #[test]
+    fn blocks_and_siblings() {
+        let document = document(&TRIPS);
+        assert_eq!(document.note_block(1), 1..4);
+        assert_eq!(document.note_block(2), 2..3);
+        assert_eq!(document.note_block(4), 4..5);
+        assert_eq!(document.note_block(5), 5..5);
+        let around = |index| {
+            (document.previous_sibling_note(index), document.next_sibling_note(index))
+        };
+        assert_eq!(around(1), (Some(0), Some(4)));
+        assert_eq!(around(2), (None, Some(3)));
+        assert_eq!(around(3), (Some(2), None));
+        assert_eq!(around(9), (None, None));
+    }
+
+    #[test]
+    fn parents_move_with_their_children() {
+        let mut document = document(&TRIPS);
+        // A parent with two children above an unrelated note
+        document.move_note(1, 0).unwrap();
+        assert_eq!(titles(&document), ["Trips", "Flights", "Hotels", "Reference", "Shopping"]);
+        // And below the last one, counted without the moved notes
+        document.move_note(0, 2).unwrap();
+        assert_eq!(titles(&document), ["Reference", "Shopping", "Trips", "Flights", "Hotels"]);
+        // Children keep their level, a note without children moves alone
+        assert_eq!(document.notes[3].level(), 4);
+        document.move_note(1, 4).unwrap();
+        assert_eq!(titles(&document), ["Reference", "Trips", "Flights", "Hotels", "Shopping"]);
+    }
+
+    #[test]
+    fn children_stay_within_their_parent() {
+        let mut document = document(&TRIPS);
+        document.move_note(2, 3).unwrap();
+        assert_eq!(titles(&document), ["Reference", "Trips", "Hotels", "Flights", "Shopping"]);
+        for to in [0, 1, 4] {
+            let error = document.move_note(3, to).unwrap_err();
+            assert_eq!(error, "Note 'Flights' cannot leave the note it is nested in", "{to}");
+        }
+        // Nor does a note end up between a parent and its children
+        let error = document.move_note(0, 2).unwrap_err();
+        assert_eq!(error, "Note 'Reference' would end up within another note");
+        assert!(document.move_note(5, 0).is_err());
+        assert_eq!(titles(&document), ["Reference", "Trips", "Hotels", "Flights", "Shopping"]);
+    }
+}
@@ -0,0 +1,268 @@
+use std::str::FromStr;
+
+use super::OrgDocument;
+use crate::{Tag, TagCollection};
+
+/// Which parts of a document `OrgDocument::replace_text` looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaceScope {
+    Tasks,
+    Notes,
+    #[default]
+    Both,
+}
+
+/// How `OrgDocument::replace_text` matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaceOptions {
+    /// Match upper and lower case exactly
+    pub case_sensitive: bool,
+    /// Only match whole words, not parts of longer words
+    pub whole_word: bool,
+    pub scope: ReplaceScope,
+    /// Also replace in tags like `+project`, which are skipped otherwise
+    pub include_tags: bool,
+}
+
+impl Default for ReplaceOptions {
+    fn default() -> Self {
+        Self {
+            case_sensitive: true,
+            whole_word: false,
+            scope: ReplaceScope::Both,
+            include_tags: false,
+        }
+    }
+}
+
+/// Where `OrgDocument::replace_text` changed something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeTarget {
+    /// Description of the task at the index
+    Task(usize),
+    /// Tags of the task at the index
+    TaskTags(usize),
+    /// Content line `line` of the note at index `note`
+    NoteLine { note: usize, line: usize },
+    /// Tags of the note at the index
+    NoteTags(usize),
+}
+
+/// A changed text, before and after the replacement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeRecord {
+    pub target: ChangeTarget,
+    pub before: String,
+    pub after: String,
+}
+
+impl OrgDocument {
+    /// Changes `replace_text` would make, without making them
+    pub fn preview_replace(
+        &self,
+        find: &str,
+        replace: &str,
+        options: ReplaceOptions,
+    ) -> Vec<ChangeRecord> {
+        let mut changes = Vec::new();
+        if find.is_empty() {
+            return changes;
+        }
+        let mut record = |target, before: &str, skip_tags| {
+            let after = replace_in(before, find, replace, options, skip_tags);
+            if after != before {
+                changes.push(ChangeRecord {
+                    target,
+                    before: before.to_string(),
+                    after,
+                });
+            }
+        };
+
+        if options.scope != ReplaceScope::Notes {
+            for (idx, task) in self.tasks.iter().enumerate() {
+                record(ChangeTarget::Task(idx), task.description(), !options.include_tags);
+                if options.include_tags
+                    && let Some(tags) = task.tags()
+                {
+                    record(ChangeTarget::TaskTags(idx), &tags.to_string(), false);
+                }
+            }
+        }
+        if options.scope != ReplaceScope::Tasks {
+            for (idx, note) in self.notes.iter().enumerate() {
+                for (line, text) in note.content().iter().enumerate() {
+                    let target = ChangeTarget::NoteLine { note: idx, line };
+                    record(target, text, !options.include_tags);
+                }
+                if options.include_tags {
+                    record(ChangeTarget::NoteTags(idx), &note.tags().to_string(), false);
+                }
+            }
+        }
+        // Tags that would no longer parse are left alone
+        changes.retain(|change| match change.target {
+            ChangeTarget::TaskTags(_) | ChangeTarget::NoteTags(_) => {
+                TagCollection::from_str(&change.after).is_ok()
+            }
+            _ => true,
+        });
+        changes
+    }
+
+    /// Replace `find` with `replace` in task descriptions and note content
+    ///
+    /// Words in note content that are tags are skipped unless
+    /// `options.include_tags` is set, which also replaces in the tags of
+    /// tasks and notes. Returns what changed, in document order.
+    pub fn replace_text(
+        &mut self,
+        find: &str,
+        replace: &str,
+        options: ReplaceOptions,
+    ) -> Vec<ChangeRecord> {
+        let changes = self.preview_replace(find, replace, options);
+        for change in changes.iter() {
+            let after = change.after.clone();
+            match change.target {
+                ChangeTarget::Task(idx) => self.tasks[idx].set_description(after),
+                ChangeTarget::TaskTags(idx) => {
+                    if let Ok(tags) = TagCollection::from_str(&after) {
+                        self.tasks[idx].set_tags(tags);
+                    }
+                }
+                ChangeTarget::NoteLine { note, line } => {
+                    self.notes[note].set_content_line(line, after)
+                }
+                ChangeTarget::NoteTags(idx) => {
+                    if let Ok(tags) = TagCollection::from_str(&after) {
+                        self.notes[idx].set_tags(tags);
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// `text` with the matches of `find` replaced, left to right
+///
+/// With `skip_tags`, matches touching a word that is a tag are kept as they
+/// are.
+fn replace_in(
+    text: &str,
+    find: &str,
+    replace: &str,
+    options: ReplaceOptions,
+    skip_tags: bool,
+) -> String {
+    let tags = if skip_tags { tag_ranges(text) } else { Vec::new() };
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut start = 0;
+    while start < text.len() {
+        let found = match_at(text, start, find, options.case_sensitive)
+            .filter(|&end| !options.whole_word || is_whole_word(text, start, end))
+            .filter(|&end| !tags.iter().any(|&(from, to)| start < to && from < end));
+        match found {
+            Some(end) => {
+                result.push_str(&text[copied..start]);
+                result.push_str(replace);
+                copied = end;
+                start = end;
+            }
+            None => start += text[start..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// Byte offset where a match of `find` starting at byte `start` ends
+fn match_at(text: &str, start: usize, find: &str, case_sensitive: bool) -> Option<usize> {
+    let mut rest = text[start..].char_indices();
+    let mut end = start;
+    for expected in find.chars() {
+        let (offset, actual) = rest.next()?;
+        let same = if case_sensitive {
+            actual == expected
+        } else {
+            actual.to_lowercase().eq(expected.to_lowercase())
+        };
+        if !same {
+            return None;
+        }
+        end = start + offset + actual.len_utf8();
+    }
+    Some(end)
+}
+
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..start].chars().next_back().is_some_and(is_word)
+        && !text[end..].chars().next().is_some_and(is_word)
+}
+
+/// Byte ranges of the whitespace separated words of `text` that are tags
+fn tag_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut word_start = None;
+    for (idx, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (word_start, c.is_whitespace()) {
+            (None, false) => word_start = Some(idx),
+            (Some(from), true) => {
+                if Tag::from_str(&text[from..idx]).is_ok() {
+                    ranges.push((from, idx));
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replaced(text: &str, find: &str, replace: &str, options: ReplaceOptions) -> String {
+        replace_in(text, find, replace, options, !options.include_tags)
+    }
+
+    #[test]
+    fn matches_are_case_and_word_aware() {
+        let options = ReplaceOptions::default();
+        assert_eq!(replaced("Jira and jira", "Jira", "Linear", options), "Linear and jira");
+
+        let ignore_case = ReplaceOptions {
+            case_sensitive: false,
+            ..options
+        };
+        assert_eq!(replaced("Jira and JIRA", "jira", "Lin", ignore_case), "Lin and Lin");
+        // Lower case needs more bytes than upper case here
+        assert_eq!(replaced("Ⱥx ⱥx", "ⱥX", "ok", ignore_case), "ok ok");
+
+        let whole_word = ReplaceOptions {
+            whole_word: true,
+            ..options
+        };
+        assert_eq!(replaced("cat category cat.", "cat", "dog", whole_word), "dog category dog.");
+        assert_eq!(replaced("größe grö", "grö", "x", whole_word), "größe x");
+    }
+
+    #[test]
+    fn tags_are_skipped_by_default() {
+        let options = ReplaceOptions::default();
+        assert_eq!(replaced("Ask jira on +jira", "jira", "lin", options), "Ask lin on +jira");
+        // A match reaching into a tag-lookalike is not a match either
+        assert_eq!(replaced("see old @oldsys", "old @old", "new @new", options), "see old @oldsys");
+        assert_eq!(replaced("room:hall ten", "l ten", "l 11", options), "room:hall ten");
+
+        let with_tags = ReplaceOptions {
+            include_tags: true,
+            ..options
+        };
+        assert_eq!(replaced("Ask jira on +jira", "jira", "lin", with_tags), "Ask lin on +lin");
+    }
+}
@@ -0,0 +1,141 @@
+use std::io;
+use std::io::Result as IoResult;
+use std::ops::{Deref, DerefMut};
+
+use super::OrgDocument;
+
+/// Changes to a document that are persisted all at once or not at all
+///
+/// Created by `OrgDocument::begin`, which keeps a serialized copy of the
+/// document as it is then, i.e. as it was last loaded or committed. Mutate
+/// the document through the transaction and `commit` it with a single write.
+/// If that write fails, or the transaction is dropped without committing,
+/// the document is restored from the copy.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    document: &'a mut OrgDocument,
+    snapshot: Vec<u8>,
+    committed: bool,
+}
+
+impl OrgDocument {
+    /// Start a transaction, fails for partially loaded documents as they
+    /// cannot be written
+    pub fn begin(&mut self) -> IoResult<Transaction<'_>> {
+        let snapshot = self.to_bytes()?;
+        Ok(Transaction {
+            document: self,
+            snapshot,
+            committed: false,
+        })
+    }
+}
+
+impl Transaction<'_> {
+    /// Write the document to `path` with one atomic replace of the file
+    pub fn commit(self, path: &str) -> IoResult<()> {
+        let temp_path = format!("{path}.tmp");
+        let (tasks, notes) = self.document.len();
+        self.commit_with(|contents| {
+            std::fs::write(&temp_path, contents)?;
+            std::fs::rename(&temp_path, path)?;
+            log::info!("save path={path} tasks={tasks} notes={notes}");
+            Ok(())
+        })
+    }
+
+    /// Hand the serialized document to `write`, rolling back if it fails
+    pub fn commit_with<F>(mut self, write: F) -> IoResult<()>
+    where
+        F: FnOnce(&[u8]) -> IoResult<()>,
+    {
+        write(&self.document.to_bytes()?)?;
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Drop all changes made through the transaction
+    pub fn rollback(self) {}
+}
+
+impl Deref for Transaction<'_> {
+    type Target = OrgDocument;
+    fn deref(&self) -> &OrgDocument {
+        self.document
+    }
+}
+
+impl DerefMut for Transaction<'_> {
+    fn deref_mut(&mut self) -> &mut OrgDocument {
+        self.document
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        // Unchanged documents keep their parse, e.g. the source lines
+        if self.committed || self.document.to_bytes().ok().as_ref() == Some(&self.snapshot) {
+            return;
+        }
+        // The snapshot was written by `to_bytes`, so it always parses
+        let snapshot = io::Cursor::new(&self.snapshot);
+        if let Ok(document) = OrgDocument::parse_reader(snapshot, true) {
+            *self.document = document;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+    use std::str::FromStr;
+
+    fn document() -> OrgDocument {
+        let mut document = OrgDocument::default();
+        document.push_task(Task::from_str("Pack the boxes @home").unwrap());
+        document
+    }
+
+    #[test]
+    fn failed_write_rolls_back() {
+        let mut document = document();
+        let before = document.to_bytes().unwrap();
+
+        let mut transaction = document.begin().unwrap();
+        transaction.push_task(Task::from_str("Book the van").unwrap());
+        transaction.complete_tasks(&[0], crate::OnComplete::Archive);
+        assert_eq!(transaction.tasks.len(), 1);
+        let result = transaction.commit_with(|_| Err(io::Error::other("disk full")));
+
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+        assert_eq!(document.to_bytes().unwrap(), before);
+        assert_eq!(document.tasks[0].description(), "Pack the boxes");
+    }
+
+    #[test]
+    fn commit_writes_once() {
+        let mut document = document();
+        let mut writes = Vec::new();
+
+        let mut transaction = document.begin().unwrap();
+        transaction.push_task(Task::from_str("Book the van").unwrap());
+        transaction.push_task(Task::from_str("Cancel the newspaper").unwrap());
+        transaction
+            .commit_with(|contents| {
+                writes.push(contents.to_vec());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0], document.to_bytes().unwrap());
+        assert_eq!(document.tasks.len(), 3);
+
+        // Dropping without commit forgets the change
+        let mut transaction = document.begin().unwrap();
+        transaction.push_task(Task::from_str("Forget this").unwrap());
+        transaction.rollback();
+        assert_eq!(document.tasks.len(), 3);
+    }
+}
@@ -1,9 +1,41 @@
+mod batch;
+mod capture;
 mod config;
 mod core;
+mod defaults;
+mod draft;
+mod export;
+mod filter;
 mod io;
+#[cfg(feature = "testing")]
+pub mod testing;
 
-pub use config::Configuration;
+pub use batch::apply_batch;
+pub use capture::{
+    CodeBlocks, TITLE_MAX_LEN, code_lines, extract_tags, is_long_capture, note_from_capture,
+    note_parts, reflow, sanitize_line, single_line, strip_tags,
+};
+pub use config::{
+    Configuration, DOCUMENT_POINTER, OnComplete, Rollover, SuggestFrom, resolve_document,
+};
+pub use core::dates::{Date, WeekStart};
+pub use core::locale::Locale;
 pub use core::note::Note;
-pub use core::task::Task;
-pub use core::tags::{Tag, TagCollection};
-pub use io::{OrgDocument, TagSuggestions};
+pub use core::priority::Priority;
+pub use core::tags::guid::Guid;
+pub use core::tags::{Tag, TagCollection, TaskState};
+pub use core::task::{
+    ChecklistItem, DONE_HISTORY_LEN, DONE_KEY, FOLLOWUP_KEY, Task, TaskBuilder, TaskParseError,
+    TaskWarning, URGENCY_AGE, URGENCY_AGE_DAYS, URGENCY_DUE, URGENCY_HELD_BACK,
+    URGENCY_PRIORITY_A, URGENCY_PRIORITY_B, URGENCY_PRIORITY_C, URGENCY_THRESHOLD,
+    WAIT_SINCE_KEY, WAITING_CONTEXT,
+};
+pub use defaults::{DefaultsMap, PROJECT_DEFAULTS, apply_project_defaults};
+pub use draft::DraftTask;
+pub use export::{VIEW_EXPORT, export_view};
+pub use filter::{FilterSet, TaskFilter, TaskQuery, TaskSort, visible_position};
+pub use io::{
+    CARRIED_KEY, ChangeRecord, ChangeTarget, DUPLICATE_DISTANCE, LintIssue, NO_CONTEXT, NO_PERSON,
+    NO_PROJECT, OrgDocument, READONLY_MARKER, ReadOnlyReason, ReplaceOptions, ReplaceScope,
+    TagIndex, TagSuggestions, Transaction, validate_preamble, weekly_streak,
+};
@@ -0,0 +1,68 @@
+//! Hooks making the output of a frontend predictable in its tests
+//!
+//! Enabled with the `testing` feature, typically as a dev-dependency. The
+//! hooks only affect the calling thread, so tests running in parallel do not
+//! see each other's clock or guids. Production builds never consult them.
+//! The tests of this module run with `cargo test --features testing`, which
+//! the workspace test run enables through the TUI's dev-dependency.
+
+use crate::Date;
+use crate::core::dates::PINNED_TODAY;
+use crate::core::tags::guid::NEXT_SEED;
+
+/// Let `Date::now` return `today` on this thread, `None` goes back to the
+/// system date
+pub fn pin_today(today: Option<Date>) {
+    PINNED_TODAY.set(today);
+}
+
+/// Let new guids on this thread count up from `seed` (see
+/// `Guid::from_seed`), `None` makes them random again
+pub fn seed_guids(seed: Option<u128>) {
+    NEXT_SEED.set(seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{Note, OrgDocument};
+
+    /// A document with a freshly created note, as written to disk
+    fn written_note() -> String {
+        let mut document = OrgDocument::default();
+        document.push_note(Note::with("Standup".to_string(), vec!["Nothing new".to_string()]));
+        String::from_utf8(document.to_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn same_seed_writes_the_same_notes() {
+        pin_today(Some(Date::from_str("2025-03-03").unwrap()));
+        seed_guids(Some(42));
+        let first = written_note();
+        seed_guids(Some(42));
+        let second = written_note();
+        assert_eq!(first, second);
+        let metadata = "cre:2025-03-03 mod:2025-03-03 guid:00000000-0000-0000-0000-00000000002a";
+        assert!(first.contains(metadata), "{first}");
+        // The next note gets the next guid
+        assert!(written_note().contains("-00000000002b"));
+
+        seed_guids(None);
+        pin_today(None);
+        assert_ne!(written_note(), written_note());
+    }
+
+    #[test]
+    fn hooks_stay_on_their_thread() {
+        pin_today(Some(Date::from_str("2000-01-01").unwrap()));
+        seed_guids(Some(1));
+        let (today, note) = std::thread::spawn(|| (Date::now(), written_note())).join().unwrap();
+        assert_ne!(today, Date::from_str("2000-01-01").unwrap());
+        assert!(!note.contains("00000000-0000-0000-0000-000000000001"));
+        assert_eq!(Date::now(), Date::from_str("2000-01-01").unwrap());
+        seed_guids(None);
+        pin_today(None);
+    }
+}
@@ -1,5 +1,9 @@
-use orgflow::Configuration;
+use orgflow::{
+    Configuration, DOCUMENT_POINTER, Locale, OnComplete, OrgDocument, ReadOnlyReason, Rollover,
+    SuggestFrom, WeekStart, resolve_document,
+};
 use std::env;
+use std::path::{Path, PathBuf};
 
 #[test]
 fn test_basefolder_default() {
@@ -51,3 +55,348 @@ fn test_basefolder_from_env() {
         }
     }
 }
+
+#[test]
+fn test_on_complete_from_env() {
+    let original = env::var("ORGFLOW_ON_COMPLETE").ok();
+
+    let cases = [
+        ("in_place", OnComplete::InPlace),
+        ("move_to_bottom", OnComplete::MoveToBottom),
+        ("archive", OnComplete::Archive),
+        ("unknown", OnComplete::InPlace),
+    ];
+    for (value, expected) in cases {
+        unsafe {
+            env::set_var("ORGFLOW_ON_COMPLETE", value);
+        }
+        assert_eq!(Configuration::on_complete(), expected);
+    }
+
+    if let Some(value) = original {
+        unsafe {
+            env::set_var("ORGFLOW_ON_COMPLETE", value);
+        }
+    } else {
+        unsafe {
+            env::remove_var("ORGFLOW_ON_COMPLETE");
+        }
+        assert_eq!(Configuration::on_complete(), OnComplete::InPlace);
+    }
+}
+
+#[test]
+fn test_rollover_from_env() {
+    let original = env::var("ORGFLOW_ROLLOVER").ok();
+
+    let cases = [
+        (None, Rollover::Clear),
+        (Some("carry"), Rollover::Carry),
+        (Some("off"), Rollover::Off),
+        (Some("sometimes"), Rollover::Clear),
+    ];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_ROLLOVER", v),
+                None => env::remove_var("ORGFLOW_ROLLOVER"),
+            }
+        }
+        assert_eq!(Configuration::rollover(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_ROLLOVER", value),
+            None => env::remove_var("ORGFLOW_ROLLOVER"),
+        }
+    }
+}
+
+#[test]
+fn test_locale_from_env() {
+    let original = env::var("ORGFLOW_LOCALE").ok();
+
+    let cases = [
+        (None, Locale::En),
+        (Some("de"), Locale::De),
+        (Some("de_DE.UTF-8"), Locale::De),
+        (Some("klingon"), Locale::En),
+    ];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_LOCALE", v),
+                None => env::remove_var("ORGFLOW_LOCALE"),
+            }
+        }
+        assert_eq!(Configuration::locale(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_LOCALE", value),
+            None => env::remove_var("ORGFLOW_LOCALE"),
+        }
+    }
+}
+
+#[test]
+fn test_week_start_from_env() {
+    let original = env::var("ORGFLOW_WEEK_STARTS").ok();
+
+    let cases = [
+        (None, WeekStart::Monday),
+        (Some("sunday"), WeekStart::Sunday),
+        (Some("Monday"), WeekStart::Monday),
+        (Some("friday"), WeekStart::Monday),
+    ];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_WEEK_STARTS", v),
+                None => env::remove_var("ORGFLOW_WEEK_STARTS"),
+            }
+        }
+        assert_eq!(Configuration::week_start(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_WEEK_STARTS", value),
+            None => env::remove_var("ORGFLOW_WEEK_STARTS"),
+        }
+    }
+}
+
+#[test]
+fn test_capture_max_len_from_env() {
+    let original = env::var("ORGFLOW_CAPTURE_MAX_LEN").ok();
+
+    let cases = [(None, 200), (Some("80"), 80), (Some("long"), 200)];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_CAPTURE_MAX_LEN", v),
+                None => env::remove_var("ORGFLOW_CAPTURE_MAX_LEN"),
+            }
+        }
+        assert_eq!(Configuration::capture_max_len(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_CAPTURE_MAX_LEN", value),
+            None => env::remove_var("ORGFLOW_CAPTURE_MAX_LEN"),
+        }
+    }
+}
+
+#[test]
+fn test_wrap_width_from_env() {
+    let original = env::var("ORGFLOW_WRAP_WIDTH").ok();
+
+    let cases = [(None, 0), (Some("72"), 72), (Some("0"), 0), (Some("wide"), 0)];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_WRAP_WIDTH", v),
+                None => env::remove_var("ORGFLOW_WRAP_WIDTH"),
+            }
+        }
+        assert_eq!(Configuration::wrap_width(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_WRAP_WIDTH", value),
+            None => env::remove_var("ORGFLOW_WRAP_WIDTH"),
+        }
+    }
+}
+
+#[test]
+fn test_document_name_follows_pointer() {
+    let original = env::var("ORGFLOW_DOCUMENT").ok();
+    let original_refile = env::var("ORGFLOW_REFILE").ok();
+    let dir = env::temp_dir().join(format!("orgflow-config-document-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let basefolder = dir.to_str().unwrap();
+    unsafe {
+        env::remove_var("ORGFLOW_DOCUMENT");
+        env::remove_var("ORGFLOW_REFILE");
+    }
+
+    assert_eq!(Configuration::document_name(basefolder), "refile.org");
+    std::fs::write(dir.join(DOCUMENT_POINTER), "inbox.org\n").unwrap();
+    assert_eq!(Configuration::document_name(basefolder), "inbox.org");
+    unsafe {
+        env::set_var("ORGFLOW_DOCUMENT", "work.org");
+    }
+    assert_eq!(Configuration::document_name(basefolder), "work.org");
+    unsafe {
+        env::set_var("ORGFLOW_REFILE", "home.org");
+    }
+    assert_eq!(Configuration::document_name(basefolder), "home.org");
+    assert_eq!(Configuration::document_path(basefolder), Ok(dir.join("home.org")));
+    unsafe {
+        env::set_var("ORGFLOW_REFILE", " ");
+    }
+    assert_eq!(Configuration::document_var(), Some(("ORGFLOW_DOCUMENT", "work.org".into())));
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_DOCUMENT", value),
+            None => env::remove_var("ORGFLOW_DOCUMENT"),
+        }
+        match original_refile {
+            Some(value) => env::set_var("ORGFLOW_REFILE", value),
+            None => env::remove_var("ORGFLOW_REFILE"),
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_document_outside_basefolder_needs_permission() {
+    let base = Path::new("/home/me/orgflow");
+    assert_eq!(resolve_document(base, "inbox.org", false), Ok(base.join("inbox.org")));
+    assert_eq!(resolve_document(base, "work/inbox.org", false), Ok(base.join("work/inbox.org")));
+    assert_eq!(
+        resolve_document(base, "/home/me/orgflow/inbox.org", false),
+        Ok(base.join("inbox.org"))
+    );
+
+    let error = resolve_document(base, "/home/me/inbox.org", false).unwrap_err();
+    assert!(error.contains("/home/me/inbox.org") && error.contains("ORGFLOW_ALLOW_OUTSIDE"));
+    assert_eq!(
+        resolve_document(base, "/home/me/inbox.org", true),
+        Ok(PathBuf::from("/home/me/inbox.org"))
+    );
+
+    assert!(resolve_document(base, "../inbox.org", true).is_err());
+    assert!(resolve_document(base, "", true).is_err());
+}
+
+#[test]
+fn test_task_format_from_env() {
+    let original = env::var("ORGFLOW_TASK_FORMAT").ok();
+
+    let cases = [(None, None), (Some(" "), None), (Some("{desc}"), Some("{desc}"))];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_TASK_FORMAT", v),
+                None => env::remove_var("ORGFLOW_TASK_FORMAT"),
+            }
+        }
+        assert_eq!(Configuration::task_format().as_deref(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_TASK_FORMAT", value),
+            None => env::remove_var("ORGFLOW_TASK_FORMAT"),
+        }
+    }
+}
+
+#[test]
+fn test_suggest_from_env() {
+    let original = env::var("ORGFLOW_SUGGEST_FROM").ok();
+    let original_days = env::var("ORGFLOW_SUGGEST_RECENT_DAYS").ok();
+
+    let cases = [
+        (None, None, SuggestFrom::All),
+        (Some("open_only"), None, SuggestFrom::OpenOnly),
+        (Some("recent"), None, SuggestFrom::Recent(90)),
+        (Some("recent"), Some("30"), SuggestFrom::Recent(30)),
+        (Some("everything"), None, SuggestFrom::All),
+    ];
+    for (value, days, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_SUGGEST_FROM", v),
+                None => env::remove_var("ORGFLOW_SUGGEST_FROM"),
+            }
+            match days {
+                Some(d) => env::set_var("ORGFLOW_SUGGEST_RECENT_DAYS", d),
+                None => env::remove_var("ORGFLOW_SUGGEST_RECENT_DAYS"),
+            }
+        }
+        assert_eq!(Configuration::suggest_from(), expected, "{:?} {:?}", value, days);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_SUGGEST_FROM", value),
+            None => env::remove_var("ORGFLOW_SUGGEST_FROM"),
+        }
+        match original_days {
+            Some(value) => env::set_var("ORGFLOW_SUGGEST_RECENT_DAYS", value),
+            None => env::remove_var("ORGFLOW_SUGGEST_RECENT_DAYS"),
+        }
+    }
+}
+
+#[test]
+fn test_stale_after_from_env() {
+    let original = env::var("ORGFLOW_STALE_DAYS").ok();
+
+    let cases = [
+        (None, (30, 90)),
+        (Some("14, 60"), (14, 60)),
+        (Some("90,30"), (30, 90)),
+        (Some("30"), (30, 90)),
+    ];
+    for (value, expected) in cases {
+        unsafe {
+            match value {
+                Some(v) => env::set_var("ORGFLOW_STALE_DAYS", v),
+                None => env::remove_var("ORGFLOW_STALE_DAYS"),
+            }
+        }
+        assert_eq!(Configuration::stale_after(), expected, "{:?}", value);
+    }
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_STALE_DAYS", value),
+            None => env::remove_var("ORGFLOW_STALE_DAYS"),
+        }
+    }
+}
+
+#[test]
+fn test_readonly_paths_from_env() {
+    let original = env::var_os("ORGFLOW_READONLY");
+    let dir = env::temp_dir().join(format!("orgflow-config-readonly-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let shared = dir.join("shared.org");
+    std::fs::write(&shared, "## Tasks\n").unwrap();
+    let shared = shared.to_str().unwrap();
+    let document = OrgDocument::from(shared).unwrap();
+
+    unsafe {
+        env::remove_var("ORGFLOW_READONLY");
+    }
+    assert!(Configuration::readonly_paths().is_empty());
+    assert_eq!(document.readonly_reason(shared), None);
+
+    // Listed with a detour, the same file is still recognised
+    let detour = dir.join("..").join(dir.file_name().unwrap()).join("shared.org");
+    let list = env::join_paths(["/elsewhere/team.org".into(), detour]).unwrap();
+    unsafe {
+        env::set_var("ORGFLOW_READONLY", &list);
+    }
+    assert_eq!(Configuration::readonly_paths().len(), 2);
+    assert_eq!(document.readonly_reason(shared), Some(ReadOnlyReason::Configured));
+
+    unsafe {
+        match original {
+            Some(value) => env::set_var("ORGFLOW_READONLY", value),
+            None => env::remove_var("ORGFLOW_READONLY"),
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,6 +1,13 @@
 use std::collections::HashMap;
-use orgflow::OrgDocument;
+use orgflow::{
+    ChangeTarget, Date, FilterSet, Guid, NO_CONTEXT, NO_PERSON, NO_PROJECT, Note, OnComplete,
+    OrgDocument, Priority, ReplaceOptions, READONLY_MARKER, ReadOnlyReason, ReplaceScope,
+    SuggestFrom, Task, TaskFilter, TaskSort, WeekStart, export_view, validate_preamble,
+    weekly_streak,
+};
 use std::io::Cursor;
+use std::time::Duration;
+use std::str::FromStr;
 
 #[test]
 fn read_document() {
@@ -29,3 +36,1248 @@ fn roundtrip() {
         assert_eq!(r[..r.len() - 1], exp); // TODO: Fix additional extra line at end
     }
 }
+
+fn temp_copy(source: &str, name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let target = dir.join(name);
+    std::fs::copy(source, &target).unwrap();
+    target.to_str().unwrap().to_string()
+}
+
+const COMMENTED_DOCUMENT: &str = "# Plans
+
+## Tasks
+// Waiting for the quote before booking
+Book the van +move
+# ask Sam about the boxes
+Pack the kitchen +move @home
+  start with the plates
+Label the boxes +move
+// Nothing below this line yet
+
+## Notes
+
+";
+
+#[test]
+fn comments_in_the_tasks_section_are_kept() {
+    let path = temp_copy("tests/document.md", "comments.md");
+    std::fs::write(&path, COMMENTED_DOCUMENT).unwrap();
+    let od = OrgDocument::from(&path).unwrap();
+    assert_eq!(String::from_utf8(od.to_bytes().unwrap()).unwrap(), COMMENTED_DOCUMENT);
+
+    // Comments are no tasks, they only go along with the one below them
+    assert_eq!(od.len(), (3, 0));
+    assert!(od.lint().is_empty());
+    assert_eq!(od.tasks[0].comments(), ["// Waiting for the quote before booking"]);
+    assert_eq!(od.tasks[1].comments(), ["# ask Sam about the boxes"]);
+    assert_eq!(od.tasks[1].details(), ["start with the plates"]);
+    assert!(od.tasks[2].comments().is_empty());
+    assert_eq!(od.closing_comments(), ["// Nothing below this line yet"]);
+    let lines: Vec<Option<usize>> = od.tasks.iter().map(|t| t.source_line()).collect();
+    assert_eq!(lines, [Some(5), Some(7), Some(9)]);
+    let by_context = od.tasks_by_context();
+    let counted: usize = by_context.values().map(Vec::len).sum();
+    assert_eq!(counted, 3);
+
+    let partial = OrgDocument::from_tasks_only(&path).unwrap();
+    assert_eq!(partial.tasks, od.tasks);
+}
+
+#[test]
+fn tasks_starting_with_a_hash_are_no_comments() {
+    let path = temp_copy("tests/document.md", "hashes.md");
+    let document =
+        "\n## Tasks\n#42 fix login @work\n### later\n#\n(B) #7 reply to Sam\n\n## Notes\n\n";
+    std::fs::write(&path, document).unwrap();
+    let od = OrgDocument::from(&path).unwrap();
+    assert_eq!(od.len(), (2, 0));
+    assert_eq!(od.tasks[0].description(), "#42 fix login");
+    assert_eq!(od.tasks[1].comments(), ["### later", "#"]);
+    assert_eq!(od.tasks[1].description(), "#7 reply to Sam");
+    assert_eq!(String::from_utf8(od.to_bytes().unwrap()).unwrap(), document);
+    std::fs::remove_file(&path).unwrap();
+}
+
+const UNTIDY_TASKS: &str = "# Moving
+
+## Tasks
+(A)  Book the van  @phone +move
+x 2025-03-02 2025-03-01 Pack the kitchen  due:2025-03-01 +move @home
+  start with the plates
+Label the boxes +move   p:sam
+
+## Notes
+
+";
+
+#[test]
+fn untouched_tasks_are_written_as_they_were_read() {
+    let path = temp_copy("tests/document.md", "untidy.md");
+    std::fs::write(&path, UNTIDY_TASKS).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    assert!(od.tasks.iter().all(|task| !task.is_dirty()));
+    assert_eq!(String::from_utf8(od.to_bytes().unwrap()).unwrap(), UNTIDY_TASKS);
+
+    // Only the changed task is tidied up
+    od.tasks[2].set_priority(None);
+    let written = String::from_utf8(od.to_bytes().unwrap()).unwrap();
+    let tidied = UNTIDY_TASKS.replace("+move   p:sam", "+move p:sam");
+    assert_eq!(written, tidied);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn tasks_read_from_different_lines_are_equal() {
+    let path = temp_copy("tests/document.md", "repeated.md");
+    let line = "(A) 2025-03-01 Book the van @phone +move";
+    let document = format!("## Tasks\n{line}\nWater plants\n{line}\n\n## Notes\n\n");
+    std::fs::write(&path, document).unwrap();
+    let od = OrgDocument::from(&path).unwrap();
+    let (first, second) = (&od.tasks[0], &od.tasks[2]);
+    assert_ne!(first.source_line(), second.source_line());
+    assert_eq!(first, second);
+    assert_eq!(first.cmp(second), std::cmp::Ordering::Equal);
+    assert_eq!(*first, Task::from_str(line).unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+const CHECKLIST_TASKS: &str = "# Offsite
+
+## Tasks
+Plan the offsite +team
+  - [ ] book room
+  - [x] send invite
+  menu still open
+Order the badges
+
+## Notes
+
+";
+
+#[test]
+fn checklist_items_belong_to_the_task_above() {
+    let path = temp_copy("tests/document.md", "checklist.md");
+    std::fs::write(&path, CHECKLIST_TASKS).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    assert_eq!(od.tasks.len(), 2);
+    let done: Vec<bool> = od.tasks[0].checklist().iter().map(|item| item.done).collect();
+    assert_eq!(done, [false, true]);
+    assert!(od.tasks[1].checklist().is_empty());
+    assert_eq!(String::from_utf8(od.to_bytes().unwrap()).unwrap(), CHECKLIST_TASKS);
+
+    // Changed tasks keep their items as well
+    od.tasks[0].set_priority(Some(Priority::B));
+    let written = String::from_utf8(od.to_bytes().unwrap()).unwrap();
+    assert_eq!(written, CHECKLIST_TASKS.replace("Plan the", "(B) Plan the"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn comments_stay_when_their_task_leaves() {
+    let path = temp_copy("tests/document.md", "comments_archive.md");
+    std::fs::write(&path, COMMENTED_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    od.complete_tasks(&[0, 2], OnComplete::Archive);
+    od.to(&path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(written.contains(
+        "## Tasks
+// Waiting for the quote before booking
+# ask Sam about the boxes
+Pack the kitchen +move @home
+  start with the plates
+// Nothing below this line yet
+"
+    ));
+
+    // New tasks go below the last task, not below the closing comments
+    let mut od = OrgDocument::from(&path).unwrap();
+    od.append_task(&path, Task::from_str("Return the van +move").unwrap()).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(written.contains("  start with the plates\nReturn the van +move\n// Nothing below"));
+    let od = OrgDocument::from(&path).unwrap();
+    assert!(od.tasks[1].comments().is_empty());
+    assert_eq!(od.closing_comments().len(), 1);
+}
+
+const MARKED_DOCUMENT: &str = "## Tasks
+Call the landlord today:1 +move
+Book the van today:2 today:3 @phone
+x 2024-03-04 2024-03-01 Measure the hallway today:1
+Pack the kitchen +move
+
+## Notes
+
+";
+
+#[test]
+fn marker_tags_of_open_tasks_are_cleared() {
+    let path = temp_copy("tests/document.md", "markers_cleared.md");
+    std::fs::write(&path, MARKED_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    assert_eq!(OrgDocument::clear_tag_key(&mut od, "today"), 2);
+    let lines: Vec<String> = od.tasks.iter().map(|task| task.to_string()).collect();
+    assert_eq!(
+        lines,
+        [
+            "Call the landlord +move",
+            "Book the van @phone",
+            "x 2024-03-04 2024-03-01 Measure the hallway today:1",
+            "Pack the kitchen +move",
+        ]
+    );
+    // Nothing left to clear
+    assert_eq!(od.clear_tag_key("today"), 0);
+}
+
+#[test]
+fn marker_tags_of_open_tasks_are_carried() {
+    let path = temp_copy("tests/document.md", "markers_carried.md");
+    std::fs::write(&path, MARKED_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    let day = Date::from_str("2024-03-04").unwrap();
+    assert_eq!(od.carry_tag_key("today", &day), 2);
+    assert_eq!(od.tasks[0].to_string(), "Call the landlord +move carried:2024-03-04");
+    assert_eq!(od.tasks[1].to_string(), "Book the van @phone carried:2024-03-04");
+    assert!(od.tasks[2].to_string().ends_with("today:1"));
+
+    // Carried markers read back as they were written
+    od.to(&path).unwrap();
+    let od = OrgDocument::from(&path).unwrap();
+    assert_eq!(od.tasks[0].tags().as_ref().unwrap().custom_value("carried"), Some("2024-03-04"));
+}
+
+const REORDERED_DOCUMENT: &str = "## Tasks
+
+## Notes
+
+### Reference
+> cre:2025-01-01 mod:2025-01-01 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d701
+Phone numbers
+
+### Trips
+> cre:2025-01-02 mod:2025-01-02 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d702
+
+### Shopping
+> cre:2025-01-05 mod:2025-01-05 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d705
+
+";
+
+#[test]
+fn moved_notes_are_written_in_their_new_order() {
+    let path = temp_copy("tests/document.md", "reordered_notes.md");
+    std::fs::write(&path, REORDERED_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    od.move_note(2, 0).unwrap();
+    od.to(&path).unwrap();
+
+    let od = OrgDocument::from(&path).unwrap();
+    let titles: Vec<&str> = od.notes.iter().map(|note| note.title()).collect();
+    assert_eq!(titles, ["Shopping", "Reference", "Trips"]);
+    assert_eq!(od.notes[1].content(), ["Phone numbers"]);
+}
+
+#[test]
+fn wrapped_writes_leave_the_notes_in_memory_alone() {
+    let path = temp_copy("tests/document.md", "wrapped_notes.md");
+    std::fs::write(&path, REORDERED_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    let long = "Call the front desk before noon to confirm the late check-in";
+    let content = vec!["Phone numbers".to_string(), long.to_string()];
+    od.update_note(0, Note::with("Reference".to_string(), content.clone()));
+
+    let written = String::from_utf8(od.to_bytes_wrapped(30).unwrap()).unwrap();
+    assert!(written.contains("Phone numbers\nCall the front desk before\nnoon to confirm"));
+    assert_eq!(od.notes[0].content(), content);
+    assert_eq!(od.to_bytes_wrapped(0).unwrap(), od.to_bytes().unwrap());
+
+    // Saving the saved file again writes the same bytes
+    std::fs::write(&path, &written).unwrap();
+    let reread = OrgDocument::from(&path).unwrap();
+    assert_eq!(String::from_utf8(reread.to_bytes_wrapped(30).unwrap()).unwrap(), written);
+}
+
+#[test]
+fn preamble_is_replaced_unless_it_starts_a_section() {
+    let path = temp_copy("tests/document.md", "preamble.md");
+    let mut od = OrgDocument::from(&path).unwrap();
+    let lines = |text: &str| -> Vec<String> { text.lines().map(str::to_string).collect() };
+
+    let error = od.set_preamble(lines("# Inbox\n## Links\nhttps://example.com")).unwrap_err();
+    assert_eq!(error, "Line 2 starts with '## ', which begins a section");
+    assert!(validate_preamble(&lines("# Inbox\n### Links\n##Links")).is_ok());
+    assert_eq!(od.preamble()[0], "# Document");
+
+    od.set_preamble(lines("# Inbox\n\nhttps://example.com")).unwrap();
+    od.to(&path).unwrap();
+    let reread = OrgDocument::from(&path).unwrap();
+    assert_eq!(reread.preamble(), ["# Inbox", "https://example.com"]);
+    assert_eq!(reread.len(), od.len());
+
+    // Without a preamble the file starts with a blank line and the Tasks
+    od.set_preamble(Vec::new()).unwrap();
+    od.to(&path).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("\n## Tasks\n"));
+    assert!(OrgDocument::from(&path).unwrap().preamble().is_empty());
+}
+
+#[test]
+fn read_tasks_only() {
+    let od = OrgDocument::from_tasks_only("tests/document.md").unwrap();
+    assert!(od.is_partial());
+    assert_eq!(od.len(), (2, 0));
+
+    let full = OrgDocument::from("tests/document.md").unwrap();
+    assert!(!full.is_partial());
+    assert_eq!(od.tasks, full.tasks);
+}
+
+#[test]
+fn partial_document_refuses_write() {
+    let od = OrgDocument::from_tasks_only("tests/document.md").unwrap();
+    let mut c = Cursor::new(Vec::new());
+    assert!(od.write(&mut c).is_err());
+    assert!(c.into_inner().is_empty());
+}
+
+#[test]
+fn merge_remaining_completes_document() {
+    let mut od = OrgDocument::from_tasks_only("tests/document.md").unwrap();
+    od.push_task(Task::from_str("Task added while loading").unwrap());
+    od.merge_remaining(OrgDocument::from("tests/document.md").unwrap());
+    assert!(!od.is_partial());
+    assert_eq!(od.len(), (3, 3));
+
+    let mut c = Cursor::new(Vec::new());
+    assert!(od.write(&mut c).is_ok());
+}
+
+#[test]
+fn append_task_keeps_notes() {
+    let path = temp_copy("tests/document.md", "append_task.md");
+    let mut od = OrgDocument::from_tasks_only(&path).unwrap();
+    let task = Task::from_str("Appended task @home").unwrap();
+    od.append_task(&path, task).unwrap();
+    assert_eq!(od.len(), (3, 0));
+
+    let reloaded = OrgDocument::from(&path).unwrap();
+    assert_eq!(reloaded.len(), (3, 3));
+    assert_eq!(reloaded.tasks[2].description(), "Appended task");
+
+    // Everything apart from the new task line is untouched
+    let original = std::fs::read_to_string("tests/document.md").unwrap();
+    let appended = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(appended.replace("Appended task @home\n", ""), original);
+}
+
+#[test]
+fn insert_tasks_in_memory() {
+    let original = std::fs::read_to_string("tests/document.md").unwrap();
+    let tasks = [
+        Task::from_str("First inserted").unwrap(),
+        Task::from_str("Second inserted").unwrap(),
+    ];
+    let inserted = OrgDocument::insert_tasks(&original, &tasks).unwrap();
+    assert_eq!(
+        inserted.replace("First inserted\nSecond inserted\n", ""),
+        original
+    );
+    assert!(OrgDocument::insert_tasks("# Only a heading\n", &tasks).is_err());
+
+    let od = OrgDocument::from("tests/document.md").unwrap();
+    let mut c = Cursor::new(Vec::new());
+    od.write(&mut c).unwrap();
+    assert_eq!(od.to_bytes().unwrap(), c.into_inner());
+}
+
+#[test]
+fn source_lines() {
+    let od = OrgDocument::from("tests/document.md").unwrap();
+    let task_lines: Vec<Option<usize>> = od.tasks.iter().map(|t| t.source_line()).collect();
+    assert_eq!(task_lines, vec![Some(6), Some(7)]);
+
+    // Blank lines between the note blocks are counted as well
+    let note_lines: Vec<Option<usize>> = od.notes.iter().map(|n| n.source_line()).collect();
+    assert_eq!(note_lines, vec![Some(16), Some(20), Some(24)]);
+
+    let od = OrgDocument::from("tests/document_with_post.md").unwrap();
+    assert_eq!(od.notes[0].source_line(), Some(11));
+
+    assert_eq!(Task::from_str("Not parsed from a file").unwrap().source_line(), None);
+}
+
+const COMPLETION_DOCUMENT: &str = "## Tasks
+2025-01-01 First open
+2025-01-01 Second open
+2025-01-01 Third open
+x 2025-01-03 2025-01-01 Already done
+
+## Notes
+
+";
+
+#[test]
+fn find_similar_task() {
+    let mut od = OrgDocument::default();
+    for line in COMPLETION_DOCUMENT.lines().skip(1).take(4) {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    od.push_task(Task::from_str("(A) Call mum @phone +family").unwrap());
+
+    // Exact match
+    let exact = Task::from_str("(A) Call mum @phone +family").unwrap();
+    assert_eq!(od.find_similar_task(&exact), Some(4));
+    // Same description with different tags and priority
+    let retagged = Task::from_str("(B) Call mum +home").unwrap();
+    assert_eq!(od.find_similar_task(&retagged), Some(4));
+    assert_eq!(od.find_similar_task(&Task::from_str("Second open").unwrap()), Some(1));
+    // Completed tasks and other descriptions do not match
+    assert_eq!(od.find_similar_task(&Task::from_str("Already done").unwrap()), None);
+    assert_eq!(od.find_similar_task(&Task::from_str("Call dad").unwrap()), None);
+}
+
+fn descriptions(od: &OrgDocument) -> Vec<(&str, bool)> {
+    od.tasks
+        .iter()
+        .map(|t| (t.description(), t.is_completed()))
+        .collect()
+}
+
+fn complete_on_disk(name: &str, indices: &[usize], mode: OnComplete) -> (OrgDocument, Vec<Task>) {
+    let path = temp_copy("tests/document.md", name);
+    std::fs::write(&path, COMPLETION_DOCUMENT).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    let archived = od.complete_tasks(indices, mode);
+    od.to(&path).unwrap();
+    (OrgDocument::from(&path).unwrap(), archived)
+}
+
+#[test]
+fn complete_in_place() {
+    let (od, archived) = complete_on_disk("in_place.md", &[0], OnComplete::InPlace);
+    assert!(archived.is_empty());
+    assert_eq!(
+        descriptions(&od),
+        vec![
+            ("First open", true),
+            ("Second open", false),
+            ("Third open", false),
+            ("Already done", true),
+        ]
+    );
+    assert!(od.tasks[0].completion_date().is_some());
+}
+
+#[test]
+fn complete_move_to_bottom() {
+    let (od, _) = complete_on_disk("move_to_bottom.md", &[0, 1], OnComplete::MoveToBottom);
+    assert_eq!(
+        descriptions(&od),
+        vec![
+            ("Third open", false),
+            ("First open", true),
+            ("Second open", true),
+            ("Already done", true),
+        ]
+    );
+    assert!(od.tasks[1].completion_date().is_some());
+}
+
+#[test]
+fn complete_archive() {
+    let (od, archived) = complete_on_disk("archive.md", &[3, 2, 0], OnComplete::Archive);
+    // Already completed tasks are left alone
+    assert_eq!(
+        descriptions(&od),
+        vec![("Second open", false), ("Already done", true)]
+    );
+    let archived: Vec<&str> = archived.iter().map(|t| t.description()).collect();
+    assert_eq!(archived, vec!["First open", "Third open"]);
+}
+
+#[test]
+fn recurring_tasks_come_back_when_completed() {
+    let mut od = OrgDocument::default();
+    for line in ["Water the plants rec:3d", "Pay rent rec:+4w due:2025-03-01", "Call mum"] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    let archived = od.complete_tasks(&[0, 1, 2], OnComplete::Archive);
+    // The next occurrences stay where the completed tasks were
+    let archived: Vec<&str> = archived.iter().map(|t| t.description()).collect();
+    assert_eq!(archived, ["Water the plants", "Pay rent", "Call mum"]);
+    assert_eq!(
+        descriptions(&od),
+        vec![("Water the plants", false), ("Pay rent", false)]
+    );
+    let today = Date::now();
+    assert_eq!(od.tasks[0].done_history(), [today]);
+    assert_eq!(od.tasks[1].due_date(), Some(&Date::from_str("2025-03-29").unwrap()));
+
+    let mut od = OrgDocument::default();
+    od.push_task(Task::from_str("Water the plants rec:3d").unwrap());
+    od.complete_tasks(&[0], OnComplete::MoveToBottom);
+    assert_eq!(
+        descriptions(&od),
+        vec![("Water the plants", false), ("Water the plants", true)]
+    );
+}
+
+#[test]
+fn archive_tasks_creates_and_appends() {
+    let path = temp_copy("tests/document.md", "done.org");
+    std::fs::remove_file(&path).unwrap();
+
+    let tasks = vec![Task::from_str("x 2025-01-02 2025-01-01 Archived one").unwrap()];
+    OrgDocument::archive_tasks(&path, tasks).unwrap();
+    let tasks = vec![Task::from_str("x 2025-01-03 2025-01-01 Archived two").unwrap()];
+    OrgDocument::archive_tasks(&path, tasks).unwrap();
+
+    let od = OrgDocument::from(&path).unwrap();
+    assert_eq!(
+        descriptions(&od),
+        vec![("Archived one", true), ("Archived two", true)]
+    );
+}
+
+#[test]
+fn move_task_clamps_target() {
+    let mut od = OrgDocument::default();
+    for d in ["One", "Two", "Three"] {
+        od.push_task(Task::from_str(d).unwrap());
+    }
+    od.move_task(0, 10);
+    od.move_task(2, 1);
+    assert_eq!(
+        descriptions(&od),
+        vec![("Two", false), ("One", false), ("Three", false)]
+    );
+    assert_eq!(od.after_last_open_task(), 3);
+}
+
+#[test]
+fn roundtrip_blockquote_content() {
+    let contents = [
+        vec!["> quoted text".to_string(), "after the quote".to_string()],
+        vec![
+            "> cre:2023-04-04 mod:2023-04-05 guid:b1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 +other"
+                .to_string(),
+        ],
+    ];
+    let mut od = OrgDocument::default();
+    for content in contents.iter() {
+        od.push_note(Note::with("Quoting".to_string(), content.clone()));
+    }
+
+    let path = temp_copy("tests/document.md", "blockquote.md");
+    od.to(&path).unwrap();
+    let reread = OrgDocument::from(&path).unwrap();
+
+    assert_eq!(reread.notes.len(), 2);
+    for (note, content) in reread.notes.iter().zip(contents.iter()) {
+        assert_eq!(note.title(), "Quoting");
+        assert_eq!(note.content(), content.as_slice());
+        assert!(note.tags().to_string().is_empty());
+    }
+    assert_eq!(reread.notes[0].guid(), od.notes[0].guid());
+}
+
+#[test]
+fn roundtrip_title_only_note() {
+    // What the TUI does when saving a note with an empty content area
+    let path = temp_copy("tests/document.md", "title_only.md");
+    let mut od = OrgDocument::from(&path).unwrap();
+    od.push_note(Note::with("Only a title".to_string(), Vec::new()));
+    od.to(&path).unwrap();
+
+    // Loading it again on the next launch must not fail
+    let reread = OrgDocument::from(&path).unwrap();
+    assert_eq!(reread.len(), (2, 4));
+    let note = reread.notes.last().unwrap();
+    assert_eq!(note.title(), "Only a title");
+    assert!(note.content().is_empty());
+
+    // and writing it once more is stable
+    let mut first = Cursor::new(Vec::new());
+    reread.write(&mut first).unwrap();
+    reread.to(&path).unwrap();
+    let mut second = Cursor::new(Vec::new());
+    OrgDocument::from(&path).unwrap().write(&mut second).unwrap();
+    assert_eq!(first.into_inner(), second.into_inner());
+}
+
+#[test]
+fn throughput_week_boundaries() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "x 2025-06-02 2025-05-01 Monday of the current week +garden",
+        "x 2025-06-01 2025-05-01 Sunday of the previous week +garden",
+        "x 2025-05-26 2025-05-01 Monday of the previous week +garden +home",
+        "x 2025-05-12 2025-05-01 Oldest week in range",
+        "x 2025-05-11 2025-05-01 Sunday before the range +garden",
+        "x 2025-06-09 2025-05-01 Next week +garden",
+        "2025-05-01 Still open +garden",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+
+    // A Wednesday, the current week starts on 2025-06-02
+    let today = Date::from_str("2025-06-04").unwrap();
+    let throughput = od.throughput_at(4, &today, WeekStart::Monday);
+
+    assert_eq!(throughput.len(), 3);
+    assert_eq!(throughput["+garden"], vec![0, 0, 2, 1]);
+    assert_eq!(throughput["+home"], vec![0, 0, 1, 0]);
+    assert_eq!(throughput[NO_PROJECT], vec![1, 0, 0, 0]);
+    assert!(od.throughput_at(0, &today, WeekStart::Monday).is_empty());
+
+    // Weeks starting on Sunday move 2025-06-01 into the current week and
+    // 2025-05-11 into the oldest week of the range
+    let throughput = od.throughput_at(4, &today, WeekStart::Sunday);
+    assert_eq!(throughput["+garden"], vec![1, 0, 1, 2]);
+    assert_eq!(throughput["+home"], vec![0, 0, 1, 0]);
+    assert_eq!(throughput[NO_PROJECT], vec![1, 0, 0, 0]);
+}
+
+#[test]
+fn streak_of_weeks() {
+    assert_eq!(weekly_streak(&[1, 1, 1, 2]), 4);
+    // The current week does not break the streak yet
+    assert_eq!(weekly_streak(&[1, 1, 1, 0]), 3);
+    assert_eq!(weekly_streak(&[0, 1, 0, 2]), 1);
+    assert_eq!(weekly_streak(&[1, 0, 1, 1]), 2);
+    assert_eq!(weekly_streak(&[0, 0]), 0);
+    assert_eq!(weekly_streak(&[]), 0);
+}
+
+#[test]
+fn lenient_parse_keeps_bad_lines() {
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("lenient.md");
+    let path = path.to_str().unwrap();
+    let content = "## Tasks
+Call the plumber @phone
+x (A) @phone
+Water the plants
+
+## Notes
+
+### Good note
+> cre:2022-03-03 mod:2022-03-03 guid:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8
+Content
+
+### Broken note without metadata
+";
+    std::fs::write(path, content).unwrap();
+
+    assert!(OrgDocument::from(path).unwrap_err().to_string().contains("line 3"));
+    let (od, issues) = OrgDocument::from_lenient(path).unwrap();
+    assert_eq!(od.len(), (2, 1));
+    assert_eq!(issues.len(), 2);
+    assert_eq!((issues[0].line, issues[0].content.as_str()), (3, "x (A) @phone"));
+    assert_eq!(issues[1].line, 12);
+
+    // Both bad lines survive writing and are quarantined again on reload
+    od.to(path).unwrap();
+    let written = std::fs::read_to_string(path).unwrap();
+    assert!(written.contains("x (A) @phone"));
+    assert!(written.contains("### Broken note without metadata"));
+    let (od, issues) = OrgDocument::from_lenient(path).unwrap();
+    assert_eq!(od.len(), (2, 1));
+    assert_eq!(issues.len(), 2);
+    assert!(OrgDocument::from(path).is_ok());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn roundtrip_task_details() {
+    let content = "## Tasks
+(A) Renew the VPN access @it
+  login: jdoe
+  x this is a detail, not a completed task
+  - [ ] request the token
+  - [x] fill in the form
+Water the plants
+x 2025-01-02 Call the vendor +infra
+  blocked until the vendor replies
+
+## Notes
+
+";
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("details.md");
+    std::fs::write(&path, content).unwrap();
+
+    let od = OrgDocument::from(path.to_str().unwrap()).unwrap();
+    assert_eq!(od.len(), (3, 0));
+    assert_eq!(od.tasks[0].details().len(), 4);
+    assert_eq!(od.tasks[0].details()[1], "x this is a detail, not a completed task");
+    assert!(!od.tasks[0].is_completed());
+    assert!(od.tasks[1].details().is_empty());
+    assert_eq!(od.tasks[2].details(), ["blocked until the vendor replies"]);
+
+    let written = String::from_utf8(od.to_bytes().unwrap()).unwrap();
+    assert_eq!(written.trim_start(), content);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn repair_duplicate_and_missing_guids() {
+    let guid = "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8";
+    let content = format!(
+        "## Tasks
+Follow up on the plan n:{guid}
+
+## Notes
+
+### Original
+> cre:2022-03-03 mod:2022-03-03 guid:{guid}
+First
+
+### Without guid
+> cre:2022-03-04 mod:2022-03-04 @aid
+Second
+
+### Copy
+> cre:2022-03-05 mod:2022-03-05 guid:{guid}
+Third
+"
+    );
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("guids.md");
+    std::fs::write(&path, &content).unwrap();
+
+    let (mut od, _) = OrgDocument::from_lenient(path.to_str().unwrap()).unwrap();
+    let issues = od.lint();
+    assert_eq!(issues.iter().map(|i| i.line).collect::<Vec<_>>(), vec![10, 14]);
+
+    let changes = od.repair_guids();
+    assert_eq!(changes.len(), 2);
+    assert!(changes[0].message.starts_with("Added missing guid"));
+    assert!(changes[1].message.starts_with(&format!("Replaced guid {guid}")));
+    assert!(od.lint().is_empty());
+
+    // The note moved back into place, the first note keeps the linked guid
+    let titles: Vec<&str> = od.notes.iter().map(|n| n.title()).collect();
+    assert_eq!(titles, vec!["Original", "Without guid", "Copy"]);
+    assert_eq!(od.notes[0].guid().to_string(), guid);
+    assert_ne!(od.notes[2].guid().to_string(), guid);
+    assert_eq!(od.notes[1].tags().to_string(), "@aid");
+    assert!(od.tasks[0].to_string().ends_with(&format!("n:{guid}")));
+
+    // A clean document is left alone
+    assert!(od.repair_guids().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn duplicate_oneoffs_of_open_tasks() {
+    let content = "## Tasks
+Fix the login !ticket-123 +web
+x 2025-03-01 Fix the logout !ticket-200
+Reply to the customer !ticket-123
+Deploy the fix !ticket-200
+
+## Notes
+";
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("oneoffs.md");
+    std::fs::write(&path, content).unwrap();
+
+    let od = OrgDocument::from(path.to_str().unwrap()).unwrap();
+    let issues = od.lint();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, 4);
+    assert_eq!(issues[0].message, "Duplicate one-off !ticket-123, already on 'Fix the login'");
+
+    // Completed tasks do not count, neither as first nor as duplicate
+    let index = od.open_oneoffs();
+    assert_eq!(index.get("!ticket-123"), Some(&0));
+    assert_eq!(index.get("!ticket-200"), Some(&3));
+    assert_eq!(index.len(), 2);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn tasks_with_dates_that_do_not_fit() {
+    let content = "## Tasks
+x 2025-03-04 2025-03-01 Book the flights
+x 2025-03-01 2025-03-04 Book the hotel
+2025-03-04 2025-03-01 Rent a car
+
+## Notes
+";
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dates.md");
+    std::fs::write(&path, content).unwrap();
+
+    // Such lines load, they are only pointed out
+    let od = OrgDocument::from(path.to_str().unwrap()).unwrap();
+    assert_eq!(od.tasks.len(), 3);
+    let issues = od.lint();
+    let found: Vec<(usize, &str)> =
+        issues.iter().map(|issue| (issue.line, issue.message.as_str())).collect();
+    assert_eq!(found, [(3, "Completed on 2025-03-01 before it was created on 2025-03-04")]);
+    // An open task has a creation date only, the second date is its own
+    assert_eq!(od.tasks[2].description(), "2025-03-01 Rent a car");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn tasks_grouped_by_context() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Buy stamps @town @errands",
+        "Call the dentist @phone",
+        "Pick up the parcel @errands @errands",
+        "x 2025-01-02 Return the books @town",
+        "Think about the garden",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+
+    let groups = od.tasks_by_context();
+    let names: Vec<&str> = groups.keys().map(String::as_str).collect();
+    assert_eq!(names, vec![NO_CONTEXT, "@errands", "@phone", "@town"]);
+    let descriptions = |context: &str| -> Vec<&str> {
+        groups[context].iter().map(|t| t.description()).collect()
+    };
+    assert_eq!(descriptions("@errands"), vec!["Buy stamps", "Pick up the parcel"]);
+    assert_eq!(descriptions("@town"), vec!["Buy stamps"]);
+    assert_eq!(descriptions(NO_CONTEXT), vec!["Think about the garden"]);
+}
+
+#[test]
+fn tasks_ordered_by_urgency() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Think about the garden",
+        "(C) Oil the hinges",
+        "x 2025-01-02 (A) Return the books",
+        "(A) Call the dentist",
+        "Water the plants",
+        "(B) Write the report",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    // Tasks without urgency, the completed one among them, keep their order
+    assert_eq!(od.tasks_by_urgency(), [3, 5, 1, 0, 2, 4]);
+}
+
+#[test]
+fn custom_keys_out_of_grammar_are_flagged() {
+    assert!(OrgDocument::from("tests/legacy_keys.md").is_err());
+    let (od, issues) = OrgDocument::from_lenient("tests/legacy_keys.md").unwrap();
+    let descriptions: Vec<&str> = od.tasks.iter().map(|t| t.description()).collect();
+    assert_eq!(descriptions, ["Renew the lease", "Water the plants"]);
+    assert_eq!(od.notes.len(), 1);
+
+    let flagged = od.lint();
+    assert_eq!(flagged, issues);
+    assert_eq!(flagged.iter().map(|i| i.line).collect::<Vec<_>>(), [3, 5]);
+    assert!(flagged[0].message.contains("is longer than 32 characters"), "{flagged:?}");
+    assert!(flagged[1].message.contains("has 'ö'"), "{flagged:?}");
+
+    // Suggestions only offer keys that can be typed again
+    let suggestions = od.collect_unique_tags(SuggestFrom::All);
+    // Due dates are no tags to reuse
+    assert_eq!(suggestions.custom.tags(), ["raum:küche"]);
+}
+
+#[test]
+fn tasks_found_by_id() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Call the plumber @phone",
+        "Renew passport id:a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8 +travel",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    let id = od.tasks[0].ensure_id();
+    assert_eq!(od.find_task_by_id(&id), Some(0));
+    let passport = od.tasks[1].id().cloned().unwrap();
+    assert_eq!(od.find_task_by_id(&passport), Some(1));
+    assert_eq!(od.find_task_by_id(&Guid::new()), None);
+
+    // Ids are no tags to reuse
+    let suggestions = od.collect_unique_tags(SuggestFrom::All);
+    assert!(suggestions.custom.tags().is_empty());
+    assert_eq!(suggestions.project.tags(), ["+travel"]);
+}
+
+#[test]
+fn note_links_in_both_directions() {
+    let mut od = OrgDocument::default();
+    od.push_note(Note::with("Flight times".to_string(), Vec::new()));
+    od.push_note(Note::with("Packing list".to_string(), Vec::new()));
+    let flights = od.notes[0].guid().clone();
+    let packing = od.notes[1].guid().clone();
+    let dangling = Guid::new();
+    for line in [
+        format!("Book the flights n:{flights} +travel"),
+        format!("Pack the bags n:{packing} n:{flights}"),
+        format!("Water the plants n:{dangling}"),
+    ] {
+        od.push_task(Task::from_str(&line).unwrap());
+    }
+
+    assert_eq!(od.tasks[1].note_refs(), [&packing, &flights]);
+    let titles: Vec<Option<&str>> = od.tasks[1]
+        .note_refs()
+        .into_iter()
+        .map(|guid| od.resolve_note(guid).map(Note::title))
+        .collect();
+    assert_eq!(titles, [Some("Packing list"), Some("Flight times")]);
+    let linking: Vec<&str> = od.tasks_for_note(&flights).iter().map(|t| t.description()).collect();
+    assert_eq!(linking, ["Book the flights", "Pack the bags"]);
+    assert_eq!(od.tasks_for_note(&packing).len(), 1);
+
+    // A guid no note has leads nowhere
+    assert!(od.resolve_note(&dangling).is_none());
+    assert!(od.tasks_for_note(&Guid::new()).is_empty());
+}
+
+#[test]
+fn tasks_blocked_by_open_tasks() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Pour the foundation id:00000000-0000-0000-0000-00000000000a",
+        "Build the walls id:00000000-0000-0000-0000-00000000000b \
+         blocked:00000000-0000-0000-0000-00000000000a",
+        "Paint the walls blocked:00000000-0000-0000-0000-00000000000b",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    let blocked = |od: &OrgDocument| od.tasks.iter().map(|t| od.is_blocked(t)).collect::<Vec<_>>();
+    assert_eq!(blocked(&od), [false, true, true]);
+    od.complete_tasks(&[0], OnComplete::InPlace);
+    assert_eq!(blocked(&od), [false, false, true]);
+    assert!(od.lint().is_empty());
+
+    // Each waiting on the other, looked up once each
+    let mut od = OrgDocument::default();
+    for line in [
+        "Hen id:00000000-0000-0000-0000-00000000000c blocked:00000000-0000-0000-0000-00000000000d",
+        "Egg id:00000000-0000-0000-0000-00000000000d blocked:00000000-0000-0000-0000-00000000000c",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    assert_eq!(blocked(&od), [true, true]);
+
+    // Ids no task has do not block but need attention
+    let task = Task::from_str("Move in blocked:00000000-0000-0000-0000-0000000000ff").unwrap();
+    od.push_task(task);
+    assert!(!od.is_blocked(&od.tasks[2]));
+    let issues = od.lint();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].message,
+        "Blocked by 00000000-0000-0000-0000-0000000000ff, but no task has this id"
+    );
+}
+
+#[test]
+fn waiting_tasks_grouped_by_person() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Signed contract s:wait(landlord) wait_since:2024-03-04",
+        "Quote for the roof @waiting p:ben p:anna",
+        "Call the plumber",
+        "x 2024-03-05 Keys back s:wait(landlord)",
+        "Invoice s:wait() p:anna",
+        "Parcel @waiting",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+
+    let groups = od.waiting_by_person();
+    let names: Vec<&str> = groups.keys().map(String::as_str).collect();
+    assert_eq!(names, vec![NO_PERSON, "anna", "ben", "landlord"]);
+    assert_eq!(groups["anna"], vec![1, 4]);
+    assert_eq!(groups["ben"], vec![1]);
+    assert_eq!(groups["landlord"], vec![0]);
+    assert_eq!(groups[NO_PERSON], vec![5]);
+
+    let today = Date::from_str("2024-03-11").unwrap();
+    assert_eq!(od.tasks[0].waiting_days(&today), Some(7));
+}
+
+#[test]
+fn project_progress_and_tag_counts() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Draft the budget +house +money",
+        "x 2025-01-03 Pay the roofer +house +money @phone",
+        "x 2025-01-02 Fix the gate +house +house",
+        "Call mum @phone",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+
+    // Tasks with several projects count for each, without one for none
+    let progress = od.project_progress();
+    assert_eq!(progress.len(), 2);
+    assert_eq!(progress["+house"], (1, 2));
+    assert_eq!(progress["+money"], (1, 1));
+
+    let counts = od.tag_counts();
+    assert_eq!(counts["+house"], 3);
+    assert_eq!(counts["@phone"], 2);
+    assert_eq!(counts.len(), 3);
+}
+
+#[test]
+fn estimates_add_up() {
+    let mut od = OrgDocument::default();
+    for line in [
+        "Answer the mails @work est:30min",
+        "Call the plumber @home est:15min",
+        "Write the report @work est:90min",
+        "Tidy the desk @work",
+        "x 2025-01-03 Book the flights @work est:20min",
+    ] {
+        od.push_task(Task::from_str(line).unwrap());
+    }
+    let open_at_work = |task: &Task| !task.is_completed() && task.contexts() == ["work"];
+    assert_eq!(od.total_estimate(open_at_work), Duration::from_secs(120 * 60));
+    assert_eq!(od.total_estimate(|_| true), Duration::from_secs(155 * 60));
+    assert_eq!(od.total_estimate(|_| false), Duration::ZERO);
+}
+
+#[test]
+fn tag_suggestions_skip_completed_tasks() {
+    let today = Date::now();
+    let mut od = OrgDocument::default();
+    for line in [
+        "x 2020-03-02 2020-03-01 Ship the old release +oldrelease @office".to_string(),
+        format!("x {today} {today} Water the plants +garden"),
+        "Plan the holiday +travel @office".to_string(),
+    ] {
+        od.push_task(Task::from_str(&line).unwrap());
+    }
+
+    let all = od.collect_unique_tags(SuggestFrom::All);
+    assert_eq!(all.project.tags(), vec!["+garden", "+oldrelease", "+travel"]);
+
+    let recent = od.collect_unique_tags(SuggestFrom::Recent(90));
+    assert_eq!(recent.project.tags(), vec!["+garden", "+travel"]);
+    // Still suggested thanks to the open task
+    assert_eq!(recent.context.tags(), vec!["@office"]);
+
+    let open = od.collect_unique_tags(SuggestFrom::OpenOnly);
+    assert_eq!(open.project.tags(), vec!["+travel"]);
+}
+
+#[test]
+fn replace_text_in_tasks_and_notes() {
+    let mut od = OrgDocument::default();
+    od.push_task(Task::from_str("Move tickets from Jira +jira").unwrap());
+    od.push_task(Task::from_str("x 2024-01-02 2024-01-01 Close jira account").unwrap());
+    od.push_note(Note::with(
+        "Migration".to_string(),
+        vec!["Jira export in jira:backup".to_string(), "Nothing to do".to_string()],
+    ));
+
+    let options = ReplaceOptions {
+        case_sensitive: false,
+        ..ReplaceOptions::default()
+    };
+    let preview = od.preview_replace("jira", "Linear", options);
+    assert_eq!(preview.len(), 3);
+    assert_eq!(od.tasks[0].description(), "Move tickets from Jira", "preview changes nothing");
+
+    let changes = od.replace_text("jira", "Linear", options);
+    assert_eq!(changes, preview);
+    assert_eq!(changes[2].target, ChangeTarget::NoteLine { note: 0, line: 0 });
+    assert_eq!(od.tasks[1].description(), "Close Linear account");
+    // The tag and the tag-lookalike in the note stay as they are
+    assert_eq!(od.tasks[0].tags().as_ref().unwrap().to_string(), "+jira");
+    assert_eq!(od.notes[0].content()[0], "Linear export in jira:backup");
+
+    let tags_only_in_notes = ReplaceOptions {
+        scope: ReplaceScope::Notes,
+        include_tags: true,
+        ..options
+    };
+    let changes = od.replace_text("jira", "linear", tags_only_in_notes);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(od.notes[0].content()[0], "Linear export in linear:backup");
+    assert_eq!(od.tasks[0].tags().as_ref().unwrap().to_string(), "+jira");
+}
+
+#[test]
+fn readonly_marker_and_permissions() {
+    let path = temp_copy("tests/document.md", "readonly.md");
+    let od = OrgDocument::from(&path).unwrap();
+    assert!(!od.is_marked_readonly());
+    assert!(!OrgDocument::is_readonly_file(&path));
+    assert_eq!(od.readonly_reason(&path), None);
+
+    // The marker is found in the preamble, also when only the tasks are loaded
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("{READONLY_MARKER}\n{content}")).unwrap();
+    let od = OrgDocument::from_tasks_only(&path).unwrap();
+    assert!(od.is_marked_readonly());
+    assert_eq!(od.readonly_reason(&path), Some(ReadOnlyReason::Marked));
+
+    std::fs::write(&path, content).unwrap();
+    let od = OrgDocument::from(&path).unwrap();
+    let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&path, permissions.clone()).unwrap();
+    assert!(OrgDocument::is_readonly_file(&path));
+    assert_eq!(od.readonly_reason(&path), Some(ReadOnlyReason::Permissions));
+    assert!(!OrgDocument::is_readonly_file("tests/does-not-exist.md"));
+
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    std::fs::set_permissions(&path, permissions).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn move_task_between_sections() {
+    let path = temp_copy("tests/document.md", "between.md");
+    let mut od = OrgDocument::from(&path).unwrap();
+    assert_eq!(od.preamble()[0], "# Document");
+    assert_eq!(od.between().len(), 4);
+    assert_eq!(od.between()[2], "## Chapter: Again");
+
+    let first = od.tasks[0].to_string();
+    let moved = od.move_task_to_section(0, "## Chapter: Between").unwrap();
+    assert_eq!(moved.to_string(), first);
+    od.move_task_to_section(0, "## Waiting For").unwrap();
+    assert!(od.move_task_to_section(0, "## Waiting For").is_none());
+    od.to(&path).unwrap();
+
+    // The lines end up in their sections, which stay between Tasks and Notes
+    let od = OrgDocument::from(&path).unwrap();
+    assert!(od.tasks.is_empty());
+    assert_eq!(od.between()[0], "## Chapter: Between");
+    assert_eq!(od.between()[2], first);
+    assert_eq!(od.between()[3], "## Chapter: Again");
+    assert_eq!(od.between()[5..], ["## Waiting For", first.as_str()]);
+    assert_eq!(od.len().1, 3);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn update_note_keeps_its_identity() {
+    let mut od = OrgDocument::from("tests/document.md").unwrap();
+    od.push_note(Note::from(vec![
+        "### Draft".to_string(),
+        "> cre:2024-01-01 mod:2024-01-02 guid:0b1c2d3e-0000-4000-8000-000000000003".to_string(),
+    ]));
+    let guid = od.notes[3].guid().to_string();
+    let created = *od.notes[3].creation_date();
+    assert_eq!(od.find_note(&guid), Some(3));
+    assert_eq!(od.find_note("no-such-guid"), None);
+
+    od.update_note(3, Note::with("Edited".into(), vec!["New content".into()]));
+    let note = &od.notes[3];
+    assert_eq!(note.title(), "Edited");
+    assert_eq!(note.content(), ["New content"]);
+    assert_eq!(note.guid().to_string(), guid);
+    assert_eq!(*note.creation_date(), created);
+    assert_eq!(*note.modification_date(), Date::now());
+}
+
+#[test]
+fn original_order_survives_sorting_and_mutations() {
+    let dir = std::env::temp_dir().join(format!("orgflow-io-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("order.md").to_str().unwrap().to_string();
+    let lines = [
+        "(B) Call the plumber",
+        "Water the plants",
+        "(A) Book flights",
+        "(B) Pay the rent",
+        "Read a book",
+        "(A) Renew the passport",
+    ];
+    std::fs::write(&path, format!("## Tasks\n{}\n\n## Notes\n", lines.join("\n"))).unwrap();
+    let mut od = OrgDocument::from(&path).unwrap();
+    let description = |od: &OrgDocument, idx: usize| od.tasks[idx].description().to_string();
+
+    // Equal priorities keep the file order
+    assert_eq!(od.sorted_task_indices(TaskSort::Priority), [2, 5, 0, 3, 1, 4]);
+    assert_eq!(od.original_order(), [0, 1, 2, 3, 4, 5]);
+
+    let archived = od.complete_tasks(&[3, 0], OnComplete::Archive);
+    assert_eq!(archived.len(), 2);
+    od.insert_task(1, Task::from_str("(A) Fix the bike").unwrap());
+    od.insert_task(99, Task::from_str("Plan the garden").unwrap());
+    od.move_task(0, 2);
+    let sorted: Vec<String> = od
+        .sorted_task_indices(TaskSort::Priority)
+        .into_iter()
+        .map(|idx| description(&od, idx))
+        .collect();
+    assert_eq!(
+        sorted,
+        [
+            "Fix the bike",
+            "Book flights",
+            "Renew the passport",
+            "Water the plants",
+            "Read a book",
+            "Plan the garden",
+        ]
+    );
+
+    // The original order is exactly what is written to the file
+    let original: Vec<String> =
+        od.original_order().into_iter().map(|idx| description(&od, idx)).collect();
+    od.to(&path).unwrap();
+    let written: Vec<String> = OrgDocument::from(&path)
+        .unwrap()
+        .tasks
+        .iter()
+        .map(|task| task.description().to_string())
+        .collect();
+    assert_eq!(original, written);
+    assert_eq!(
+        written,
+        [
+            "Fix the bike",
+            "Book flights",
+            "Water the plants",
+            "Read a book",
+            "Renew the passport",
+            "Plan the garden",
+        ]
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn exported_view_matches_the_golden_file() {
+    let tasks: Vec<Task> = [
+        "2025-03-01 Sow carrots +garden",
+        "(B) Review the budget +work",
+        "(A) 2025-02-20 Buy seed potatoes +garden @errands due:2025-03-08\n  the early ones",
+        "x 2025-03-04 Order compost +garden",
+        "(C) Prune the apple tree +garden p:anna",
+    ]
+    .iter()
+    .map(|line| Task::from_str(line).unwrap())
+    .collect();
+    let view = FilterSet {
+        filters: vec![TaskFilter::Open, TaskFilter::Project("+garden".to_string())],
+        sort: TaskSort::Priority,
+    };
+    let shown: Vec<&Task> = view.apply(&tasks).into_iter().map(|idx| &tasks[idx]).collect();
+    let text = export_view(&shown, &view, &Date::from_str("2025-03-05").unwrap());
+
+    let golden = "tests/golden/view.txt";
+    if std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1") {
+        std::fs::write(golden, &text).unwrap();
+    }
+    assert_eq!(text, std::fs::read_to_string(golden).unwrap());
+}
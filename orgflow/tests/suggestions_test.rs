@@ -0,0 +1,68 @@
+//! Tag suggestions of documents that collected thousands of tags over the
+//! years, mostly one-shot ticket ids
+
+use std::time::{Duration, Instant};
+
+use orgflow::TagIndex;
+
+/// Size of the generated corpus
+const CORPUS_LEN: usize = 5000;
+
+/// Custom tags in mixed case, in the order a document would list them
+fn corpus() -> Vec<String> {
+    let kinds = ["ticket:OPS-", "ticket:web-", "review:Q", "sprint:", "owner:team-"];
+    (0..CORPUS_LEN)
+        .map(|i| format!("{}{}", kinds[i % kinds.len()], (i * 7919) % CORPUS_LEN))
+        .collect()
+}
+
+/// How suggestions were found before the index: lowercasing every tag
+fn scan(tags: &[String], prefix: &str) -> Vec<String> {
+    tags.iter()
+        .filter(|tag| tag.to_lowercase().starts_with(&prefix.to_lowercase()))
+        .cloned()
+        .collect()
+}
+
+/// Prefixes as they come in while typing a few tags
+fn keystrokes() -> Vec<String> {
+    let words = ["ticket:ops-4711", "TICKET:WEB-12", "review:q3", "sprint:99", "nothing:here"];
+    let mut prefixes = vec![String::new()];
+    for word in words {
+        prefixes.extend((1..=word.len()).map(|end| word[..end].to_string()));
+    }
+    prefixes
+}
+
+#[test]
+fn index_finds_what_the_scan_found() {
+    let tags = corpus();
+    let index = TagIndex::from(tags.clone());
+    assert_eq!(index.len(), CORPUS_LEN);
+    for prefix in keystrokes() {
+        // Same tags, same order, whatever the case
+        assert_eq!(index.starting_with(&prefix), scan(&tags, &prefix), "{prefix}");
+    }
+}
+
+#[test]
+fn index_is_faster_than_the_scan() {
+    let tags = corpus();
+    let index = TagIndex::from(tags.clone());
+    let prefixes = keystrokes();
+    let time = |find: &dyn Fn(&str) -> Vec<String>| {
+        let start = Instant::now();
+        let found: usize = prefixes.iter().map(|prefix| find(prefix).len()).sum();
+        (start.elapsed(), found)
+    };
+
+    // The empty prefix matches everything, the rest narrow down quickly
+    let (scanned, scan_found) = time(&|prefix| scan(&tags, prefix));
+    let (indexed, index_found) = time(&|prefix| index.starting_with(prefix));
+    assert_eq!(scan_found, index_found);
+    assert!(
+        indexed < scanned.max(Duration::from_millis(1)),
+        "{} keystrokes over {CORPUS_LEN} tags: scan {scanned:?}, index {indexed:?}",
+        prefixes.len()
+    );
+}